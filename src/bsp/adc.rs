@@ -6,6 +6,15 @@
 
 // 导入内部生成的设备驱动库
 use library::*;
+use crate::bsp::dma::*;
+use crate::bsp::timer::{Timer, TimerError, TimerNumber};
+use crate::bsp::exti::{Exti, ExtiLine, ExtiTriggerMode};
+use crate::bsp::gpio::{GpioPortStruct, gpio_exti_line_config, GpioPort, GpioPortType, Pin, Analog};
+use crate::bsp::delay::get_uptime_ms;
+use crate::bsp::rcc::RccDriver;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::convert::Infallible;
+use embedded_hal::adc::{Channel, OneShot};
 
 /// ADC模式枚举
 #[derive(Debug, Clone, Copy)]
@@ -83,6 +92,80 @@ pub enum AdcChannel {
     Channel17 = 17,  // 内部参考电压
 }
 
+impl AdcChannel {
+    /// 由通道号获取通道，超出0~17时返回None
+    pub const fn from_index(index: u8) -> Option<Self> {
+        Some(match index {
+            0 => AdcChannel::Channel0,
+            1 => AdcChannel::Channel1,
+            2 => AdcChannel::Channel2,
+            3 => AdcChannel::Channel3,
+            4 => AdcChannel::Channel4,
+            5 => AdcChannel::Channel5,
+            6 => AdcChannel::Channel6,
+            7 => AdcChannel::Channel7,
+            8 => AdcChannel::Channel8,
+            9 => AdcChannel::Channel9,
+            10 => AdcChannel::Channel10,
+            11 => AdcChannel::Channel11,
+            12 => AdcChannel::Channel12,
+            13 => AdcChannel::Channel13,
+            14 => AdcChannel::Channel14,
+            15 => AdcChannel::Channel15,
+            16 => AdcChannel::Channel16,
+            17 => AdcChannel::Channel17,
+            _ => return None,
+        })
+    }
+    
+    /// 获取引脚对应的ADC通道
+    /// 
+    /// PA0~PA7为通道0~7，PB0/PB1为通道8/9，PC0~PC5为通道10~15，其他引脚返回None
+    pub const fn for_pin(port: GpioPort, pin: u8) -> Option<Self> {
+        match (port, pin) {
+            (GpioPort::A, 0..=7) => Self::from_index(pin),
+            (GpioPort::B, 0..=1) => Self::from_index(8 + pin),
+            (GpioPort::C, 0..=5) => Self::from_index(10 + pin),
+            _ => None,
+        }
+    }
+}
+
+/// ADC模拟输入引脚
+/// 
+/// 持有已配置为模拟输入的引脚，通道号在类型中确定，实现embedded-hal的`adc::Channel`，
+/// 可以用`OneShot::read(&mut adc, &mut pin)`读取，例如PA1为`AdcPin<Gpioa, 1>`
+pub struct AdcPin<P: GpioPortType, const CHANNEL: u8> {
+    pin: Pin<P, Analog>,
+}
+
+impl<P: GpioPortType, const CHANNEL: u8> AdcPin<P, CHANNEL> {
+    /// 创建模拟输入引脚
+    /// 
+    /// # 返回值
+    /// 引脚不是通道`CHANNEL`的输入时返回`Err`并交还引脚
+    pub fn new(pin: Pin<P, Analog>) -> Result<Self, Pin<P, Analog>> {
+        match AdcChannel::for_pin(P::PORT, pin.pin_number()) {
+            Some(channel) if channel as u8 == CHANNEL => Ok(Self { pin }),
+            _ => Err(pin),
+        }
+    }
+    
+    /// 释放引脚
+    pub fn release(self) -> Pin<P, Analog> {
+        self.pin
+    }
+}
+
+/// 实现embedded-hal的ADC通道特性
+impl<P: GpioPortType, const CHANNEL: u8> Channel<Adc> for AdcPin<P, CHANNEL> {
+    type ID = u8;
+    
+    fn channel() -> u8 {
+        CHANNEL
+    }
+}
+
 /// ADC采样时间枚举
 #[derive(Debug, Clone, Copy)]
 pub enum AdcSampleTime {
@@ -97,12 +180,20 @@ pub enum AdcSampleTime {
 }
 
 /// ADC枚举
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AdcNumber {
     ADC1,
     ADC2,
 }
 
+/// ADC错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdcError {
+    InvalidSequenceLength,  // 规则序列通道数不在1~16之间
+    LengthMismatch,         // 缓冲区长度与通道数不一致
+    DmaUnavailable,         // 该ADC没有DMA请求（ADC2）
+}
+
 /// ADC配置结构体
 #[derive(Debug, Clone, Copy)]
 pub struct AdcConfig {
@@ -130,6 +221,8 @@ impl Default for AdcConfig {
 /// ADC结构体
 pub struct Adc {
     number: AdcNumber,
+    vdda_mv: u32,               // 缓存的VDDA（毫伏）
+    vref_time_ms: Option<u32>,  // 上次校准VDDA的时间
 }
 
 impl AdcNumber {
@@ -163,6 +256,8 @@ impl Adc {
     pub const fn new(number: AdcNumber) -> Self {
         Self {
             number,
+            vdda_mv: VDDA_DEFAULT_MV,
+            vref_time_ms: None,
         }
     }
     
@@ -332,6 +427,36 @@ impl Adc {
         }
     }
     
+    /// 计算单次转换时间（微秒）
+    /// 
+    /// 转换时间 = (采样周期 + 12.5) / ADC时钟，ADC时钟由当前PCLK2和ADCPRE计算
+    /// 
+    /// # 参数
+    /// * `sample_time` - 通道的采样时间
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用，需要读取当前的时钟配置
+    pub unsafe fn conversion_time_us(&self, sample_time: AdcSampleTime) -> f32 {
+        let adcclk = RccDriver::new().get_clocks_freq().adcclk_frequency;
+        Self::conversion_time_ns(sample_time, adcclk) as f32 / 1000.0
+    }
+    
+    /// 由采样时间和ADC时钟频率计算单次转换时间（纳秒）
+    pub const fn conversion_time_ns(sample_time: AdcSampleTime, adcclk: u32) -> u32 {
+        // 以半个ADC时钟周期为单位：采样周期 + 12.5个周期的逐次逼近
+        let sample_half_cycles = match sample_time {
+            AdcSampleTime::Cycles1_5 => 3,
+            AdcSampleTime::Cycles7_5 => 15,
+            AdcSampleTime::Cycles13_5 => 27,
+            AdcSampleTime::Cycles28_5 => 57,
+            AdcSampleTime::Cycles41_5 => 83,
+            AdcSampleTime::Cycles55_5 => 111,
+            AdcSampleTime::Cycles71_5 => 143,
+            AdcSampleTime::Cycles239_5 => 479,
+        };
+        ((sample_half_cycles + 25) as u64 * 1_000_000_000 / (2 * adcclk as u64)) as u32
+    }
+    
     /// 设置通道采样时间
     pub fn set_sample_time(&self, channel: AdcChannel, time: AdcSampleTime) {
         let channel = channel as u8;
@@ -522,6 +647,111 @@ impl Adc {
         self.read_result()
     }
     
+    /// 多次转换取平均值（阻塞式）
+    /// 
+    /// 对同一通道连续做`samples`次单次转换，返回四舍五入后的算术平均值
+    /// 
+    /// # 参数
+    /// * `channel` - 要读取的通道
+    /// * `samples` - 采样次数，为0时按1次处理
+    pub fn read_averaged(&self, channel: AdcChannel, samples: u16) -> u16 {
+        Self::averaged(samples, || self.read_single_channel(channel))
+    }
+    
+    /// 双ADC规则通道同时转换（阻塞式）
+    /// 
+    /// ADC1作为主ADC、ADC2作为从ADC，在同一时刻分别转换`ch1`和`ch2`，适合同时采样电压和电流。
+    /// 两个ADC都需已初始化并校准，转换期间ADC1.CR1的DUALMOD临时设为规则同时模式，完成后恢复。
+    /// 两个ADC的软件触发都会被配置为EXTSEL=SWSTART并置位EXTTRIG。
+    /// 
+    /// # 参数
+    /// * `ch1` - ADC1转换的通道
+    /// * `ch2` - ADC2转换的通道，不能与`ch1`相同（同一通道不能被两个ADC同时采样）
+    /// 
+    /// # 返回值
+    /// (ADC1结果, ADC2结果)
+    pub fn read_dual_simultaneous(&self, ch1: AdcChannel, ch2: AdcChannel) -> (u16, u16) {
+        assert!(matches!(self.number, AdcNumber::ADC1), "Dual mode is controlled by the ADC1 (master) instance");
+        assert!(ch1 as u8 != ch2 as u8, "ADC1 and ADC2 must not sample the same channel simultaneously");
+        
+        let slave = ADC2;
+        slave.regular_channel_config(ch2, 1, AdcSampleTime::Cycles13_5);
+        self.regular_channel_config(ch1, 1, AdcSampleTime::Cycles13_5);
+        
+        unsafe {
+            let adc1 = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+            let adc2 = &mut *(0x40012800 as *mut library::adc2::RegisterBlock);
+            adc2.cr2().modify(|r, w| w.bits(r.bits() | CR2_EXTSEL | CR2_EXTTRIG));
+            adc1.cr2().modify(|r, w| w.bits(r.bits() | CR2_EXTSEL | CR2_EXTTRIG));
+            
+            let cr1 = adc1.cr1().read().bits();
+            adc1.cr1().write(|w| w.bits(Self::dual_mode_cr1(cr1, AdcMode::RegSimult)));
+            
+            // 主ADC的软件触发同时启动从ADC
+            self.software_start_conv_cmd(true);
+            while !self.is_conversion_complete() {
+                core::hint::spin_loop();
+            }
+            let dr = adc1.dr().read().bits();
+            // 读ADC1.DR不会清除ADC2的EOC，这里手动清除
+            slave.clear_flag(AdcFlag::EOC);
+            
+            adc1.cr1().write(|w| w.bits(cr1));
+            Self::split_dual_result(dr)
+        }
+    }
+    
+    /// 用双ADC模式替换CR1中的DUALMOD字段（位19:16）
+    pub const fn dual_mode_cr1(cr1: u32, mode: AdcMode) -> u32 {
+        (cr1 & !CR1_DUALMOD_MASK) | (mode as u32 & CR1_DUALMOD_MASK)
+    }
+    
+    /// 拆分双ADC模式下ADC1.DR的32位结果
+    /// 
+    /// # 返回值
+    /// (ADC1结果（低16位）, ADC2结果（高16位）)
+    pub const fn split_dual_result(dr: u32) -> (u16, u16) {
+        (dr as u16, (dr >> 16) as u16)
+    }
+    
+    /// 调用`samples`次转换函数并计算四舍五入后的平均值
+    /// 
+    /// 累加使用u32，65535次满量程采样也不会溢出
+    pub fn averaged<F: FnMut() -> u16>(samples: u16, mut convert: F) -> u16 {
+        let samples = samples.max(1) as u32;
+        let mut sum: u32 = 0;
+        for _ in 0..samples {
+            sum += convert() as u32;
+        }
+        ((sum + samples / 2) / samples) as u16
+    }
+    
+    /// 轮流读取多个通道（不使用扫描模式）
+    /// 
+    /// 依次将每个通道配置为规则序列的唯一通道并做一次阻塞转换，只用到
+    /// `regular_channel_config`和`software_start_conv_cmd`，不需要DMA。
+    /// 代价是各通道的采样时刻不同：相邻通道间隔一次完整转换加上软件配置的时间，
+    /// 而扫描模式+DMA中各通道是硬件连续采样的，对需要同时刻采样的信号应使用扫描模式。
+    /// 
+    /// # 参数
+    /// * `channels` - 要读取的通道，按顺序转换
+    /// * `out` - 转换结果，`out[i]`对应`channels[i]`
+    /// 
+    /// # 返回值
+    /// 实际读取的通道数（两者长度的较小值）
+    pub fn read_round_robin(&self, channels: &[AdcChannel], out: &mut [u16]) -> usize {
+        Self::round_robin(channels, out, |channel| self.read_single_channel(channel))
+    }
+    
+    /// 按通道顺序调用转换函数并写入结果
+    pub fn round_robin<F: FnMut(AdcChannel) -> u16>(channels: &[AdcChannel], out: &mut [u16], mut convert: F) -> usize {
+        let count = channels.len().min(out.len());
+        for (result, &channel) in out.iter_mut().zip(channels.iter()) {
+            *result = convert(channel);
+        }
+        count
+    }
+    
     /// 开始连续转换
     pub fn start_continuous(&self, channel: AdcChannel) {
         unsafe {
@@ -570,6 +800,90 @@ impl Adc {
         }
     }
     
+    /// 启动规则通道扫描转换，结果由DMA循环写入缓冲区
+    /// 
+    /// 按`channels`顺序配置规则序列，连续扫描转换，`buffer[i]`始终保存`channels[i]`的最新结果。
+    /// 使用DMA1通道1（循环模式），各通道采样时间需预先用`set_sample_time`配置。
+    /// 
+    /// # 参数
+    /// * `channels` - 规则序列（1~16个通道）
+    /// * `buffer` - 结果缓冲区，长度必须等于通道数
+    /// 
+    /// # 返回值
+    /// 通道数或缓冲区长度不合法、或ADC不支持DMA（只有ADC1支持）时返回错误，此时不修改任何寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC已初始化并校准，DMA1时钟已启用，且DMA1通道1未被其他代码使用
+    /// - `buffer`在调用`stop_scan_dma`之前不得被释放，DMA会持续写入
+    pub unsafe fn start_scan_dma(&self, channels: &[AdcChannel], buffer: &mut [u16]) -> Result<(), AdcError> {
+        Self::check_scan_dma(self.number, channels.len(), buffer.len())?;
+        let sqr = Self::regular_sequence(channels);
+        
+        let dma = DMA1_CHANNEL1;
+        dma.disable();
+        dma.init(
+            DmaDirection::PeripheralToMemory,
+            DmaPeripheralIncrementMode::Disabled,
+            DmaMemoryIncrementMode::Enabled,
+            DmaPeripheralDataSize::HalfWord,
+            DmaMemoryDataSize::HalfWord,
+            DmaChannelPriority::High,
+            DmaCircularMode::Enabled,
+        );
+        dma.configure_transfer(ADC1_DR_ADDRESS, buffer.as_mut_ptr() as u32, buffer.len() as u16);
+        dma.start();
+        
+        let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+        adc.sqr1().write(|w| w.bits(sqr[0]));
+        adc.sqr2().write(|w| w.bits(sqr[1]));
+        adc.sqr3().write(|w| w.bits(sqr[2]));
+        adc.cr1().modify(|r, w| w.bits((r.bits() & !CR1_DISCEN) | CR1_SCAN));
+        adc.cr2().modify(|r, w| w.bits(r.bits() | CR2_CONT | CR2_DMA));
+        self.software_start_conv_cmd(true);
+        Ok(())
+    }
+    
+    /// 停止扫描转换，关闭连续转换、扫描模式和DMA
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC1和DMA1通道1未被其他代码使用
+    pub unsafe fn stop_scan_dma(&self) {
+        if let AdcNumber::ADC1 = self.number {
+            let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+            adc.cr2().modify(|r, w| w.bits(r.bits() & !(CR2_CONT | CR2_DMA)));
+            adc.cr1().modify(|r, w| w.bits(r.bits() & !CR1_SCAN));
+            DMA1_CHANNEL1.stop();
+        }
+    }
+    
+    /// 检查扫描DMA转换的参数
+    pub fn check_scan_dma(number: AdcNumber, channel_count: usize, buffer_len: usize) -> Result<(), AdcError> {
+        if channel_count == 0 || channel_count > 16 {
+            return Err(AdcError::InvalidSequenceLength);
+        }
+        if buffer_len != channel_count {
+            return Err(AdcError::LengthMismatch);
+        }
+        match number {
+            AdcNumber::ADC1 => Ok(()),
+            AdcNumber::ADC2 => Err(AdcError::DmaUnavailable),
+        }
+    }
+    
+    /// 计算规则序列寄存器
+    /// 
+    /// # 返回值
+    /// [SQR1, SQR2, SQR3]，SQR1包含序列长度L；通道数需为1~16
+    pub fn regular_sequence(channels: &[AdcChannel]) -> [u32; 3] {
+        let mut sqr = [((channels.len() as u32) - 1) << 20, 0, 0];
+        for (index, &channel) in channels.iter().enumerate() {
+            // 第1~6个在SQR3，第7~12个在SQR2，第13~16个在SQR1
+            let register = 2 - index / 6;
+            sqr[register] |= (channel as u32 & 0x1F) << (5 * (index % 6));
+        }
+        sqr
+    }
+    
     /// 外部触发转换命令
     pub fn external_trig_conv_cmd(&self, enable: bool) {
         unsafe {
@@ -871,6 +1185,108 @@ impl Adc {
         }
     }
     
+    /// 采样VREFINT并缓存计算出的VDDA
+    /// 
+    /// 之后`to_millivolts`使用缓存值换算，不必每次转换都读取VREFINT。
+    /// VDDA随温度和负载漂移，可根据`vref_age_ms`定期重新校准。
+    /// 会启用TSVREFE并占用规则通道序列的第1个转换，VREFINT只连接到ADC1。
+    pub fn calibrate_vref(&mut self) {
+        assert!(matches!(self.number, AdcNumber::ADC1), "VREFINT is only connected to ADC1");
+        
+        self.temp_sensor_vrefint_cmd(true);
+        // 第一次转换用于等待VREFINT稳定：239.5周期在14MHz下约17us，大于tSTART（10us）
+        let mut raw = 0;
+        for _ in 0..2 {
+            self.regular_channel_config(AdcChannel::Channel17, 1, AdcSampleTime::Cycles239_5);
+            self.software_start_conv_cmd(true);
+            while !self.is_conversion_complete() {
+                core::hint::spin_loop();
+            }
+            raw = self.read_result();
+        }
+        
+        self.record_vref(raw, get_uptime_ms());
+    }
+    
+    /// 获取缓存的VDDA（毫伏），未校准时为`VDDA_DEFAULT_MV`
+    pub fn vdda_mv(&self) -> u32 {
+        self.vdda_mv
+    }
+    
+    /// 获取距上次`calibrate_vref`经过的毫秒数，从未校准时返回None
+    pub fn vref_age_ms(&self) -> Option<u32> {
+        self.vref_time_ms.map(|time| get_uptime_ms().wrapping_sub(time))
+    }
+    
+    /// 使用缓存的VDDA把原始转换值换算为毫伏
+    pub fn to_millivolts(&self, raw: u16) -> u32 {
+        raw as u32 * self.vdda_mv / 4095
+    }
+    
+    /// 由VREFINT读数计算VDDA（毫伏）
+    /// 
+    /// VDDA = VREFINT × 4095 / raw，读数为0时返回默认值
+    pub const fn vdda_from_vrefint(raw: u16) -> u32 {
+        if raw == 0 {
+            return VDDA_DEFAULT_MV;
+        }
+        (VREFINT_TYPICAL_MV * 4095 + raw as u32 / 2) / raw as u32
+    }
+    
+    /// 读取片内温度传感器（摄氏度）
+    /// 
+    /// 通道16和通道17均以239.5周期采样（温度传感器要求采样时间不少于17.1us），
+    /// 用VREFINT读数补偿VDDA的偏差后按手册公式换算：
+    /// T = (V25 - Vsense) / Avg_Slope + 25。
+    /// 会启用TSVREFE并占用规则通道序列的第1个转换，温度传感器只连接到ADC1。
+    /// 
+    /// # 返回值
+    /// 温度（摄氏度），精度受V25的器件间离散（约±1.5°C）限制，可通过修改`TEMP_V25_MV`单点校准
+    pub fn read_temperature_celsius(&self) -> f32 {
+        assert!(matches!(self.number, AdcNumber::ADC1), "Temperature sensor is only connected to ADC1");
+        
+        self.temp_sensor_vrefint_cmd(true);
+        // 丢弃第一次转换，等待温度传感器和VREFINT稳定（tSTART最大10us）
+        self.read_slow_channel(AdcChannel::Channel16);
+        let ts_raw = self.read_slow_channel(AdcChannel::Channel16);
+        let vref_raw = self.read_slow_channel(AdcChannel::Channel17);
+        Self::temperature_from_raw(ts_raw, vref_raw)
+    }
+    
+    /// 温度传感器25°C时的输出电压（毫伏，典型值1430）
+    pub const TEMP_V25_MV: f32 = 1430.0;
+    
+    /// 温度传感器平均斜率（毫伏/°C，典型值4.3）
+    pub const TEMP_AVG_SLOPE_MV_PER_C: f32 = 4.3;
+    
+    /// 由温度传感器和VREFINT的原始读数计算温度
+    /// 
+    /// Vsense = VREFINT × ts_raw / vref_raw，与VDDA无关；VREFINT读数为0时按默认VDDA换算
+    pub fn temperature_from_raw(ts_raw: u16, vref_raw: u16) -> f32 {
+        let vsense_mv = if vref_raw == 0 {
+            ts_raw as f32 * VDDA_DEFAULT_MV as f32 / 4095.0
+        } else {
+            ts_raw as f32 * VREFINT_TYPICAL_MV as f32 / vref_raw as f32
+        };
+        (Self::TEMP_V25_MV - vsense_mv) / Self::TEMP_AVG_SLOPE_MV_PER_C + 25.0
+    }
+    
+    /// 以239.5周期采样时间单次转换，用于内部通道
+    fn read_slow_channel(&self, channel: AdcChannel) -> u16 {
+        self.regular_channel_config(channel, 1, AdcSampleTime::Cycles239_5);
+        self.software_start_conv_cmd(true);
+        while !self.is_conversion_complete() {
+            core::hint::spin_loop();
+        }
+        self.read_result()
+    }
+    
+    /// 记录一次VREFINT读数
+    fn record_vref(&mut self, raw: u16, now_ms: u32) {
+        self.vdda_mv = Self::vdda_from_vrefint(raw);
+        self.vref_time_ms = Some(now_ms);
+    }
+    
     /// 配置注入通道
     pub fn injected_channel_config(&self, channel: AdcChannel, rank: u8, sample_time: AdcSampleTime) {
         let rank = rank as u8;
@@ -1162,6 +1578,53 @@ impl Adc {
         }
     }
     
+    /// 配置自动注入转换（JAUTO）
+    /// 
+    /// 规则组转换结束后硬件自动转换注入组，适用于交替采样的场合。
+    /// 注入组的外部触发和不连续模式会被关闭（两者都不能与JAUTO同时使用），
+    /// 各通道的采样时间沿用`set_sample_time`的配置。
+    /// 
+    /// # 参数
+    /// * `injected_channels` - 注入通道序列（1~4个），按顺序转换
+    pub fn enable_auto_injected(&self, injected_channels: &[AdcChannel]) {
+        let (jauto, jsqr) = Self::auto_injected_config(injected_channels);
+        
+        self.external_trig_injected_conv_cmd(false);
+        self.injected_disc_mode_cmd(false);
+        unsafe {
+            match self.number {
+                AdcNumber::ADC1 => {
+                    let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+                    adc.jsqr().write(|w| w.bits(jsqr));
+                    adc.cr1().modify(|r, w| w.bits(r.bits() | jauto));
+                },
+                AdcNumber::ADC2 => {
+                    let adc = &mut *(0x40012800 as *mut library::adc2::RegisterBlock);
+                    adc.jsqr().write(|w| w.bits(jsqr));
+                    adc.cr1().modify(|r, w| w.bits(r.bits() | jauto));
+                },
+            }
+        }
+    }
+    
+    /// 计算自动注入转换的CR1和JSQR配置
+    /// 
+    /// JL小于3时注入序列从JSQ(4-JL)开始，因此n个通道依次放在JSQ(5-n)~JSQ4中。
+    /// 
+    /// # 返回值
+    /// (CR1中的JAUTO位, JSQR寄存器值)
+    pub fn auto_injected_config(injected_channels: &[AdcChannel]) -> (u32, u32) {
+        let count = injected_channels.len();
+        assert!((1..=4).contains(&count), "Injected sequence must have 1 to 4 channels");
+        
+        let mut jsqr = ((count as u32) - 1) << 20; // JL
+        for (i, &channel) in injected_channels.iter().enumerate() {
+            let slot = 4 - count + i; // 0对应JSQ1
+            jsqr |= (channel as u32 & 0x1F) << (5 * slot);
+        }
+        (CR1_JAUTO, jsqr)
+    }
+    
     /// 注入通道不连续模式命令
     pub fn injected_disc_mode_cmd(&self, enable: bool) {
         unsafe {
@@ -1194,8 +1657,8 @@ impl Adc {
                     let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
                     adc.cr1().modify(|r, w| {
                         let mut value = r.bits();
-                        value &= !(0x00001F00); // 清除DISCNUM位
-                        value |= ((number - 1) as u32) << 8;
+                        value &= !CR1_DISCNUM_MASK; // 清除DISCNUM位
+                        value |= ((number - 1) as u32) << 13;
                         w.bits(value)
                     });
                 },
@@ -1203,8 +1666,8 @@ impl Adc {
                     let adc = &mut *(0x40012800 as *mut library::adc2::RegisterBlock);
                     adc.cr1().modify(|r, w| {
                         let mut value = r.bits();
-                        value &= !(0x00001F00); // 清除DISCNUM位
-                        value |= ((number - 1) as u32) << 8;
+                        value &= !CR1_DISCNUM_MASK; // 清除DISCNUM位
+                        value |= ((number - 1) as u32) << 13;
                         w.bits(value)
                     });
                 },
@@ -1212,6 +1675,55 @@ impl Adc {
         }
     }
     
+    /// 配置规则组不连续模式
+    /// 
+    /// 按`channels`顺序设置规则序列，每个外部触发（或软件启动）转换其中`group_size`个通道，
+    /// 序列转换完后从头开始，适合在多个触发时刻依次采样多相信号。
+    /// 不连续模式不能与连续转换同时使用，CR2.CONT会被清除；外部触发源需另行配置。
+    /// 
+    /// # 参数
+    /// * `channels` - 规则序列（1~16个通道）
+    /// * `group_size` - 每次触发转换的通道数（1~8）
+    pub fn configure_discontinuous(&self, channels: &[AdcChannel], group_size: u8) {
+        let (cr1, sqr) = Self::discontinuous_config(channels, group_size);
+        let cr1_mask = CR1_DISCNUM_MASK | CR1_DISCEN | CR1_SCAN;
+        
+        unsafe {
+            match self.number {
+                AdcNumber::ADC1 => {
+                    let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+                    adc.cr2().modify(|r, w| w.bits(r.bits() & !CR2_CONT));
+                    adc.sqr1().write(|w| w.bits(sqr[0]));
+                    adc.sqr2().write(|w| w.bits(sqr[1]));
+                    adc.sqr3().write(|w| w.bits(sqr[2]));
+                    adc.cr1().modify(|r, w| w.bits((r.bits() & !cr1_mask) | cr1));
+                },
+                AdcNumber::ADC2 => {
+                    let adc = &mut *(0x40012800 as *mut library::adc2::RegisterBlock);
+                    adc.cr2().modify(|r, w| w.bits(r.bits() & !CR2_CONT));
+                    adc.sqr1().write(|w| w.bits(sqr[0]));
+                    adc.sqr2().write(|w| w.bits(sqr[1]));
+                    adc.sqr3().write(|w| w.bits(sqr[2]));
+                    adc.cr1().modify(|r, w| w.bits((r.bits() & !cr1_mask) | cr1));
+                },
+            }
+        }
+    }
+    
+    /// 计算不连续模式的CR1位和规则序列寄存器
+    /// 
+    /// # 返回值
+    /// (CR1中的DISCNUM、DISCEN和SCAN位, [SQR1, SQR2, SQR3])，SQR1包含序列长度L
+    pub fn discontinuous_config(channels: &[AdcChannel], group_size: u8) -> (u32, [u32; 3]) {
+        let count = channels.len();
+        assert!((1..=16).contains(&count), "Regular sequence must have 1 to 16 channels");
+        assert!((1..=8).contains(&group_size), "Discontinuous group size must be 1 to 8");
+        
+        let sqr = Self::regular_sequence(channels);
+        let cr1 = (((group_size - 1) as u32) << 13) | CR1_DISCEN | CR1_SCAN;
+        (cr1, sqr)
+    }
+    
     /// 规则通道不连续模式命令
     pub fn disc_mode_cmd(&self, enable: bool) {
         unsafe {
@@ -1280,6 +1792,70 @@ impl Adc {
         }
     }
     
+    /// 配置过流保护：ADC注入通道采样电流，超过阈值时触发定时器刹车关闭PWM输出
+    /// 
+    /// 注入通道由定时器TRGO（更新事件）触发，即每个PWM周期采样一次电流；
+    /// 模拟看门狗只监控该注入通道，超过`threshold`时产生AWD中断，
+    /// 需要在ADC1_2中断中调用`handle_overcurrent_interrupt`产生刹车。
+    /// 
+    /// 注意：注入触发源TIM1_TRGO只适用于ADC1/ADC2，且只有高级定时器TIM1支持刹车。
+    /// 
+    /// # 参数
+    /// * `channel` - 电流采样通道
+    /// * `threshold` - 过流阈值（12位原始值）
+    /// * `timer` - 需要关闭输出的PWM定时器
+    /// 
+    /// # 返回值
+    /// `timer`不是高级定时器时返回`UnsupportedFeature`，此时不修改ADC配置
+    pub fn configure_overcurrent_protection(&self, channel: AdcChannel, threshold: u16, timer: TimerNumber) -> Result<(), TimerError> {
+        if timer != TimerNumber::TIM1 {
+            return Err(TimerError::UnsupportedFeature);
+        }
+        
+        let (watchdog_mode, high, low) = Self::overcurrent_watchdog_config(threshold);
+        
+        // 注入通道：1个通道，由TIM1 TRGO触发
+        self.injected_sequencer_length_config(1);
+        self.injected_channel_config(channel, 1, AdcSampleTime::Cycles7_5);
+        self.external_trig_injected_conv_config(0x0000_0000); // JEXTSEL = TIM1_TRGO
+        self.external_trig_injected_conv_cmd(true);
+        unsafe {
+            Timer::new(timer).enable_update_trgo();
+        }
+        
+        // 模拟看门狗：只监控注入通道上的该通道
+        self.analog_watchdog_thresholds_config(high, low);
+        self.analog_watchdog_single_channel_config(channel);
+        self.analog_watchdog_cmd(watchdog_mode);
+        self.clear_flag(AdcFlag::AWD);
+        self.it_config(AdcInterrupt::AWD, true);
+        Ok(())
+    }
+    
+    /// 处理过流保护中断，应在ADC1_2中断服务函数中调用
+    /// 
+    /// # 返回值
+    /// 检测到过流并已产生刹车时返回true，`timer`不支持刹车时返回false
+    pub fn handle_overcurrent_interrupt(&self, timer: TimerNumber) -> bool {
+        if !self.get_flag_status(AdcFlag::AWD) {
+            return false;
+        }
+        
+        // 先关断输出，再清除标志
+        let braked = unsafe { Timer::new(timer).generate_break().is_ok() };
+        self.clear_flag(AdcFlag::AWD);
+        braked
+    }
+    
+    /// 计算过流保护的模拟看门狗配置
+    /// 
+    /// # 返回值
+    /// (CR1中JAWDEN/AWDSGL的值, 高阈值, 低阈值)
+    pub const fn overcurrent_watchdog_config(threshold: u16) -> (u32, u16, u16) {
+        // JAWDEN（位22）+ AWDSGL（位9），不启用规则通道看门狗
+        (0x0040_0200, threshold & 0x0FFF, 0)
+    }
+    
     /// 模拟看门狗单通道配置
     pub fn analog_watchdog_single_channel_config(&self, channel: AdcChannel) {
         let channel = channel as u8;
@@ -1309,6 +1885,906 @@ impl Adc {
     }
 }
 
+/// 示波器触发边沿枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeTriggerEdge {
+    Rising,   // 上升沿（由低于电平穿越到高于等于电平）
+    Falling,  // 下降沿（由高于电平穿越到低于等于电平）
+}
+
+/// 示波器触发源枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeTriggerSource {
+    AnalogWatchdog,  // 模拟看门狗硬件比较
+    Software,        // 软件比较相邻两个采样点
+}
+
+/// 示波器采集状态枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeState {
+    Idle,                // 空闲
+    Armed,               // 已启动，等待触发
+    Triggered(usize),    // 已触发，正在采集触发后数据（参数为触发点索引）
+    Done(usize),         // 采集完成，缓冲区已冻结（参数为触发点索引）
+}
+
+/// 示波器式ADC触发采集
+/// 
+/// ADC1以连续模式运行，由DMA1通道1循环写入采样缓冲区。
+/// 检测到触发条件（模拟看门狗或软件电平比较）后，再采集`post_trigger`个点即停止DMA，
+/// 缓冲区中保留触发前`pre_trigger`个点和触发后（含触发点）`post_trigger`个点。
+/// 
+/// 注意：只有ADC1支持DMA，ADC2不能用于示波器采集。
+pub struct ScopeCapture {
+    adc: Adc,
+    dma: Dma,
+    buffer: &'static mut [u16],
+    pre_trigger: usize,
+    post_trigger: usize,
+    level: u16,
+    edge: ScopeTriggerEdge,
+    source: ScopeTriggerSource,
+    pre_filled: bool,
+    state: ScopeState,
+}
+
+impl ScopeCapture {
+    /// 创建示波器采集实例
+    /// 
+    /// # 参数
+    /// * `adc` - ADC实例，必须为ADC1
+    /// * `buffer` - 循环采样缓冲区
+    /// * `pre_trigger` - 触发前保留的采样点数
+    /// * `post_trigger` - 触发后（含触发点）采集的采样点数
+    pub fn new(adc: Adc, buffer: &'static mut [u16], pre_trigger: usize, post_trigger: usize) -> Self {
+        assert!(matches!(adc.number, AdcNumber::ADC1), "Only ADC1 supports DMA");
+        assert!(buffer.len() <= 0xFFFF, "Buffer length must fit in CNDTR");
+        assert!(post_trigger >= 1, "Post-trigger count must be at least 1");
+        assert!(pre_trigger + post_trigger <= buffer.len(), "Pre/post-trigger counts exceed buffer length");
+        
+        Self {
+            adc,
+            dma: DMA1_CHANNEL1,
+            buffer,
+            pre_trigger,
+            post_trigger,
+            level: 2048,
+            edge: ScopeTriggerEdge::Rising,
+            source: ScopeTriggerSource::Software,
+            pre_filled: false,
+            state: ScopeState::Idle,
+        }
+    }
+    
+    /// 设置触发条件
+    /// 
+    /// # 参数
+    /// * `level` - 触发电平（12位原始值）
+    /// * `edge` - 触发边沿
+    /// * `source` - 触发源
+    pub fn set_trigger(&mut self, level: u16, edge: ScopeTriggerEdge, source: ScopeTriggerSource) {
+        self.level = level & 0x0FFF;
+        self.edge = edge;
+        self.source = source;
+    }
+    
+    /// 启动采集并等待触发
+    /// 
+    /// 使用模拟看门狗作为触发源时，上升沿对应“高于电平”，下降沿对应“低于电平”。
+    pub fn arm(&mut self, channel: AdcChannel, sample_time: AdcSampleTime) {
+        self.stop();
+        
+        // 配置DMA：外设到存储器，16位，存储器地址递增，循环模式
+        unsafe {
+            self.dma.init(
+                DmaDirection::PeripheralToMemory,
+                DmaPeripheralIncrementMode::Disabled,
+                DmaMemoryIncrementMode::Enabled,
+                DmaPeripheralDataSize::HalfWord,
+                DmaMemoryDataSize::HalfWord,
+                DmaChannelPriority::High,
+                DmaCircularMode::Enabled,
+            );
+            self.dma.configure_transfer(0x4001244C, self.buffer.as_mut_ptr() as u32, self.buffer.len() as u16);
+            self.dma.enable();
+        }
+        
+        // 配置触发条件
+        match self.source {
+            ScopeTriggerSource::AnalogWatchdog => {
+                let (high, low) = Self::watchdog_thresholds(self.level, self.edge);
+                self.adc.analog_watchdog_thresholds_config(high, low);
+                self.adc.analog_watchdog_single_channel_config(channel);
+                self.adc.analog_watchdog_cmd(0x00800200); // AWDEN + AWDSGL
+            },
+            ScopeTriggerSource::Software => {
+                self.adc.analog_watchdog_cmd(0);
+            },
+        }
+        self.adc.clear_flag(AdcFlag::AWD);
+        
+        // 启动连续转换
+        self.adc.dma_cmd(true);
+        self.adc.regular_channel_config(channel, 1, sample_time);
+        unsafe {
+            let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+            adc.cr2().modify(|_, w| w.cont().set_bit());
+        }
+        self.adc.software_start_conv_cmd(true);
+        
+        self.pre_filled = self.pre_trigger == 0;
+        self.state = ScopeState::Armed;
+    }
+    
+    /// 停止采集
+    pub fn stop(&mut self) {
+        self.adc.stop_continuous();
+        self.adc.dma_cmd(false);
+        unsafe {
+            self.dma.disable();
+        }
+        if let ScopeState::Armed | ScopeState::Triggered(_) = self.state {
+            self.state = ScopeState::Idle;
+        }
+    }
+    
+    /// 轮询采集状态，可在主循环或ADC1_2中断中调用
+    /// 
+    /// 需要足够频繁地调用，保证触发后的采集不会被DMA绕回覆盖。
+    /// 
+    /// # 返回值
+    /// 采集完成返回true
+    pub fn poll(&mut self) -> bool {
+        let len = self.buffer.len();
+        let remaining = unsafe { self.dma.get_remaining_count() } as usize;
+        let last = Self::last_written_index(len, remaining);
+        
+        match self.state {
+            ScopeState::Armed => {
+                // 触发前数据尚未填满时不接受触发
+                if !self.pre_filled {
+                    if Self::samples_written(len, remaining) >= self.pre_trigger {
+                        self.pre_filled = true;
+                    }
+                    self.adc.clear_flag(AdcFlag::AWD);
+                    return false;
+                }
+                
+                let triggered = match self.source {
+                    ScopeTriggerSource::AnalogWatchdog => {
+                        let flag = self.adc.get_flag_status(AdcFlag::AWD);
+                        if flag {
+                            self.adc.clear_flag(AdcFlag::AWD);
+                        }
+                        flag
+                    },
+                    ScopeTriggerSource::Software => {
+                        let previous = self.sample_at((last + len - 1) % len);
+                        let current = self.sample_at(last);
+                        Self::is_crossing(previous, current, self.level, self.edge)
+                    },
+                };
+                
+                if triggered {
+                    self.state = ScopeState::Triggered(last);
+                }
+                false
+            },
+            ScopeState::Triggered(trigger_index) => {
+                let captured = (last + len - trigger_index) % len + 1;
+                if captured >= self.post_trigger {
+                    self.adc.stop_continuous();
+                    self.adc.dma_cmd(false);
+                    unsafe {
+                        self.dma.disable();
+                    }
+                    self.state = ScopeState::Done(trigger_index);
+                    true
+                } else {
+                    false
+                }
+            },
+            ScopeState::Done(_) => true,
+            ScopeState::Idle => false,
+        }
+    }
+    
+    /// 获取当前采集状态
+    pub fn state(&self) -> ScopeState {
+        self.state
+    }
+    
+    /// 获取按时间顺序对齐的采集窗口
+    /// 
+    /// 由于缓冲区是循环的，窗口可能跨越缓冲区末尾，因此以两段切片返回，
+    /// 依次拼接即为完整窗口，触发点位于窗口第`pre_trigger`个位置。
+    /// 
+    /// # 返回值
+    /// 采集完成时返回`Some((前段, 后段))`，否则返回`None`
+    pub fn window(&self) -> Option<(&[u16], &[u16])> {
+        match self.state {
+            ScopeState::Done(trigger_index) => {
+                let (first, second) = Self::window_segments(
+                    self.buffer.len(),
+                    trigger_index,
+                    self.pre_trigger,
+                    self.post_trigger,
+                );
+                Some((&self.buffer[first], &self.buffer[second]))
+            },
+            _ => None,
+        }
+    }
+    
+    /// 将对齐后的采集窗口复制到输出缓冲区
+    /// 
+    /// # 返回值
+    /// 复制的采样点数，未完成采集时返回0
+    pub fn copy_window(&self, out: &mut [u16]) -> usize {
+        match self.window() {
+            Some((first, second)) => {
+                let mut count = 0;
+                for (dst, src) in out.iter_mut().zip(first.iter().chain(second.iter())) {
+                    *dst = *src;
+                    count += 1;
+                }
+                count
+            },
+            None => 0,
+        }
+    }
+    
+    /// 计算采集窗口在循环缓冲区中的两段索引范围
+    /// 
+    /// # 参数
+    /// * `len` - 缓冲区长度
+    /// * `trigger_index` - 触发点索引
+    /// * `pre_trigger` - 触发前采样点数
+    /// * `post_trigger` - 触发后（含触发点）采样点数
+    /// 
+    /// # 返回值
+    /// 两段索引范围，第二段在窗口未跨越缓冲区末尾时为空
+    pub fn window_segments(
+        len: usize,
+        trigger_index: usize,
+        pre_trigger: usize,
+        post_trigger: usize,
+    ) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+        let total = pre_trigger + post_trigger;
+        let start = (trigger_index + len - pre_trigger) % len;
+        let first_len = core::cmp::min(total, len - start);
+        (start..start + first_len, 0..total - first_len)
+    }
+    
+    /// 根据DMA剩余计数计算最近写入的采样点索引
+    pub fn last_written_index(len: usize, remaining: usize) -> usize {
+        // 下一个写入位置为 len - remaining（remaining == len 时为0）
+        (len - remaining + len - 1) % len
+    }
+    
+    /// 根据DMA剩余计数计算本轮已写入的采样点数
+    /// 
+    /// `remaining == len`时DMA尚未写入（或刚绕回），返回0
+    pub fn samples_written(len: usize, remaining: usize) -> usize {
+        len.saturating_sub(remaining)
+    }
+    
+    /// 判断相邻两个采样点是否满足触发边沿
+    pub fn is_crossing(previous: u16, current: u16, level: u16, edge: ScopeTriggerEdge) -> bool {
+        match edge {
+            ScopeTriggerEdge::Rising => previous < level && current >= level,
+            ScopeTriggerEdge::Falling => previous > level && current <= level,
+        }
+    }
+    
+    /// 计算模拟看门狗触发所用的高/低阈值
+    /// 
+    /// # 返回值
+    /// (高阈值, 低阈值)
+    pub fn watchdog_thresholds(level: u16, edge: ScopeTriggerEdge) -> (u16, u16) {
+        match edge {
+            ScopeTriggerEdge::Rising => (level, 0),
+            ScopeTriggerEdge::Falling => (0x0FFF, level),
+        }
+    }
+    
+    /// 读取DMA正在写入的缓冲区中的采样点
+    fn sample_at(&self, index: usize) -> u16 {
+        unsafe { core::ptr::read_volatile(self.buffer.as_ptr().add(index)) }
+    }
+}
+
+/// ADC通道统计结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    pub min: u16,      // 窗口内最小值
+    pub max: u16,      // 窗口内最大值
+    pub average: u16,  // 窗口内平均值
+    pub count: u32,    // 窗口内采样数
+}
+
+/// 带统计的ADC监测通道
+/// 
+/// 适用于电池电压、温度等缓慢变化的信号：在一个采样窗口内累计最小值、最大值和平均值，
+/// 窗口采满后下一个采样开始新的窗口。
+/// 
+/// 使用方法：调用`start`启动连续转换并使能EOC中断，在ADC1_2中断中调用`on_conversion_complete`。
+pub struct MonitoredChannel {
+    adc: Adc,
+    channel: AdcChannel,
+    window: u32,
+    count: u32,
+    sum: u32,
+    min: u16,
+    max: u16,
+}
+
+impl MonitoredChannel {
+    /// 创建新的监测通道
+    /// 
+    /// # 参数
+    /// * `adc` - ADC实例
+    /// * `channel` - 监测的通道
+    /// * `window` - 采样窗口长度（采样数）
+    pub const fn new(adc: Adc, channel: AdcChannel, window: u32) -> Self {
+        assert!(window > 0, "Sampling window must not be empty");
+        Self {
+            adc,
+            channel,
+            window,
+            count: 0,
+            sum: 0,
+            min: u16::MAX,
+            max: 0,
+        }
+    }
+    
+    /// 启动连续转换并使能转换结束中断
+    pub fn start(&mut self) {
+        self.reset();
+        self.adc.it_config(AdcInterrupt::EOC, true);
+        self.adc.start_continuous(self.channel);
+    }
+    
+    /// 停止连续转换并关闭转换结束中断
+    pub fn stop(&mut self) {
+        self.adc.stop_continuous();
+        self.adc.it_config(AdcInterrupt::EOC, false);
+    }
+    
+    /// 转换结束回调，应在ADC中断服务函数中调用
+    /// 
+    /// # 返回值
+    /// 本次是否读取到新的采样
+    pub fn on_conversion_complete(&mut self) -> bool {
+        if !self.adc.is_conversion_complete() {
+            return false;
+        }
+        // 读取数据寄存器同时清除EOC标志
+        let sample = self.adc.read_result();
+        self.record(sample);
+        true
+    }
+    
+    /// 记录一个采样值
+    pub fn record(&mut self, sample: u16) {
+        if self.count >= self.window {
+            self.reset();
+        }
+        
+        self.count += 1;
+        self.sum += sample as u32;
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+    }
+    
+    /// 清空当前窗口的统计
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.sum = 0;
+        self.min = u16::MAX;
+        self.max = 0;
+    }
+    
+    /// 获取当前窗口的统计结果，尚无采样时各项均为0
+    pub fn stats(&self) -> ChannelStats {
+        if self.count == 0 {
+            return ChannelStats { min: 0, max: 0, average: 0, count: 0 };
+        }
+        
+        ChannelStats {
+            min: self.min,
+            max: self.max,
+            average: (self.sum / self.count) as u16,
+            count: self.count,
+        }
+    }
+    
+    /// 检查当前窗口是否已采满
+    pub fn is_window_complete(&self) -> bool {
+        self.count >= self.window
+    }
+}
+
+/// 未校准时假定的VDDA（毫伏）
+pub const VDDA_DEFAULT_MV: u32 = 3300;
+
+/// VREFINT典型值（毫伏），数据手册范围为1.16V~1.24V
+pub const VREFINT_TYPICAL_MV: u32 = 1200;
+
+/// CR1.SCAN：扫描模式
+const CR1_SCAN: u32 = 1 << 8;
+/// CR1.DISCEN：规则通道不连续模式
+const CR1_DISCEN: u32 = 1 << 11;
+/// CR1.DISCNUM：不连续模式通道数减1
+const CR1_DISCNUM_MASK: u32 = 0b111 << 13;
+
+/// CR1.JAUTO：自动注入转换
+const CR1_JAUTO: u32 = 1 << 10;
+
+/// CR1.DUALMOD双ADC模式字段（位19:16）
+const CR1_DUALMOD_MASK: u32 = 0xF << 16;
+
+/// ADC1规则通道外部触发相关的CR2位
+/// ADC1数据寄存器地址（DMA外设地址）
+const ADC1_DR_ADDRESS: u32 = 0x4001_244C;
+
+const CR2_CONT: u32 = 1 << 1;       // 连续转换
+const CR2_DMA: u32 = 1 << 8;        // DMA请求
+const CR2_EXTSEL: u32 = 0b111 << 17; // 外部触发源选择
+const CR2_EXTTRIG: u32 = 1 << 20;   // 规则通道外部触发使能
+
+/// GPIO边沿触发的ADC缓冲采集
+/// 
+/// 触发引脚经EXTI11直接连到ADC1规则通道的外部触发（EXTSEL=110），
+/// 引脚边沿启动连续转换，DMA1通道1把结果写入缓冲区；缓冲区写满后在DMA传输完成中断中
+/// 停止转换、置位完成标志，并可选地拉高一个GPIO作为完成信号。
+/// 
+/// 注意：
+/// - 只有引脚号为11的引脚（PA11~PG11）可以作为触发引脚
+/// - 要求AFIO_MAPR.ADC1_ETRGREG_REMAP为0（复位值），否则触发源为TIM8_TRGO
+/// - 只有ADC1支持DMA
+/// 
+/// 使用方法：定义为静态变量，在DMA1_Channel1中断中调用`on_transfer_complete`。
+pub struct TriggeredCapture {
+    adc: Adc,
+    dma: Dma,
+    done_pin: Option<GpioPortStruct>,
+    done: AtomicBool,
+}
+
+impl TriggeredCapture {
+    /// 创建触发采集实例
+    /// 
+    /// # 参数
+    /// * `adc` - ADC实例，必须为ADC1
+    /// * `done_pin` - 采集完成时拉高的引脚（需预先配置为输出），不需要时为None
+    pub const fn new(adc: Adc, done_pin: Option<GpioPortStruct>) -> Self {
+        assert!(matches!(adc.number, AdcNumber::ADC1), "Only ADC1 supports DMA");
+        Self {
+            adc,
+            dma: DMA1_CHANNEL1,
+            done_pin,
+            done: AtomicBool::new(false),
+        }
+    }
+    
+    /// 配置触发采集并等待引脚边沿
+    /// 
+    /// # 参数
+    /// * `trigger_pin` - 触发引脚，上升沿启动采集，引脚号必须为11
+    /// * `channel` - 采集的ADC通道
+    /// * `buf` - 采样缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC1已初始化，AFIO和触发引脚所在端口的时钟已启用
+    /// - `buf`在采集完成（`is_done`返回true）或调用`cancel`之前不得被访问或释放
+    pub unsafe fn capture_on_trigger(&self, trigger_pin: GpioPortStruct, channel: AdcChannel, buf: &mut [u16]) {
+        let line = match Self::trigger_line(trigger_pin.pin) {
+            Some(line) => line,
+            None => panic!("ADC external trigger is only wired to EXTI line 11"),
+        };
+        assert!(!buf.is_empty() && buf.len() <= 0xFFFF, "Buffer length must fit in CNDTR");
+        
+        self.cancel();
+        self.done.store(false, Ordering::SeqCst);
+        if let Some(pin) = self.done_pin {
+            pin.set_low();
+        }
+        
+        // 配置DMA：外设到存储器，16位，存储器地址递增，单次模式，传输完成中断
+        self.dma.init(
+            DmaDirection::PeripheralToMemory,
+            DmaPeripheralIncrementMode::Disabled,
+            DmaMemoryIncrementMode::Enabled,
+            DmaPeripheralDataSize::HalfWord,
+            DmaMemoryDataSize::HalfWord,
+            DmaChannelPriority::High,
+            DmaCircularMode::Disabled,
+        );
+        self.dma.configure_transfer(0x4001244C, buf.as_mut_ptr() as u32, buf.len() as u16);
+        self.dma.clear_interrupt(DmaInterrupt::TransferComplete);
+        self.dma.enable_interrupt(DmaInterrupt::TransferComplete);
+        self.dma.enable();
+        
+        // 触发引脚连接到EXTI11，只使用边沿检测，不产生中断
+        trigger_pin.into_floating_input();
+        gpio_exti_line_config(trigger_pin.port, trigger_pin.pin);
+        Exti::new().init(line, ExtiTriggerMode::Rising, false);
+        
+        // ADC1：外部触发选择EXTI11，触发后连续转换并发出DMA请求
+        self.adc.regular_channel_config(channel, 1, AdcSampleTime::Cycles13_5);
+        let (mask, value) = Self::trigger_cr2_bits();
+        let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+        adc.cr2().modify(|r, w| w.bits((r.bits() & !mask) | value));
+    }
+    
+    /// DMA传输完成中断处理，应在DMA1_Channel1中断服务函数中调用
+    /// 
+    /// # 返回值
+    /// 本次中断是否完成了一次采集
+    pub fn on_transfer_complete(&self) -> bool {
+        unsafe {
+            if !self.dma.check_interrupt(DmaInterrupt::TransferComplete) {
+                return false;
+            }
+            self.dma.clear_interrupt(DmaInterrupt::TransferComplete);
+            self.cancel();
+        }
+        self.finish();
+        true
+    }
+    
+    /// 停止采集，关闭外部触发、连续转换和DMA
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC1和DMA1通道1未被其他代码使用
+    pub unsafe fn cancel(&self) {
+        let adc = &mut *(0x40012400 as *mut library::adc1::RegisterBlock);
+        adc.cr2().modify(|r, w| w.bits(r.bits() & !(CR2_EXTTRIG | CR2_CONT | CR2_DMA)));
+        self.dma.disable_interrupt(DmaInterrupt::TransferComplete);
+        self.dma.disable();
+    }
+    
+    /// 检查采集是否完成
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+    
+    /// 置位完成标志并输出完成信号
+    fn finish(&self) {
+        self.done.store(true, Ordering::SeqCst);
+        if let Some(pin) = self.done_pin {
+            unsafe {
+                pin.set_high();
+            }
+        }
+    }
+    
+    /// 获取触发引脚对应的EXTI线
+    /// 
+    /// ADC1/ADC2规则通道的外部触发只连接EXTI11
+    pub const fn trigger_line(pin: u8) -> Option<ExtiLine> {
+        match pin {
+            11 => Some(ExtiLine::Line11),
+            _ => None,
+        }
+    }
+    
+    /// 计算触发采集时CR2需要修改的位
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：EXTSEL=110（EXTI11），EXTTRIG、CONT、DMA置位
+    pub const fn trigger_cr2_bits() -> (u32, u32) {
+        let mask = CR2_EXTSEL | CR2_EXTTRIG | CR2_CONT | CR2_DMA;
+        let value = (AdcExternalTrig::ExtIT11TIM8TRGO as u32) | CR2_EXTTRIG | CR2_CONT | CR2_DMA;
+        (mask, value)
+    }
+}
+
+/// 实现embedded-hal单次转换特性，使用`read_single_channel`阻塞转换
+/// 
+/// ADC需已初始化并校准，转换会占用规则通道序列的第一个位置
+impl<PIN: Channel<Adc, ID = u8>> OneShot<Adc, u16, PIN> for Adc {
+    type Error = Infallible;
+    
+    fn read(&mut self, _pin: &mut PIN) -> nb::Result<u16, Infallible> {
+        let channel = AdcChannel::from_index(PIN::channel()).expect("Invalid ADC channel");
+        Ok(self.read_single_channel(channel))
+    }
+}
+
 /// 预定义的ADC常量
 pub const ADC1: Adc = Adc::new(AdcNumber::ADC1);
 pub const ADC2: Adc = Adc::new(AdcNumber::ADC2);
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试示波器窗口不跨越缓冲区末尾的情况
+    #[test]
+    fn test_scope_window_contiguous() {
+        let (first, second) = ScopeCapture::window_segments(16, 8, 4, 6);
+        assert_eq!(first, 4..14, "窗口起点应为触发点前4个采样");
+        assert!(second.is_empty(), "窗口未跨越末尾时第二段应为空");
+    }
+    
+    /// 测试示波器窗口跨越缓冲区末尾的情况
+    #[test]
+    fn test_scope_window_wrapped() {
+        // 触发点在索引2，触发前4点应从索引14开始
+        let (first, second) = ScopeCapture::window_segments(16, 2, 4, 6);
+        assert_eq!(first, 14..16, "第一段应为缓冲区末尾的2个采样");
+        assert_eq!(second, 0..8, "第二段应从缓冲区开头继续");
+        
+        // 拼接后触发点应位于窗口第pre_trigger个位置
+        let buffer: [u16; 16] = core::array::from_fn(|i| i as u16);
+        let window: heapless::Vec<u16, 16> = buffer[first].iter().chain(buffer[second].iter()).copied().collect();
+        assert_eq!(window.len(), 10, "窗口长度应为pre+post");
+        assert_eq!(window[4], 2, "触发点应位于窗口第pre_trigger个位置");
+        assert_eq!(window[0], 14, "窗口首个采样应为触发前第4个点");
+    }
+    
+    /// 测试窗口占满整个缓冲区的情况
+    #[test]
+    fn test_scope_window_full_buffer() {
+        let (first, second) = ScopeCapture::window_segments(8, 5, 3, 5);
+        assert_eq!(first, 2..8, "第一段应从触发前第3个点开始");
+        assert_eq!(second, 0..2, "第二段应补足剩余采样");
+    }
+    
+    /// 测试DMA剩余计数到写入索引的换算
+    #[test]
+    fn test_scope_last_written_index() {
+        assert_eq!(ScopeCapture::last_written_index(16, 16), 15, "刚绕回时最近写入的是末尾");
+        assert_eq!(ScopeCapture::last_written_index(16, 13), 2, "已写入3个点时最近写入索引应为2");
+        assert_eq!(ScopeCapture::last_written_index(16, 1), 14, "剩余1个时最近写入索引应为14");
+        
+        assert_eq!(ScopeCapture::samples_written(16, 16), 0, "DMA尚未写入时已写入点数应为0");
+        assert!(ScopeCapture::samples_written(16, 16) < 4, "尚未写入时不应认为触发前数据已填满");
+        assert_eq!(ScopeCapture::samples_written(16, 12), 4, "剩余12个时应已写入4个点");
+        assert_eq!(ScopeCapture::samples_written(16, 0), 16, "计数为0时应已写满");
+    }
+    
+    /// 测试过流保护的看门狗阈值与刹车配置
+    #[test]
+    fn test_overcurrent_protection_config() {
+        let (mode, high, low) = Adc::overcurrent_watchdog_config(3000);
+        assert_eq!(mode & 0x0040_0000, 0x0040_0000, "应启用注入通道看门狗JAWDEN");
+        assert_eq!(mode & 0x0000_0200, 0x0000_0200, "应启用单通道看门狗AWDSGL");
+        assert_eq!(mode & 0x0080_0000, 0, "不应启用规则通道看门狗AWDEN");
+        assert_eq!((high, low), (3000, 0), "只在超过上阈值时触发");
+        
+        let (_, high, _) = Adc::overcurrent_watchdog_config(0xFFFF);
+        assert_eq!(high, 0x0FFF, "阈值应限制在12位");
+        
+        assert_eq!(crate::bsp::timer::EGR_BG, 0x80, "刹车应通过EGR.BG产生");
+    }
+    
+    /// 测试软件触发边沿判断
+    #[test]
+    fn test_scope_crossing() {
+        assert!(ScopeCapture::is_crossing(1000, 2048, 2048, ScopeTriggerEdge::Rising), "上升穿越应触发");
+        assert!(!ScopeCapture::is_crossing(2048, 3000, 2048, ScopeTriggerEdge::Rising), "已在电平之上不应触发");
+        assert!(ScopeCapture::is_crossing(3000, 2000, 2048, ScopeTriggerEdge::Falling), "下降穿越应触发");
+        assert!(!ScopeCapture::is_crossing(1000, 2000, 2048, ScopeTriggerEdge::Falling), "上升时不应触发下降沿");
+    }
+    
+    /// 测试监测通道的最小/最大/平均值统计
+    #[test]
+    fn test_monitored_channel_stats() {
+        let mut monitor = MonitoredChannel::new(ADC1, AdcChannel::Channel0, 4);
+        assert_eq!(monitor.stats().count, 0, "未采样时计数应为0");
+        
+        for sample in [1000, 1200, 800, 1000] {
+            monitor.record(sample);
+        }
+        let stats = monitor.stats();
+        assert_eq!(stats, ChannelStats { min: 800, max: 1200, average: 1000, count: 4 }, "窗口统计错误");
+        assert!(monitor.is_window_complete(), "采满4个点后窗口应完成");
+        
+        // 窗口采满后下一个采样开始新窗口
+        monitor.record(4095);
+        let stats = monitor.stats();
+        assert_eq!(stats, ChannelStats { min: 4095, max: 4095, average: 4095, count: 1 }, "新窗口应只包含最新采样");
+    }
+    
+    /// 测试EXTI到ADC外部触发的连接配置
+    #[test]
+    fn test_trigger_capture_wiring() {
+        assert_eq!(TriggeredCapture::trigger_line(11), Some(ExtiLine::Line11), "引脚11应连接到EXTI11");
+        assert_eq!(TriggeredCapture::trigger_line(0), None, "其他引脚不能触发ADC");
+        
+        let (mask, value) = TriggeredCapture::trigger_cr2_bits();
+        assert_eq!(value & CR2_EXTSEL, 0b110 << 17, "EXTSEL应选择EXTI11");
+        assert_eq!(value & (CR2_EXTTRIG | CR2_CONT | CR2_DMA), CR2_EXTTRIG | CR2_CONT | CR2_DMA, "应使能外部触发、连续转换和DMA");
+        assert_eq!(value & !mask, 0, "不应修改掩码以外的位");
+        assert_eq!(mask & 0x0000_0001, 0, "不应修改ADON位");
+    }
+    
+    /// 测试采集完成标志
+    #[test]
+    fn test_trigger_capture_done_flag() {
+        let capture = TriggeredCapture::new(ADC1, None);
+        assert!(!capture.is_done(), "新建时不应处于完成状态");
+        
+        capture.finish();
+        assert!(capture.is_done(), "传输完成后应置位完成标志");
+    }
+    
+    /// 测试轮流读取时结果顺序与通道顺序一致
+    #[test]
+    fn test_read_round_robin_order() {
+        let channels = [AdcChannel::Channel3, AdcChannel::Channel0, AdcChannel::Channel7];
+        let mut out = [0u16; 3];
+        let mut converted: heapless::Vec<u8, 4> = heapless::Vec::new();
+        
+        // 模拟DR依次返回100、101、102
+        let mut dr = 100;
+        let count = Adc::round_robin(&channels, &mut out, |channel| {
+            converted.push(channel as u8).unwrap();
+            dr += 1;
+            dr - 1
+        });
+        
+        assert_eq!(count, 3, "应读取全部通道");
+        assert_eq!(out, [100, 101, 102], "结果应按通道顺序存放");
+        assert_eq!(&converted[..], &[3, 0, 7], "应按给定顺序转换通道");
+        
+        // 输出缓冲区较短时只读取前几个通道
+        let mut short = [0u16; 2];
+        assert_eq!(Adc::round_robin(&channels, &mut short, |_| 1), 2, "应以较短的长度为准");
+    }
+    
+    /// 测试自动注入转换的JAUTO位和JSQR配置
+    #[test]
+    fn test_auto_injected_config() {
+        let (jauto, jsqr) = Adc::auto_injected_config(&[AdcChannel::Channel5]);
+        assert_eq!(jauto, 0x0000_0400, "应设置CR1.JAUTO");
+        assert_eq!(jsqr, 5 << 15, "单个通道应放在JSQ4，JL=0");
+        
+        let (_, jsqr) = Adc::auto_injected_config(&[AdcChannel::Channel1, AdcChannel::Channel2, AdcChannel::Channel3]);
+        assert_eq!(jsqr, (2 << 20) | (1 << 5) | (2 << 10) | (3 << 15), "三个通道应依次放在JSQ2~JSQ4，JL=2");
+        
+        let (_, jsqr) = Adc::auto_injected_config(&[AdcChannel::Channel17, AdcChannel::Channel0, AdcChannel::Channel9, AdcChannel::Channel4]);
+        assert_eq!(jsqr, (3 << 20) | 17 | (0 << 5) | (9 << 10) | (4 << 15), "四个通道应依次放在JSQ1~JSQ4，JL=3");
+    }
+    
+    /// 测试毫伏换算使用缓存的VDDA且重新校准会更新缓存
+    #[test]
+    fn test_cached_vdda_conversion() {
+        let mut adc = Adc::new(AdcNumber::ADC1);
+        assert_eq!(adc.vref_age_ms(), None, "未校准时不应有校准时间");
+        assert_eq!(adc.to_millivolts(4095), VDDA_DEFAULT_MV, "未校准时应使用默认VDDA");
+        
+        // VDDA = 3.3V时VREFINT读数约为1489
+        adc.record_vref(1489, 0);
+        assert_eq!(adc.vdda_mv(), 3300, "VDDA计算错误");
+        assert_eq!(adc.to_millivolts(2048), 1650, "应使用缓存的VDDA换算");
+        assert!(adc.vref_age_ms().is_some(), "校准后应记录校准时间");
+        
+        // 电池电压下降到3.0V后重新校准
+        adc.record_vref(1638, 0);
+        assert_eq!(adc.vdda_mv(), 3000, "重新校准应更新缓存的VDDA");
+        assert_eq!(adc.to_millivolts(4095), 3000, "换算应使用更新后的VDDA");
+        
+        assert_eq!(Adc::vdda_from_vrefint(0), VDDA_DEFAULT_MV, "读数为0时应返回默认值");
+    }
+    
+    /// 测试不连续模式的DISCNUM/DISCEN配置和规则序列
+    #[test]
+    fn test_discontinuous_config() {
+        let channels = [
+            AdcChannel::Channel0, AdcChannel::Channel1, AdcChannel::Channel2,
+            AdcChannel::Channel3, AdcChannel::Channel4, AdcChannel::Channel5,
+            AdcChannel::Channel6, AdcChannel::Channel7,
+        ];
+        let (cr1, sqr) = Adc::discontinuous_config(&channels, 3);
+        
+        assert_eq!(cr1 & CR1_DISCNUM_MASK, 2 << 13, "每组3个通道时DISCNUM应为2");
+        assert_ne!(cr1 & CR1_DISCEN, 0, "应启用DISCEN");
+        assert_ne!(cr1 & CR1_SCAN, 0, "应启用扫描模式");
+        
+        assert_eq!(sqr[0], 7 << 20, "SQR1中序列长度L应为7");
+        assert_eq!(sqr[1], 6 | (7 << 5), "第7、8个通道应在SQR2");
+        assert_eq!(sqr[2], (1 << 5) | (2 << 10) | (3 << 15) | (4 << 20) | (5 << 25), "前6个通道应在SQR3");
+        
+        // 16个通道时最后4个在SQR1
+        let channels = [AdcChannel::Channel9; 16];
+        let (cr1, sqr) = Adc::discontinuous_config(&channels, 8);
+        assert_eq!(cr1 & CR1_DISCNUM_MASK, 7 << 13, "每组8个通道时DISCNUM应为7");
+        assert_eq!(sqr[0], (15 << 20) | 9 | (9 << 5) | (9 << 10) | (9 << 15), "SQR1应包含L和第13~16个通道");
+    }
+    
+    /// 测试扫描DMA转换的参数检查和规则序列
+    #[test]
+    fn test_scan_dma_sequence() {
+        assert_eq!(Adc::check_scan_dma(AdcNumber::ADC1, 3, 3), Ok(()), "长度一致时应通过检查");
+        assert_eq!(Adc::check_scan_dma(AdcNumber::ADC1, 3, 4), Err(AdcError::LengthMismatch), "缓冲区长度不一致应报错");
+        assert_eq!(Adc::check_scan_dma(AdcNumber::ADC1, 17, 17), Err(AdcError::InvalidSequenceLength), "超过16个通道应报错");
+        assert_eq!(Adc::check_scan_dma(AdcNumber::ADC1, 0, 0), Err(AdcError::InvalidSequenceLength), "空序列应报错");
+        assert_eq!(Adc::check_scan_dma(AdcNumber::ADC2, 2, 2), Err(AdcError::DmaUnavailable), "ADC2没有DMA应报错");
+        
+        let sqr = Adc::regular_sequence(&[AdcChannel::Channel3, AdcChannel::Channel0, AdcChannel::Channel16]);
+        assert_eq!(sqr, [2 << 20, 0, 3 | (16 << 10)], "3个通道应只占用SQR3，L=2");
+        
+        let channels = [AdcChannel::Channel1; 7];
+        let sqr = Adc::regular_sequence(&channels);
+        assert_eq!(sqr[1], 1, "第7个通道应位于SQR2");
+        assert_eq!(sqr[0], 6 << 20, "序列长度L应为6");
+    }
+    
+    /// 测试温度传感器读数换算
+    #[test]
+    fn test_temperature_from_raw() {
+        // VREFINT读数1200时1个LSB为1mV
+        let t = Adc::temperature_from_raw(1430, 1200);
+        assert!((t - 25.0).abs() < 0.01, "Vsense等于V25时应为25°C");
+        let t = Adc::temperature_from_raw(1387, 1200);
+        assert!((t - 35.0).abs() < 0.01, "Vsense低43mV时应为35°C");
+        
+        // VDDA变化时两路读数按比例变化，温度不变
+        let t = Adc::temperature_from_raw(715, 600);
+        assert!((t - 25.0).abs() < 0.01, "VREFINT应补偿VDDA的变化");
+    }
+    
+    /// 测试多次采样平均值的舍入和采样次数
+    #[test]
+    fn test_read_averaged() {
+        let mut readings = [100u16, 101, 101, 103].iter().cycle().copied();
+        assert_eq!(Adc::averaged(4, || readings.next().unwrap()), 101, "101.25应舍入为101");
+        
+        let mut readings = [10u16, 11].iter().cycle().copied();
+        assert_eq!(Adc::averaged(2, || readings.next().unwrap()), 11, "10.5应向上舍入为11");
+        
+        let mut calls = 0;
+        assert_eq!(Adc::averaged(0, || { calls += 1; 2000 }), 2000, "采样次数为0时应读取一次");
+        assert_eq!(calls, 1, "采样次数为0时应只转换一次");
+        
+        assert_eq!(Adc::averaged(u16::MAX, || 4095), 4095, "满量程累加不应溢出");
+    }
+    
+    /// 测试双ADC同时转换的模式配置和结果拆分
+    #[test]
+    fn test_dual_simultaneous_result() {
+        assert_eq!(Adc::split_dual_result(0x0ABC_0123), (0x0123, 0x0ABC), "低16位为ADC1，高16位为ADC2");
+        assert_eq!(Adc::split_dual_result(0x0FFF_0000), (0, 0x0FFF), "ADC2满量程时ADC1为0");
+        
+        // 保留SCAN等其他位，只替换DUALMOD
+        let cr1 = 0x0001_0100;
+        assert_eq!(Adc::dual_mode_cr1(cr1, AdcMode::RegSimult), 0x0006_0100, "应设置规则同时模式");
+        assert_eq!(Adc::dual_mode_cr1(0x0006_0100, AdcMode::Independent), 0x0000_0100, "应能恢复独立模式");
+    }
+    
+    /// 测试由采样时间和ADC时钟计算转换时间
+    #[test]
+    fn test_conversion_time() {
+        // 12MHz下最短转换为1.5 + 12.5 = 14个周期
+        assert_eq!(Adc::conversion_time_ns(AdcSampleTime::Cycles1_5, 12_000_000), 1166, "1.5周期采样转换时间错误");
+        assert_eq!(Adc::conversion_time_ns(AdcSampleTime::Cycles239_5, 12_000_000), 21_000, "239.5周期采样应为21us");
+        // 14MHz下最短转换为1us
+        assert_eq!(Adc::conversion_time_ns(AdcSampleTime::Cycles1_5, 14_000_000), 1000, "14MHz下最短转换应为1us");
+    }
+    
+    /// 测试引脚到ADC通道的映射和embedded-hal特性
+    #[test]
+    fn test_pin_channel_mapping() {
+        assert_eq!(AdcChannel::for_pin(GpioPort::A, 0).map(|c| c as u8), Some(0), "PA0应为通道0");
+        assert_eq!(AdcChannel::for_pin(GpioPort::B, 1).map(|c| c as u8), Some(9), "PB1应为通道9");
+        assert_eq!(AdcChannel::for_pin(GpioPort::C, 5).map(|c| c as u8), Some(15), "PC5应为通道15");
+        assert!(AdcChannel::for_pin(GpioPort::A, 8).is_none(), "PA8不是模拟输入");
+        assert!(AdcChannel::for_pin(GpioPort::B, 2).is_none(), "PB2不是模拟输入");
+        assert!(AdcChannel::from_index(18).is_none(), "通道号超出范围");
+        
+        let pa1 = unsafe { Pin::<Gpioa, Analog>::new(Gpioa::steal(), 1) };
+        let pa1 = AdcPin::<Gpioa, 2>::new(pa1).err().expect("PA1不能作为通道2");
+        let pa1 = AdcPin::<Gpioa, 1>::new(pa1).ok().expect("PA1应为通道1");
+        assert_eq!(<AdcPin<Gpioa, 1> as Channel<Adc>>::channel(), 1, "通道号应来自类型参数");
+        assert_eq!(pa1.release().pin_number(), 1, "释放后应得到原引脚");
+        
+        fn assert_one_shot<T: OneShot<Adc, u16, AdcPin<Gpiob, 8>>>() {}
+        assert_one_shot::<Adc>();
+    }
+}
@@ -0,0 +1,279 @@
+//! 电池电压监测模块
+//! 提供基于分压电阻和VREFINT补偿的电池电压测量，以及带回差的低电量检测
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::adc::{Adc, AdcChannel, AdcFlag, AdcInterrupt};
+use crate::bsp::gpio::GpioPortStruct;
+
+/// 模拟看门狗配置：AWDEN（位23）+ AWDSGL（位9），只监控规则通道上的单个通道
+const AWD_REGULAR_SINGLE: u32 = 0x0080_0200;
+
+/// 12位ADC满量程
+const ADC_FULL_SCALE: u32 = 4095;
+
+/// 电池状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatteryState {
+    Normal, // 电压正常
+    Low,    // 低电量
+}
+
+/// 电池电压监测器
+/// 
+/// 电池经分压电阻`r_top`/`r_bottom`接到ADC通道，电压低于`low_mv`时进入低电量状态，
+/// 回升到`recover_mv`以上才恢复正常，避免负载变化引起的反复切换。
+/// 状态变化时点亮/熄灭指示灯（高电平点亮），并调用回调函数。
+/// 
+/// 可以周期调用`sample`轮询，也可以调用`enable_watchdog`用模拟看门狗监测阈值：
+/// 看门狗只在该通道被转换时比较，需让ADC持续转换该通道（如`start_continuous`），
+/// 并在ADC1_2中断中调用`handle_interrupt`。
+pub struct BatteryMonitor {
+    adc: Adc,
+    channel: AdcChannel,
+    r_top: u32,
+    r_bottom: u32,
+    low_mv: u32,
+    recover_mv: u32,
+    state: BatteryState,
+    voltage_mv: u32,
+    indicator: Option<GpioPortStruct>,
+    callback: Option<fn(BatteryState, u32)>,
+}
+
+impl BatteryMonitor {
+    /// 创建电池电压监测器
+    /// 
+    /// # 参数
+    /// * `adc` - ADC实例，必须为ADC1（VREFINT只连接到ADC1），需已初始化并校准
+    /// * `channel` - 分压点所接的ADC通道
+    /// * `r_top` - 电池正极到分压点的电阻（欧姆）
+    /// * `r_bottom` - 分压点到地的电阻（欧姆）
+    /// * `low_mv` - 低电量阈值（电池电压，毫伏）
+    /// * `recover_mv` - 恢复阈值（电池电压，毫伏），必须大于`low_mv`
+    pub const fn new(adc: Adc, channel: AdcChannel, r_top: u32, r_bottom: u32, low_mv: u32, recover_mv: u32) -> Self {
+        assert!(r_bottom != 0, "Divider bottom resistor must not be zero");
+        assert!(recover_mv > low_mv, "Recovery threshold must be above the low threshold");
+        Self {
+            adc,
+            channel,
+            r_top,
+            r_bottom,
+            low_mv,
+            recover_mv,
+            state: BatteryState::Normal,
+            voltage_mv: 0,
+            indicator: None,
+            callback: None,
+        }
+    }
+    
+    /// 设置低电量指示灯，引脚需预先配置为推挽输出
+    pub fn set_indicator(&mut self, pin: GpioPortStruct) {
+        self.indicator = Some(pin);
+    }
+    
+    /// 设置状态变化回调，参数为新状态和当前电池电压（毫伏）
+    /// 
+    /// 从`handle_interrupt`调用时回调运行在中断上下文中
+    pub fn set_callback(&mut self, callback: fn(BatteryState, u32)) {
+        self.callback = Some(callback);
+    }
+    
+    /// 采样一次电池电压并更新状态
+    /// 
+    /// 每次先采样VREFINT补偿VDDA，再转换分压通道；会占用规则通道序列
+    /// 
+    /// # 返回值
+    /// 电池电压（毫伏）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC未被其他代码同时使用
+    pub unsafe fn sample(&mut self) -> u32 {
+        self.adc.calibrate_vref();
+        let raw = self.adc.read_single_channel(self.channel);
+        self.process(raw);
+        self.voltage_mv
+    }
+    
+    /// 启用模拟看门狗监测当前状态对应的阈值
+    /// 
+    /// 正常状态下电压低于低电量阈值时触发，低电量状态下电压高于恢复阈值时触发。
+    /// 阈值按调用时缓存的VDDA换算，应在`sample`之后调用。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC未被其他代码同时使用，且已在NVIC中启用ADC1_2中断
+    pub unsafe fn enable_watchdog(&self) {
+        self.program_watchdog();
+        self.adc.analog_watchdog_single_channel_config(self.channel);
+        self.adc.analog_watchdog_cmd(AWD_REGULAR_SINGLE);
+        self.adc.clear_flag(AdcFlag::AWD);
+        self.adc.it_config(AdcInterrupt::AWD, true);
+    }
+    
+    /// 关闭模拟看门狗监测
+    pub fn disable_watchdog(&self) {
+        self.adc.it_config(AdcInterrupt::AWD, false);
+        self.adc.analog_watchdog_cmd(0);
+    }
+    
+    /// 处理模拟看门狗中断，应在ADC1_2中断服务函数中调用
+    /// 
+    /// 读取最近一次转换结果更新状态，并切换看门狗阈值到新状态
+    /// 
+    /// # 返回值
+    /// 看门狗触发时返回true
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`enable_watchdog`
+    pub unsafe fn handle_interrupt(&mut self) -> bool {
+        if !self.adc.get_flag_status(AdcFlag::AWD) {
+            return false;
+        }
+        
+        let raw = self.adc.read_result();
+        self.process(raw);
+        self.program_watchdog();
+        self.adc.clear_flag(AdcFlag::AWD);
+        true
+    }
+    
+    /// 获取当前状态
+    pub fn state(&self) -> BatteryState {
+        self.state
+    }
+    
+    /// 获取最近一次测得的电池电压（毫伏）
+    pub fn voltage_mv(&self) -> u32 {
+        self.voltage_mv
+    }
+    
+    /// 换算原始转换值并更新状态
+    unsafe fn process(&mut self, raw: u16) {
+        let voltage_mv = Self::battery_mv(self.adc.to_millivolts(raw), self.r_top, self.r_bottom);
+        if let Some(state) = self.update(voltage_mv) {
+            if let Some(pin) = self.indicator {
+                match state {
+                    BatteryState::Low => pin.set_high(),
+                    BatteryState::Normal => pin.set_low(),
+                }
+            }
+            if let Some(callback) = self.callback {
+                callback(state, voltage_mv);
+            }
+        }
+    }
+    
+    /// 记录电压并推进状态机
+    /// 
+    /// # 返回值
+    /// 状态发生变化时返回新状态
+    fn update(&mut self, voltage_mv: u32) -> Option<BatteryState> {
+        self.voltage_mv = voltage_mv;
+        let next = Self::next_state(self.state, voltage_mv, self.low_mv, self.recover_mv);
+        if next == self.state {
+            return None;
+        }
+        self.state = next;
+        Some(next)
+    }
+    
+    /// 按当前状态设置看门狗阈值
+    unsafe fn program_watchdog(&self) {
+        let (high, low) = Self::watchdog_thresholds(
+            self.state,
+            self.low_mv,
+            self.recover_mv,
+            self.r_top,
+            self.r_bottom,
+            self.adc.vdda_mv(),
+        );
+        self.adc.analog_watchdog_thresholds_config(high, low);
+    }
+    
+    /// 由分压点电压计算电池电压
+    /// 
+    /// V_bat = V_pin × (r_top + r_bottom) / r_bottom
+    pub const fn battery_mv(pin_mv: u32, r_top: u32, r_bottom: u32) -> u32 {
+        (pin_mv as u64 * (r_top as u64 + r_bottom as u64) / r_bottom as u64) as u32
+    }
+    
+    /// 计算电池电压对应的ADC原始值，超过满量程时取4095
+    pub const fn battery_raw(battery_mv: u32, r_top: u32, r_bottom: u32, vdda_mv: u32) -> u16 {
+        let pin_mv = battery_mv as u64 * r_bottom as u64 / (r_top as u64 + r_bottom as u64);
+        let raw = pin_mv * ADC_FULL_SCALE as u64 / vdda_mv as u64;
+        if raw > ADC_FULL_SCALE as u64 { ADC_FULL_SCALE as u16 } else { raw as u16 }
+    }
+    
+    /// 带回差的状态转移
+    /// 
+    /// 正常状态下电压低于`low_mv`进入低电量，低电量状态下电压高于`recover_mv`恢复正常
+    pub const fn next_state(state: BatteryState, voltage_mv: u32, low_mv: u32, recover_mv: u32) -> BatteryState {
+        match state {
+            BatteryState::Normal if voltage_mv < low_mv => BatteryState::Low,
+            BatteryState::Low if voltage_mv > recover_mv => BatteryState::Normal,
+            _ => state,
+        }
+    }
+    
+    /// 计算当前状态下的看门狗阈值
+    /// 
+    /// # 返回值
+    /// (高阈值, 低阈值)，转换值超出该窗口时触发看门狗
+    pub const fn watchdog_thresholds(
+        state: BatteryState,
+        low_mv: u32,
+        recover_mv: u32,
+        r_top: u32,
+        r_bottom: u32,
+        vdda_mv: u32,
+    ) -> (u16, u16) {
+        match state {
+            BatteryState::Normal => (ADC_FULL_SCALE as u16, Self::battery_raw(low_mv, r_top, r_bottom, vdda_mv)),
+            BatteryState::Low => (Self::battery_raw(recover_mv, r_top, r_bottom, vdda_mv), 0),
+        }
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsp::adc::ADC1;
+    
+    /// 测试由分压比计算电池电压
+    #[test]
+    fn test_battery_voltage_from_divider() {
+        // 100k/100k分压，分压点1850mV对应电池3700mV
+        assert_eq!(BatteryMonitor::battery_mv(1850, 100_000, 100_000), 3700, "1:1分压应为2倍");
+        // 300k/100k分压，分压点3000mV对应电池12000mV
+        assert_eq!(BatteryMonitor::battery_mv(3000, 300_000, 100_000), 12000, "3:1分压应为4倍");
+        assert_eq!(BatteryMonitor::battery_mv(1650, 0, 10_000), 1650, "无上拉电阻时应为原值");
+        
+        // VDDA为3300mV时，3300mV电池经1:1分压约为满量程的一半
+        assert_eq!(BatteryMonitor::battery_raw(3300, 100_000, 100_000, 3300), 2047, "原始值换算错误");
+        assert_eq!(BatteryMonitor::battery_raw(9000, 100_000, 100_000, 3300), 4095, "超出量程应取4095");
+        
+        let (high, low) = BatteryMonitor::watchdog_thresholds(BatteryState::Normal, 3300, 3600, 100_000, 100_000, 3300);
+        assert_eq!((high, low), (4095, 2047), "正常状态应监测低电量阈值");
+        let (high, low) = BatteryMonitor::watchdog_thresholds(BatteryState::Low, 3300, 3600, 100_000, 100_000, 3300);
+        assert_eq!((high, low), (2233, 0), "低电量状态应监测恢复阈值");
+    }
+    
+    /// 测试带回差的低电量状态机
+    #[test]
+    fn test_low_battery_hysteresis() {
+        let mut monitor = BatteryMonitor::new(ADC1, AdcChannel::Channel1, 100_000, 100_000, 3300, 3500);
+        assert_eq!(monitor.state(), BatteryState::Normal, "初始应为正常状态");
+        
+        assert_eq!(monitor.update(3400), None, "高于低电量阈值不应变化");
+        assert_eq!(monitor.update(3299), Some(BatteryState::Low), "低于阈值应进入低电量");
+        assert_eq!(monitor.update(3200), None, "持续低电量不应重复报告");
+        assert_eq!(monitor.update(3400), None, "回差区间内应保持低电量");
+        assert_eq!(monitor.update(3500), None, "等于恢复阈值时应保持低电量");
+        assert_eq!(monitor.update(3501), Some(BatteryState::Normal), "高于恢复阈值应恢复正常");
+        assert_eq!(monitor.update(3400), None, "回差区间内应保持正常");
+        assert_eq!(monitor.voltage_mv(), 3400, "应记录最近一次电压");
+    }
+}
@@ -1,21 +1,78 @@
 ﻿//! BKP模块
 //! 提供备份寄存器功能封装
+//! 
+//! 备份数据寄存器位于备份域，由VBAT供电，系统复位和从待机模式唤醒后内容保持，
+//! 只有VDD和VBAT都掉电或备份域复位时才会清除
 
 #![allow(unused)]
 
 // 导入内部生成的设备驱动库
 use library::*;
+use crate::bsp::rcc::RccDriver;
+use crate::bsp::flash::{read_flash_size_kb, FlashDensity};
+
+/// BKP寄存器基地址
+const BKP_BASE: u32 = 0x4000_6C00;
+
+/// 默认备份数据寄存器数量（小容量和中容量器件，DR1~DR10），`Bkp::new`使用此数量
+pub const BKP_DATA_REGISTERS: u8 = 10;
+
+/// BKP_CR.TPE：侵入检测引脚使能
+const CR_TPE: u32 = 1 << 0;
+/// BKP_CR.TPAL：侵入检测引脚低电平有效
+const CR_TPAL: u32 = 1 << 1;
+/// BKP_CSR.CTE：清除侵入事件
+const CSR_CTE: u32 = 1 << 0;
+/// BKP_CSR.CTI：清除侵入中断
+const CSR_CTI: u32 = 1 << 1;
+/// BKP_CSR.TPIE：侵入中断使能
+const CSR_TPIE: u32 = 1 << 2;
+/// BKP_CSR.TEF：侵入事件标志
+const CSR_TEF: u32 = 1 << 8;
+/// BKP_CSR.TIF：侵入中断标志
+const CSR_TIF: u32 = 1 << 9;
+
+/// 备份数据寄存器访问错误
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BkpError {
+    InvalidRegister, // 寄存器编号超出当前器件的范围
+}
 
 /// BKP结构体
-pub struct Bkp;
+/// 
+/// 记录器件容量类别，决定可用的备份数据寄存器数量
+pub struct Bkp {
+    density: FlashDensity,
+}
 
 impl Bkp {
-    /// 创建新的BKP实例
+    /// 创建新的BKP实例，按中容量器件（DR1~DR10）配置
     pub const fn new() -> Self {
-        Self
+        Self { density: FlashDensity::Medium }
+    }
+    
+    /// 按容量类别创建BKP实例，与`FlashDriver::density`一致
+    pub const fn with_density(density: FlashDensity) -> Self {
+        Self { density }
+    }
+    
+    /// 读取F_SIZE寄存器，按实际器件容量创建BKP实例
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn detect() -> Self {
+        Self::with_density(FlashDensity::from_size_kb(read_flash_size_kb()))
+    }
+    
+    /// 当前器件的备份数据寄存器数量
+    pub const fn data_registers(&self) -> u8 {
+        self.density.backup_data_registers()
     }
     
     /// 初始化BKP
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn init(&self) {
         let rcc = &mut *(0x40021000 as *mut library::rcc::RegisterBlock);
         let pwr = &mut *(0x40007000 as *mut library::pwr::RegisterBlock);
@@ -33,52 +90,81 @@ impl Bkp {
     }
     
     /// 写入备份数据寄存器
-    pub unsafe fn write_data_register(&self, register: u8, value: u16) {
-        // 检查参数范围
-        assert!(register >= 1 && register <= 10, "Register must be between 1 and 10");
-        
-        let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
-        
-        // 根据寄存器编号选择对应的寄存器写入
-        match register {
-            1 => bkp.dr1().write(|w: &mut library::bkp::dr1::W| w.d1().bits(value)),
-            2 => bkp.dr2().write(|w: &mut library::bkp::dr2::W| w.d2().bits(value)),
-            3 => bkp.dr3().write(|w: &mut library::bkp::dr3::W| w.d3().bits(value)),
-            4 => bkp.dr4().write(|w: &mut library::bkp::dr4::W| w.d4().bits(value)),
-            5 => bkp.dr5().write(|w: &mut library::bkp::dr5::W| w.d5().bits(value)),
-            6 => bkp.dr6().write(|w: &mut library::bkp::dr6::W| w.d6().bits(value)),
-            7 => bkp.dr7().write(|w: &mut library::bkp::dr7::W| w.d7().bits(value)),
-            8 => bkp.dr8().write(|w: &mut library::bkp::dr8::W| w.d8().bits(value)),
-            9 => bkp.dr9().write(|w: &mut library::bkp::dr9::W| w.d9().bits(value)),
-            10 => bkp.dr10().write(|w: &mut library::bkp::dr10::W| w.d10().bits(value)),
-            _ => unreachable!(),
-        };
+    /// 
+    /// 自动启用PWR/BKP时钟并解除备份域写保护（PWR_CR.DBP）
+    /// 
+    /// # 参数
+    /// * `register` - 寄存器编号（1~`data_registers()`）
+    /// * `value` - 写入的16位值
+    /// 
+    /// # 返回值
+    /// 编号超出范围时返回`InvalidRegister`，不访问任何寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
+    pub unsafe fn write(&self, register: u8, value: u16) -> Result<(), BkpError> {
+        let offset = Self::data_register_offset(register, self.density).ok_or(BkpError::InvalidRegister)?;
+        self.init();
+        core::ptr::write_volatile((BKP_BASE + offset) as *mut u32, value as u32);
+        Ok(())
     }
     
     /// 读取备份数据寄存器
-    pub unsafe fn read_data_register(&self, register: u8) -> u16 {
-        // 检查参数范围
-        assert!(register >= 1 && register <= 10, "Register must be between 1 and 10");
-        
-        let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
-        
-        // 根据寄存器编号选择对应的寄存器读取
-        match register {
-            1 => bkp.dr1().read().d1().bits(),
-            2 => bkp.dr2().read().d2().bits(),
-            3 => bkp.dr3().read().d3().bits(),
-            4 => bkp.dr4().read().d4().bits(),
-            5 => bkp.dr5().read().d5().bits(),
-            6 => bkp.dr6().read().d6().bits(),
-            7 => bkp.dr7().read().d7().bits(),
-            8 => bkp.dr8().read().d8().bits(),
-            9 => bkp.dr9().read().d9().bits(),
-            10 => bkp.dr10().read().d10().bits(),
-            _ => unreachable!(),
+    /// 
+    /// # 参数
+    /// * `register` - 寄存器编号（1~`data_registers()`）
+    /// 
+    /// # 返回值
+    /// 编号超出范围时返回`InvalidRegister`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
+    pub unsafe fn read(&self, register: u8) -> Result<u16, BkpError> {
+        let offset = Self::data_register_offset(register, self.density).ok_or(BkpError::InvalidRegister)?;
+        self.init();
+        Ok(core::ptr::read_volatile((BKP_BASE + offset) as *const u32) as u16)
+    }
+    
+    /// 计算备份数据寄存器的偏移
+    /// 
+    /// DR1~DR10位于0x04~0x28，DR11~DR42位于0x40~0xBC
+    /// 
+    /// # 参数
+    /// * `register` - 寄存器编号
+    /// * `density` - 器件容量类别，决定寄存器数量
+    /// 
+    /// # 返回值
+    /// 编号超出1~`density.backup_data_registers()`时返回None
+    pub const fn data_register_offset(register: u8, density: FlashDensity) -> Option<u32> {
+        if register == 0 || register > density.backup_data_registers() {
+            None
+        } else if register <= 10 {
+            Some(4 * register as u32)
+        } else {
+            Some(0x40 + 4 * (register as u32 - 11))
         }
     }
     
+    /// 写入备份数据寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
+    pub unsafe fn write_data_register(&self, register: u8, value: u16) -> Result<(), BkpError> {
+        self.write(register, value)
+    }
+    
+    /// 读取备份数据寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
+    pub unsafe fn read_data_register(&self, register: u8) -> Result<u16, BkpError> {
+        self.read(register)
+    }
+    
     /// 设置RTC校准值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn set_rtc_calibration(&self, calibration: u8) {
         // 检查参数范围
         assert!(calibration <= 0x7F, "Calibration value must be between 0 and 127");
@@ -90,37 +176,116 @@ impl Bkp {
     }
     
     /// 获取RTC校准值
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_rtc_calibration(&self) -> u8 {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.rtccr().read().cal().bits()
     }
     
     /// 启用RTC输出
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn enable_rtc_output(&self) {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.cr().write(|w: &mut library::bkp::cr::W| w.bits(1 << 7)); // 启用RTC输出
     }
     
     /// 禁用RTC输出
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn disable_rtc_output(&self) {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.cr().write(|w: &mut library::bkp::cr::W| w.bits(0 << 7)); // 禁用RTC输出
     }
     
-    /// 检查侵入检测标志
+    /// 启用侵入检测
+    /// 
+    /// TAMPER引脚（PC13）出现有效电平时硬件清除所有备份数据寄存器，置位侵入事件标志，
+    /// 并产生TAMPER中断（需在NVIC中启用）。侵入事件标志置位期间侵入检测不再响应，
+    /// 处理完成后需调用`clear_tamper_flag`。
+    /// 
+    /// 引脚在TPE置位前已处于有效电平时也会触发侵入事件。启用后PC13不能再作为RTC输出或GPIO使用。
+    /// 
+    /// # 参数
+    /// * `active_high` - true时高电平触发，false时低电平触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
+    pub unsafe fn enable_tamper(&self, active_high: bool) {
+        self.init();
+        let cr = (BKP_BASE + 0x30) as *mut u32;
+        let csr = (BKP_BASE + 0x34) as *mut u32;
+        
+        // TPAL只能在TPE为0时修改，否则可能产生误触发
+        core::ptr::write_volatile(cr, 0);
+        core::ptr::write_volatile(csr, CSR_CTE | CSR_CTI | CSR_TPIE);
+        core::ptr::write_volatile(cr, Self::tamper_cr(active_high) & !CR_TPE);
+        core::ptr::write_volatile(cr, Self::tamper_cr(active_high));
+    }
+    
+    /// 禁用侵入检测
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
+    pub unsafe fn disable_tamper(&self) {
+        self.init();
+        let cr = (BKP_BASE + 0x30) as *mut u32;
+        core::ptr::write_volatile(cr, core::ptr::read_volatile(cr) & !CR_TPE);
+    }
+    
+    /// 计算启用侵入检测时BKP_CR的值
+    pub const fn tamper_cr(active_high: bool) -> u32 {
+        if active_high { CR_TPE } else { CR_TPE | CR_TPAL }
+    }
+    
+    /// 检查侵入检测标志（TEF），置位表示发生过侵入事件且备份数据寄存器已被清除
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_tamper_flag(&self) -> bool {
-        let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
-        // 由于内部库中没有tampf方法，暂时返回固定值
-        false
+        let csr = (BKP_BASE + 0x34) as *const u32;
+        core::ptr::read_volatile(csr) & CSR_TEF != 0
     }
     
-    /// 清除侵入检测标志
+    /// 检查侵入中断标志（TIF）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn get_tamper_interrupt_flag(&self) -> bool {
+        let csr = (BKP_BASE + 0x34) as *const u32;
+        core::ptr::read_volatile(csr) & CSR_TIF != 0
+    }
+    
+    /// 清除侵入检测标志和侵入中断标志，保持中断使能
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn clear_tamper_flag(&self) {
-        let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
-        bkp.csr().write(|w: &mut library::bkp::csr::W| w.bits(1 << 3)); // 清除侵入检测标志
+        self.init();
+        let csr = (BKP_BASE + 0x34) as *mut u32;
+        let tpie = core::ptr::read_volatile(csr) & CSR_TPIE;
+        core::ptr::write_volatile(csr, tpie | CSR_CTE | CSR_CTI);
+    }
+    
+    /// 复位整个备份域
+    /// 
+    /// 清除所有备份数据寄存器、RTC计数器和配置、LSE及RTC时钟源选择，之后需重新调用`Rtc::init`。
+    /// 完成后备份域写保护被重新打开，`write`会自动解除。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保RTC和备份数据寄存器中没有仍需保留的数据
+    pub unsafe fn reset_backup_domain(&self) {
+        RccDriver::new().reset_backup_domain();
     }
     
     /// 启用侵入检测中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn enable_tamper_interrupt(&self) {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.csr().modify(|_, w: &mut library::bkp::csr::W| w
@@ -129,6 +294,9 @@ impl Bkp {
     }
     
     /// 禁用侵入检测中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn disable_tamper_interrupt(&self) {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.csr().modify(|_, w: &mut library::bkp::csr::W| w
@@ -137,6 +305,9 @@ impl Bkp {
     }
     
     /// 启用侵入检测引脚滤波
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn enable_tamper_filter(&self) {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.csr().modify(|_, w: &mut library::bkp::csr::W| w
@@ -145,6 +316,9 @@ impl Bkp {
     }
     
     /// 禁用侵入检测引脚滤波
+    /// 
+    /// # Safety
+    /// - 调用者必须确保备份域寄存器未被其他代码同时修改
     pub unsafe fn disable_tamper_filter(&self) {
         let bkp = &mut *(0x40006C00 as *mut library::bkp::RegisterBlock);
         bkp.csr().modify(|_, w: &mut library::bkp::csr::W| w
@@ -153,5 +327,42 @@ impl Bkp {
     }
 }
 
+impl Default for Bkp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的BKP实例
 pub const BKP: Bkp = Bkp::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试备份数据寄存器编号到偏移的换算
+    #[test]
+    fn test_data_register_offset() {
+        let medium = FlashDensity::Medium;
+        assert_eq!(Bkp::data_register_offset(1, medium), Some(0x04), "DR1偏移应为0x04");
+        assert_eq!(Bkp::data_register_offset(10, medium), Some(0x28), "DR10偏移应为0x28");
+        assert_eq!(Bkp::data_register_offset(0, medium), None, "编号0无效");
+        assert_eq!(Bkp::data_register_offset(BKP_DATA_REGISTERS + 1, medium), None, "超出寄存器数量应无效");
+        
+        // 大容量器件有42个备份数据寄存器，DR11起位于0x40
+        let high = FlashDensity::High;
+        assert_eq!(Bkp::new().data_registers(), BKP_DATA_REGISTERS, "默认应为10个寄存器");
+        assert_eq!(Bkp::with_density(high).data_registers(), 42, "大容量器件应为42个寄存器");
+        assert_eq!(Bkp::data_register_offset(11, high), Some(0x40), "DR11偏移应为0x40");
+        assert_eq!(Bkp::data_register_offset(42, high), Some(0xBC), "DR42偏移应为0xBC");
+        assert_eq!(Bkp::data_register_offset(43, high), None, "DR43无效");
+    }
+    
+    /// 测试侵入检测引脚有效电平配置
+    #[test]
+    fn test_tamper_config() {
+        assert_eq!(Bkp::tamper_cr(true), 0x01, "高电平有效时只置位TPE");
+        assert_eq!(Bkp::tamper_cr(false), 0x03, "低电平有效时应同时置位TPAL");
+    }
+}
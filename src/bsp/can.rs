@@ -1,10 +1,80 @@
 ﻿//! CAN模块
 //! 提供控制器局域网功能封装
+//! 
+//! CAN1引脚（TX配置为复用推挽输出，RX配置为浮空或上拉输入）：
+//! - 默认：PA11（RX）、PA12（TX），与USB引脚复用
+//! - `GpioRemap::Remap1CAN1`：PB8（RX）、PB9（TX）
+//! - `GpioRemap::Remap2CAN1`：PD0（RX）、PD1（TX）
+//! 
+//! bxCAN与USB共用512字节SRAM，两者不能同时使用。
 
 #![allow(unused)]
 
 // 导入内部生成的设备驱动库
 use library::*;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use crate::bsp::delay::wait_with_timeout;
+use crate::bsp::rcc::RccDriver;
+
+/// CAN1寄存器基地址
+const CAN1_BASE: u32 = 0x4000_6400;
+/// 发送状态寄存器（CAN_TSR）偏移
+const CAN_TSR: u32 = 0x08;
+/// 中断使能寄存器（CAN_IER）偏移
+const CAN_IER: u32 = 0x14;
+/// 发送邮箱0标识符寄存器（CAN_TI0R）偏移，每个邮箱占0x10字节
+const CAN_TI0R: u32 = 0x180;
+/// 主控制寄存器（CAN_MCR）偏移
+const CAN_MCR: u32 = 0x00;
+/// 主状态寄存器（CAN_MSR）偏移
+const CAN_MSR: u32 = 0x04;
+/// 接收FIFO 0寄存器（CAN_RF0R）偏移，CAN_RF1R紧随其后
+const CAN_RF0R: u32 = 0x0C;
+/// 错误状态寄存器（CAN_ESR）偏移
+const CAN_ESR: u32 = 0x18;
+/// 位时序寄存器（CAN_BTR）偏移
+const CAN_BTR: u32 = 0x1C;
+/// FIFO 0接收邮箱标识符寄存器（CAN_RI0R）偏移，FIFO 1的邮箱在其后0x10字节
+const CAN_RI0R: u32 = 0x1B0;
+/// 过滤器主控寄存器（CAN_FMR）偏移
+const CAN_FMR: u32 = 0x200;
+/// 过滤器模式寄存器（CAN_FM1R）偏移
+const CAN_FM1R: u32 = 0x204;
+/// 过滤器尺度寄存器（CAN_FS1R）偏移
+const CAN_FS1R: u32 = 0x20C;
+/// 过滤器FIFO分配寄存器（CAN_FFA1R）偏移
+const CAN_FFA1R: u32 = 0x214;
+/// 过滤器激活寄存器（CAN_FA1R）偏移
+const CAN_FA1R: u32 = 0x21C;
+/// 过滤器组0寄存器1（CAN_F0R1）偏移，每组占8字节
+const CAN_F0R1: u32 = 0x240;
+
+const MCR_INRQ: u32 = 1 << 0;
+const MCR_SLEEP: u32 = 1 << 1;
+const MCR_TXFP: u32 = 1 << 2;
+const MCR_ABOM: u32 = 1 << 6;
+const MSR_INAK: u32 = 1 << 0;
+const MSR_SLAK: u32 = 1 << 1;
+const RFR_FMP: u32 = 0b11;
+const RFR_RFOM: u32 = 1 << 5;
+const FMR_FINIT: u32 = 1 << 0;
+/// 标识符寄存器中的IDE位
+const ID_IDE: u32 = 1 << 2;
+
+/// 过滤器组数量
+pub const CAN_FILTER_BANKS: u8 = 14;
+
+/// 等待INAK/SLAK变化的超时时间（微秒），离开初始化或睡眠模式需要检测到11个隐性位
+const CAN_MODE_TIMEOUT_US: u32 = 10_000;
+
+/// CAN错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CanError {
+    Timeout,        // 等待模式切换（INAK/SLAK）超时
+    MailboxFull,    // 三个发送邮箱都忙
+    InvalidBitrate, // 无法由PCLK1得到该波特率，或位时序超出寄存器范围
+}
 
 /// CAN模式枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,12 +86,67 @@ pub enum CanMode {
 }
 
 /// CAN位时序结构体
+/// 
+/// 各字段均为实际值（不减1），一位时间为`1 + time_segment_1 + time_segment_2`个时间份额（tq）
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CanBitTiming {
-    pub prescaler: u16,      // 预分频系数
-    pub time_segment_1: u8,  // 时间段1
-    pub time_segment_2: u8,  // 时间段2
-    pub sjw: u8,             // 同步跳转宽度
+    pub prescaler: u16,      // 预分频系数（1~1024）
+    pub time_segment_1: u8,  // 时间段1（1~16 tq）
+    pub time_segment_2: u8,  // 时间段2（1~8 tq）
+    pub sjw: u8,             // 同步跳转宽度（1~4 tq）
+}
+
+impl CanBitTiming {
+    /// 由PCLK1和波特率计算位时序
+    /// 
+    /// 优先选择每位时间份额较多的配置，采样点约87.5%，SJW为1 tq。
+    /// 36MHz PCLK1下125k/250k/500k/1M波特率均为每位18 tq。
+    /// 
+    /// # 返回值
+    /// PCLK1不能整除为每位8~19 tq时返回None
+    pub const fn from_bitrate(pclk1: u32, bitrate: u32) -> Option<Self> {
+        if bitrate == 0 {
+            return None;
+        }
+        let mut tq = 19;
+        while tq >= 8 {
+            let divisor = bitrate as u64 * tq as u64;
+            let prescaler = pclk1 as u64 / divisor;
+            if prescaler >= 1 && prescaler <= 1024 && (pclk1 as u64).is_multiple_of(divisor) {
+                // 采样点在(1 + TS1) / tq处，取最接近87.5%的值
+                let ts1 = (tq * 7 + 4) / 8 - 1;
+                return Some(Self {
+                    prescaler: prescaler as u16,
+                    time_segment_1: ts1 as u8,
+                    time_segment_2: (tq - 1 - ts1) as u8,
+                    sjw: 1,
+                });
+            }
+            tq -= 1;
+        }
+        None
+    }
+    
+    /// 检查各字段是否在寄存器允许的范围内
+    pub const fn is_valid(&self) -> bool {
+        self.prescaler >= 1 && self.prescaler <= 1024
+            && self.time_segment_1 >= 1 && self.time_segment_1 <= 16
+            && self.time_segment_2 >= 1 && self.time_segment_2 <= 8
+            && self.sjw >= 1 && self.sjw <= 4
+    }
+    
+    /// 计算BTR寄存器中BRP、TS1、TS2、SJW位的值（各字段减1写入）
+    pub const fn btr(&self) -> u32 {
+        ((self.prescaler as u32 - 1) & 0x3FF)
+            | (((self.time_segment_1 as u32 - 1) & 0x0F) << 16)
+            | (((self.time_segment_2 as u32 - 1) & 0x07) << 20)
+            | (((self.sjw as u32 - 1) & 0x03) << 24)
+    }
+    
+    /// 计算给定PCLK1下的实际波特率
+    pub const fn bitrate(&self, pclk1: u32) -> u32 {
+        pclk1 / (self.prescaler as u32 * (1 + self.time_segment_1 as u32 + self.time_segment_2 as u32))
+    }
 }
 
 /// CAN过滤器模式枚举
@@ -79,90 +204,661 @@ impl Can {
     }
     
     /// 初始化CAN
-    pub unsafe fn init(&self, _mode: CanMode, _bit_timing: CanBitTiming) {
+    /// 
+    /// 退出睡眠模式并进入初始化模式（等待INAK置位），配置位时序和工作模式后返回正常模式
+    /// （等待INAK清零）。启用总线离线自动恢复，发送邮箱按请求顺序发送。
+    /// 离开初始化模式需要在RX引脚上检测到11个连续隐性位，收发器未连接时会超时。
+    /// 
+    /// 初始化后还需要用`set_filter`或`configure_filter`激活至少一个过滤器才能接收
+    /// 
+    /// # 返回值
+    /// 位时序超出寄存器范围时返回`CanError::InvalidBitrate`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN引脚已配置
+    pub unsafe fn init(&self, mode: CanMode, bit_timing: CanBitTiming) -> Result<(), CanError> {
+        if !bit_timing.is_valid() {
+            return Err(CanError::InvalidBitrate);
+        }
+        
         let rcc = Can::rcc();
         
         // 启用CAN时钟
         rcc.apb1enr().modify(|_, w: &mut library::rcc::apb1enr::W| w
             .canen().set_bit()
         );
+        
+        let mcr = Can::reg(CAN_MCR);
+        
+        // 退出睡眠模式，请求进入初始化模式
+        let value = core::ptr::read_volatile(mcr);
+        core::ptr::write_volatile(mcr, (value & !MCR_SLEEP) | MCR_INRQ);
+        Self::wait_mode(MSR_INAK | MSR_SLAK, MSR_INAK)?;
+        
+        core::ptr::write_volatile(mcr, core::ptr::read_volatile(mcr) | MCR_ABOM | MCR_TXFP);
+        core::ptr::write_volatile(Can::reg(CAN_BTR), bit_timing.btr() | Self::mode_bits(mode));
+        
+        // 离开初始化模式
+        core::ptr::write_volatile(mcr, core::ptr::read_volatile(mcr) & !MCR_INRQ);
+        Self::wait_mode(MSR_INAK, 0)
+    }
+    
+    /// 按波特率初始化CAN，位时序由当前PCLK1计算
+    /// 
+    /// # 参数
+    /// * `bitrate` - 波特率，如125_000、250_000、500_000、1_000_000
+    /// 
+    /// # Safety
+    /// - 同`init`
+    pub unsafe fn init_bitrate(&self, mode: CanMode, bitrate: u32) -> Result<(), CanError> {
+        let pclk1 = RccDriver::new().get_clocks_freq().pclk1_frequency;
+        let bit_timing = CanBitTiming::from_bitrate(pclk1, bitrate).ok_or(CanError::InvalidBitrate)?;
+        self.init(mode, bit_timing)
+    }
+    
+    /// 计算BTR寄存器中LBKM、SILM位的值
+    pub const fn mode_bits(mode: CanMode) -> u32 {
+        (mode as u32) << 30
+    }
+    
+    /// 等待MSR中`mask`选中的位变为`expected`
+    unsafe fn wait_mode(mask: u32, expected: u32) -> Result<(), CanError> {
+        let msr = Can::reg(CAN_MSR);
+        if wait_with_timeout(CAN_MODE_TIMEOUT_US, || core::ptr::read_volatile(msr) & mask == expected) {
+            Err(CanError::Timeout)
+        } else {
+            Ok(())
+        }
     }
     
     /// 配置过滤器
+    /// 
+    /// 修改期间过滤器进入初始化模式（FMR.FINIT），不影响CAN收发，但该组过滤器暂停匹配
+    /// 
+    /// # 参数
+    /// * `filter_number` - 过滤器组（0~13）
+    /// * `filter_id` - 写入FxR1的值，32位尺度下与接收邮箱标识符寄存器格式相同
+    /// * `filter_mask` - 写入FxR2的值，掩码模式下为掩码，列表模式下为第二个标识符
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    #[allow(clippy::too_many_arguments)]
     pub unsafe fn configure_filter(
         &self,
-        _filter_number: u8,
-        _mode: CanFilterMode,
-        _scale: CanFilterScale,
-        _fifo: CanFilterFifo,
-        _filter_id: u32,
-        _filter_mask: u32,
-        _activate: bool,
+        filter_number: u8,
+        mode: CanFilterMode,
+        scale: CanFilterScale,
+        fifo: CanFilterFifo,
+        filter_id: u32,
+        filter_mask: u32,
+        activate: bool,
     ) {
-        // 由于内部库中CAN寄存器结构不同，暂时为空实现
+        assert!(filter_number < CAN_FILTER_BANKS, "CAN filter bank out of range");
+        
+        let bit = 1u32 << filter_number;
+        let set_bit = |offset: u32, enable: bool| {
+            let reg = Can::reg(offset);
+            let value = core::ptr::read_volatile(reg);
+            core::ptr::write_volatile(reg, if enable { value | bit } else { value & !bit });
+        };
+        
+        let fmr = Can::reg(CAN_FMR);
+        core::ptr::write_volatile(fmr, core::ptr::read_volatile(fmr) | FMR_FINIT);
+        
+        // 修改前先停用该过滤器组
+        set_bit(CAN_FA1R, false);
+        set_bit(CAN_FM1R, mode == CanFilterMode::ListMode);
+        set_bit(CAN_FS1R, scale == CanFilterScale::Scale32Bit);
+        set_bit(CAN_FFA1R, fifo == CanFilterFifo::Fifo1);
+        
+        let bank = CAN_F0R1 + 8 * filter_number as u32;
+        core::ptr::write_volatile(Can::reg(bank), filter_id);
+        core::ptr::write_volatile(Can::reg(bank + 0x04), filter_mask);
+        set_bit(CAN_FA1R, activate);
+        
+        core::ptr::write_volatile(fmr, core::ptr::read_volatile(fmr) & !FMR_FINIT);
+    }
+    
+    /// 设置32位掩码过滤器，匹配的帧进入FIFO 0
+    /// 
+    /// 接收ID与`id`在`mask`为1的位上相同、且ID类型（标准/扩展）一致的帧，数据帧和远程帧都接收。
+    /// 例如`set_filter(0, 0, 0, false)`接收所有标准帧。
+    /// 
+    /// # 参数
+    /// * `bank` - 过滤器组（0~13）
+    /// * `id` - 标准ID（11位）或扩展ID（29位）
+    /// * `mask` - ID掩码
+    /// * `is_extended` - 是否为扩展ID
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn set_filter(&self, bank: u8, id: u32, mask: u32, is_extended: bool) {
+        let (filter_id, filter_mask) = Self::filter_words(id, mask, is_extended);
+        self.configure_filter(
+            bank,
+            CanFilterMode::MaskMode,
+            CanFilterScale::Scale32Bit,
+            CanFilterFifo::Fifo0,
+            filter_id,
+            filter_mask,
+            true,
+        );
+    }
+    
+    /// 计算32位掩码过滤器的FxR1、FxR2值
+    /// 
+    /// 掩码中包含IDE位，使标准帧和扩展帧不会互相匹配
+    pub const fn filter_words(id: u32, mask: u32, is_extended: bool) -> (u32, u32) {
+        if is_extended {
+            (((id & 0x1FFF_FFFF) << 3) | ID_IDE, ((mask & 0x1FFF_FFFF) << 3) | ID_IDE)
+        } else {
+            ((id & 0x7FF) << 21, ((mask & 0x7FF) << 21) | ID_IDE)
+        }
+    }
+    
+    /// 读写CAN1寄存器（按偏移访问）
+    unsafe fn reg(offset: u32) -> *mut u32 {
+        (CAN1_BASE + offset) as *mut u32
+    }
+    
+    /// 获取空闲发送邮箱掩码（TSR.TME0~TME2，位0对应邮箱0）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn free_mailboxes(&self) -> u8 {
+        ((core::ptr::read_volatile(Can::reg(CAN_TSR)) >> 26) & 0x07) as u8
+    }
+    
+    /// 将消息写入指定发送邮箱并请求发送
+    /// 
+    /// # 参数
+    /// * `mailbox` - 邮箱编号（0-2），调用者需确保该邮箱空闲
+    /// 
+    /// # Safety
+    /// - 调用者必须确保邮箱空闲，且CAN外设未被其他代码同时访问
+    pub unsafe fn load_mailbox(&self, mailbox: usize, message: &CanMessage) {
+        let base = CAN_TI0R + 0x10 * mailbox as u32;
+        let (tir, tdtr, tdlr, tdhr) = Self::mailbox_words(message);
+        
+        core::ptr::write_volatile(Can::reg(base), tir & !0x01);
+        core::ptr::write_volatile(Can::reg(base + 0x04), tdtr);
+        core::ptr::write_volatile(Can::reg(base + 0x08), tdlr);
+        core::ptr::write_volatile(Can::reg(base + 0x0C), tdhr);
+        
+        // 最后置位TXRQ请求发送
+        core::ptr::write_volatile(Can::reg(base), tir | 0x01);
+    }
+    
+    /// 计算消息对应的邮箱寄存器值
+    /// 
+    /// # 返回值
+    /// (TIxR（不含TXRQ）, TDTxR, TDLxR, TDHxR)
+    pub fn mailbox_words(message: &CanMessage) -> (u32, u32, u32, u32) {
+        let mut tir = if message.is_extended {
+            ((message.id & 0x1FFF_FFFF) << 3) | (1 << 2)  // EXID + IDE
+        } else {
+            (message.id & 0x7FF) << 21                    // STID
+        };
+        if message.rtr {
+            tir |= 1 << 1;
+        }
+        
+        let tdtr = (message.dlc.min(8) as u32) & 0x0F;
+        let d = &message.data;
+        let tdlr = u32::from_le_bytes([d[0], d[1], d[2], d[3]]);
+        let tdhr = u32::from_le_bytes([d[4], d[5], d[6], d[7]]);
+        (tir, tdtr, tdlr, tdhr)
     }
     
     /// 发送消息
-    pub unsafe fn send_message(&self, _message: &CanMessage) -> bool {
-        // 由于内部库中CAN寄存器结构不同，暂时返回固定值
-        false
+    /// 
+    /// 使用任一空闲邮箱发送，三个邮箱都忙时返回false
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且CAN外设未被其他代码同时访问
+    pub unsafe fn send_message(&self, message: &CanMessage) -> bool {
+        let free = self.free_mailboxes();
+        match (0..3).find(|&mailbox| free & (1 << mailbox) != 0) {
+            Some(mailbox) => {
+                self.load_mailbox(mailbox, message);
+                true
+            },
+            None => false,
+        }
+    }
+    
+    /// 发送帧
+    /// 
+    /// 装入空闲邮箱后立即返回，不等待发送完成
+    /// 
+    /// # 返回值
+    /// 三个邮箱都忙时返回`Err(CanError::MailboxFull)`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且CAN外设未被其他代码同时访问
+    pub unsafe fn transmit(&self, frame: CanMessage) -> Result<(), CanError> {
+        if self.send_message(&frame) {
+            Ok(())
+        } else {
+            Err(CanError::MailboxFull)
+        }
+    }
+    
+    /// 清除发送完成标志（TSR.RQCP0~RQCP2，写1清零）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn clear_tx_complete_flags(&self) {
+        core::ptr::write_volatile(Can::reg(CAN_TSR), (1 << 0) | (1 << 8) | (1 << 16));
+    }
+    
+    /// 接收帧，先检查FIFO 0再检查FIFO 1
+    /// 
+    /// # 返回值
+    /// 两个FIFO都为空时返回None
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且接收FIFO未被其他代码同时读取
+    pub unsafe fn receive(&self) -> Option<CanMessage> {
+        self.receive_message_fifo0().or_else(|| self.receive_message_fifo1())
     }
     
     /// 接收消息（FIFO 0）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且接收FIFO未被其他代码同时读取
     pub unsafe fn receive_message_fifo0(&self) -> Option<CanMessage> {
-        // 由于内部库中CAN寄存器结构不同，暂时返回固定值
-        None
+        self.read_fifo(0)
     }
     
     /// 接收消息（FIFO 1）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且接收FIFO未被其他代码同时读取
     pub unsafe fn receive_message_fifo1(&self) -> Option<CanMessage> {
-        // 由于内部库中CAN寄存器结构不同，暂时返回固定值
-        None
+        self.read_fifo(1)
+    }
+    
+    /// 读取FIFO输出邮箱中的帧并释放邮箱
+    unsafe fn read_fifo(&self, fifo: u32) -> Option<CanMessage> {
+        let rfr = Can::reg(CAN_RF0R + 0x04 * fifo);
+        if core::ptr::read_volatile(rfr) & RFR_FMP == 0 {
+            return None;
+        }
+        
+        let base = CAN_RI0R + 0x10 * fifo;
+        let message = Self::decode_mailbox(
+            core::ptr::read_volatile(Can::reg(base)),
+            core::ptr::read_volatile(Can::reg(base + 0x04)),
+            core::ptr::read_volatile(Can::reg(base + 0x08)),
+            core::ptr::read_volatile(Can::reg(base + 0x0C)),
+        );
+        
+        // 置位RFOM释放输出邮箱，FULL/FOVR写0无影响
+        core::ptr::write_volatile(rfr, RFR_RFOM);
+        Some(message)
+    }
+    
+    /// 由接收邮箱寄存器值解析消息
+    /// 
+    /// # 参数
+    /// * `rir`, `rdtr`, `rdlr`, `rdhr` - RIxR、RDTxR、RDLxR、RDHxR的值
+    pub fn decode_mailbox(rir: u32, rdtr: u32, rdlr: u32, rdhr: u32) -> CanMessage {
+        let is_extended = rir & ID_IDE != 0;
+        let id = if is_extended {
+            (rir >> 3) & 0x1FFF_FFFF
+        } else {
+            (rir >> 21) & 0x7FF
+        };
+        
+        let mut data = [0; 8];
+        data[..4].copy_from_slice(&rdlr.to_le_bytes());
+        data[4..].copy_from_slice(&rdhr.to_le_bytes());
+        CanMessage {
+            id,
+            is_extended,
+            rtr: rir & (1 << 1) != 0,
+            dlc: ((rdtr & 0x0F) as u8).min(8),
+            data,
+        }
     }
     
     /// 启用中断
-    pub unsafe fn enable_interrupt(&self, _interrupt_mask: u32) {
-        // 由于内部库中CAN寄存器结构不同，暂时为空实现
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn enable_interrupt(&self, interrupt_mask: u32) {
+        let ier = Can::reg(CAN_IER);
+        core::ptr::write_volatile(ier, core::ptr::read_volatile(ier) | interrupt_mask);
     }
     
     /// 禁用中断
-    pub unsafe fn disable_interrupt(&self, _interrupt_mask: u32) {
-        // 由于内部库中CAN寄存器结构不同，暂时为空实现
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn disable_interrupt(&self, interrupt_mask: u32) {
+        let ier = Can::reg(CAN_IER);
+        core::ptr::write_volatile(ier, core::ptr::read_volatile(ier) & !interrupt_mask);
     }
     
     /// 检查错误状态
+    /// 
+    /// # 返回值
+    /// CAN_ESR的值：位0 EWGF、位1 EPVF、位2 BOFF、位6:4 LEC、位23:16 TEC、位31:24 REC
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn check_error_status(&self) -> u32 {
-        // 由于内部库中CAN寄存器结构不同，暂时返回固定值
-        0
+        core::ptr::read_volatile(Can::reg(CAN_ESR))
     }
     
-    /// 进入睡眠模式
-    pub unsafe fn enter_sleep_mode(&self) {
-        // 由于内部库中CAN寄存器结构不同，暂时为空实现
+    /// 进入睡眠模式，等待SLAK置位
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn enter_sleep_mode(&self) -> Result<(), CanError> {
+        let mcr = Can::reg(CAN_MCR);
+        let value = core::ptr::read_volatile(mcr);
+        core::ptr::write_volatile(mcr, (value & !MCR_INRQ) | MCR_SLEEP);
+        Self::wait_mode(MSR_INAK | MSR_SLAK, MSR_SLAK)
     }
     
-    /// 唤醒
-    pub unsafe fn wakeup(&self) {
-        // 由于内部库中CAN寄存器结构不同，暂时为空实现
+    /// 唤醒，等待SLAK清零（需检测到11个隐性位）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn wakeup(&self) -> Result<(), CanError> {
+        let mcr = Can::reg(CAN_MCR);
+        core::ptr::write_volatile(mcr, core::ptr::read_volatile(mcr) & !MCR_SLEEP);
+        Self::wait_mode(MSR_SLAK, 0)
+    }
+}
+
+impl Default for Can {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// CAN中断掩码常量
+/// CAN中断掩码常量（CAN_IER中的使能位）
 pub const CAN_IT_TME: u32 = 1 << 0;    // 发送邮箱空中断
 pub const CAN_IT_FMP0: u32 = 1 << 1;   // FIFO 0 消息挂起中断
-pub const CAN_IT_FMP1: u32 = 1 << 2;   // FIFO 1 消息挂起中断
-pub const CAN_IT_FF0: u32 = 1 << 3;    // FIFO 0 满中断
-pub const CAN_IT_FF1: u32 = 1 << 4;    // FIFO 1 满中断
-pub const CAN_IT_FOV0: u32 = 1 << 5;   // FIFO 0 溢出中断
+pub const CAN_IT_FF0: u32 = 1 << 2;    // FIFO 0 满中断
+pub const CAN_IT_FOV0: u32 = 1 << 3;   // FIFO 0 溢出中断
+pub const CAN_IT_FMP1: u32 = 1 << 4;   // FIFO 1 消息挂起中断
+pub const CAN_IT_FF1: u32 = 1 << 5;    // FIFO 1 满中断
 pub const CAN_IT_FOV1: u32 = 1 << 6;   // FIFO 1 溢出中断
-pub const CAN_IT_WKU: u32 = 1 << 7;    // 唤醒中断
-pub const CAN_IT_SLK: u32 = 1 << 8;    // 睡眠中断
-pub const CAN_IT_ERR: u32 = 1 << 9;    // 错误中断
-pub const CAN_IT_LEC: u32 = 1 << 10;   // 最后错误代码中断
-pub const CAN_IT_BOF: u32 = 1 << 11;   // 总线离线中断
-pub const CAN_IT_EPV: u32 = 1 << 12;   // 错误被动中断
-pub const CAN_IT_EWG: u32 = 1 << 13;   // 错误警告中断
+pub const CAN_IT_EWG: u32 = 1 << 8;    // 错误警告中断
+pub const CAN_IT_EPV: u32 = 1 << 9;    // 错误被动中断
+pub const CAN_IT_BOF: u32 = 1 << 10;   // 总线离线中断
+pub const CAN_IT_LEC: u32 = 1 << 11;   // 最后错误代码中断
+pub const CAN_IT_ERR: u32 = 1 << 15;   // 错误中断
 pub const CAN_IT_ERRIE: u32 = 1 << 15; // 错误中断使能
+pub const CAN_IT_WKU: u32 = 1 << 16;   // 唤醒中断
+pub const CAN_IT_SLK: u32 = 1 << 17;   // 睡眠中断
+
+/// CAN发送队列容量
+const CAN_TX_QUEUE_SIZE: usize = 16;
+
+/// 发送队列已满错误
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueFull;
+
+/// CAN软件发送队列
+/// 
+/// 缓存待发送的帧，在邮箱空闲时（TME标志/发送邮箱空中断）依次装入邮箱，
+/// 应用程序可以连续突发发送而无需管理三个硬件邮箱。
+/// 
+/// 使用方法：调用`enable_tx_interrupt`后在USB_HP_CAN_TX中断中调用`on_tx_interrupt`。
+pub struct CanTxQueue {
+    frames: UnsafeCell<[CanMessage; CAN_TX_QUEUE_SIZE]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+/// 实现 Sync trait，队列的修改都在临界区内进行
+unsafe impl Sync for CanTxQueue {}
+
+impl CanTxQueue {
+    /// 创建新的发送队列
+    pub const fn new() -> Self {
+        const EMPTY: CanMessage = CanMessage {
+            id: 0,
+            is_extended: false,
+            rtr: false,
+            dlc: 0,
+            data: [0; 8],
+        };
+        Self {
+            frames: UnsafeCell::new([EMPTY; CAN_TX_QUEUE_SIZE]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+    
+    /// 将帧加入发送队列，并立即尝试装入空闲邮箱
+    /// 
+    /// # 返回值
+    /// 队列已满时返回`Err(QueueFull)`
+    pub fn enqueue(&self, frame: CanMessage) -> Result<(), QueueFull> {
+        cortex_m::interrupt::free(|_| {
+            let result = self.push(frame);
+            unsafe {
+                self.pump();
+            }
+            result
+        })
+    }
+    
+    /// 启用发送邮箱空中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CAN外设未被其他代码同时访问
+    pub unsafe fn enable_tx_interrupt(&self) {
+        CAN.enable_interrupt(CAN_IT_TME);
+    }
+    
+    /// 发送完成中断处理，应在USB_HP_CAN_TX中断服务函数中调用
+    pub fn on_tx_interrupt(&self) {
+        cortex_m::interrupt::free(|_| unsafe {
+            CAN.clear_tx_complete_flags();
+            self.pump();
+        });
+    }
+    
+    /// 将队列中的帧装入当前空闲的邮箱
+    unsafe fn pump(&self) -> usize {
+        let free = CAN.free_mailboxes();
+        self.feed_mailboxes(free, |mailbox, frame| CAN.load_mailbox(mailbox, frame))
+    }
+    
+    /// 按空闲邮箱掩码依次从队列取帧交给`load`装入邮箱
+    /// 
+    /// # 参数
+    /// * `free_mask` - 空闲邮箱掩码（位0~2对应邮箱0~2）
+    /// * `load` - 装入邮箱的操作，参数为邮箱编号和帧
+    /// 
+    /// # 返回值
+    /// 装入邮箱的帧数
+    pub fn feed_mailboxes<F: FnMut(usize, &CanMessage)>(&self, free_mask: u8, mut load: F) -> usize {
+        let mut count = 0;
+        for mailbox in 0..3 {
+            if free_mask & (1 << mailbox) == 0 {
+                continue;
+            }
+            match self.pop() {
+                Some(frame) => {
+                    load(mailbox, &frame);
+                    count += 1;
+                },
+                None => break,
+            }
+        }
+        count
+    }
+    
+    /// 向队列尾部添加一帧
+    fn push(&self, frame: CanMessage) -> Result<(), QueueFull> {
+        let head = self.head.load(Ordering::Relaxed);
+        let next_head = (head + 1) % CAN_TX_QUEUE_SIZE;
+        
+        if next_head == self.tail.load(Ordering::Relaxed) {
+            return Err(QueueFull);
+        }
+        unsafe {
+            let frames = &mut *self.frames.get();
+            frames[head] = frame;
+        }
+        self.head.store(next_head, Ordering::Relaxed);
+        Ok(())
+    }
+    
+    /// 从队列头部取出一帧
+    fn pop(&self) -> Option<CanMessage> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        
+        if tail == self.head.load(Ordering::Relaxed) {
+            return None;
+        }
+        let frame = unsafe {
+            let frames = &*self.frames.get();
+            frames[tail]
+        };
+        self.tail.store((tail + 1) % CAN_TX_QUEUE_SIZE, Ordering::Relaxed);
+        Some(frame)
+    }
+    
+    /// 获取队列中等待发送的帧数
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Relaxed);
+        (head + CAN_TX_QUEUE_SIZE - tail) % CAN_TX_QUEUE_SIZE
+    }
+    
+    /// 检查队列是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for CanTxQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 预定义的CAN发送队列
+pub static CAN_TX_QUEUE: CanTxQueue = CanTxQueue::new();
 
 /// 预定义的CAN实例
 pub const CAN: Can = Can::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 创建测试用的标准帧
+    fn frame(id: u32) -> CanMessage {
+        CanMessage {
+            id,
+            is_extended: false,
+            rtr: false,
+            dlc: 1,
+            data: [id as u8, 0, 0, 0, 0, 0, 0, 0],
+        }
+    }
+    
+    /// 测试邮箱空闲时队列按顺序装入邮箱
+    #[test]
+    fn test_queue_to_mailbox_handoff() {
+        let queue = CanTxQueue::new();
+        for id in 1..=5 {
+            assert_eq!(queue.push(frame(id)), Ok(()), "队列未满时应入队成功");
+        }
+        
+        // 只有邮箱1空闲
+        let mut loaded: heapless::Vec<(usize, u32), 8> = heapless::Vec::new();
+        let count = queue.feed_mailboxes(0b010, |mailbox, f| { loaded.push((mailbox, f.id)).unwrap(); });
+        assert_eq!(count, 1, "只应装入一个邮箱");
+        assert_eq!(&loaded[..], &[(1, 1)], "第一帧应装入邮箱1");
+        
+        // 三个邮箱全部空闲
+        loaded.clear();
+        let count = queue.feed_mailboxes(0b111, |mailbox, f| { loaded.push((mailbox, f.id)).unwrap(); });
+        assert_eq!(count, 3, "应装满三个邮箱");
+        assert_eq!(&loaded[..], &[(0, 2), (1, 3), (2, 4)], "帧应按入队顺序装入邮箱");
+        assert_eq!(queue.len(), 1, "队列中应剩余一帧");
+        
+        // 邮箱全忙时不应出队
+        let count = queue.feed_mailboxes(0b000, |_, _| panic!("邮箱全忙时不应装入"));
+        assert_eq!(count, 0, "邮箱全忙时不应装入");
+        assert_eq!(queue.len(), 1, "邮箱全忙时队列不应变化");
+    }
+    
+    /// 测试队列满时返回QueueFull
+    #[test]
+    fn test_queue_full() {
+        let queue = CanTxQueue::new();
+        for id in 0..(CAN_TX_QUEUE_SIZE - 1) as u32 {
+            assert_eq!(queue.push(frame(id)), Ok(()), "队列未满时应入队成功");
+        }
+        assert_eq!(queue.push(frame(99)), Err(QueueFull), "队列满时应返回QueueFull");
+    }
+    
+    /// 测试邮箱寄存器值的编码
+    #[test]
+    fn test_mailbox_words() {
+        let (tir, tdtr, tdlr, _) = Can::mailbox_words(&frame(0x123));
+        assert_eq!(tir, 0x123 << 21, "标准ID应写入STID");
+        assert_eq!(tdtr, 1, "DLC编码错误");
+        assert_eq!(tdlr, 0x23, "数据低字编码错误");
+        
+        let mut ext = frame(0x1ABC_DEF0);
+        ext.is_extended = true;
+        ext.rtr = true;
+        let (tir, _, _, _) = Can::mailbox_words(&ext);
+        assert_eq!(tir, (0x1ABC_DEF0 << 3) | 0x06, "扩展ID应写入EXID并置位IDE/RTR");
+    }
+    
+    /// 测试由PCLK1计算常用波特率的位时序
+    #[test]
+    fn test_bit_timing_from_pclk1() {
+        for &(bitrate, prescaler) in &[(125_000, 16), (250_000, 8), (500_000, 4), (1_000_000, 2)] {
+            let timing = CanBitTiming::from_bitrate(36_000_000, bitrate).unwrap();
+            assert_eq!(timing.prescaler, prescaler, "36MHz下预分频错误");
+            assert_eq!((timing.time_segment_1, timing.time_segment_2, timing.sjw), (15, 2, 1), "应为18tq、采样点约87.5%");
+            assert!(timing.is_valid(), "位时序应在寄存器范围内");
+            assert_eq!(timing.bitrate(36_000_000), bitrate, "实际波特率错误");
+        }
+        
+        let timing = CanBitTiming::from_bitrate(8_000_000, 1_000_000).unwrap();
+        assert_eq!((timing.prescaler, timing.time_segment_1, timing.time_segment_2), (1, 6, 1), "8MHz下1M应为8tq");
+        assert_eq!(timing.btr(), 0x0005_0000, "BTR编码错误");
+        assert_eq!(CanBitTiming::from_bitrate(36_000_000, 33_333), None, "无法整除时应返回None");
+        
+        assert_eq!(Can::mode_bits(CanMode::LoopBack), 1 << 30, "回环模式应置位LBKM");
+        assert_eq!(Can::mode_bits(CanMode::SilentLoopBack), 3 << 30, "静默回环应置位LBKM和SILM");
+    }
+    
+    /// 测试过滤器编码和接收邮箱解析
+    #[test]
+    fn test_filter_and_receive_decode() {
+        assert_eq!(Can::filter_words(0x123, 0x7F0, false), (0x123 << 21, (0x7F0 << 21) | 0x04), "标准ID过滤器编码错误");
+        assert_eq!(Can::filter_words(0x1ABC_DEF0, 0x1FFF_FFFF, true), ((0x1ABC_DEF0 << 3) | 0x04, 0xFFFF_FFFC), "扩展ID过滤器编码错误");
+        
+        // 发送邮箱与接收邮箱的寄存器格式相同，编码后应能解析回原消息
+        let mut ext = CanMessage { id: 0x1ABC_DEF0, is_extended: true, rtr: false, dlc: 8, data: [1, 2, 3, 4, 5, 6, 7, 8] };
+        let (tir, tdtr, tdlr, tdhr) = Can::mailbox_words(&ext);
+        assert_eq!(Can::decode_mailbox(tir, tdtr | 0xAB00, tdlr, tdhr), ext, "扩展帧解析错误");
+        
+        let std = frame(0x7FF);
+        let (tir, tdtr, tdlr, tdhr) = Can::mailbox_words(&std);
+        assert_eq!(Can::decode_mailbox(tir, tdtr, tdlr, tdhr), std, "标准帧解析错误");
+        
+        ext.rtr = true;
+        let (tir, _, _, _) = Can::mailbox_words(&ext);
+        assert!(Can::decode_mailbox(tir, 0x0F, 0, 0).rtr, "应解析RTR位");
+        assert_eq!(Can::decode_mailbox(tir, 0x0F, 0, 0).dlc, 8, "DLC超过8时应按8处理");
+    }
+}
@@ -0,0 +1,203 @@
+//! 闭环控制模块
+//! 提供定点PID控制器，以及读取ADC通道、输出PWM占空比的闭环控制器
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::adc::{Adc, AdcChannel};
+use crate::bsp::timer::{Timer, PwmChannel};
+
+/// PID增益的小数位数，增益为Q24.8定点数（256表示1.0）
+pub const PID_FRAC_BITS: u32 = 8;
+
+/// PID增益（Q24.8定点数）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PidGains {
+    pub kp: i32,    // 比例增益
+    pub ki: i32,    // 积分增益（每次迭代）
+    pub kd: i32,    // 微分增益（每次迭代）
+}
+
+impl PidGains {
+    /// 由千分之一单位的增益创建，例如`from_milli(1500, 200, 0)`表示Kp=1.5、Ki=0.2、Kd=0
+    pub const fn from_milli(kp: i32, ki: i32, kd: i32) -> Self {
+        Self {
+            kp: (kp << PID_FRAC_BITS) / 1000,
+            ki: (ki << PID_FRAC_BITS) / 1000,
+            kd: (kd << PID_FRAC_BITS) / 1000,
+        }
+    }
+}
+
+/// 定点PID控制器
+/// 
+/// 积分项按定点数累加并限制在输出范围内（抗积分饱和），
+/// 微分项对误差求差分，第一次迭代不计微分以避免突变。
+pub struct Pid {
+    gains: PidGains,
+    out_min: i32,
+    out_max: i32,
+    integral: i64,
+    prev_error: Option<i32>,
+}
+
+impl Pid {
+    /// 创建PID控制器
+    /// 
+    /// # 参数
+    /// * `gains` - PID增益
+    /// * `out_min` - 输出下限
+    /// * `out_max` - 输出上限
+    pub const fn new(gains: PidGains, out_min: i32, out_max: i32) -> Self {
+        assert!(out_min <= out_max, "PID output range is empty");
+        Self {
+            gains,
+            out_min,
+            out_max,
+            integral: 0,
+            prev_error: None,
+        }
+    }
+    
+    /// 设置输出范围，积分项会被限制到新范围内
+    pub fn set_output_limits(&mut self, out_min: i32, out_max: i32) {
+        assert!(out_min <= out_max, "PID output range is empty");
+        self.out_min = out_min;
+        self.out_max = out_max;
+        self.integral = self.clamp_integral(self.integral);
+    }
+    
+    /// 执行一次PID迭代
+    /// 
+    /// # 参数
+    /// * `setpoint` - 目标值
+    /// * `measurement` - 测量值
+    /// 
+    /// # 返回值
+    /// 限幅后的输出
+    pub fn step(&mut self, setpoint: i32, measurement: i32) -> i32 {
+        let error = setpoint - measurement;
+        
+        let p = self.gains.kp as i64 * error as i64;
+        self.integral = self.clamp_integral(self.integral + self.gains.ki as i64 * error as i64);
+        let d = match self.prev_error {
+            Some(prev) => self.gains.kd as i64 * (error - prev) as i64,
+            None => 0,
+        };
+        self.prev_error = Some(error);
+        
+        let output = (p + self.integral + d) >> PID_FRAC_BITS;
+        output.clamp(self.out_min as i64, self.out_max as i64) as i32
+    }
+    
+    /// 清除积分和微分状态
+    pub fn reset(&mut self) {
+        self.integral = 0;
+        self.prev_error = None;
+    }
+    
+    /// 将积分项限制在输出范围内
+    fn clamp_integral(&self, integral: i64) -> i64 {
+        integral.clamp(
+            (self.out_min as i64) << PID_FRAC_BITS,
+            (self.out_max as i64) << PID_FRAC_BITS,
+        )
+    }
+}
+
+/// ADC到PWM的闭环控制器
+/// 
+/// 每次`tick`读取一次ADC通道作为测量值，执行PID迭代，并把输出作为比较值写入PWM通道。
+/// 输出范围为0到定时器周期（ARR），`tick`应以固定周期调用（如定时器更新中断中），
+/// 积分和微分增益按每次迭代计算。
+pub struct Controller {
+    adc: Adc,
+    channel: AdcChannel,
+    timer: Timer,
+    pwm_channel: PwmChannel,
+    pid: Pid,
+}
+
+impl Controller {
+    /// 创建闭环控制器
+    /// 
+    /// # 参数
+    /// * `adc` - ADC实例，需已初始化和校准
+    /// * `channel` - 测量通道
+    /// * `timer` - PWM输出定时器，需已用`init_pwm`配置
+    /// * `pwm_channel` - PWM输出通道
+    /// * `gains` - PID增益
+    pub const fn new(adc: Adc, channel: AdcChannel, timer: Timer, pwm_channel: PwmChannel, gains: PidGains) -> Self {
+        Self {
+            adc,
+            channel,
+            timer,
+            pwm_channel,
+            pid: Pid::new(gains, 0, 0),
+        }
+    }
+    
+    /// 执行一次控制迭代
+    /// 
+    /// # 参数
+    /// * `setpoint` - 目标ADC原始值（0~4095）
+    /// 
+    /// # 返回值
+    /// 写入PWM通道的比较值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC和定时器未被其他代码同时使用
+    pub unsafe fn tick(&mut self, setpoint: u16) -> u16 {
+        let period = self.timer.get_period() as i32;
+        if self.pid.out_max != period {
+            self.pid.set_output_limits(0, period);
+        }
+        
+        let measurement = self.adc.read_single_channel(self.channel);
+        let duty = self.pid.step(setpoint as i32, measurement as i32) as u16;
+        self.timer.set_pwm_duty(self.pwm_channel, duty);
+        duty
+    }
+    
+    /// 清除PID状态
+    pub fn reset(&mut self) {
+        self.pid.reset();
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试定点PID的比例、积分和微分项
+    #[test]
+    fn test_pid_step_terms() {
+        // Kp=2.0、Ki=0.5、Kd=1.0
+        let gains = PidGains::from_milli(2000, 500, 1000);
+        assert_eq!(gains, PidGains { kp: 512, ki: 128, kd: 256 }, "增益应转换为Q24.8");
+        
+        let mut pid = Pid::new(gains, 0, 1000);
+        // 误差100：P=200，I=50，首次不计微分
+        assert_eq!(pid.step(1000, 900), 250, "第一次迭代输出错误");
+        // 误差50：P=100，I=75，D=-50
+        assert_eq!(pid.step(1000, 950), 125, "第二次迭代输出错误");
+        // 误差20：P=40，I=85，D=-30
+        assert_eq!(pid.step(1000, 980), 95, "第三次迭代输出错误");
+        
+        pid.reset();
+        assert_eq!(pid.step(1000, 900), 250, "复位后应与首次迭代相同");
+    }
+    
+    /// 测试输出限幅和积分抗饱和
+    #[test]
+    fn test_pid_anti_windup() {
+        let mut pid = Pid::new(PidGains { kp: 0, ki: 256, kd: 0 }, 0, 100);
+        for _ in 0..10 {
+            assert!(pid.step(1000, 0) <= 100, "输出应限制在上限内");
+        }
+        // 积分被限制在100，误差反向后应立即退出饱和
+        assert_eq!(pid.step(0, 30), 70, "积分项不应超出输出范围累积");
+        assert_eq!(pid.step(0, 500), 0, "输出应限制在下限");
+    }
+}
@@ -1,10 +1,24 @@
 ﻿//! CRC模块
 //! 提供循环冗余校验功能封装
+//! 
+//! STM32F1的CRC单元固定使用CRC-32多项式0x04C11DB7、初值0xFFFFFFFF，
+//! 多项式和初值都不可配置，也不支持输入/输出位反转和结果异或。
+//! 数据寄存器按32位字计算，每个字从最高位开始移入，相当于对字的大端字节序列计算
+//! CRC-32/MPEG-2，与以太网/zlib使用的CRC-32（位反转）结果不同。
+//! 以8位或16位写入DR时同样按高位补0的32位字计算。
 
 #![allow(unused)]
 
 // 导入内部生成的设备驱动库
 use library::*;
+use core::ops::Range;
+use crate::bsp::flash::FLASH;
+
+/// CRC-32多项式（与硬件CRC单元相同）
+pub const CRC32_POLYNOMIAL: u32 = 0x04C1_1DB7;
+
+/// CRC计算初值（复位后DR的值）
+pub const CRC32_INIT: u32 = 0xFFFF_FFFF;
 
 /// CRC结构体
 pub struct Crc;
@@ -26,6 +40,9 @@ impl Crc {
     }
     
     /// 初始化CRC
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn init(&self) {
         let rcc = Crc::rcc();
         
@@ -36,6 +53,9 @@ impl Crc {
     }
     
     /// 重置CRC计算单元
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn reset(&self) {
         let crc = Crc::crc();
         crc.cr().write(|w: &mut library::crc::cr::W| w
@@ -43,7 +63,49 @@ impl Crc {
         );
     }
     
-    /// 计算8位数据的CRC
+    /// 送入一个32位字
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
+    pub unsafe fn feed_word(&self, word: u32) {
+        let crc = Crc::crc();
+        crc.dr().write(|w: &mut library::crc::dr::W| w
+            .dr().bits(word)
+        );
+    }
+    
+    /// 依次送入一组32位字
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
+    pub unsafe fn feed_slice(&self, words: &[u32]) {
+        for &word in words {
+            self.feed_word(word);
+        }
+    }
+    
+    /// 读取当前CRC结果
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
+    pub unsafe fn result(&self) -> u32 {
+        self.get_crc()
+    }
+    
+    /// 复位后计算一组32位字的CRC，结果与`crc32_words`相同
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且CRC单元没有被其他代码同时使用
+    pub unsafe fn checksum(&self, words: &[u32]) -> u32 {
+        self.reset();
+        self.feed_slice(words);
+        self.result()
+    }
+    
+    /// 计算8位数据的CRC（按高位补0的32位字计算）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn calculate8(&self, data: u8) -> u32 {
         let crc = Crc::crc();
         crc.dr().write(|w: &mut library::crc::dr::W| w
@@ -52,7 +114,10 @@ impl Crc {
         crc.dr().read().dr().bits()
     }
     
-    /// 计算16位数据的CRC
+    /// 计算16位数据的CRC（按高位补0的32位字计算）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn calculate16(&self, data: u16) -> u32 {
         let crc = Crc::crc();
         crc.dr().write(|w: &mut library::crc::dr::W| w
@@ -62,6 +127,9 @@ impl Crc {
     }
     
     /// 计算32位数据的CRC
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn calculate32(&self, data: u32) -> u32 {
         let crc = Crc::crc();
         crc.dr().write(|w: &mut library::crc::dr::W| w
@@ -71,6 +139,11 @@ impl Crc {
     }
     
     /// 计算数据块的CRC
+    /// 
+    /// 每个字节单独作为一个32位字送入，结果不是字节流的标准CRC-32
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn calculate_block(&self, data: &[u8]) -> u32 {
         // 重置CRC计算单元
         self.reset();
@@ -88,12 +161,18 @@ impl Crc {
     }
     
     /// 获取当前CRC值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn get_crc(&self) -> u32 {
         let crc = Crc::crc();
         crc.dr().read().dr().bits()
     }
     
     /// 写入独立数据寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保CRC单元没有被其他代码同时使用
     pub unsafe fn write_idr(&self, data: u8) {
         let crc = Crc::crc();
         crc.idr().write(|w: &mut library::crc::idr::W| w
@@ -102,11 +181,124 @@ impl Crc {
     }
     
     /// 读取独立数据寄存器
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn read_idr(&self) -> u8 {
         let crc = Crc::crc();
         crc.idr().read().idr().bits()
     }
 }
 
+impl Default for Crc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的CRC实例
 pub const CRC: Crc = Crc::new();
+
+/// 启动时校验固件完整性
+/// 
+/// 以32位字为单位把闪存区域送入硬件CRC单元，与构建时计算并写入闪存固定位置的校验值比较，
+/// 用于在启动时发现闪存损坏。期望值通常由构建脚本写在固件末尾，可用`FLASH.read_word`读出。
+/// 
+/// # 参数
+/// * `range` - 闪存地址范围，起止地址必须4字节对齐
+/// * `expected` - 期望的CRC值
+/// 
+/// # 返回值
+/// CRC一致返回true
+/// 
+/// # Safety
+/// 调用者必须确保地址范围位于有效的闪存区域内，且CRC单元没有被其他代码同时使用
+pub unsafe fn verify_firmware(range: Range<u32>, expected: u32) -> bool {
+    assert!(range.start.is_multiple_of(4) && range.end.is_multiple_of(4), "Firmware range must be word aligned");
+    
+    CRC.init();
+    CRC.reset();
+    
+    verify_region(range, expected, |address| FLASH.read_word(address), |word| CRC.calculate32(word))
+}
+
+/// 按字读取区域并计算CRC，与期望值比较
+/// 
+/// # 参数
+/// * `range` - 地址范围，步长为4字节
+/// * `expected` - 期望的CRC值
+/// * `read_word` - 读取指定地址的32位字
+/// * `feed` - 送入一个字并返回当前CRC值
+pub fn verify_region<R, F>(range: Range<u32>, expected: u32, mut read_word: R, mut feed: F) -> bool
+where
+    R: FnMut(u32) -> u32,
+    F: FnMut(u32) -> u32,
+{
+    let mut crc = CRC32_INIT;
+    for address in range.step_by(4) {
+        crc = feed(read_word(address));
+    }
+    crc == expected
+}
+
+/// 软件计算一个32位字的CRC，结果与硬件CRC单元一致
+/// 
+/// # 参数
+/// * `crc` - 当前CRC值，第一个字使用`CRC32_INIT`
+/// * `word` - 输入数据字
+pub const fn crc32_word(crc: u32, word: u32) -> u32 {
+    let mut crc = crc ^ word;
+    let mut bit = 0;
+    while bit < 32 {
+        crc = if crc & 0x8000_0000 != 0 {
+            (crc << 1) ^ CRC32_POLYNOMIAL
+        } else {
+            crc << 1
+        };
+        bit += 1;
+    }
+    crc
+}
+
+/// 软件计算一组32位字的CRC，结果与硬件CRC单元复位后依次写入这些字相同
+pub fn crc32_words(words: &[u32]) -> u32 {
+    words.iter().fold(CRC32_INIT, |crc, &word| crc32_word(crc, word))
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试软件CRC与硬件CRC单元的已知结果一致
+    #[test]
+    fn test_crc32_word_known_value() {
+        assert_eq!(crc32_word(CRC32_INIT, 0x0000_0000), 0xC704_DD7B, "复位后写入0的结果应为0xC704DD7B");
+        assert_eq!(crc32_words(&[]), CRC32_INIT, "空数据应返回初值");
+    }
+    
+    /// 测试固件区域校验：一致时通过，损坏一个字节时失败
+    #[test]
+    fn test_firmware_verification() {
+        const BASE: u32 = 0x0800_0000;
+        
+        // 模拟闪存中的固件区域，用软件CRC代替硬件CRC单元
+        fn verify(flash: &[u32], expected: u32) -> bool {
+            let mut crc = CRC32_INIT;
+            verify_region(
+                BASE..BASE + 4 * flash.len() as u32,
+                expected,
+                |address| flash[((address - BASE) / 4) as usize],
+                |word| { crc = crc32_word(crc, word); crc },
+            )
+        }
+        
+        let mut flash = [0x2000_5000, 0x0800_0101, 0x0800_0105, 0x1234_5678, 0x9ABC_DEF0];
+        let expected = crc32_words(&flash);
+        assert!(verify(&flash, expected), "未损坏的区域应校验通过");
+        
+        // 翻转一个字节中的一位
+        flash[3] ^= 0x0000_0100;
+        assert!(!verify(&flash, expected), "损坏一个字节后校验应失败");
+    }
+}
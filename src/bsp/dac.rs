@@ -1,28 +1,108 @@
 ﻿//! DAC模块
 //! 提供数模转换器功能封装
+//! 
+//! DAC只存在于大容量及互联型产品（如STM32F103RC/VE/ZE），通道1输出在PA4，
+//! 通道2输出在PA5，引脚需配置为模拟输入以避免寄生电流
 
 #![allow(unused)]
 
 // 导入内部生成的设备驱动库
 use library::*;
+use core::sync::atomic::{AtomicU16, Ordering};
+use crate::bsp::dma::{
+    Dma, DmaChannel, DmaDirection, DmaPeripheralIncrementMode, DmaMemoryIncrementMode,
+    DmaPeripheralDataSize, DmaMemoryDataSize, DmaChannelPriority, DmaCircularMode,
+};
+use crate::bsp::timer::{MasterMode, Timer, TimerNumber};
+
+/// 默认参考电压（毫伏）
+pub const DAC_DEFAULT_VREF_MV: u16 = 3300;
+
+/// 当前参考电压（毫伏），所有DAC通道共用VREF+
+static DAC_VREF_MV: AtomicU16 = AtomicU16::new(DAC_DEFAULT_VREF_MV);
+
+/// DAC寄存器基地址
+const DAC_BASE: u32 = 0x4000_7400;
+
+/// 通道2的CR位相对通道1的偏移
+const CR_CHANNEL2_SHIFT: u32 = 16;
+
+/// DAC_CR中单个通道的位定义（通道1）
+const CR_EN: u32 = 1 << 0;
+const CR_BOFF: u32 = 1 << 1;
+const CR_TEN: u32 = 1 << 2;
+const CR_TSEL_SHIFT: u32 = 3;
+const CR_TSEL_MASK: u32 = 0b111 << CR_TSEL_SHIFT;
+const CR_DMAEN: u32 = 1 << 12;
+
+/// 单个通道在DAC_CR中占用的配置位（不含波形生成）
+const CR_CHANNEL_MASK: u32 = CR_EN | CR_BOFF | CR_TEN | CR_TSEL_MASK;
 
 /// DAC通道枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DacChannel {
-    Channel1 = 0,
-    Channel2 = 1,
+    Channel1 = 0,   // PA4
+    Channel2 = 1,   // PA5
 }
 
-/// DAC触发源枚举
+/// DAC触发源枚举，值为TSEL字段
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DacTriggerSource {
-    Software = 0,
-    Timer2TRGO = 1,
-    Timer4TRGO = 2,
+    Timer6TRGO = 0,
+    Timer8TRGO = 1,     // 互联型产品上为TIM3 TRGO
+    Timer7TRGO = 2,
     Timer5TRGO = 3,
-    Timer6TRGO = 4,
-    Timer7TRGO = 5,
+    Timer2TRGO = 4,
+    Timer4TRGO = 5,
     Exti9 = 6,
+    Software = 7,
+}
+
+/// DAC错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DacError {
+    UnsupportedTimer,   // 该定时器的TRGO不能触发DAC
+    InvalidSample,      // 样本超出数据格式的范围
+    EmptySamples,       // 样本为空或超过65535个
+}
+
+/// DAC数据格式（对齐方式）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DacAlignment {
+    Right12,    // 12位右对齐（DHR12Rx）
+    Left12,     // 12位左对齐（DHR12Lx）
+    Right8,     // 8位右对齐（DHR8Rx）
+}
+
+impl DacAlignment {
+    /// 获取通道对应的数据保持寄存器地址
+    pub const fn dhr_address(&self, channel: DacChannel) -> u32 {
+        let offset = match self {
+            DacAlignment::Right12 => 0x08,
+            DacAlignment::Left12 => 0x0C,
+            DacAlignment::Right8 => 0x10,
+        };
+        // 通道2的三个数据保持寄存器紧跟在通道1之后
+        DAC_BASE + offset + channel as u32 * 0x0C
+    }
+    
+    /// 获取该格式下的最大数据值
+    pub const fn max_value(&self) -> u16 {
+        match self {
+            DacAlignment::Right12 => 0x0FFF,
+            DacAlignment::Left12 => 0xFFF0,
+            DacAlignment::Right8 => 0x00FF,
+        }
+    }
+    
+    /// 把12位（8位格式为8位）数据编码为写入数据保持寄存器的值，超出范围时取最大值
+    pub const fn encode(&self, value: u16) -> u16 {
+        match self {
+            DacAlignment::Right12 => if value > 0x0FFF { 0x0FFF } else { value },
+            DacAlignment::Left12 => (if value > 0x0FFF { 0x0FFF } else { value }) << 4,
+            DacAlignment::Right8 => if value > 0x00FF { 0x00FF } else { value },
+        }
+    }
 }
 
 /// DAC结构体
@@ -45,6 +125,9 @@ impl Dac {
     }
     
     /// 初始化DAC
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn init(&self) {
         let rcc = Dac::rcc();
         
@@ -54,7 +137,165 @@ impl Dac {
         );
     }
     
+    /// 初始化并启用DAC通道
+    /// 
+    /// 启用DAC时钟，配置触发源和输出缓冲后使能通道。对应引脚（PA4/PA5）需预先配置为模拟输入。
+    /// 
+    /// # 参数
+    /// * `channel` - DAC通道
+    /// * `trigger` - 触发源，None表示写入数据保持寄存器后一个APB1周期即输出
+    /// * `output_buffer` - 是否启用输出缓冲（可直接驱动负载，但输出范围约为0.2V到VREF+ - 0.2V）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
+    pub unsafe fn init_channel(&self, channel: DacChannel, trigger: Option<DacTriggerSource>, output_buffer: bool) {
+        self.init();
+        
+        let (mask, value) = Self::channel_cr_bits(channel, trigger, output_buffer);
+        let dac = Dac::dac();
+        dac.cr().modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) });
+    }
+    
+    /// 计算通道配置在DAC_CR中的掩码和值
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：包含EN、BOFF、TEN、TSEL，通道2的位左移16位
+    pub const fn channel_cr_bits(channel: DacChannel, trigger: Option<DacTriggerSource>, output_buffer: bool) -> (u32, u32) {
+        let mut value = CR_EN;
+        if !output_buffer {
+            value |= CR_BOFF;
+        }
+        if let Some(source) = trigger {
+            value |= CR_TEN | ((source as u32) << CR_TSEL_SHIFT);
+        }
+        let shift = channel as u32 * CR_CHANNEL2_SHIFT;
+        (CR_CHANNEL_MASK << shift, value << shift)
+    }
+    
+    /// 按指定格式写入通道数据
+    /// 
+    /// # 参数
+    /// * `channel` - DAC通道
+    /// * `alignment` - 数据格式
+    /// * `value` - 12位格式为0~4095，8位格式为0~255，超出范围时取最大值
+    /// 
+    /// `set_channel_data`等按格式命名的方法都通过此方法写入数据保持寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
+    pub unsafe fn set_value(&self, channel: DacChannel, alignment: DacAlignment, value: u16) {
+        (alignment.dhr_address(channel) as *mut u32).write_volatile(alignment.encode(value) as u32);
+    }
+    
+    /// 按指定参考电压设置输出电压（毫伏）
+    /// 
+    /// 与`set_voltage_mv`相同，但不使用全局参考电压
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
+    pub unsafe fn set_voltage(&self, channel: DacChannel, mv: u16, vref_mv: u16) {
+        assert!(vref_mv > 0, "VREF must be greater than 0");
+        self.set_channel_data(channel, Self::millivolts_to_code(mv, vref_mv));
+    }
+    
+    /// 启动DMA波形输出
+    /// 
+    /// 定时器每次更新事件经TRGO触发一次DAC转换，DMA以循环模式把下一个样本搬运到
+    /// 数据保持寄存器，波形输出不占用CPU。样本率由定时器的PSC/ARR决定，
+    /// 调用前需用`Timer::init`配置；定时器会被设置为更新事件主模式并启动。
+    /// 
+    /// # 参数
+    /// * `channel` - DAC通道，通道1使用DMA2通道3，通道2使用DMA2通道4
+    /// * `alignment` - 样本的数据格式，样本按该格式直接写入对应的数据保持寄存器
+    /// * `samples` - 一个周期的样本，DMA循环读取
+    /// * `timer` - 触发定时器，本库支持的定时器中只有TIM2和TIM4能触发DAC
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DMA2对应通道和定时器未被其他代码占用
+    pub unsafe fn start_waveform_dma(
+        &self,
+        channel: DacChannel,
+        alignment: DacAlignment,
+        samples: &'static [u16],
+        timer: TimerNumber,
+    ) -> Result<(), DacError> {
+        let trigger = Self::trigger_for_timer(timer).ok_or(DacError::UnsupportedTimer)?;
+        Self::check_samples(samples, alignment)?;
+        
+        self.init_channel(channel, Some(trigger), true);
+        
+        let dma = Self::waveform_dma(channel);
+        dma.init(
+            DmaDirection::MemoryToPeripheral,
+            DmaPeripheralIncrementMode::Disabled,
+            DmaMemoryIncrementMode::Enabled,
+            DmaPeripheralDataSize::HalfWord,
+            DmaMemoryDataSize::HalfWord,
+            DmaChannelPriority::High,
+            DmaCircularMode::Enabled,
+        );
+        dma.clear_flags();
+        dma.configure_transfer(alignment.dhr_address(channel), samples.as_ptr() as u32, samples.len() as u16);
+        dma.enable();
+        
+        let dmaen = CR_DMAEN << (channel as u32 * CR_CHANNEL2_SHIFT);
+        let dac = Dac::dac();
+        dac.cr().modify(|r, w| unsafe { w.bits(r.bits() | dmaen) });
+        
+        let timer = Timer::new(timer);
+        timer.set_master_mode(MasterMode::Update);
+        timer.start();
+        Ok(())
+    }
+    
+    /// 停止DMA波形输出，通道保持使能并输出最后一个样本
+    /// 
+    /// 触发定时器不会被停止，可能仍被其他通道使用
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DMA2对应通道和定时器未被其他代码占用
+    pub unsafe fn stop_waveform_dma(&self, channel: DacChannel) {
+        let dmaen = CR_DMAEN << (channel as u32 * CR_CHANNEL2_SHIFT);
+        let dac = Dac::dac();
+        dac.cr().modify(|r, w| unsafe { w.bits(r.bits() & !dmaen) });
+        Self::waveform_dma(channel).disable();
+    }
+    
+    /// 获取定时器TRGO对应的DAC触发源，不能触发DAC的定时器返回None
+    pub const fn trigger_for_timer(timer: TimerNumber) -> Option<DacTriggerSource> {
+        match timer {
+            TimerNumber::TIM2 => Some(DacTriggerSource::Timer2TRGO),
+            TimerNumber::TIM4 => Some(DacTriggerSource::Timer4TRGO),
+            TimerNumber::TIM1 | TimerNumber::TIM3 => None,
+        }
+    }
+    
+    /// 获取DAC通道的DMA请求所连接的DMA通道
+    pub const fn waveform_dma(channel: DacChannel) -> Dma {
+        match channel {
+            DacChannel::Channel1 => Dma::new(2, DmaChannel::Channel3),
+            DacChannel::Channel2 => Dma::new(2, DmaChannel::Channel4),
+        }
+    }
+    
+    /// 检查样本是否符合数据格式
+    /// 
+    /// 12位右对齐不超过4095，12位左对齐低4位为0，8位右对齐不超过255
+    pub fn check_samples(samples: &[u16], alignment: DacAlignment) -> Result<(), DacError> {
+        if samples.is_empty() || samples.len() > u16::MAX as usize {
+            return Err(DacError::EmptySamples);
+        }
+        let max = alignment.max_value();
+        if samples.iter().any(|&sample| sample & !max != 0) {
+            return Err(DacError::InvalidSample);
+        }
+        Ok(())
+    }
+    
     /// 启用DAC通道
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn enable_channel(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -73,6 +314,9 @@ impl Dac {
     }
     
     /// 禁用DAC通道
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn disable_channel(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -91,6 +335,9 @@ impl Dac {
     }
     
     /// 启用DAC通道触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn enable_trigger(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -109,6 +356,9 @@ impl Dac {
     }
     
     /// 禁用DAC通道触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn disable_trigger(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -127,6 +377,9 @@ impl Dac {
     }
     
     /// 设置DAC通道触发源
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn set_trigger_source(&self, channel: DacChannel, source: DacTriggerSource) {
         let dac = Dac::dac();
         
@@ -145,6 +398,9 @@ impl Dac {
     }
     
     /// 启用DAC通道输出缓冲
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn enable_output_buffer(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -163,6 +419,9 @@ impl Dac {
     }
     
     /// 禁用DAC通道输出缓冲
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn disable_output_buffer(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -181,6 +440,9 @@ impl Dac {
     }
     
     /// 软件触发DAC转换
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn software_trigger(&self, channel: DacChannel) {
         let dac = Dac::dac();
         
@@ -198,63 +460,34 @@ impl Dac {
         }
     }
     
-    /// 设置DAC通道12位右对齐数据
+    /// 设置DAC通道12位右对齐数据，超过4095时取4095
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn set_channel_data(&self, channel: DacChannel, value: u16) {
-        let dac = Dac::dac();
-        let value_clamped = if value > 4095 { 4095 } else { value };
-        
-        match channel {
-            DacChannel::Channel1 => {
-                dac.dhr12r1().write(|w: &mut library::dac::dhr12r1::W| w
-                    .dacc1dhr().bits(value_clamped)
-                );
-            }
-            DacChannel::Channel2 => {
-                dac.dhr12r2().write(|w: &mut library::dac::dhr12r2::W| w
-                    .dacc2dhr().bits(value_clamped)
-                );
-            }
-        }
+        self.set_value(channel, DacAlignment::Right12, value);
     }
     
-    /// 设置DAC通道12位左对齐数据
+    /// 设置DAC通道12位左对齐数据，`value`为12位数据，超过4095时取4095
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn set_channel_data_left_aligned(&self, channel: DacChannel, value: u16) {
-        let dac = Dac::dac();
-        let value_clamped = if value > 4095 { 4095 } else { value };
-        
-        match channel {
-            DacChannel::Channel1 => {
-                dac.dhr12l1().write(|w: &mut library::dac::dhr12l1::W| w
-                    .dacc1dhr().bits(value_clamped << 4)
-                );
-            }
-            DacChannel::Channel2 => {
-                dac.dhr12l2().write(|w: &mut library::dac::dhr12l2::W| w
-                    .dacc2dhr().bits(value_clamped << 4)
-                );
-            }
-        }
+        self.set_value(channel, DacAlignment::Left12, value);
     }
     
     /// 设置DAC通道8位右对齐数据
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn set_channel_data_8bit(&self, channel: DacChannel, value: u8) {
-        let dac = Dac::dac();
-        
-        match channel {
-            DacChannel::Channel1 => {
-                dac.dhr8r1().write(|w: &mut library::dac::dhr8r1::W| w
-                    .dacc1dhr().bits(value as u8)
-                );
-            }
-            DacChannel::Channel2 => {
-                dac.dhr8r2().write(|w: &mut library::dac::dhr8r2::W| w
-                    .dacc2dhr().bits(value as u8)
-                );
-            }
-        }
+        self.set_value(channel, DacAlignment::Right8, value as u16);
     }
     
     /// 设置双通道12位右对齐数据
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
     pub unsafe fn set_dual_channel_data(&self, value1: u16, value2: u16) {
         let dac = Dac::dac();
         let value1_clamped = if value1 > 4095 { 4095 } else { value1 };
@@ -266,7 +499,47 @@ impl Dac {
         );
     }
     
+    /// 设置参考电压VREF+（毫伏），用于`set_voltage_mv`换算
+    /// 
+    /// 36/48脚封装的VREF+内部连接到VDDA
+    pub fn set_vref_mv(&self, vref_mv: u16) {
+        assert!(vref_mv > 0, "VREF must be greater than 0");
+        DAC_VREF_MV.store(vref_mv, Ordering::Relaxed);
+    }
+    
+    /// 获取当前配置的参考电压（毫伏）
+    pub fn vref_mv(&self) -> u16 {
+        DAC_VREF_MV.load(Ordering::Relaxed)
+    }
+    
+    /// 按毫伏设置DAC通道输出电压
+    /// 
+    /// 超过参考电压时输出满量程。启用输出缓冲时输出范围约为0.2V到VREF+ - 0.2V。
+    /// 
+    /// # 参数
+    /// * `channel` - DAC通道
+    /// * `mv` - 输出电压（毫伏）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DAC通道未被其他代码同时配置
+    pub unsafe fn set_voltage_mv(&self, channel: DacChannel, mv: u16) {
+        self.set_channel_data(channel, Self::millivolts_to_code(mv, self.vref_mv()));
+    }
+    
+    /// 将毫伏换算为12位DAC码值（四舍五入）
+    /// 
+    /// 输出电压 = VREF+ × 码值 / 4095
+    pub const fn millivolts_to_code(mv: u16, vref_mv: u16) -> u16 {
+        if mv >= vref_mv {
+            return 4095;
+        }
+        ((mv as u32 * 4095 + vref_mv as u32 / 2) / vref_mv as u32) as u16
+    }
+    
     /// 获取DAC通道数据输出
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_channel_output(&self, channel: DacChannel) -> u16 {
         let dac = Dac::dac();
         
@@ -281,5 +554,81 @@ impl Dac {
     }
 }
 
+impl Default for Dac {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的DAC实例
 pub const DAC: Dac = Dac::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试毫伏到DAC码值的换算和舍入
+    #[test]
+    fn test_millivolts_to_code() {
+        assert_eq!(Dac::millivolts_to_code(0, 3300), 0, "0mV应为码值0");
+        assert_eq!(Dac::millivolts_to_code(1650, 3300), 2048, "半量程应四舍五入到2048");
+        assert_eq!(Dac::millivolts_to_code(1000, 3300), 1241, "1000mV码值错误");
+        assert_eq!(Dac::millivolts_to_code(1, 3300), 1, "1mV应舍入到码值1");
+        assert_eq!(Dac::millivolts_to_code(3300, 3300), 4095, "等于参考电压时应为满量程");
+        assert_eq!(Dac::millivolts_to_code(5000, 3300), 4095, "超过参考电压时应限制为满量程");
+        
+        // 2.5V外部基准
+        assert_eq!(Dac::millivolts_to_code(1250, 2500), 2048, "2.5V基准下半量程码值错误");
+    }
+    
+    /// 测试通道配置位和数据保持寄存器选择
+    #[test]
+    fn test_channel_config_and_alignment() {
+        let (mask, value) = Dac::channel_cr_bits(DacChannel::Channel1, Some(DacTriggerSource::Timer6TRGO), true);
+        assert_eq!(mask, 0x0000_003F, "通道1应只修改低6位");
+        assert_eq!(value, CR_EN | CR_TEN, "TIM6触发时TSEL应为000");
+        
+        let (mask, value) = Dac::channel_cr_bits(DacChannel::Channel2, Some(DacTriggerSource::Software), false);
+        assert_eq!(mask, 0x003F_0000, "通道2应左移16位");
+        assert_eq!(value, (CR_EN | CR_BOFF | CR_TEN | (0b111 << CR_TSEL_SHIFT)) << 16, "软件触发时TSEL应为111");
+        
+        let (_, value) = Dac::channel_cr_bits(DacChannel::Channel1, None, true);
+        assert_eq!(value, CR_EN, "无触发时不应置位TEN");
+        
+        assert_eq!(DacAlignment::Right12.dhr_address(DacChannel::Channel1), 0x4000_7408, "DHR12R1地址错误");
+        assert_eq!(DacAlignment::Left12.dhr_address(DacChannel::Channel1), 0x4000_740C, "DHR12L1地址错误");
+        assert_eq!(DacAlignment::Right8.dhr_address(DacChannel::Channel2), 0x4000_741C, "DHR8R2地址错误");
+        assert_eq!(DacAlignment::Right12.dhr_address(DacChannel::Channel2), 0x4000_7414, "DHR12R2地址错误");
+        
+        assert_eq!(DacAlignment::Right12.encode(5000), 0x0FFF, "12位数据应限制在4095");
+        assert_eq!(DacAlignment::Left12.encode(0x0ABC), 0xABC0, "左对齐应左移4位");
+        assert_eq!(DacAlignment::Right8.encode(300), 0x00FF, "8位数据应限制在255");
+    }
+    
+    /// 测试波形输出的触发源、DMA通道和样本检查
+    #[test]
+    fn test_waveform_dma_config() {
+        assert_eq!(Dac::trigger_for_timer(TimerNumber::TIM2), Some(DacTriggerSource::Timer2TRGO), "TIM2应可触发DAC");
+        assert_eq!(Dac::trigger_for_timer(TimerNumber::TIM4), Some(DacTriggerSource::Timer4TRGO), "TIM4应可触发DAC");
+        assert_eq!(Dac::trigger_for_timer(TimerNumber::TIM3), None, "TIM3不能触发DAC");
+        
+        assert_eq!(
+            Dac::waveform_dma(DacChannel::Channel1).channel_regs() as u32,
+            Dma::channel_base_address(2, DmaChannel::Channel3),
+            "通道1应使用DMA2通道3"
+        );
+        assert_eq!(
+            Dac::waveform_dma(DacChannel::Channel2).channel_regs() as u32,
+            Dma::channel_base_address(2, DmaChannel::Channel4),
+            "通道2应使用DMA2通道4"
+        );
+        
+        let sine = [2048, 3495, 4095, 3495, 2048, 600, 0, 600];
+        assert_eq!(Dac::check_samples(&sine, DacAlignment::Right12), Ok(()), "12位样本应有效");
+        assert_eq!(Dac::check_samples(&sine, DacAlignment::Right8), Err(DacError::InvalidSample), "超过255的样本不能用8位格式");
+        assert_eq!(Dac::check_samples(&[0x8000, 0xFFF0], DacAlignment::Left12), Ok(()), "左对齐样本应有效");
+        assert_eq!(Dac::check_samples(&[0x8001], DacAlignment::Left12), Err(DacError::InvalidSample), "左对齐样本低4位应为0");
+        assert_eq!(Dac::check_samples(&[], DacAlignment::Right12), Err(DacError::EmptySamples), "空样本应被拒绝");
+    }
+}
@@ -6,6 +6,73 @@
 // 导入内部生成的设备驱动库
 use library::*;
 
+/// DBGMCU_IDCODE寄存器地址
+const DBGMCU_IDCODE: u32 = 0xE004_2000;
+/// DBGMCU_CR寄存器地址
+const DBGMCU_CR: u32 = 0xE004_2004;
+
+/// DBGMCU_CR中所有外设冻结位（位8~21）
+const FREEZE_MASK: u32 = 0x003F_FF00;
+
+/// 内核停机时可冻结的外设枚举
+/// 
+/// 取值即为DBGMCU_CR中对应的控制位。F1系列所有冻结位都位于DBGMCU_CR中，
+/// 置位后内核在断点处停机时对应的计数器/看门狗同步停止，I2C的SMBus超时暂停计时。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DbgFreeze {
+    IWDG = 1 << 8,                // DBG_IWDG_STOP：独立看门狗
+    WWDG = 1 << 9,                // DBG_WWDG_STOP：窗口看门狗
+    TIM1 = 1 << 10,               // DBG_TIM1_STOP
+    TIM2 = 1 << 11,               // DBG_TIM2_STOP
+    TIM3 = 1 << 12,               // DBG_TIM3_STOP
+    TIM4 = 1 << 13,               // DBG_TIM4_STOP
+    CAN1 = 1 << 14,               // DBG_CAN1_STOP
+    I2C1SmbusTimeout = 1 << 15,   // DBG_I2C1_SMBUS_TIMEOUT
+    I2C2SmbusTimeout = 1 << 16,   // DBG_I2C2_SMBUS_TIMEOUT
+    TIM8 = 1 << 17,               // DBG_TIM8_STOP
+    TIM5 = 1 << 18,               // DBG_TIM5_STOP
+    TIM6 = 1 << 19,               // DBG_TIM6_STOP
+    TIM7 = 1 << 20,               // DBG_TIM7_STOP
+    CAN2 = 1 << 21,               // DBG_CAN2_STOP
+}
+
+impl DbgFreeze {
+    /// 计算多个冻结选项组合后的DBGMCU_CR位
+    pub fn mask(peripherals: &[DbgFreeze]) -> u32 {
+        peripherals.iter().fold(0, |acc, &p| acc | p as u32)
+    }
+}
+
+/// 修改DBGMCU_CR寄存器中的指定位
+unsafe fn modify_cr(mask: u32, set: bool) {
+    let cr = DBGMCU_CR as *mut u32;
+    let value = core::ptr::read_volatile(cr);
+    if set {
+        core::ptr::write_volatile(cr, value | mask);
+    } else {
+        core::ptr::write_volatile(cr, value & !mask);
+    }
+}
+
+/// 内核调试停机时冻结指定外设
+/// 
+/// 避免断点期间看门狗复位或定时器继续运行导致的调试困惑。
+/// DBGMCU_CR只在上电复位时清零，系统复位不会清除已配置的冻结位。
+/// 
+/// # Safety
+/// 直接访问调试寄存器
+pub unsafe fn freeze(peripheral: DbgFreeze) {
+    modify_cr(peripheral as u32, true);
+}
+
+/// 取消内核调试停机时对指定外设的冻结
+/// 
+/// # Safety
+/// 直接访问调试寄存器
+pub unsafe fn unfreeze(peripheral: DbgFreeze) {
+    modify_cr(peripheral as u32, false);
+}
+
 /// DBGMCU结构体
 pub struct Dbgmcu;
 
@@ -19,136 +86,145 @@ impl Dbgmcu {
     /// 
     /// # 返回值
     /// 设备ID代码
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_device_id(&self) -> u32 {
-        // 由于内部库中没有dbgmcu模块，暂时返回固定值
-        0x00000000
+        core::ptr::read_volatile(DBGMCU_IDCODE as *const u32)
     }
     
     /// 获取设备ID
     /// 
     /// # 返回值
     /// 设备ID
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_dev_id(&self) -> u16 {
-        // 由于内部库中没有dbgmcu模块，暂时返回固定值
-        0x0000
+        (self.get_device_id() & 0x0FFF) as u16
     }
     
     /// 获取修订ID
     /// 
     /// # 返回值
     /// 修订ID
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_rev_id(&self) -> u16 {
-        // 由于内部库中没有dbgmcu模块，暂时返回固定值
-        0x0000
+        (self.get_device_id() >> 16) as u16
     }
     
     /// 启用调试停止模式
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
     pub unsafe fn enable_debug_stop(&self) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+        modify_cr(1 << 1, true);
     }
     
     /// 禁用调试停止模式
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
     pub unsafe fn disable_debug_stop(&self) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+        modify_cr(1 << 1, false);
     }
     
     /// 启用调试待机模式
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
     pub unsafe fn enable_debug_standby(&self) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+        modify_cr(1 << 2, true);
     }
     
     /// 禁用调试待机模式
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
     pub unsafe fn disable_debug_standby(&self) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+        modify_cr(1 << 2, false);
     }
     
     /// 启用调试睡眠模式
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
     pub unsafe fn enable_debug_sleep(&self) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+        modify_cr(1 << 0, true);
     }
     
     /// 禁用调试睡眠模式
-    pub unsafe fn disable_debug_sleep(&self) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
-    }
-    
-    /// 配置APB1外设调试冻结
     /// 
-    /// # 参数
-    /// * `peripherals` - 要冻结的外设
-    pub unsafe fn configure_apb1_freeze(&self, _peripherals: u32) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
+    pub unsafe fn disable_debug_sleep(&self) {
+        modify_cr(1 << 0, false);
     }
     
-    /// 配置APB2外设调试冻结
+    /// 内核调试停机时冻结指定外设，见`freeze`
     /// 
-    /// # 参数
-    /// * `peripherals` - 要冻结的外设
-    pub unsafe fn configure_apb2_freeze(&self, _peripherals: u32) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
+    pub unsafe fn freeze(&self, peripheral: DbgFreeze) {
+        freeze(peripheral);
     }
     
-    /// 启用APB1外设调试冻结
+    /// 取消对指定外设的冻结，见`unfreeze`
     /// 
-    /// # 参数
-    /// * `peripheral` - 要冻结的外设
-    pub unsafe fn enable_apb1_freeze(&self, _peripheral: u32) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
+    pub unsafe fn unfreeze(&self, peripheral: DbgFreeze) {
+        unfreeze(peripheral);
     }
     
-    /// 禁用APB1外设调试冻结
+    /// 配置调试停机时冻结的外设
     /// 
-    /// # 参数
-    /// * `peripheral` - 要禁用冻结的外设
-    pub unsafe fn disable_apb1_freeze(&self, _peripheral: u32) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
-    }
-    
-    /// 启用APB2外设调试冻结
+    /// 只冻结列出的外设，其余外设的冻结位被清除；低功耗模式调试位和跟踪配置保持不变
     /// 
     /// # 参数
-    /// * `peripheral` - 要冻结的外设
-    pub unsafe fn enable_apb2_freeze(&self, _peripheral: u32) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
-    }
-    
-    /// 禁用APB2外设调试冻结
+    /// * `peripherals` - 要冻结的外设
     /// 
-    /// # 参数
-    /// * `peripheral` - 要禁用冻结的外设
-    pub unsafe fn disable_apb2_freeze(&self, _peripheral: u32) {
-        // 由于内部库中没有dbgmcu模块，暂时为空实现
+    /// # Safety
+    /// - 调用者必须确保DBGMCU_CR未被其他代码同时修改
+    pub unsafe fn configure_freeze(&self, peripherals: &[DbgFreeze]) {
+        let cr = DBGMCU_CR as *mut u32;
+        let value = core::ptr::read_volatile(cr);
+        core::ptr::write_volatile(cr, (value & !FREEZE_MASK) | DbgFreeze::mask(peripherals));
     }
 }
 
-/// APB1外设调试冻结枚举
-pub enum Apb1DebugFreeze {
-    TIM2 = 1 << 0,     // TIM2定时器
-    TIM3 = 1 << 1,     // TIM3定时器
-    TIM4 = 1 << 2,     // TIM4定时器
-    TIM5 = 1 << 3,     // TIM5定时器
-    TIM6 = 1 << 4,     // TIM6定时器
-    TIM7 = 1 << 5,     // TIM7定时器
-    TIM12 = 1 << 6,    // TIM12定时器
-    TIM13 = 1 << 7,    // TIM13定时器
-    TIM14 = 1 << 8,    // TIM14定时器
-    RTC = 1 << 10,     // RTC实时时钟
-    WWDG = 1 << 11,    // WWDG窗口看门狗
-    IWDG = 1 << 12,    // IWDG独立看门狗
-    I2C1 = 1 << 15,     // I2C1接口
-    I2C2 = 1 << 16,     // I2C2接口
-    CAN1 = 1 << 21,     // CAN1接口
-    CAN2 = 1 << 22,     // CAN2接口
-}
-
-/// APB2外设调试冻结枚举
-pub enum Apb2DebugFreeze {
-    TIM1 = 1 << 11,     // TIM1定时器
-    TIM8 = 1 << 12,     // TIM8定时器
-    TIM9 = 1 << 19,     // TIM9定时器
-    TIM10 = 1 << 20,    // TIM10定时器
-    TIM11 = 1 << 21,    // TIM11定时器
+impl Default for Dbgmcu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// 预定义的DBGMCU实例
 pub const DBGMCU: Dbgmcu = Dbgmcu::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试冻结选项在DBGMCU_CR中的位编码
+    #[test]
+    fn test_freeze_bits() {
+        assert_eq!(DbgFreeze::IWDG as u32, 0x0000_0100, "IWDG应为DBG_IWDG_STOP（位8）");
+        assert_eq!(DbgFreeze::TIM2 as u32, 0x0000_0800, "TIM2应为DBG_TIM2_STOP（位11）");
+        assert_eq!(DbgFreeze::I2C1SmbusTimeout as u32, 0x0000_8000, "I2C1应为SMBus超时位（位15）");
+        
+        let mask = DbgFreeze::mask(&[DbgFreeze::IWDG, DbgFreeze::WWDG, DbgFreeze::TIM1]);
+        assert_eq!(mask, 0x0000_0700, "组合冻结选项编码错误");
+        assert_eq!(DbgFreeze::mask(&[]), 0, "空选项应为0");
+        
+        let all = DbgFreeze::mask(&[
+            DbgFreeze::IWDG, DbgFreeze::WWDG, DbgFreeze::TIM1, DbgFreeze::TIM2, DbgFreeze::TIM3,
+            DbgFreeze::TIM4, DbgFreeze::CAN1, DbgFreeze::I2C1SmbusTimeout, DbgFreeze::I2C2SmbusTimeout,
+            DbgFreeze::TIM8, DbgFreeze::TIM5, DbgFreeze::TIM6, DbgFreeze::TIM7, DbgFreeze::CAN2,
+        ]);
+        assert_eq!(all, FREEZE_MASK, "冻结位掩码应覆盖所有外设冻结位");
+    }
+}
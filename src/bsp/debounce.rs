@@ -0,0 +1,162 @@
+//! 按键消抖模块
+//! 提供基于定时器节拍的多路输入集中消抖服务
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::gpio::{GpioPortStruct, GpioPortBatch};
+use heapless::Deque;
+
+/// 边沿事件队列容量
+pub const DEBOUNCE_EVENT_QUEUE_SIZE: usize = 16;
+
+/// 消抖边沿类型枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebounceEdge {
+    Rising,     // 稳定状态由低变高
+    Falling,    // 稳定状态由高变低
+}
+
+/// 消抖边沿事件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebounceEvent {
+    pub index: usize,           // 输入序号
+    pub edge: DebounceEdge,     // 边沿类型
+}
+
+/// 多路输入消抖服务
+/// 
+/// 在定时器中断中周期调用`tick`同时采样最多N个引脚，每个引脚维护一个积分计数器：
+/// 采样为高时加1、为低时减1，计数到达`threshold`时稳定状态变为高，回到0时变为低。
+/// 稳定状态变化时产生边沿事件，主循环通过`pop_event`取出。
+/// 
+/// 消抖时间约为`threshold × 节拍周期`，例如1ms节拍、threshold为20时约20ms。
+pub struct DebounceService<const N: usize> {
+    pins: [GpioPortStruct; N],
+    threshold: u8,
+    integrators: [u8; N],
+    stable: [bool; N],
+    events: Deque<DebounceEvent, DEBOUNCE_EVENT_QUEUE_SIZE>,
+}
+
+impl<const N: usize> DebounceService<N> {
+    /// 创建消抖服务
+    /// 
+    /// # 参数
+    /// * `pins` - 输入引脚，序号即数组下标
+    /// * `threshold` - 积分阈值（节拍数），至少为1
+    pub fn new(pins: [GpioPortStruct; N], threshold: u8) -> Self {
+        assert!(threshold > 0, "Debounce threshold must be at least 1");
+        assert!(N <= 32, "Debounce service supports at most 32 inputs");
+        Self {
+            pins,
+            threshold,
+            integrators: [0; N],
+            stable: [false; N],
+            events: Deque::new(),
+        }
+    }
+    
+    /// 采样所有引脚并更新消抖状态，应在定时器中断中周期调用
+    /// # Safety
+    /// - 调用者必须确保引脚已配置为输入且端口时钟已启用
+    pub unsafe fn tick(&mut self) {
+        let mut samples = 0u32;
+        for (index, pin) in self.pins.iter().enumerate() {
+            if GpioPortBatch::new(pin.port).read_input_data() & (1 << pin.pin) != 0 {
+                samples |= 1 << index;
+            }
+        }
+        self.update(samples);
+    }
+    
+    /// 按一次采样结果更新积分计数器
+    /// 
+    /// # 参数
+    /// * `samples` - 采样值，位i对应第i个输入
+    pub fn update(&mut self, samples: u32) {
+        for index in 0..N {
+            let high = samples & (1 << index) != 0;
+            let integrator = &mut self.integrators[index];
+            
+            if high {
+                if *integrator < self.threshold {
+                    *integrator += 1;
+                }
+            } else if *integrator > 0 {
+                *integrator -= 1;
+            }
+            
+            let new_state = if *integrator >= self.threshold {
+                true
+            } else if *integrator == 0 {
+                false
+            } else {
+                self.stable[index]
+            };
+            
+            if new_state != self.stable[index] {
+                self.stable[index] = new_state;
+                let edge = if new_state { DebounceEdge::Rising } else { DebounceEdge::Falling };
+                // 队列满时丢弃最早的事件，保留最新状态
+                if self.events.is_full() {
+                    self.events.pop_front();
+                }
+                let _ = self.events.push_back(DebounceEvent { index, edge });
+            }
+        }
+    }
+    
+    /// 获取输入的稳定状态
+    /// 
+    /// # 参数
+    /// * `index` - 输入序号
+    pub fn stable_state(&self, index: usize) -> bool {
+        self.stable[index]
+    }
+    
+    /// 取出最早的边沿事件
+    pub fn pop_event(&mut self) -> Option<DebounceEvent> {
+        self.events.pop_front()
+    }
+    
+    /// 获取待处理的边沿事件数
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::bsp::gpio::{PA0, PA1};
+    
+    /// 测试积分器从抖动的多路输入得到稳定状态
+    #[test]
+    fn test_integrator_stable_states() {
+        let mut service = DebounceService::new([PA0, PA1], 3);
+        
+        // 输入0抖动后稳定为高，输入1持续为低并伴有单次毛刺
+        let samples = [0b01, 0b00, 0b01, 0b01, 0b11, 0b01, 0b01];
+        for (tick, &sample) in samples.iter().enumerate() {
+            service.update(sample);
+            if tick < 4 {
+                assert!(!service.stable_state(0), "第{}次采样时输入0尚未稳定", tick);
+            }
+            assert!(!service.stable_state(1), "单次毛刺不应改变输入1的状态");
+        }
+        assert!(service.stable_state(0), "输入0应稳定为高");
+        assert_eq!(service.pop_event(), Some(DebounceEvent { index: 0, edge: DebounceEdge::Rising }), "应产生输入0上升沿事件");
+        assert_eq!(service.pop_event(), None, "不应产生其他事件");
+        
+        // 输入0短暂变低不足以翻转，持续变低后产生下降沿
+        service.update(0b00);
+        assert!(service.stable_state(0), "短暂变低不应翻转");
+        service.update(0b00);
+        service.update(0b00);
+        assert!(!service.stable_state(0), "持续变低后应稳定为低");
+        assert_eq!(service.pop_event(), Some(DebounceEvent { index: 0, edge: DebounceEdge::Falling }), "应产生输入0下降沿事件");
+    }
+}
@@ -5,8 +5,9 @@
 #![allow(unused)]
 
 use core::sync::atomic::{AtomicU32, Ordering};
-use core::arch::asm;
 use core::time::Duration;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use crate::bsp::rcc::RccDriver;
 
 /// 系统运行时间计数器（毫秒）
 static SYSTEM_TICK: AtomicU32 = AtomicU32::new(0);
@@ -17,12 +18,18 @@ static mut SYSTICK_RELOAD: u32 = 0;
 /// 系统时钟频率（Hz）
 static mut SYSTEM_CLOCK: u32 = 72_000_000;
 
+/// SysTick LOAD寄存器的最大值（24位）
+pub const SYSTICK_MAX_RELOAD: u32 = 0x00FF_FFFF;
+
+/// 长延时每段的最大计数，对应一次完整的24位重装载周期
+const SYSTICK_SEGMENT_TICKS: u32 = SYSTICK_MAX_RELOAD + 1;
+
 /// 初始化系统滴答定时器
 /// 
 /// 配置SysTick为1kHz，根据实际系统时钟频率计算重装载值
 /// 
 /// # Arguments
-/// * `sysclk` - 系统时钟频率（Hz），如果为0则自动检测
+/// * `sysclk` - 系统时钟频率（Hz），如果为0则由RCC配置计算
 /// 
 /// # Safety
 /// 直接访问硬件寄存器，需要确保在正确的上下文中调用
@@ -34,23 +41,15 @@ pub unsafe fn init_systick(sysclk: u32) {
     let actual_sysclk = if sysclk > 0 {
         sysclk
     } else {
-        // 读取系统时钟配置，确定当前系统时钟频率
-        let rcc_cfgr = core::ptr::read_volatile(0x40021004 as *const u32);
-        let sysclk_source = rcc_cfgr & 0x0C;
-        
-        if sysclk_source == 0x08 {
-            // PLL作为系统时钟源（72MHz）
-            72_000_000
-        } else {
-            // HSI作为系统时钟源（8MHz）
-            8_000_000
-        }
+        // 按RCC当前的时钟源、PLL倍频计算系统时钟频率
+        RccDriver::new().get_clocks_freq().sysclk_frequency
     };
     
     SYSTEM_CLOCK = actual_sysclk;
     
     // 计算重装载值（1kHz）
     let reload_value = (actual_sysclk / 1000) - 1;
+    assert!(reload_value <= SYSTICK_MAX_RELOAD, "SysTick reload exceeds 24 bits");
     SYSTICK_RELOAD = reload_value;
     
     if (csr & 0x01) == 0 {
@@ -101,9 +100,8 @@ pub fn get_uptime_us() -> u64 {
 
 /// 基于系统时钟的延时函数（微秒）
 /// 
-/// 实现高精度的微秒级延时，结合SysTick和空循环
-/// 对于大于1ms的延时，使用SysTick的COUNTFLAG标志
-/// 对于小于1ms的延时，使用精确的空循环
+/// 按系统时钟频率把延时换算为SysTick计数，轮询SysTick当前值累计经过的计数，
+/// 不依赖固定的72MHz假设和循环指令周期
 /// 
 /// # Arguments
 /// * `us` - 延时时间，单位：微秒
@@ -120,51 +118,75 @@ pub unsafe fn delay_us(us: u32) {
         return;
     }
     
-    // 对于大于1ms的延时，使用SysTick的COUNTFLAG标志
-    if us >= 1000 {
-        let ms = us / 1000;
-        delay_ms(ms);
-        
-        // 处理剩余的微秒
-        let remaining_us = us % 1000;
-        if remaining_us > 0 {
-            delay_us_precise(remaining_us);
-        }
-    } else {
-        // 对于小于1ms的延时，使用精确的空循环
-        delay_us_precise(us);
-    }
+    wait_systick_ticks(us_to_ticks(us, SYSTEM_CLOCK));
 }
 
-/// 精确的微秒级延时，基于空循环
+/// 等待SysTick经过指定的计数
 /// 
-/// 根据当前系统时钟频率计算循环次数
+/// 长延时按`systick_segments`分为多段，每段不超过一次24位重装载周期
 /// 
-/// # Arguments
-/// * `us` - 延时时间，单位：微秒
+/// # Safety
+/// 直接访问硬件寄存器，需要确保SysTick已启用
+unsafe fn wait_systick_ticks(ticks: u64) {
+    let (segments, remainder) = systick_segments(ticks);
+    for _ in 0..segments {
+        wait_systick_segment(SYSTICK_SEGMENT_TICKS);
+    }
+    wait_systick_segment(remainder);
+}
+
+/// 等待SysTick经过一段计数
+/// 
+/// SysTick为24位递减计数器，由VAL的变化累计经过的计数，重装载值从LOAD寄存器读取，
+/// 两次读取之间SysTick回绕不超过一次即可正确计算
 /// 
 /// # Safety
-/// 使用内联汇编，需要确保在正确的上下文中调用
-#[inline(always)]
-unsafe fn delay_us_precise(us: u32) {
-    // 根据系统时钟频率计算循环次数
-    let cycles_per_us = SYSTEM_CLOCK / 1_000_000;
-    let total_cycles = us as u32 * cycles_per_us;
+/// 直接访问硬件寄存器，需要确保SysTick已启用
+unsafe fn wait_systick_segment(ticks: u32) {
+    let reload = core::ptr::read_volatile(0xE000E014 as *const u32) & SYSTICK_MAX_RELOAD;
+    let mut last = core::ptr::read_volatile(0xE000E018 as *const u32);
+    let mut elapsed: u32 = 0;
     
-    // 使用内联汇编实现精确的空循环
-    asm!(
-        "mov r0, {cycles}",
-        "0:",
-        "subs r0, r0, #1",
-        "bne 0b",
-        cycles = in(reg) total_cycles,
-        options(nomem, nostack, preserves_flags),
-    );
+    while elapsed < ticks {
+        let now = core::ptr::read_volatile(0xE000E018 as *const u32);
+        elapsed = elapsed.saturating_add(systick_elapsed(last, now, reload));
+        last = now;
+    }
+}
+
+/// 将延时计数分段，每段对应的重装载值（计数 - 1）不超过24位
+/// 
+/// # Returns
+/// (完整段数, 最后一段的计数)，完整段的计数为0x0100_0000
+pub const fn systick_segments(ticks: u64) -> (u32, u32) {
+    let segment = SYSTICK_SEGMENT_TICKS as u64;
+    ((ticks / segment) as u32, (ticks % segment) as u32)
+}
+
+/// 计算SysTick两次读数之间经过的计数
+/// 
+/// # Arguments
+/// * `last` - 上次读取的VAL
+/// * `now` - 本次读取的VAL
+/// * `reload` - LOAD重装载值
+pub const fn systick_elapsed(last: u32, now: u32, reload: u32) -> u32 {
+    if now <= last {
+        last - now
+    } else {
+        // 递减到0后重装载为reload，期间经过last + 1 + (reload - now)个计数
+        last + 1 + (reload - now)
+    }
+}
+
+/// 将微秒换算为系统时钟计数
+pub const fn us_to_ticks(us: u32, sysclk: u32) -> u64 {
+    us as u64 * sysclk as u64 / 1_000_000
 }
 
 /// 基于系统时钟的延时函数（毫秒）
 /// 
-/// 使用SysTick的COUNTFLAG标志实现精确的毫秒级延时，不依赖中断
+/// 按系统时钟频率换算为SysTick计数，超过24位的长延时分段等待，不依赖中断，
+/// 也不依赖SysTick当前的重装载值（与RTOS或引导程序共用SysTick时同样准确）
 /// 
 /// # Arguments
 /// * `ms` - 延时时间，单位：毫秒
@@ -177,14 +199,12 @@ pub unsafe fn delay_ms(ms: u32) {
         init_systick(0);
     }
     
-    // 使用SysTick的COUNTFLAG标志实现延时
-    for _ in 0..ms {
-        // 等待SysTick计数完成
-        while (core::ptr::read_volatile(0xE000E010 as *const u32) & (1 << 16)) == 0 {
-            core::sync::atomic::compiler_fence(Ordering::SeqCst);
-        }
-        // COUNTFLAG被读取后会自动清零，无需额外操作
-    }
+    wait_systick_ticks(ms_to_ticks(ms, SYSTEM_CLOCK));
+}
+
+/// 将毫秒换算为系统时钟计数
+pub const fn ms_to_ticks(ms: u32, sysclk: u32) -> u64 {
+    ms as u64 * sysclk as u64 / 1000
 }
 
 /// 基于系统时钟的延时函数（使用Duration）
@@ -208,6 +228,9 @@ pub unsafe fn delay(duration: Duration) {
 
 /// 基于系统时钟的超时函数，返回是否超时
 /// 
+/// 与`delay_us`相同，由SysTick VAL的变化累计经过的计数，不依赖SysTick中断，
+/// 每次检查条件的耗时不应超过一个SysTick重装载周期
+/// 
 /// # Arguments
 /// * `timeout_us` - 超时时间，单位：微秒
 /// * `condition` - 要检查的条件，返回true表示条件满足
@@ -227,8 +250,10 @@ where
         init_systick(0);
     }
     
-    // 记录开始时间
-    let start_time = get_uptime_us();
+    let timeout_ticks = us_to_ticks(timeout_us, SYSTEM_CLOCK);
+    let reload = core::ptr::read_volatile(0xE000E014 as *const u32) & SYSTICK_MAX_RELOAD;
+    let mut last = core::ptr::read_volatile(0xE000E018 as *const u32);
+    let mut elapsed: u64 = 0;
     
     // 等待条件满足或超时
     loop {
@@ -236,9 +261,11 @@ where
             return false; // 条件满足，未超时
         }
         
-        // 检查是否超时
-        let current_time = get_uptime_us();
-        if current_time.wrapping_sub(start_time) >= timeout_us as u64 {
+        // 累计经过的计数并检查是否超时
+        let now = core::ptr::read_volatile(0xE000E018 as *const u32);
+        elapsed += systick_elapsed(last, now, reload) as u64;
+        last = now;
+        if elapsed >= timeout_ticks {
             return true; // 超时
         }
         
@@ -293,6 +320,70 @@ impl Timestamp {
     }
 }
 
+/// 基于SysTick的阻塞延时器
+/// 
+/// 创建时记录系统时钟频率，延时按该频率换算为SysTick计数，
+/// 系统时钟不是72MHz时延时也是准确的。实现embedded-hal的`DelayMs`和`DelayUs`特性，可直接传给通用驱动。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delay {
+    sysclk: u32,
+}
+
+impl Delay {
+    /// 创建延时器
+    /// 
+    /// 由RCC配置计算当前系统时钟频率，SysTick未启用时按该频率初始化
+    /// 
+    /// # Safety
+    /// 直接访问硬件寄存器，创建后修改系统时钟需要重新创建
+    pub unsafe fn new() -> Self {
+        let sysclk = RccDriver::new().get_clocks_freq().sysclk_frequency;
+        init_systick(sysclk);
+        Self { sysclk }
+    }
+    
+    /// 获取创建时记录的系统时钟频率（Hz）
+    pub fn sysclk(&self) -> u32 {
+        self.sysclk
+    }
+}
+
+impl DelayUs<u32> for Delay {
+    fn delay_us(&mut self, us: u32) {
+        unsafe { wait_systick_ticks(us_to_ticks(us, self.sysclk)) };
+    }
+}
+
+impl DelayUs<u16> for Delay {
+    fn delay_us(&mut self, us: u16) {
+        DelayUs::<u32>::delay_us(self, us as u32);
+    }
+}
+
+impl DelayUs<u8> for Delay {
+    fn delay_us(&mut self, us: u8) {
+        DelayUs::<u32>::delay_us(self, us as u32);
+    }
+}
+
+impl DelayMs<u32> for Delay {
+    fn delay_ms(&mut self, ms: u32) {
+        unsafe { wait_systick_ticks(ms_to_ticks(ms, self.sysclk)) };
+    }
+}
+
+impl DelayMs<u16> for Delay {
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::<u32>::delay_ms(self, ms as u32);
+    }
+}
+
+impl DelayMs<u8> for Delay {
+    fn delay_ms(&mut self, ms: u8) {
+        DelayMs::<u32>::delay_ms(self, ms as u32);
+    }
+}
+
 /// 周期性定时器
 pub struct PeriodicTimer {
     /// 周期（毫秒）
@@ -505,4 +596,34 @@ mod tests {
         };
         assert!(result, "条件不满足时应该超时");
     }
+    
+    /// 测试SysTick计数换算和回绕处理
+    #[test]
+    fn test_systick_ticks() {
+        assert_eq!(us_to_ticks(10, 72_000_000), 720, "72MHz下10us应为720个计数");
+        assert_eq!(us_to_ticks(10, 48_000_000), 480, "48MHz下10us应为480个计数");
+        assert_eq!(us_to_ticks(1, 8_000_000), 8, "8MHz下1us应为8个计数");
+        
+        // 1kHz重装载（72MHz下LOAD = 71999）
+        assert_eq!(systick_elapsed(50_000, 40_000, 71_999), 10_000, "未回绕时应为两次读数之差");
+        assert_eq!(systick_elapsed(100, 71_900, 71_999), 200, "回绕时应加上重装载周期");
+        assert_eq!(systick_elapsed(500, 500, 71_999), 0, "读数相同时经过计数应为0");
+    }
+    
+    /// 测试长延时分段后每段的重装载值不超过24位
+    #[test]
+    fn test_long_delay_segments() {
+        // 72MHz下1000ms共72000000个计数
+        let ticks = ms_to_ticks(1000, 72_000_000);
+        assert_eq!(ticks, 72_000_000, "1000ms计数换算错误");
+        assert!(ticks - 1 > SYSTICK_MAX_RELOAD as u64, "1000ms无法用单次24位重装载实现");
+        
+        let (segments, remainder) = systick_segments(ticks);
+        assert_eq!((segments, remainder), (4, 4_891_136), "分段结果错误");
+        assert!(SYSTICK_SEGMENT_TICKS - 1 <= SYSTICK_MAX_RELOAD, "完整段的重装载值不应超过24位");
+        assert!(remainder.saturating_sub(1) <= SYSTICK_MAX_RELOAD, "最后一段的重装载值不应超过24位");
+        assert_eq!(segments as u64 * SYSTICK_SEGMENT_TICKS as u64 + remainder as u64, ticks, "各段计数之和应等于总计数");
+        
+        assert_eq!(systick_segments(71_999), (0, 71_999), "短延时不应分段");
+    }
 }
@@ -0,0 +1,256 @@
+//! 诊断模块
+//! 提供外设关键寄存器的快照和可读格式输出，用于现场通过串口诊断问题
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use core::fmt;
+
+/// RCC寄存器基地址
+const RCC_BASE: usize = 0x4002_1000;
+
+/// GPIO端口A寄存器基地址，端口间隔0x400
+const GPIOA_BASE: usize = 0x4001_0800;
+
+/// 快照包含的GPIO端口数（A~E）
+pub const DIAG_GPIO_PORTS: usize = 5;
+
+/// 定时器名称和寄存器基地址
+const TIMERS: [(&str, usize); 4] = [
+    ("TIM1", 0x4001_2C00),
+    ("TIM2", 0x4000_0000),
+    ("TIM3", 0x4000_0400),
+    ("TIM4", 0x4000_0800),
+];
+
+/// 串口名称和寄存器基地址
+const USARTS: [(&str, usize); 3] = [
+    ("USART1", 0x4001_3800),
+    ("USART2", 0x4000_4400),
+    ("USART3", 0x4000_4800),
+];
+
+/// TIMx_CR1.CEN位
+const TIM_CR1_CEN: u32 = 1 << 0;
+
+/// USART_CR1.UE位
+const USART_CR1_UE: u32 = 1 << 13;
+
+/// GPIO端口寄存器快照
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpioStatus {
+    pub crl: u32,   // 端口配置低寄存器
+    pub crh: u32,   // 端口配置高寄存器
+    pub idr: u32,   // 输入数据寄存器
+    pub odr: u32,   // 输出数据寄存器
+}
+
+/// 定时器寄存器快照
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimerStatus {
+    pub cr1: u32,   // 控制寄存器1
+    pub psc: u32,   // 预分频器
+    pub arr: u32,   // 自动重装载值
+}
+
+/// 串口寄存器快照
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsartStatus {
+    pub cr1: u32,   // 控制寄存器1
+    pub brr: u32,   // 波特率寄存器
+    pub sr: u32,    // 状态寄存器
+}
+
+/// 外设状态快照
+/// 
+/// 只保存寄存器原始值，`write_status`负责格式化输出，便于在中断外读取、离线比较
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusSnapshot {
+    pub rcc_cr: u32,                            // RCC_CR
+    pub rcc_cfgr: u32,                          // RCC_CFGR
+    pub rcc_apb1enr: u32,                       // RCC_APB1ENR
+    pub rcc_apb2enr: u32,                       // RCC_APB2ENR
+    pub gpio: [GpioStatus; DIAG_GPIO_PORTS],    // GPIOA~GPIOE
+    pub timers: [TimerStatus; 4],               // TIM1~TIM4
+    pub usarts: [UsartStatus; 3],               // USART1~USART3
+}
+
+impl StatusSnapshot {
+    /// 读取当前外设寄存器生成快照
+    /// 
+    /// 时钟未启用的外设寄存器读数为0，不会产生总线错误
+    /// 
+    /// # Safety
+    /// - 只读取寄存器，不修改外设状态，但调用者需确保在目标硬件上运行
+    pub unsafe fn capture() -> Self {
+        let read = |addr: usize| (addr as *const u32).read_volatile();
+        
+        let mut gpio = [GpioStatus { crl: 0, crh: 0, idr: 0, odr: 0 }; DIAG_GPIO_PORTS];
+        for (port, status) in gpio.iter_mut().enumerate() {
+            let base = GPIOA_BASE + 0x400 * port;
+            *status = GpioStatus {
+                crl: read(base),
+                crh: read(base + 0x04),
+                idr: read(base + 0x08),
+                odr: read(base + 0x0C),
+            };
+        }
+        
+        let mut timers = [TimerStatus { cr1: 0, psc: 0, arr: 0 }; 4];
+        for (status, &(_, base)) in timers.iter_mut().zip(TIMERS.iter()) {
+            *status = TimerStatus {
+                cr1: read(base),
+                psc: read(base + 0x28),
+                arr: read(base + 0x2C),
+            };
+        }
+        
+        let mut usarts = [UsartStatus { cr1: 0, brr: 0, sr: 0 }; 3];
+        for (status, &(_, base)) in usarts.iter_mut().zip(USARTS.iter()) {
+            *status = UsartStatus {
+                cr1: read(base + 0x0C),
+                brr: read(base + 0x08),
+                sr: read(base),
+            };
+        }
+        
+        Self {
+            rcc_cr: read(RCC_BASE),
+            rcc_cfgr: read(RCC_BASE + 0x04),
+            rcc_apb1enr: read(RCC_BASE + 0x1C),
+            rcc_apb2enr: read(RCC_BASE + 0x18),
+            gpio,
+            timers,
+            usarts,
+        }
+    }
+}
+
+/// 读取外设状态并输出到串口等`fmt::Write`目标
+/// 
+/// 例如`dump_status(&mut USART1)`，串口需已初始化
+/// 
+/// # Safety
+/// - 调用者必须确保在目标硬件上运行，且输出目标可用
+pub unsafe fn dump_status<W: fmt::Write>(out: &mut W) -> fmt::Result {
+    write_status(out, &StatusSnapshot::capture())
+}
+
+/// 将外设状态快照格式化输出
+/// 
+/// 输出RCC时钟配置，以及时钟已启用的GPIO端口、正在运行的定时器和已使能的串口，每个外设一行
+pub fn write_status<W: fmt::Write>(out: &mut W, snapshot: &StatusSnapshot) -> fmt::Result {
+    let cfgr = snapshot.rcc_cfgr;
+    let sysclk = match (cfgr >> 2) & 0b11 {
+        0b00 => "HSI",
+        0b01 => "HSE",
+        0b10 => "PLL",
+        _ => "?",
+    };
+    writeln!(out, "RCC CR=0x{:08X} CFGR=0x{:08X}", snapshot.rcc_cr, cfgr)?;
+    writeln!(
+        out,
+        "RCC SYSCLK={} PLLMUL=x{} HPRE=/{} PPRE1=/{} PPRE2=/{}",
+        sysclk,
+        pll_multiplier(cfgr),
+        ahb_divider(cfgr),
+        apb_divider(cfgr >> 8),
+        apb_divider(cfgr >> 11),
+    )?;
+    
+    for (port, status) in snapshot.gpio.iter().enumerate() {
+        // APB2ENR的IOPAEN~IOPEEN为位2~6
+        if snapshot.rcc_apb2enr & (1 << (2 + port)) == 0 {
+            continue;
+        }
+        writeln!(
+            out,
+            "GPIO{} CRL=0x{:08X} CRH=0x{:08X} IDR=0x{:04X} ODR=0x{:04X}",
+            (b'A' + port as u8) as char,
+            status.crl,
+            status.crh,
+            status.idr & 0xFFFF,
+            status.odr & 0xFFFF,
+        )?;
+    }
+    
+    for (status, &(name, _)) in snapshot.timers.iter().zip(TIMERS.iter()) {
+        if status.cr1 & TIM_CR1_CEN == 0 {
+            continue;
+        }
+        writeln!(out, "{} CR1=0x{:04X} PSC={} ARR={}", name, status.cr1 & 0xFFFF, status.psc & 0xFFFF, status.arr & 0xFFFF)?;
+    }
+    
+    for (status, &(name, _)) in snapshot.usarts.iter().zip(USARTS.iter()) {
+        if status.cr1 & USART_CR1_UE == 0 {
+            continue;
+        }
+        writeln!(out, "{} CR1=0x{:04X} BRR=0x{:04X} SR=0x{:04X}", name, status.cr1 & 0xFFFF, status.brr & 0xFFFF, status.sr & 0xFFFF)?;
+    }
+    
+    Ok(())
+}
+
+/// 由CFGR.PLLMUL（位21:18）计算PLL倍频系数
+const fn pll_multiplier(cfgr: u32) -> u32 {
+    let mul = ((cfgr >> 18) & 0xF) + 2;
+    if mul > 16 { 16 } else { mul }
+}
+
+/// 由CFGR.HPRE（位7:4）计算AHB分频系数
+const fn ahb_divider(cfgr: u32) -> u32 {
+    let hpre = (cfgr >> 4) & 0xF;
+    if hpre < 8 {
+        1
+    } else if hpre < 12 {
+        2 << (hpre - 8)
+    } else {
+        // 0b1100~0b1111对应64~512分频，跳过了32分频
+        64 << (hpre - 12)
+    }
+}
+
+/// 由3位PPRE字段（已右移到最低位）计算APB分频系数
+const fn apb_divider(ppre: u32) -> u32 {
+    let ppre = ppre & 0b111;
+    if ppre < 4 { 1 } else { 2 << (ppre - 4) }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+    
+    /// 测试已知寄存器状态下的格式化输出
+    #[test]
+    fn test_status_dump_format() {
+        let idle_gpio = GpioStatus { crl: 0x4444_4444, crh: 0x4444_4444, idr: 0, odr: 0 };
+        let stopped = TimerStatus { cr1: 0, psc: 0, arr: 0xFFFF };
+        let disabled = UsartStatus { cr1: 0, brr: 0, sr: 0 };
+        
+        // 72MHz PLL配置，GPIOA时钟启用，TIM2以1kHz运行，USART1为115200波特率
+        let mut snapshot = StatusSnapshot {
+            rcc_cr: 0x0303_5A83,
+            rcc_cfgr: 0x001D_040A,
+            rcc_apb1enr: 0x0000_0001,
+            rcc_apb2enr: 0x0000_4005,
+            gpio: [idle_gpio; DIAG_GPIO_PORTS],
+            timers: [stopped; 4],
+            usarts: [disabled; 3],
+        };
+        snapshot.gpio[0] = GpioStatus { crl: 0x4444_4444, crh: 0x8884_44B4, idr: 0xA601, odr: 0x0000 };
+        snapshot.timers[1] = TimerStatus { cr1: 0x0081, psc: 71, arr: 999 };
+        snapshot.usarts[0] = UsartStatus { cr1: 0x200C, brr: 0x0271, sr: 0x00C0 };
+        
+        let mut out: heapless::String<512> = heapless::String::new();
+        write_status(&mut out, &snapshot).unwrap();
+        
+        let expected = "RCC CR=0x03035A83 CFGR=0x001D040A\n\
+                        RCC SYSCLK=PLL PLLMUL=x9 HPRE=/1 PPRE1=/2 PPRE2=/1\n\
+                        GPIOA CRL=0x44444444 CRH=0x888444B4 IDR=0xA601 ODR=0x0000\n\
+                        TIM2 CR1=0x0081 PSC=71 ARR=999\n\
+                        USART1 CR1=0x200C BRR=0x0271 SR=0x00C0\n";
+        assert_eq!(out.as_str(), expected, "输出应只包含已启用的外设且格式正确");
+    }
+}
@@ -80,7 +80,34 @@ pub enum DmaInterrupt {
     TransferError = 1 << 3,
 }
 
+/// CCR.EN：通道使能
+const CCR_EN: u32 = 1 << 0;
+/// CCR.DIR：从存储器读
+const CCR_DIR: u32 = 1 << 4;
+/// CCR.PINC：外设地址递增
+const CCR_PINC: u32 = 1 << 6;
+/// CCR.MINC：存储器地址递增
+const CCR_MINC: u32 = 1 << 7;
+/// CCR.PL：通道优先级
+const CCR_PL_MASK: u32 = 0b11 << 12;
+/// CCR.MEM2MEM：存储器到存储器模式
+const CCR_MEM2MEM: u32 = 1 << 14;
+/// CCR中DIR、CIRC、PINC、MINC、PSIZE、MSIZE、PL、MEM2MEM位
+const CCR_CONFIG_MASK: u32 = 0x0000_7FF0;
+/// ISR/IFCR中每个通道的标志：GIF、TCIF、HTIF、TEIF
+const CHANNEL_FLAGS: u32 = 0b1111;
+
+/// 通道寄存器组内的偏移（以字为单位）：CCR、CNDTR、CPAR、CMAR
+const CH_CNDTR: usize = 1;
+const CH_CPAR: usize = 2;
+const CH_CMAR: usize = 3;
+
+/// RCC_AHBENR地址
+const RCC_AHBENR: u32 = 0x4002_1014;
+
 /// DMA结构体
+/// 
+/// DMA1有通道1~7，DMA2有通道1~5
 pub struct Dma {
     dma_number: u8,
     channel: DmaChannel,
@@ -88,7 +115,13 @@ pub struct Dma {
 
 impl Dma {
     /// 创建新的DMA实例
+    /// 
+    /// # 参数
+    /// * `dma_number` - 1或2
+    /// * `channel` - DMA1为通道1~7，DMA2为通道1~5
     pub const fn new(dma_number: u8, channel: DmaChannel) -> Self {
+        assert!(dma_number == 1 || dma_number == 2, "DMA number must be 1 or 2");
+        assert!(dma_number == 1 || (channel as u8) < 5, "DMA2 only has channels 1-5");
         Self {
             dma_number,
             channel,
@@ -104,7 +137,47 @@ impl Dma {
         }
     }
     
-        /// 初始化DMA通道
+    /// 获取通道寄存器组首地址（CCR）
+    /// 
+    /// 内部库中每个通道的寄存器类型各不相同，这里直接按地址访问，其后依次为CNDTR、CPAR、CMAR
+    pub(crate) fn channel_regs(&self) -> *mut u32 {
+        Self::channel_base_address(self.dma_number, self.channel) as *mut u32
+    }
+    
+    /// 计算通道寄存器组首地址（CCRx）
+    /// 
+    /// CCRx = 基地址 + 0x08 + 0x14 * (x - 1)
+    /// 
+    /// # 参数
+    /// * `dma_number` - 1或2
+    /// * `channel` - 通道
+    pub const fn channel_base_address(dma_number: u8, channel: DmaChannel) -> u32 {
+        let base = match dma_number {
+            2 => 0x4002_0400,
+            _ => 0x4002_0000,
+        };
+        base + 0x08 + 0x14 * channel as u32
+    }
+    
+    /// 启用DMA时钟
+    unsafe fn enable_clock(&self) {
+        let bit = match self.dma_number {
+            2 => 1 << 1,  // DMA2EN
+            _ => 1 << 0,  // DMA1EN
+        };
+        self.modify_reg(RCC_AHBENR as *mut u32, 0, bit);
+    }
+    
+    /// 修改寄存器：清除`clear`位后置位`set`位
+    unsafe fn modify_reg(&self, reg: *mut u32, clear: u32, set: u32) {
+        core::ptr::write_volatile(reg, (core::ptr::read_volatile(reg) & !clear) | set);
+    }
+    
+    /// 初始化DMA通道
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    #[allow(clippy::too_many_arguments)]
     pub unsafe fn init(
         &self,
         direction: DmaDirection,
@@ -115,35 +188,106 @@ impl Dma {
         priority: DmaChannelPriority,
         circular_mode: DmaCircularMode,
     ) {
-        // 由于内部库中DMA寄存器结构不同，暂时为空实现
+        let config = Self::ccr_config(
+            direction,
+            peripheral_increment,
+            memory_increment,
+            peripheral_data_size,
+            memory_data_size,
+            priority,
+            circular_mode,
+        );
+        self.init_with_ccr(config);
+    }
+    
+    /// 使用预先计算的CCR配置值初始化DMA通道
+    /// 
+    /// # 参数
+    /// * `config` - `ccr_config`计算的配置值，只使用DIR~MEM2MEM位
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn init_with_ccr(&self, config: u32) {
+        self.enable_clock();
+        
+        let ccr = self.channel_regs();
+        
+        // 配置前必须先关闭通道
+        self.modify_reg(ccr, CCR_EN, 0);
+        self.modify_reg(ccr, CCR_CONFIG_MASK, config & CCR_CONFIG_MASK);
+    }
+    
+    /// 计算CCR寄存器中DIR、CIRC、PINC、MINC、PSIZE、MSIZE、PL、MEM2MEM位的值
+    pub const fn ccr_config(
+        direction: DmaDirection,
+        peripheral_increment: DmaPeripheralIncrementMode,
+        memory_increment: DmaMemoryIncrementMode,
+        peripheral_data_size: DmaPeripheralDataSize,
+        memory_data_size: DmaMemoryDataSize,
+        priority: DmaChannelPriority,
+        circular_mode: DmaCircularMode,
+    ) -> u32 {
+        let mut value = match direction {
+            DmaDirection::PeripheralToMemory => 0,
+            DmaDirection::MemoryToPeripheral => 1 << 4,   // DIR: 从存储器读
+            DmaDirection::MemoryToMemory => 1 << 14,      // MEM2MEM
+        };
+        value |= (circular_mode as u32) << 5;
+        value |= (peripheral_increment as u32) << 6;
+        value |= (memory_increment as u32) << 7;
+        value |= (peripheral_data_size as u32) << 8;
+        value |= (memory_data_size as u32) << 10;
+        value |= (priority as u32) << 12;
+        value
     }
     
     /// 配置DMA传输
-    pub unsafe fn configure_transfer(&self, _peripheral_addr: u32, _memory_addr: u32, _data_count: u16) {
-        // 由于内部库中DMA寄存器结构不同，暂时为空实现
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn configure_transfer(&self, peripheral_addr: u32, memory_addr: u32, data_count: u16) {
+        let ccr = self.channel_regs();
+        core::ptr::write_volatile(ccr.add(CH_CNDTR), data_count as u32);
+        core::ptr::write_volatile(ccr.add(CH_CPAR), peripheral_addr);
+        core::ptr::write_volatile(ccr.add(CH_CMAR), memory_addr);
     }
     
     /// 启用DMA通道
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
     pub unsafe fn enable(&self) {
-        // 由于内部库中DMA寄存器结构不同，暂时为空实现
+        self.modify_reg(self.channel_regs(), 0, CCR_EN);
     }
     
     /// 禁用DMA通道
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
     pub unsafe fn disable(&self) {
-        // 由于内部库中DMA寄存器结构不同，暂时为空实现
+        self.modify_reg(self.channel_regs(), CCR_EN, 0);
     }
     
     /// 启用中断
-    pub unsafe fn enable_interrupt(&self, _interrupt: DmaInterrupt) {
-        // 由于内部库中DMA寄存器结构不同，暂时为空实现
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn enable_interrupt(&self, interrupt: DmaInterrupt) {
+        self.modify_reg(self.channel_regs(), 0, interrupt as u32);
     }
     
     /// 禁用中断
-    pub unsafe fn disable_interrupt(&self, _interrupt: DmaInterrupt) {
-        // 由于内部库中DMA寄存器结构不同，暂时为空实现
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn disable_interrupt(&self, interrupt: DmaInterrupt) {
+        self.modify_reg(self.channel_regs(), interrupt as u32, 0);
     }
     
     /// 检查中断标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn check_interrupt(&self, interrupt: DmaInterrupt) -> bool {
         let dma = self.get_dma();
         let isr = dma.isr().read().bits();
@@ -152,22 +296,124 @@ impl Dma {
     }
     
     /// 清除中断标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
     pub unsafe fn clear_interrupt(&self, interrupt: DmaInterrupt) {
         let dma = self.get_dma();
         let channel_offset = self.channel as u32 * 4;
         dma.ifcr().write(|w: &mut library::dma1::ifcr::W| unsafe { w.bits((interrupt as u32) << channel_offset) });
     }
     
+    /// 配置一次传输
+    /// 
+    /// 先关闭通道，再设置地址、数量、方向和优先级；地址递增、数据宽度和循环模式保持原配置，
+    /// 可用`set_increment`和`init`修改。调用`start`开始传输。
+    /// 
+    /// # 参数
+    /// * `peripheral_addr` - 外设地址（存储器到存储器时为源地址）
+    /// * `memory_addr` - 存储器地址（存储器到存储器时为目标地址）
+    /// * `count` - 传输数据个数
+    /// * `direction` - 传输方向
+    /// * `priority` - 通道优先级
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn configure(
+        &self,
+        peripheral_addr: u32,
+        memory_addr: u32,
+        count: u16,
+        direction: DmaDirection,
+        priority: DmaChannelPriority,
+    ) {
+        self.enable_clock();
+        let ccr = self.channel_regs();
+        let value = core::ptr::read_volatile(ccr) & !CCR_EN;
+        core::ptr::write_volatile(ccr, value);
+        
+        self.configure_transfer(peripheral_addr, memory_addr, count);
+        core::ptr::write_volatile(ccr, Self::direction_priority_ccr(value, direction, priority));
+    }
+    
+    /// 计算修改方向和优先级后的CCR值，其他位保持不变
+    pub const fn direction_priority_ccr(ccr: u32, direction: DmaDirection, priority: DmaChannelPriority) -> u32 {
+        let direction_bits = match direction {
+            DmaDirection::PeripheralToMemory => 0,
+            DmaDirection::MemoryToPeripheral => CCR_DIR,
+            DmaDirection::MemoryToMemory => CCR_MEM2MEM,
+        };
+        (ccr & !(CCR_DIR | CCR_MEM2MEM | CCR_PL_MASK)) | direction_bits | ((priority as u32) << 12)
+    }
+    
+    /// 设置外设和存储器地址是否递增，需在通道关闭时调用
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn set_increment(&self, peripheral: DmaPeripheralIncrementMode, memory: DmaMemoryIncrementMode) {
+        let ccr = self.channel_regs();
+        core::ptr::write_volatile(ccr, Self::increment_ccr(core::ptr::read_volatile(ccr), peripheral, memory));
+    }
+    
+    /// 计算修改地址递增后的CCR值，其他位保持不变
+    pub const fn increment_ccr(ccr: u32, peripheral: DmaPeripheralIncrementMode, memory: DmaMemoryIncrementMode) -> u32 {
+        (ccr & !(CCR_PINC | CCR_MINC)) | ((peripheral as u32) << 6) | ((memory as u32) << 7)
+    }
+    
+    /// 清除通道的所有标志后开始传输
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn start(&self) {
+        self.clear_flags();
+        self.enable();
+    }
+    
+    /// 停止传输
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn stop(&self) {
+        self.disable();
+    }
+    
+    /// 检查传输完成标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn transfer_complete(&self) -> bool {
+        self.check_interrupt(DmaInterrupt::TransferComplete)
+    }
+    
+    /// 清除通道的全局、传输完成、半传输和传输错误标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道未被其他代码使用
+    pub unsafe fn clear_flags(&self) {
+        let dma = self.get_dma();
+        dma.ifcr().write(|w: &mut library::dma1::ifcr::W| unsafe { w.bits(Self::channel_flags(self.channel)) });
+    }
+    
+    /// 获取通道在ISR/IFCR中的全部标志位
+    pub const fn channel_flags(channel: DmaChannel) -> u32 {
+        CHANNEL_FLAGS << (channel as u32 * 4)
+    }
+    
     /// 获取剩余数据计数
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_remaining_count(&self) -> u16 {
-        // 由于内部库中DMA寄存器结构不同，暂时返回固定值
-        0
+        (core::ptr::read_volatile(self.channel_regs().add(CH_CNDTR)) & 0xFFFF) as u16
     }
     
     /// 检查DMA通道是否正在传输
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_transferring(&self) -> bool {
-        // 由于内部库中DMA寄存器结构不同，暂时返回固定值
-        false
+        let ccr = core::ptr::read_volatile(self.channel_regs());
+        (ccr & CCR_EN) != 0 && self.get_remaining_count() != 0
     }
 }
 
@@ -185,3 +431,41 @@ pub const DMA2_CHANNEL2: Dma = Dma::new(2, DmaChannel::Channel2);
 pub const DMA2_CHANNEL3: Dma = Dma::new(2, DmaChannel::Channel3);
 pub const DMA2_CHANNEL4: Dma = Dma::new(2, DmaChannel::Channel4);
 pub const DMA2_CHANNEL5: Dma = Dma::new(2, DmaChannel::Channel5);
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试通道寄存器地址计算
+    #[test]
+    fn test_channel_base_address() {
+        assert_eq!(Dma::channel_base_address(1, DmaChannel::Channel1), 0x4002_0008, "DMA1通道1的CCR地址错误");
+        assert_eq!(Dma::channel_base_address(1, DmaChannel::Channel7), 0x4002_0080, "DMA1通道7的CCR地址错误");
+        assert_eq!(Dma::channel_base_address(2, DmaChannel::Channel1), 0x4002_0408, "DMA2通道1的CCR地址错误");
+        assert_eq!(Dma::channel_base_address(2, DmaChannel::Channel5), 0x4002_0458, "DMA2通道5的CCR地址错误");
+        assert_eq!(DMA2_CHANNEL3.channel_regs() as u32, 0x4002_0430, "预定义实例应使用相同的地址计算");
+        
+        assert_eq!(Dma::channel_flags(DmaChannel::Channel1), 0x0000_000F, "通道1标志应为位0~3");
+        assert_eq!(Dma::channel_flags(DmaChannel::Channel7), 0x0F00_0000, "通道7标志应为位24~27");
+    }
+    
+    /// 测试修改方向、优先级和地址递增时保留其他配置
+    #[test]
+    fn test_ccr_updates() {
+        // 半字宽度、循环模式、传输完成中断
+        let ccr = (1 << 8) | (1 << 10) | (1 << 5) | (1 << 1);
+        
+        let value = Dma::direction_priority_ccr(ccr, DmaDirection::MemoryToPeripheral, DmaChannelPriority::High);
+        assert_eq!(value, ccr | CCR_DIR | (2 << 12), "存储器到外设应设置DIR");
+        let value = Dma::direction_priority_ccr(value, DmaDirection::MemoryToMemory, DmaChannelPriority::Low);
+        assert_eq!(value, ccr | CCR_MEM2MEM, "存储器到存储器应清除DIR并设置MEM2MEM");
+        let value = Dma::direction_priority_ccr(value, DmaDirection::PeripheralToMemory, DmaChannelPriority::VeryHigh);
+        assert_eq!(value, ccr | (3 << 12), "外设到存储器应清除方向位");
+        
+        let value = Dma::increment_ccr(ccr, DmaPeripheralIncrementMode::Enabled, DmaMemoryIncrementMode::Enabled);
+        assert_eq!(value, ccr | CCR_PINC | CCR_MINC, "应设置地址递增");
+        let value = Dma::increment_ccr(value, DmaPeripheralIncrementMode::Disabled, DmaMemoryIncrementMode::Enabled);
+        assert_eq!(value, ccr | CCR_MINC, "应只清除外设地址递增");
+    }
+}
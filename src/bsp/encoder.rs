@@ -0,0 +1,241 @@
+//! 软件编码器模块
+//! 提供基于GPIO轮询的正交编码器四倍频计数和速度估算，适用于没有空闲定时器编码器通道的场合
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::gpio::{GpioPortStruct, GpioPortBatch};
+use crate::bsp::delay::get_uptime_ms;
+
+/// 速度估算的快照间隔（毫秒）
+pub const ENCODER_SNAPSHOT_MS: u32 = 10;
+
+/// 保存的计数快照数量，速度窗口最长为`ENCODER_SNAPSHOT_MS * ENCODER_HISTORY_LEN`毫秒
+pub const ENCODER_HISTORY_LEN: usize = 32;
+
+/// 四倍频状态转换表，索引为`(上次状态 << 2) | 当前状态`，状态为`(A << 1) | B`
+/// 
+/// A相超前B相（00→10→11→01→00）计为正方向，非法跳变（两相同时变化）计为0
+const QUADRATURE_TABLE: [i8; 16] = [
+    0, -1, 1, 0,
+    1, 0, 0, -1,
+    -1, 0, 0, 1,
+    0, 1, -1, 0,
+];
+
+/// 计数快照
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Snapshot {
+    time_ms: u32,
+    count: i32,
+}
+
+/// 软件正交编码器
+/// 
+/// 在主循环或定时器中断中周期调用`update`采样A/B两相，四倍频计数：每个完整的正交周期计4个数。
+/// 采样频率必须高于两相边沿的最高频率，否则会丢步。
+/// 
+/// 每隔`ENCODER_SNAPSHOT_MS`毫秒保存一次计数快照，`velocity_cps`用滑动窗口内的计数差估算速度。
+pub struct SoftEncoder {
+    a: GpioPortStruct,
+    b: GpioPortStruct,
+    state: u8,
+    count: i32,
+    errors: u32,
+    history: [Snapshot; ENCODER_HISTORY_LEN],
+    history_head: usize,
+    history_len: usize,
+}
+
+impl SoftEncoder {
+    /// 创建软件编码器实例
+    /// 
+    /// # 参数
+    /// * `a` - A相引脚
+    /// * `b` - B相引脚
+    pub const fn new(a: GpioPortStruct, b: GpioPortStruct) -> Self {
+        Self {
+            a,
+            b,
+            state: 0,
+            count: 0,
+            errors: 0,
+            history: [Snapshot { time_ms: 0, count: 0 }; ENCODER_HISTORY_LEN],
+            history_head: 0,
+            history_len: 0,
+        }
+    }
+    
+    /// 初始化编码器引脚并记录初始状态
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用，且SysTick已初始化（`delay::init_systick`）
+    /// - 调用者必须确保引脚未被其他代码或外设占用
+    pub unsafe fn init(&mut self) {
+        self.a.into_floating_input();
+        self.b.into_floating_input();
+        self.state = self.read_state();
+        self.count = 0;
+        self.errors = 0;
+        self.history_len = 0;
+        self.push_snapshot(get_uptime_ms());
+    }
+    
+    /// 采样A/B两相并更新计数
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn update(&mut self) {
+        let state = self.read_state();
+        self.step(state);
+        
+        let now = get_uptime_ms();
+        if self.history_len == 0 || now.wrapping_sub(self.latest_snapshot().time_ms) >= ENCODER_SNAPSHOT_MS {
+            self.push_snapshot(now);
+        }
+    }
+    
+    /// 根据新的两相状态更新计数
+    /// 
+    /// # 参数
+    /// * `state` - 当前状态，`(A << 1) | B`
+    pub fn step(&mut self, state: u8) {
+        let state = state & 0x03;
+        if state == self.state {
+            return;
+        }
+        
+        match Self::quadrature_step(self.state, state) {
+            0 => self.errors += 1,
+            delta => self.count += delta as i32,
+        }
+        self.state = state;
+    }
+    
+    /// 获取当前计数值
+    pub fn count(&self) -> i32 {
+        self.count
+    }
+    
+    /// 设置当前计数值，清空速度历史
+    pub fn set_count(&mut self, count: i32) {
+        self.count = count;
+        self.history_len = 0;
+    }
+    
+    /// 获取非法跳变（丢步）次数
+    pub fn error_count(&self) -> u32 {
+        self.errors
+    }
+    
+    /// 估算滑动窗口内的速度
+    /// 
+    /// 取距今不少于`window_ms`的最近快照（历史不足时取最早快照）与当前计数比较。
+    /// 
+    /// # 参数
+    /// * `window_ms` - 窗口长度（毫秒）
+    /// 
+    /// # 返回值
+    /// 每秒计数，正值为正方向；历史不足一个快照间隔时返回0
+    pub fn velocity_cps(&self, window_ms: u32) -> i32 {
+        let now = get_uptime_ms();
+        
+        let mut reference = None;
+        for age in 0..self.history_len {
+            let snapshot = self.snapshot(age);
+            reference = Some(snapshot);
+            if now.wrapping_sub(snapshot.time_ms) >= window_ms {
+                break;
+            }
+        }
+        
+        match reference {
+            Some(snapshot) => Self::counts_per_second(self.count - snapshot.count, now.wrapping_sub(snapshot.time_ms)),
+            None => 0,
+        }
+    }
+    
+    /// 由计数差和时间差计算每秒计数
+    /// 
+    /// # 参数
+    /// * `delta` - 时间窗口内的计数差（带方向）
+    /// * `elapsed_ms` - 时间窗口长度（毫秒），为0时返回0
+    pub const fn counts_per_second(delta: i32, elapsed_ms: u32) -> i32 {
+        if elapsed_ms == 0 {
+            return 0;
+        }
+        ((delta as i64 * 1000) / elapsed_ms as i64) as i32
+    }
+    
+    /// 查询四倍频状态转换对应的计数增量
+    /// 
+    /// # 返回值
+    /// 正方向为1，反方向为-1，无变化或非法跳变为0
+    pub const fn quadrature_step(previous: u8, current: u8) -> i8 {
+        QUADRATURE_TABLE[(((previous & 0x03) << 2) | (current & 0x03)) as usize]
+    }
+    
+    /// 读取两相当前状态
+    unsafe fn read_state(&self) -> u8 {
+        let a = GpioPortBatch::new(self.a.port).read_input_data() & (1 << self.a.pin) != 0;
+        let b = GpioPortBatch::new(self.b.port).read_input_data() & (1 << self.b.pin) != 0;
+        ((a as u8) << 1) | b as u8
+    }
+    
+    /// 保存计数快照
+    fn push_snapshot(&mut self, time_ms: u32) {
+        self.history_head = (self.history_head + 1) % ENCODER_HISTORY_LEN;
+        self.history[self.history_head] = Snapshot { time_ms, count: self.count };
+        if self.history_len < ENCODER_HISTORY_LEN {
+            self.history_len += 1;
+        }
+    }
+    
+    /// 获取最新的快照
+    fn latest_snapshot(&self) -> Snapshot {
+        self.snapshot(0)
+    }
+    
+    /// 获取倒数第`age`个快照（0为最新）
+    fn snapshot(&self, age: usize) -> Snapshot {
+        self.history[(self.history_head + ENCODER_HISTORY_LEN - age) % ENCODER_HISTORY_LEN]
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::bsp::gpio::{PA0, PA1};
+    
+    /// 测试由计数差和时间窗口计算速度
+    #[test]
+    fn test_velocity_computation() {
+        assert_eq!(SoftEncoder::counts_per_second(400, 100), 4000, "100ms内400个计数应为4000cps");
+        assert_eq!(SoftEncoder::counts_per_second(-150, 50), -3000, "反方向速度应为负值");
+        assert_eq!(SoftEncoder::counts_per_second(7, 0), 0, "时间窗口为0时应返回0");
+        assert_eq!(SoftEncoder::counts_per_second(i32::MAX, 1000), i32::MAX, "大计数差计算不应溢出");
+    }
+    
+    /// 测试四倍频计数的方向
+    #[test]
+    fn test_quadrature_x4_counting() {
+        let mut encoder = SoftEncoder::new(PA0, PA1);
+        
+        // 正方向一个完整周期计4个数
+        for state in [0b10, 0b11, 0b01, 0b00] {
+            encoder.step(state);
+        }
+        assert_eq!(encoder.count(), 4, "正方向一个周期应计4个数");
+        
+        // 反方向两步
+        for state in [0b01, 0b11] {
+            encoder.step(state);
+        }
+        assert_eq!(encoder.count(), 2, "反方向应递减计数");
+        
+        // 两相同时变化为非法跳变
+        encoder.step(0b00);
+        assert_eq!(encoder.count(), 2, "非法跳变不应改变计数");
+        assert_eq!(encoder.error_count(), 1, "非法跳变应计入错误");
+    }
+}
@@ -0,0 +1,173 @@
+//! 错误处理模块
+//! 提供统一的BSP错误类型，各模块的错误都可以通过`?`转换为`BspError`
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::can::{CanError, QueueFull};
+use crate::bsp::serial::SerialError;
+use crate::bsp::frame::FrameError;
+use crate::bsp::iic::I2cError;
+use crate::bsp::adc::AdcError;
+use crate::bsp::rcc::RccError;
+use crate::bsp::flash::{EepromError, FlashError};
+use crate::bsp::dac::DacError;
+use crate::bsp::spi::SpiError;
+use crate::bsp::timer::TimerError;
+use crate::bsp::bkp::BkpError;
+use crate::bsp::rtc::RtcError;
+
+/// BSP统一错误类型枚举
+/// 
+/// 每个变体包装一个模块自身的错误类型，保留错误来源和原始信息，
+/// 应用层可以用`Result<T, BspError>`统一处理不同模块的错误。
+/// 新增模块错误类型时，应在此添加对应变体和`From`实现。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BspError {
+    Can(QueueFull),         // CAN模块错误
+    CanBus(CanError),       // CAN控制器错误
+    Serial(SerialError),    // 串口模块错误
+    Frame(FrameError),      // 帧协议错误
+    I2c(I2cError),          // I2C主机错误
+    Adc(AdcError),          // ADC错误
+    Rcc(RccError),          // 时钟配置错误
+    Flash(FlashError),      // 闪存编程/擦除错误
+    Eeprom(EepromError),    // 模拟EEPROM错误
+    Dac(DacError),          // DAC错误
+    Spi(SpiError),          // SPI错误
+    Timer(TimerError),      // 定时器错误
+    Bkp(BkpError),          // 备份寄存器错误
+    Rtc(RtcError),          // RTC错误
+}
+
+/// BSP结果类型
+pub type BspResult<T> = Result<T, BspError>;
+
+impl From<QueueFull> for BspError {
+    fn from(error: QueueFull) -> Self {
+        BspError::Can(error)
+    }
+}
+
+impl From<CanError> for BspError {
+    fn from(error: CanError) -> Self {
+        BspError::CanBus(error)
+    }
+}
+
+impl From<SerialError> for BspError {
+    fn from(error: SerialError) -> Self {
+        BspError::Serial(error)
+    }
+}
+
+impl From<FrameError> for BspError {
+    fn from(error: FrameError) -> Self {
+        BspError::Frame(error)
+    }
+}
+
+impl From<I2cError> for BspError {
+    fn from(error: I2cError) -> Self {
+        BspError::I2c(error)
+    }
+}
+
+impl From<AdcError> for BspError {
+    fn from(error: AdcError) -> Self {
+        BspError::Adc(error)
+    }
+}
+
+impl From<RccError> for BspError {
+    fn from(error: RccError) -> Self {
+        BspError::Rcc(error)
+    }
+}
+
+impl From<FlashError> for BspError {
+    fn from(error: FlashError) -> Self {
+        BspError::Flash(error)
+    }
+}
+
+impl From<EepromError> for BspError {
+    fn from(error: EepromError) -> Self {
+        BspError::Eeprom(error)
+    }
+}
+
+impl From<DacError> for BspError {
+    fn from(error: DacError) -> Self {
+        BspError::Dac(error)
+    }
+}
+
+impl From<SpiError> for BspError {
+    fn from(error: SpiError) -> Self {
+        BspError::Spi(error)
+    }
+}
+
+impl From<TimerError> for BspError {
+    fn from(error: TimerError) -> Self {
+        BspError::Timer(error)
+    }
+}
+
+impl From<BkpError> for BspError {
+    fn from(error: BkpError) -> Self {
+        BspError::Bkp(error)
+    }
+}
+
+impl From<RtcError> for BspError {
+    fn from(error: RtcError) -> Self {
+        BspError::Rtc(error)
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 通过`?`传播CAN模块错误
+    fn forward_can(result: Result<(), QueueFull>) -> BspResult<()> {
+        result?;
+        Ok(())
+    }
+    
+    /// 通过`?`传播串口模块错误
+    fn forward_serial(result: Result<u8, SerialError>) -> BspResult<u8> {
+        Ok(result?)
+    }
+    
+    /// 通过`?`传播定时器错误
+    fn forward_timer(result: Result<(), TimerError>) -> BspResult<()> {
+        result?;
+        Ok(())
+    }
+    
+    /// 测试模块错误转换为BspError并保留来源
+    #[test]
+    fn test_module_errors_convert() {
+        assert_eq!(BspError::from(QueueFull), BspError::Can(QueueFull), "CAN错误应转换为Can变体");
+        assert_eq!(forward_can(Err(QueueFull)), Err(BspError::Can(QueueFull)), "`?`应自动转换CAN错误");
+        assert_eq!(forward_can(Ok(())), Ok(()), "成功结果不应被改变");
+        
+        assert_eq!(
+            forward_serial(Err(SerialError::Framing)),
+            Err(BspError::Serial(SerialError::Framing)),
+            "串口错误应转换为Serial变体并保留错误类型"
+        );
+        assert_ne!(BspError::from(SerialError::Overrun), BspError::Can(QueueFull), "不同模块的错误应可区分");
+        assert_eq!(BspError::from(I2cError::Nack), BspError::I2c(I2cError::Nack), "I2C错误应转换为I2c变体");
+        assert_eq!(
+            forward_timer(Err(TimerError::InvalidParameter)),
+            Err(BspError::Timer(TimerError::InvalidParameter)),
+            "`?`应把定时器错误转换为Timer变体"
+        );
+        assert_eq!(BspError::from(BkpError::InvalidRegister), BspError::Bkp(BkpError::InvalidRegister), "备份寄存器错误应转换为Bkp变体");
+    }
+}
@@ -0,0 +1,164 @@
+//! 协作式异步执行器模块
+//! 提供单线程的轮询式执行器，以及基于nb非阻塞操作的串口、ADC异步封装
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use core::future::Future;
+use core::pin::pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use crate::bsp::adc::{Adc, AdcChannel, AdcSampleTime};
+use crate::bsp::serial::{Serial, SerialError};
+
+/// 唤醒标志，由Waker或中断服务函数置位，执行器在轮询前清除
+static WOKEN: AtomicBool = AtomicBool::new(false);
+
+/// Waker虚函数表：所有Waker共享全局唤醒标志，不携带数据
+static VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |_| RawWaker::new(core::ptr::null(), &VTABLE),
+    |_| wake(),
+    |_| wake(),
+    |_| {},
+);
+
+/// 唤醒执行器
+/// 
+/// 可在中断服务函数中调用，使执行器在下一次空闲前重新轮询任务
+pub fn wake() {
+    WOKEN.store(true, Ordering::Release);
+}
+
+/// 运行Future直到完成，空闲时执行WFI进入睡眠
+/// 
+/// 任务返回Pending后，如果期间没有被唤醒，执行器执行WFI，任何中断都会使其返回并重新轮询。
+/// 因此等待外设时需要启用对应的中断（如串口RXNE、ADC EOC），否则会一直睡眠。
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    block_on_with(future, || {
+        // 关中断后再检查标志，避免在检查和WFI之间到来的中断被错过；
+        // PRIMASK置位时挂起的中断仍会唤醒WFI
+        cortex_m::interrupt::free(|_| {
+            if !WOKEN.load(Ordering::Acquire) {
+                cortex_m::asm::wfi();
+            }
+        });
+    })
+}
+
+/// 运行Future直到完成，使用指定的空闲函数
+/// 
+/// # 参数
+/// * `future` - 要运行的任务
+/// * `idle` - 任务挂起且未被唤醒时调用的空闲函数
+pub fn block_on_with<F: Future, I: FnMut()>(future: F, mut idle: I) -> F::Output {
+    let mut future = pin!(future);
+    let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+    
+    loop {
+        WOKEN.store(false, Ordering::Release);
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        if !WOKEN.load(Ordering::Acquire) {
+            idle();
+        }
+    }
+}
+
+/// 将nb非阻塞操作包装为Future
+/// 
+/// 操作返回`WouldBlock`时挂起，执行器被中断唤醒后重新尝试
+pub struct NbFuture<F> {
+    op: F,
+}
+
+impl<T, E, F: FnMut() -> nb::Result<T, E> + Unpin> Future for NbFuture<F> {
+    type Output = Result<T, E>;
+    
+    fn poll(mut self: core::pin::Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match (self.op)() {
+            Ok(value) => Poll::Ready(Ok(value)),
+            Err(nb::Error::Other(error)) => Poll::Ready(Err(error)),
+            Err(nb::Error::WouldBlock) => Poll::Pending,
+        }
+    }
+}
+
+/// 由nb非阻塞操作创建Future
+pub fn poll_nb<T, E, F: FnMut() -> nb::Result<T, E> + Unpin>(op: F) -> NbFuture<F> {
+    NbFuture { op }
+}
+
+/// 异步读取一个字节
+/// 
+/// 需要启用串口接收中断（RXNE）以便从WFI中唤醒
+pub async fn read_byte(serial: &Serial) -> Result<u8, SerialError> {
+    poll_nb(|| serial.read_nb()).await
+}
+
+/// 异步发送一个字节
+pub async fn write_byte(serial: &Serial, byte: u8) {
+    let _ = poll_nb(|| serial.write_nb(byte)).await;
+}
+
+/// 异步发送字节数组
+pub async fn write_bytes(serial: &Serial, bytes: &[u8]) {
+    for &byte in bytes {
+        write_byte(serial, byte).await;
+    }
+}
+
+/// 异步读取ADC规则通道
+/// 
+/// 需要启用EOC中断以便从WFI中唤醒
+pub async fn read_adc(adc: &Adc, channel: AdcChannel, sample_time: AdcSampleTime) -> u16 {
+    adc.regular_channel_config(channel, 1, sample_time);
+    adc.software_start_conv_cmd(true);
+    let result: Result<u16, core::convert::Infallible> = poll_nb(|| {
+        if adc.is_conversion_complete() {
+            Ok(adc.read_result())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }).await;
+    match result {
+        Ok(value) => value,
+        Err(never) => match never {},
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试挂起的任务在模拟中断后完成
+    #[test]
+    fn test_future_completes_on_interrupt() {
+        static DATA_READY: AtomicBool = AtomicBool::new(false);
+        
+        let future = poll_nb(|| -> nb::Result<u8, ()> {
+            if DATA_READY.load(Ordering::Acquire) {
+                Ok(0x5A)
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        });
+        
+        let mut idle_calls = 0;
+        let result = block_on_with(future, || {
+            idle_calls += 1;
+            // 模拟中断：外设数据就绪并唤醒执行器
+            DATA_READY.store(true, Ordering::Release);
+            wake();
+        });
+        
+        assert_eq!(result, Ok(0x5A), "任务应返回中断后就绪的数据");
+        assert_eq!(idle_calls, 1, "任务应只挂起一次");
+        
+        // 错误直接作为结果返回
+        let result = block_on_with(poll_nb(|| Err::<u8, _>(nb::Error::Other(7))), || panic!("不应进入空闲"));
+        assert_eq!(result, Err(7), "操作出错时应立即完成");
+    }
+}
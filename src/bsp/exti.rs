@@ -5,6 +5,8 @@
 
 // 导入内部生成的设备驱动库
 use library::*;
+use crate::bsp::gpio::{gpio_exti_line_config, GpioPort};
+use crate::bsp::rcc::{Apb2Peripheral, RccDriver};
 
 /// EXTI线枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -31,6 +33,42 @@ pub enum ExtiLine {
     Line19 = 19, // ETH唤醒事件
 }
 
+impl ExtiLine {
+    /// 由线号获取EXTI线，超出0~19时返回None
+    /// 
+    /// 线0~15对应各端口的同号引脚
+    pub const fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(ExtiLine::Line0),
+            1 => Some(ExtiLine::Line1),
+            2 => Some(ExtiLine::Line2),
+            3 => Some(ExtiLine::Line3),
+            4 => Some(ExtiLine::Line4),
+            5 => Some(ExtiLine::Line5),
+            6 => Some(ExtiLine::Line6),
+            7 => Some(ExtiLine::Line7),
+            8 => Some(ExtiLine::Line8),
+            9 => Some(ExtiLine::Line9),
+            10 => Some(ExtiLine::Line10),
+            11 => Some(ExtiLine::Line11),
+            12 => Some(ExtiLine::Line12),
+            13 => Some(ExtiLine::Line13),
+            14 => Some(ExtiLine::Line14),
+            15 => Some(ExtiLine::Line15),
+            16 => Some(ExtiLine::Line16),
+            17 => Some(ExtiLine::Line17),
+            18 => Some(ExtiLine::Line18),
+            19 => Some(ExtiLine::Line19),
+            _ => None,
+        }
+    }
+    
+    /// 获取该线在RTSR/FTSR/IMR/PR等寄存器中的位掩码
+    pub const fn mask(self) -> u32 {
+        1 << self as u8
+    }
+}
+
 /// EXTI触发模式枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ExtiTriggerMode {
@@ -55,49 +93,69 @@ impl Exti {
     }
     
     /// 初始化EXTI线
+    /// 
+    /// 启用中断前先清除该线的挂起标志，避免配置前残留的边沿立即触发中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI线对应的引脚和AFIO映射已配置，且EXTI寄存器未被其他代码同时修改
     pub unsafe fn init(&self, line: ExtiLine, trigger_mode: ExtiTriggerMode, enable_interrupt: bool) {
         let exti = self.exti();
-        let line = line as u8;
-        let line_mask = 1 << line;
-        
-        // 保存当前配置
-        let mut current_rtsr = exti.rtsr().read().bits();
-        let mut current_ftsr = exti.ftsr().read().bits();
-        let mut current_imr = exti.imr().read().bits();
-        
-        // 清除之前的配置
-        current_rtsr &= !line_mask;
-        current_ftsr &= !line_mask;
+        let line_mask = line.mask();
         
         // 配置触发模式
-        match trigger_mode {
-            ExtiTriggerMode::Rising => {
-                current_rtsr |= line_mask;
-            }
-            ExtiTriggerMode::Falling => {
-                current_ftsr |= line_mask;
-            }
-            ExtiTriggerMode::RisingFalling => {
-                current_rtsr |= line_mask;
-                current_ftsr |= line_mask;
-            }
-            _ => {}
-        }
+        let (current_rtsr, current_ftsr) = trigger_bits(
+            exti.rtsr().read().bits(),
+            exti.ftsr().read().bits(),
+            line,
+            trigger_mode,
+        );
+        exti.rtsr().write(|w: &mut library::exti::rtsr::W| unsafe { w.bits(current_rtsr) });
+        exti.ftsr().write(|w: &mut library::exti::ftsr::W| unsafe { w.bits(current_ftsr) });
         
         // 配置中断
+        let mut current_imr = exti.imr().read().bits();
         if enable_interrupt {
+            self.clear_pending(line);
             current_imr |= line_mask;
         } else {
             current_imr &= !line_mask;
         }
-        
-        // 写入配置
-        exti.rtsr().write(|w: &mut library::exti::rtsr::W| unsafe { w.bits(current_rtsr) });
-        exti.ftsr().write(|w: &mut library::exti::ftsr::W| unsafe { w.bits(current_ftsr) });
         exti.imr().write(|w: &mut library::exti::imr::W| unsafe { w.bits(current_imr) });
     }
     
+    /// 将GPIO引脚连接到EXTI线并配置触发边沿
+    /// 
+    /// 启用AFIO时钟，通过AFIO_EXTICRx把引脚号对应的EXTI线映射到该端口，再配置触发边沿和中断屏蔽。
+    /// 引脚需预先配置为输入，NVIC中对应的EXTIx中断仍需单独启用。
+    /// 
+    /// # 参数
+    /// * `port` - GPIO端口
+    /// * `pin` - 引脚号（0~15），同时也是EXTI线号
+    /// * `trigger_mode` - 触发边沿
+    /// * `enable_interrupt` - 是否取消该线的中断屏蔽
+    /// 
+    /// # 返回值
+    /// 引脚所连接的EXTI线
+    /// 
+    /// # Safety
+    /// - 调用者必须确保同一EXTI线未被其他端口的引脚占用
+    pub unsafe fn configure_pin(&self, port: GpioPort, pin: u8, trigger_mode: ExtiTriggerMode, enable_interrupt: bool) -> ExtiLine {
+        assert!(pin < 16, "EXTI GPIO pin must be between 0 and 15");
+        let line = match ExtiLine::from_index(pin) {
+            Some(line) => line,
+            None => unreachable!(),
+        };
+        
+        RccDriver::new().enable_apb2_peripheral(Apb2Peripheral::AFIO);
+        gpio_exti_line_config(port, pin);
+        self.init(line, trigger_mode, enable_interrupt);
+        line
+    }
+    
     /// 启用EXTI线中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn enable_interrupt(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -108,6 +166,9 @@ impl Exti {
     }
     
     /// 禁用EXTI线中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn disable_interrupt(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -118,6 +179,9 @@ impl Exti {
     }
     
     /// 启用EXTI线事件
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn enable_event(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -128,6 +192,9 @@ impl Exti {
     }
     
     /// 禁用EXTI线事件
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn disable_event(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -138,6 +205,9 @@ impl Exti {
     }
     
     /// 启用EXTI线上升沿触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn enable_rising_trigger(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -148,6 +218,9 @@ impl Exti {
     }
     
     /// 禁用EXTI线上升沿触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn disable_rising_trigger(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -158,6 +231,9 @@ impl Exti {
     }
     
     /// 启用EXTI线下降沿触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn enable_falling_trigger(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -168,6 +244,9 @@ impl Exti {
     }
     
     /// 禁用EXTI线下降沿触发
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn disable_falling_trigger(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -178,6 +257,9 @@ impl Exti {
     }
     
     /// 生成软件中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn generate_software_interrupt(&self, line: ExtiLine) {
         let exti = self.exti();
         let line = line as u8;
@@ -188,6 +270,9 @@ impl Exti {
     }
     
     /// 检查EXTI线是否挂起
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_pending(&self, line: ExtiLine) -> bool {
         let exti = self.exti();
         let line = line as u8;
@@ -197,16 +282,21 @@ impl Exti {
     }
     
     /// 清除EXTI线挂起状态
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn clear_pending(&self, line: ExtiLine) {
         let exti = self.exti();
-        let line = line as u8;
-        let line_mask = 1 << line;
+        let line_mask = line.mask();
         
         // 写入1到PR寄存器的对应位来清除挂起状态
         exti.pr().write(|w: &mut library::exti::pr::W| unsafe { w.bits(line_mask) });
     }
     
     /// 清除所有EXTI线挂起状态
+    /// 
+    /// # Safety
+    /// - 调用者必须确保EXTI寄存器未被其他代码同时修改
     pub unsafe fn clear_all_pending(&self) {
         let exti = self.exti();
         
@@ -215,5 +305,58 @@ impl Exti {
     }
 }
 
+impl Default for Exti {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的EXTI实例
 pub const EXTI: Exti = Exti::new();
+
+/// 计算配置触发模式后的RTSR和FTSR值
+/// 
+/// 只修改`line`对应的位，其他线的配置保持不变
+/// 
+/// # 返回值
+/// (RTSR, FTSR)
+pub const fn trigger_bits(rtsr: u32, ftsr: u32, line: ExtiLine, trigger_mode: ExtiTriggerMode) -> (u32, u32) {
+    let line_mask = line.mask();
+    let rtsr = rtsr & !line_mask;
+    let ftsr = ftsr & !line_mask;
+    match trigger_mode {
+        ExtiTriggerMode::None => (rtsr, ftsr),
+        ExtiTriggerMode::Rising => (rtsr | line_mask, ftsr),
+        ExtiTriggerMode::Falling => (rtsr, ftsr | line_mask),
+        ExtiTriggerMode::RisingFalling => (rtsr | line_mask, ftsr | line_mask),
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试触发边沿配置只影响对应的EXTI线
+    #[test]
+    fn test_trigger_bits() {
+        assert_eq!(trigger_bits(0, 0, ExtiLine::Line0, ExtiTriggerMode::Rising), (0x1, 0), "上升沿应只置位RTSR");
+        assert_eq!(trigger_bits(0, 0, ExtiLine::Line0, ExtiTriggerMode::Falling), (0, 0x1), "下降沿应只置位FTSR");
+        assert_eq!(
+            trigger_bits(0, 0, ExtiLine::Line13, ExtiTriggerMode::RisingFalling),
+            (1 << 13, 1 << 13),
+            "双边沿应同时置位RTSR和FTSR"
+        );
+        
+        // 从双边沿改为下降沿时应清除RTSR位，其他线保持不变
+        let (rtsr, ftsr) = trigger_bits(0x0000_2001, 0x0000_2001, ExtiLine::Line0, ExtiTriggerMode::Falling);
+        assert_eq!((rtsr, ftsr), (0x0000_2000, 0x0000_2001), "切换触发模式应清除原有配置");
+        let (rtsr, ftsr) = trigger_bits(0x0000_2001, 0x0000_2001, ExtiLine::Line0, ExtiTriggerMode::None);
+        assert_eq!((rtsr, ftsr), (0x0000_2000, 0x0000_2000), "无触发应清除两个边沿");
+        
+        assert_eq!(ExtiLine::from_index(0), Some(ExtiLine::Line0), "线号0应为Line0");
+        assert_eq!(ExtiLine::from_index(19), Some(ExtiLine::Line19), "线号19应为Line19");
+        assert_eq!(ExtiLine::from_index(20), None, "超出范围的线号应返回None");
+        assert_eq!(ExtiLine::Line16.mask(), 1 << 16, "Line16掩码错误");
+    }
+}
@@ -0,0 +1,184 @@
+//! 风扇控制模块
+//! 提供4线PC风扇的25kHz PWM调速和测速（TACH）脉冲计数
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use crate::bsp::timer::{Timer, PwmChannel, PwmMode, PwmPolarity};
+use crate::bsp::delay::get_uptime_ms;
+
+/// 4线风扇规范（Intel 4-Wire PWM Fan Specification）要求的PWM频率
+pub const FAN_PWM_FREQUENCY_HZ: u32 = 25_000;
+
+/// 风扇每转一圈输出的测速脉冲数
+pub const FAN_TACH_PULSES_PER_REV: u32 = 2;
+
+/// 计算转速的最短采样窗口（毫秒），窗口过短时低转速下脉冲数太少
+pub const FAN_RPM_WINDOW_MS: u32 = 500;
+
+/// 4线PWM风扇控制器
+/// 
+/// PWM输出接风扇的PWM线（风扇内部上拉，通道引脚可配置为复用开漏输出），
+/// 测速线接任意配置了下降沿EXTI中断或输入捕获中断的引脚，在中断中调用`on_tach_pulse`。
+/// 周期调用`update`按采样窗口内的脉冲数刷新转速。
+pub struct FanController {
+    timer: Timer,
+    channel: PwmChannel,
+    period: u16,
+    speed_percent: u8,
+    tach_pulses: AtomicU32,
+    window_start_ms: u32,
+    rpm: u32,
+}
+
+impl FanController {
+    /// 创建风扇控制器
+    /// 
+    /// # 参数
+    /// * `timer` - 输出PWM的定时器
+    /// * `channel` - PWM通道
+    pub const fn new(timer: Timer, channel: PwmChannel) -> Self {
+        Self {
+            timer,
+            channel,
+            period: 0,
+            speed_percent: 0,
+            tach_pulses: AtomicU32::new(0),
+            window_start_ms: 0,
+            rpm: 0,
+        }
+    }
+    
+    /// 初始化25kHz PWM输出，初始转速为0%
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码使用，且SysTick已初始化（`delay::init_systick`）
+    pub unsafe fn init(&mut self) {
+        let (prescaler, period) = Self::pwm_config(self.timer.get_timer_clock());
+        self.period = period;
+        self.timer.init_pwm(self.channel, PwmMode::Mode1, PwmPolarity::High, period, prescaler, 0);
+        self.speed_percent = 0;
+        self.tach_pulses.store(0, Ordering::Relaxed);
+        self.window_start_ms = get_uptime_ms();
+        self.rpm = 0;
+    }
+    
+    /// 设置转速百分比（PWM占空比）
+    /// 
+    /// # 参数
+    /// * `percent` - 0~100，超过100按100处理
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn set_speed_percent(&mut self, percent: u8) {
+        self.speed_percent = percent.min(100);
+        self.timer.set_pwm_duty(self.channel, Self::duty_for_percent(self.period, self.speed_percent));
+    }
+    
+    /// 获取当前设置的转速百分比
+    pub fn speed_percent(&self) -> u8 {
+        self.speed_percent
+    }
+    
+    /// 测速脉冲处理，应在测速引脚的中断中调用
+    pub fn on_tach_pulse(&self) {
+        self.tach_pulses.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// 刷新转速，应在主循环中周期调用
+    /// 
+    /// 距上次刷新不足`FAN_RPM_WINDOW_MS`时不更新
+    pub fn update(&mut self) {
+        self.update_at(get_uptime_ms());
+    }
+    
+    /// 获取最近一次计算的转速（转/分）
+    pub fn rpm(&self) -> u32 {
+        self.rpm
+    }
+    
+    /// 按给定时间刷新转速
+    fn update_at(&mut self, now_ms: u32) {
+        let elapsed_ms = now_ms.wrapping_sub(self.window_start_ms);
+        if elapsed_ms < FAN_RPM_WINDOW_MS {
+            return;
+        }
+        
+        let pulses = self.tach_pulses.swap(0, Ordering::Relaxed);
+        self.rpm = Self::tach_rpm(pulses, elapsed_ms, FAN_TACH_PULSES_PER_REV);
+        self.window_start_ms = now_ms;
+    }
+    
+    /// 计算25kHz PWM的预分频和自动重装载值
+    /// 
+    /// 预分频为0以获得最高的占空比分辨率，72MHz定时器时钟下ARR为2879
+    /// 
+    /// # 返回值
+    /// (PSC, ARR)
+    pub const fn pwm_config(timer_clk: u32) -> (u16, u16) {
+        let ticks = timer_clk / FAN_PWM_FREQUENCY_HZ;
+        let prescaler = (ticks - 1) / 65536;
+        let period = ticks / (prescaler + 1) - 1;
+        (prescaler as u16, period as u16)
+    }
+    
+    /// 由转速百分比计算比较值
+    pub const fn duty_for_percent(period: u16, percent: u8) -> u16 {
+        let percent = if percent > 100 { 100 } else { percent };
+        // 100%时比较值为ARR+1，输出恒为有效电平（ARR为0xFFFF时只能取到0xFFFF）
+        let duty = (period as u32 + 1) * percent as u32 / 100;
+        if duty > 0xFFFF { 0xFFFF } else { duty as u16 }
+    }
+    
+    /// 由测速脉冲数计算转速
+    /// 
+    /// # 参数
+    /// * `pulses` - 窗口内的脉冲数
+    /// * `elapsed_ms` - 窗口长度（毫秒），为0时返回0
+    /// * `pulses_per_rev` - 每转脉冲数
+    pub const fn tach_rpm(pulses: u32, elapsed_ms: u32, pulses_per_rev: u32) -> u32 {
+        if elapsed_ms == 0 || pulses_per_rev == 0 {
+            return 0;
+        }
+        (pulses as u64 * 60_000 / (elapsed_ms as u64 * pulses_per_rev as u64)) as u32
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsp::timer::TIM3;
+    
+    /// 测试25kHz PWM的预分频和周期配置
+    #[test]
+    fn test_fan_pwm_config() {
+        assert_eq!(FanController::pwm_config(72_000_000), (0, 2879), "72MHz下应为PSC=0、ARR=2879");
+        assert_eq!(FanController::pwm_config(8_000_000), (0, 319), "8MHz下应为PSC=0、ARR=319");
+        
+        let (psc, arr) = FanController::pwm_config(72_000_000);
+        assert_eq!(72_000_000 / ((psc as u32 + 1) * (arr as u32 + 1)), FAN_PWM_FREQUENCY_HZ, "输出频率应为25kHz");
+        
+        assert_eq!(FanController::duty_for_percent(2879, 50), 1440, "50%占空比比较值错误");
+        assert_eq!(FanController::duty_for_percent(2879, 100), 2880, "100%时比较值应为ARR+1");
+        assert_eq!(FanController::duty_for_percent(2879, 150), 2880, "超过100%应按100%处理");
+    }
+    
+    /// 测试测速脉冲到转速的换算（每转2个脉冲）
+    #[test]
+    fn test_tach_rpm() {
+        assert_eq!(FanController::tach_rpm(40, 1000, 2), 1200, "1秒40个脉冲应为1200转/分");
+        assert_eq!(FanController::tach_rpm(25, 500, 2), 1500, "0.5秒25个脉冲应为1500转/分");
+        assert_eq!(FanController::tach_rpm(0, 1000, 2), 0, "没有脉冲时转速应为0");
+        assert_eq!(FanController::tach_rpm(10, 0, 2), 0, "窗口为0时应返回0");
+        
+        let mut fan = FanController::new(TIM3, PwmChannel::Channel1);
+        for _ in 0..30 {
+            fan.on_tach_pulse();
+        }
+        fan.update_at(FAN_RPM_WINDOW_MS - 1);
+        assert_eq!(fan.rpm(), 0, "窗口未到时不应刷新转速");
+        fan.update_at(600);
+        assert_eq!(fan.rpm(), 1500, "600ms内30个脉冲应为1500转/分");
+    }
+}
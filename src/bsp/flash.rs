@@ -10,6 +10,41 @@ use library::*;
 const FLASH_KEY1: u32 = 0x45670123;
 const FLASH_KEY2: u32 = 0xCDEF89AB;
 
+/// 主存储器起始地址
+pub const FLASH_START: u32 = 0x0800_0000;
+
+/// 默认主存储器容量（STM32F103C8，64KB），`FlashDriver::new`使用此容量
+pub const FLASH_SIZE: u32 = 64 * 1024;
+
+/// 默认页大小（小容量和中容量产品为1KB）
+pub const FLASH_PAGE_SIZE: u32 = 1024;
+
+/// 闪存容量寄存器（F_SIZE）地址，低16位为主存储器容量（KB）
+const FLASH_SIZE_REGISTER: u32 = 0x1FFF_F7E0;
+
+/// FLASH_SR位定义
+const SR_BSY: u32 = 1 << 0;
+const SR_PGERR: u32 = 1 << 2;
+const SR_WRPRTERR: u32 = 1 << 4;
+const SR_EOP: u32 = 1 << 5;
+
+/// FLASH_CR位定义
+const CR_PG: u32 = 1 << 0;
+const CR_PER: u32 = 1 << 1;
+const CR_STRT: u32 = 1 << 6;
+const CR_LOCK: u32 = 1 << 7;
+
+/// FLASH编程/擦除错误
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlashError {
+    OutOfRange,     // 地址超出主存储器范围
+    Unaligned,      // 地址未按半字对齐
+    Locked,         // FPEC仍处于锁定状态
+    Program,        // 编程错误（目标地址未擦除）
+    WriteProtected, // 目标页被写保护
+    Verify,         // 写入后回读校验失败
+}
+
 /// FLASH擦除类型枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FlashEraseType {
@@ -38,13 +73,111 @@ pub enum FlashLatency {
     Latency2 = 2,    // 2等待周期
 }
 
+impl FlashLatency {
+    /// 按系统时钟频率选择等待周期：≤24MHz为0，≤48MHz为1，其余为2
+    pub const fn for_sysclk(sysclk: u32) -> Self {
+        if sysclk <= 24_000_000 {
+            FlashLatency::Latency0
+        } else if sysclk <= 48_000_000 {
+            FlashLatency::Latency1
+        } else {
+            FlashLatency::Latency2
+        }
+    }
+}
+
+/// 产品容量类别
+/// 
+/// 决定闪存页大小和备份数据寄存器数量（`Bkp::with_density`），可由`FlashDensity::from_size_kb`按主存储器容量判断
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlashDensity {
+    Low,    // 小容量，16~32KB，1KB页
+    Medium, // 中容量，64~128KB，1KB页
+    High,   // 大容量，256~512KB，2KB页
+    XL,     // 超大容量，768KB~1MB，2KB页
+}
+
+impl FlashDensity {
+    /// 按主存储器容量（KB）判断容量类别
+    pub const fn from_size_kb(size_kb: u16) -> Self {
+        if size_kb <= 32 {
+            FlashDensity::Low
+        } else if size_kb <= 128 {
+            FlashDensity::Medium
+        } else if size_kb <= 512 {
+            FlashDensity::High
+        } else {
+            FlashDensity::XL
+        }
+    }
+    
+    /// 闪存页大小（字节）
+    pub const fn page_size(self) -> u32 {
+        match self {
+            FlashDensity::Low | FlashDensity::Medium => 1024,
+            FlashDensity::High | FlashDensity::XL => 2048,
+        }
+    }
+    
+    /// 备份数据寄存器数量：小容量和中容量器件为10个，大容量和超大容量器件为42个
+    pub const fn backup_data_registers(self) -> u8 {
+        match self {
+            FlashDensity::Low | FlashDensity::Medium => 10,
+            FlashDensity::High | FlashDensity::XL => 42,
+        }
+    }
+}
+
+/// 读取闪存容量寄存器，返回主存储器容量（KB）
+/// 
+/// # Safety
+/// - 仅能在目标硬件上调用
+pub unsafe fn read_flash_size_kb() -> u16 {
+    core::ptr::read_volatile(FLASH_SIZE_REGISTER as *const u16)
+}
+
 /// FLASH结构体
-pub struct FlashDriver;
+/// 
+/// 记录主存储器容量，地址检查和页大小由容量决定
+pub struct FlashDriver {
+    size: u32,
+}
 
 impl FlashDriver {
-    /// 创建新的FLASH实例
+    /// 创建新的FLASH实例，按STM32F103C8（64KB，1KB页）配置
     pub const fn new() -> Self {
-        Self
+        Self { size: FLASH_SIZE }
+    }
+    
+    /// 按指定主存储器容量创建FLASH实例
+    /// 
+    /// # 参数
+    /// * `size_kb` - 主存储器容量（KB），如STM32F103ZE为512
+    pub const fn with_size_kb(size_kb: u16) -> Self {
+        Self { size: size_kb as u32 * 1024 }
+    }
+    
+    /// 读取F_SIZE寄存器，按实际器件容量创建FLASH实例
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn detect() -> Self {
+        Self::with_size_kb(read_flash_size_kb())
+    }
+    
+    /// 主存储器容量（字节）
+    pub const fn size(&self) -> u32 {
+        self.size
+    }
+    
+    /// 容量类别
+    pub const fn density(&self) -> FlashDensity {
+        FlashDensity::from_size_kb((self.size / 1024) as u16)
+    }
+    
+    /// 页大小（字节）
+    pub const fn page_size(&self) -> u32 {
+        self.density().page_size()
     }
     
     /// 获取FLASH寄存器块
@@ -53,8 +186,16 @@ impl FlashDriver {
     }
     
     /// 解锁FLASH
+    /// 
+    /// 已解锁时不再写入密钥，错误的密钥序列会让FPEC锁定到下次复位
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn unlock(&self) {
         let flash = self.get_flash();
+        if flash.cr().read().bits() & CR_LOCK == 0 {
+            return;
+        }
         // 写入第一个密钥
         flash.keyr().write(|w: &mut library::flash::keyr::W| unsafe { w.bits(FLASH_KEY1) });
         // 写入第二个密钥
@@ -62,12 +203,18 @@ impl FlashDriver {
     }
     
     /// 锁定FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn lock(&self) {
         let flash = self.get_flash();
         flash.cr().write(|w: &mut library::flash::cr::W| unsafe { w.bits(flash.cr().read().bits() | (1 << 7)) });
     }
     
     /// 解锁选项字节
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn unlock_option_bytes(&self) {
         let flash = self.get_flash();
         // 写入第一个密钥
@@ -76,36 +223,152 @@ impl FlashDriver {
         flash.optkeyr().write(|w: &mut library::flash::optkeyr::W| unsafe { w.bits(0x4C5D6E7F) });
     }
     
+    /// 检查FPEC是否处于锁定状态
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_locked(&self) -> bool {
+        let flash = self.get_flash();
+        flash.cr().read().bits() & CR_LOCK != 0
+    }
+    
+    /// 擦除地址所在的页
+    /// 
+    /// # 参数
+    /// * `address` - 页内任意地址（不要求对齐），必须位于主存储器内
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，且被擦除的页不包含正在运行的代码
+    pub unsafe fn erase_page(&self, address: u32) -> Result<(), FlashError> {
+        let page = page_address(address, self.page_size());
+        check_range(page, self.page_size(), self.size)?;
+        self.check_unlocked()?;
+        let flash = self.get_flash();
+        
+        self.wait_ready()?;
+        flash.cr().modify(|r, w| unsafe { w.bits(r.bits() | CR_PER) });
+        flash.ar().write(|w: &mut library::flash::ar::W| unsafe { w.bits(page) });
+        flash.cr().modify(|r, w| unsafe { w.bits(r.bits() | CR_STRT) });
+        let result = self.wait_ready();
+        flash.cr().modify(|r, w| unsafe { w.bits(r.bits() & !CR_PER) });
+        result
+    }
+    
+    /// 编程一个半字
+    /// 
+    /// # 参数
+    /// * `address` - 目标地址，必须半字对齐且位于主存储器内
+    /// * `data` - 写入的数据，目标地址必须已擦除（0xFFFF），写入0x0000除外
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`
+    pub unsafe fn program_half_word(&self, address: u32, data: u16) -> Result<(), FlashError> {
+        check_range(address, 2, self.size)?;
+        self.check_unlocked()?;
+        let flash = self.get_flash();
+        
+        self.wait_ready()?;
+        flash.cr().modify(|r, w| unsafe { w.bits(r.bits() | CR_PG) });
+        (address as *mut u16).write_volatile(data);
+        let result = self.wait_ready();
+        flash.cr().modify(|r, w| unsafe { w.bits(r.bits() & !CR_PG) });
+        result?;
+        
+        if (address as *const u16).read_volatile() != data {
+            return Err(FlashError::Verify);
+        }
+        Ok(())
+    }
+    
+    /// 按半字编程一段数据（小端序）
+    /// 
+    /// 长度为奇数时最后一个半字的高字节补0xFF（保持擦除状态）
+    /// 
+    /// # 参数
+    /// * `address` - 起始地址，必须半字对齐，整段数据必须位于主存储器内
+    /// * `data` - 写入的数据
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，且目标区域已擦除
+    pub unsafe fn program_slice(&self, address: u32, data: &[u8]) -> Result<(), FlashError> {
+        check_range(address, data.len() as u32, self.size)?;
+        
+        let mut addr = address;
+        for chunk in data.chunks(2) {
+            let high = if chunk.len() == 2 { chunk[1] } else { 0xFF };
+            self.program_half_word(addr, u16::from_le_bytes([chunk[0], high]))?;
+            addr += 2;
+        }
+        Ok(())
+    }
+    
+    /// 检查FPEC是否已解锁
+    unsafe fn check_unlocked(&self) -> Result<(), FlashError> {
+        if self.is_locked() {
+            Err(FlashError::Locked)
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// 等待当前操作结束，清除状态标志并返回操作结果
+    unsafe fn wait_ready(&self) -> Result<(), FlashError> {
+        let flash = self.get_flash();
+        while self.is_busy() {
+            core::hint::spin_loop();
+        }
+        
+        let sr = flash.sr().read().bits();
+        // EOP/PGERR/WRPRTERR写1清除
+        flash.sr().write(|w: &mut library::flash::sr::W| unsafe { w.bits(sr & (SR_EOP | SR_PGERR | SR_WRPRTERR)) });
+        status_result(sr)
+    }
+    
     /// 锁定选项字节
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn lock_option_bytes(&self) {
         let flash = self.get_flash();
         flash.cr().write(|w: &mut library::flash::cr::W| unsafe { w.bits(flash.cr().read().bits() | (1 << 9)) });
     }
     
     /// 设置FLASH等待周期
+    /// 
+    /// # Safety
+    /// - 调用者必须确保等待周期满足当前系统时钟频率，提高时钟前先增加等待周期
     pub unsafe fn set_latency(&self, latency: FlashLatency) {
         let flash = self.get_flash();
         let mut value = flash.acr().read().bits();
         // 清除等待周期位
-        value &= !(0x03 << 0);
+        value &= !0x03;
         // 设置等待周期
-        value |= (latency as u32) << 0;
+        value |= latency as u32;
         flash.acr().write(|w: &mut library::flash::acr::W| unsafe { w.bits(value) });
     }
     
     /// 启用FLASH预取缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn enable_prefetch(&self) {
         let flash = self.get_flash();
         flash.acr().write(|w: &mut library::flash::acr::W| unsafe { w.bits(flash.acr().read().bits() | (1 << 4)) });
     }
     
     /// 禁用FLASH预取缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn disable_prefetch(&self) {
         let flash = self.get_flash();
         flash.acr().write(|w: &mut library::flash::acr::W| unsafe { w.bits(flash.acr().read().bits() & !(1 << 4)) });
     }
     
     /// 擦除FLASH扇区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，且被擦除的区域不包含正在运行的代码
     pub unsafe fn erase_sector(&self, sector: FlashSector) {
         let flash = self.get_flash();
         
@@ -131,6 +394,9 @@ impl FlashDriver {
     }
     
     /// 整片擦除FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，整片擦除会清除正在运行的程序，只能从RAM中调用
     pub unsafe fn mass_erase(&self) {
         let flash = self.get_flash();
         
@@ -154,6 +420,9 @@ impl FlashDriver {
     }
     
     /// 写入半字到FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，且目标地址半字对齐并已擦除
     pub unsafe fn write_half_word(&self, address: u32, data: u16) {
         let flash = self.get_flash();
         
@@ -178,6 +447,9 @@ impl FlashDriver {
     }
     
     /// 写入字到FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，且目标地址半字对齐并已擦除
     pub unsafe fn write_word(&self, address: u32, data: u32) {
         // 写入高半字
         self.write_half_word(address, (data >> 16) as u16);
@@ -186,12 +458,15 @@ impl FlashDriver {
     }
     
     /// 写入数据到FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`unlock`，`data`非空，且目标区域已擦除
     pub unsafe fn write_data(&self, address: u32, data: &[u8]) {
         let mut addr = address;
         let mut i = 0;
         
         // 对齐地址
-        if (addr % 2) != 0 {
+        if !addr.is_multiple_of(2) {
             // 写入单个字节
             let byte = data[0];
             let current_data = *(addr as *mut u8);
@@ -219,16 +494,25 @@ impl FlashDriver {
     }
     
     /// 读取半字从FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保地址位于可读的存储区域内且半字对齐
     pub unsafe fn read_half_word(&self, address: u32) -> u16 {
         *(address as *mut u16)
     }
     
     /// 读取字从FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保地址位于可读的存储区域内且字对齐
     pub unsafe fn read_word(&self, address: u32) -> u32 {
         *(address as *mut u32)
     }
     
     /// 读取数据从FLASH
+    /// 
+    /// # Safety
+    /// - 调用者必须确保整个区域位于可读的存储区域内
     pub unsafe fn read_data(&self, address: u32, buffer: &mut [u8]) {
         let src = address as *const u8;
         let dst = buffer.as_mut_ptr();
@@ -239,41 +523,550 @@ impl FlashDriver {
     }
     
     /// 检查FLASH是否忙
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_busy(&self) -> bool {
         let flash = self.get_flash();
         (flash.sr().read().bits() & (1 << 0)) != 0
     }
     
     /// 检查编程错误
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn has_program_error(&self) -> bool {
         let flash = self.get_flash();
         (flash.sr().read().bits() & (1 << 2)) != 0
     }
     
     /// 检查擦除错误
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn has_erase_error(&self) -> bool {
         let flash = self.get_flash();
         (flash.sr().read().bits() & (1 << 3)) != 0
     }
     
     /// 清除所有错误标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保FPEC未被其他代码同时操作
     pub unsafe fn clear_error_flags(&self) {
         let flash = self.get_flash();
-        flash.sr().write(|w: &mut library::flash::sr::W| unsafe { w.bits(flash.sr().read().bits() & !(0x0E << 1)) });
+        // 状态标志写1清除，写0无效
+        flash.sr().write(|w: &mut library::flash::sr::W| unsafe { w.bits(SR_EOP | SR_PGERR | SR_WRPRTERR) });
     }
     
     /// 获取选项字节值
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_option_bytes(&self) -> u32 {
         let flash = self.get_flash();
         flash.obr().read().bits()
     }
     
     /// 检查写保护状态
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_write_protected(&self, sector: FlashSector) -> bool {
         let flash = self.get_flash();
         (flash.wrpr().read().bits() & (1 << (sector as u32))) != 0
     }
 }
 
+impl Default for FlashDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的FLASH实例
 pub const FLASH: FlashDriver = FlashDriver::new();
+
+/// 闪存存储接口
+/// 
+/// `EepromEmulation`通过此接口访问闪存，目标硬件上使用`FlashDriver`，主机测试中使用内存模拟
+pub trait FlashStorage {
+    /// 读取半字
+    /// # Safety
+    /// - 调用者必须确保地址有效且半字对齐
+    unsafe fn read_half_word(&self, address: u32) -> u16;
+    
+    /// 编程半字，目标地址必须已擦除（写入0x0000除外）
+    /// # Safety
+    /// - 调用者必须确保闪存已解锁
+    unsafe fn program_half_word(&self, address: u32, data: u16) -> Result<(), FlashError>;
+    
+    /// 擦除地址所在的页
+    /// # Safety
+    /// - 调用者必须确保闪存已解锁，且被擦除的页不包含正在运行的代码
+    unsafe fn erase_page(&self, address: u32) -> Result<(), FlashError>;
+    
+    /// 页大小（字节）
+    fn page_size(&self) -> u32;
+}
+
+impl FlashStorage for FlashDriver {
+    unsafe fn read_half_word(&self, address: u32) -> u16 {
+        (address as *const u16).read_volatile()
+    }
+    
+    unsafe fn program_half_word(&self, address: u32, data: u16) -> Result<(), FlashError> {
+        FlashDriver::program_half_word(self, address, data)
+    }
+    
+    unsafe fn erase_page(&self, address: u32) -> Result<(), FlashError> {
+        FlashDriver::erase_page(self, address)
+    }
+    
+    fn page_size(&self) -> u32 {
+        FlashDriver::page_size(self)
+    }
+}
+
+/// 页状态：有效页（当前使用的页）
+const PAGE_VALID: u16 = 0x0000;
+
+/// 页状态：正在接收数据（整理过程中的目标页）
+const PAGE_RECEIVE: u16 = 0xEEEE;
+
+/// 页状态：已擦除
+const PAGE_ERASED: u16 = 0xFFFF;
+
+/// 页头大小（字节），第一个半字为页状态，第二个半字保留
+const EEPROM_HEADER_SIZE: u32 = 4;
+
+/// 每条记录的大小（字节）：键和值各一个半字
+const EEPROM_RECORD_SIZE: u32 = 4;
+
+/// 每页可容纳的记录数，1KB页为255条，2KB页为511条
+pub const fn eeprom_records_per_page(page_size: u32) -> u32 {
+    (page_size - EEPROM_HEADER_SIZE) / EEPROM_RECORD_SIZE
+}
+
+/// 未写入记录的键值，不能作为用户键使用
+pub const EEPROM_EMPTY_KEY: u16 = 0xFFFF;
+
+/// EEPROM模拟错误
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EepromError {
+    Flash(FlashError),  // 闪存编程/擦除错误
+    Full,               // 不同键的数量超过一页的容量
+    InvalidKey,         // 键为保留值0xFFFF
+}
+
+impl From<FlashError> for EepromError {
+    fn from(error: FlashError) -> Self {
+        EepromError::Flash(error)
+    }
+}
+
+/// 闪存模拟EEPROM
+/// 
+/// 使用两个闪存页轮流存储`(键, 值)`记录，写入只追加不覆盖，读取时取该键最后一条记录。
+/// 有效页写满后，把每个键的最新值整理到另一页，再擦除原页。
+/// 
+/// 每页开头为页状态，整理时先把目标页标记为接收状态，复制完成后标记为有效，
+/// 最后擦除原页；`init`根据两页的状态恢复被掉电打断的整理过程。
+/// 记录先写值再写键，掉电时只写了值的记录不会被当作有效记录。
+pub struct EepromEmulation<F: FlashStorage = FlashDriver> {
+    flash: F,
+    pages: [u32; 2],
+    active: usize,
+}
+
+impl<F: FlashStorage> EepromEmulation<F> {
+    /// 创建EEPROM模拟
+    /// 
+    /// # 参数
+    /// * `flash` - 闪存存储
+    /// * `page0` - 第一个页的起始地址
+    /// * `page1` - 第二个页的起始地址，两页均不能与程序代码重叠
+    /// 
+    /// 两页都必须按`flash.page_size()`对齐
+    pub fn new(flash: F, page0: u32, page1: u32) -> Self {
+        let page_size = flash.page_size();
+        assert!(page0.is_multiple_of(page_size) && page1.is_multiple_of(page_size), "EEPROM pages must be page aligned");
+        assert!(page0 != page1, "EEPROM pages must be distinct");
+        Self {
+            flash,
+            pages: [page0, page1],
+            active: 0,
+        }
+    }
+    
+    /// 根据页状态选择有效页，必要时恢复被打断的整理过程
+    /// 
+    /// 两页状态都无法识别时格式化（丢失所有数据）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保闪存已解锁
+    pub unsafe fn init(&mut self) -> Result<(), EepromError> {
+        let status = [self.page_status(0), self.page_status(1)];
+        match status {
+            [PAGE_VALID, PAGE_ERASED] => self.active = 0,
+            [PAGE_ERASED, PAGE_VALID] => self.active = 1,
+            // 整理过程中掉电，原页仍然完整：丢弃未完成的目标页
+            [PAGE_VALID, other] if other != PAGE_VALID => {
+                self.flash.erase_page(self.pages[1])?;
+                self.active = 0;
+            },
+            [other, PAGE_VALID] if other != PAGE_VALID => {
+                self.flash.erase_page(self.pages[0])?;
+                self.active = 1;
+            },
+            // 原页已擦除，目标页复制已完成但未标记为有效
+            [PAGE_RECEIVE, PAGE_ERASED] => {
+                self.flash.program_half_word(self.pages[0], PAGE_VALID)?;
+                self.active = 0;
+            },
+            [PAGE_ERASED, PAGE_RECEIVE] => {
+                self.flash.program_half_word(self.pages[1], PAGE_VALID)?;
+                self.active = 1;
+            },
+            // 目标页已标记为有效但原页未擦除：整理只在页写满时发生，未写满的为新页
+            [PAGE_VALID, PAGE_VALID] => {
+                let active = if self.free_slot(0).is_some() { 0 } else { 1 };
+                self.flash.erase_page(self.pages[1 - active])?;
+                self.active = active;
+            },
+            _ => self.format()?,
+        }
+        Ok(())
+    }
+    
+    /// 擦除两页并把第一页标记为有效页，丢失所有数据
+    /// 
+    /// # Safety
+    /// - 调用者必须确保闪存已解锁
+    pub unsafe fn format(&mut self) -> Result<(), EepromError> {
+        self.flash.erase_page(self.pages[0])?;
+        self.flash.erase_page(self.pages[1])?;
+        self.flash.program_half_word(self.pages[0], PAGE_VALID)?;
+        self.active = 0;
+        Ok(())
+    }
+    
+    /// 读取键的最新值，键不存在时返回None
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`或`format`
+    pub unsafe fn read(&self, key: u16) -> Option<u16> {
+        if key == EEPROM_EMPTY_KEY {
+            return None;
+        }
+        self.find_latest(self.active, key)
+    }
+    
+    /// 写入键值，值与当前值相同时不写入闪存
+    /// 
+    /// 有效页写满时自动整理到另一页
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`或`format`，且闪存已解锁
+    pub unsafe fn write(&mut self, key: u16, value: u16) -> Result<(), EepromError> {
+        if key == EEPROM_EMPTY_KEY {
+            return Err(EepromError::InvalidKey);
+        }
+        if self.read(key) == Some(value) {
+            return Ok(());
+        }
+        
+        match self.free_slot(self.active) {
+            Some(slot) => self.write_record(slot, key, value),
+            None => self.transfer(key, value),
+        }
+    }
+    
+    /// 把每个键的最新值和新记录整理到另一页，再擦除原页
+    unsafe fn transfer(&mut self, key: u16, value: u16) -> Result<(), EepromError> {
+        let old = self.active;
+        let new = 1 - old;
+        
+        self.flash.erase_page(self.pages[new])?;
+        self.flash.program_half_word(self.pages[new], PAGE_RECEIVE)?;
+        self.append(new, key, value)?;
+        
+        for index in 0..self.records_per_page() {
+            let record_key = self.flash.read_half_word(self.record_address(old, index));
+            if record_key == EEPROM_EMPTY_KEY || record_key == key || self.find_latest(new, record_key).is_some() {
+                continue;
+            }
+            if let Some(record_value) = self.find_latest(old, record_key) {
+                self.append(new, record_key, record_value)?;
+            }
+        }
+        
+        // 有效状态为0x0000，可以直接覆盖接收状态
+        self.flash.program_half_word(self.pages[new], PAGE_VALID)?;
+        self.flash.erase_page(self.pages[old])?;
+        self.active = new;
+        Ok(())
+    }
+    
+    /// 在页的下一个空闲位置追加记录
+    unsafe fn append(&self, page: usize, key: u16, value: u16) -> Result<(), EepromError> {
+        match self.free_slot(page) {
+            Some(slot) => self.write_record(slot, key, value),
+            None => Err(EepromError::Full),
+        }
+    }
+    
+    /// 写入一条记录：先写值，再写键
+    unsafe fn write_record(&self, address: u32, key: u16, value: u16) -> Result<(), EepromError> {
+        self.flash.program_half_word(address + 2, value)?;
+        self.flash.program_half_word(address, key)?;
+        Ok(())
+    }
+    
+    /// 从后向前查找键的最后一条记录
+    unsafe fn find_latest(&self, page: usize, key: u16) -> Option<u16> {
+        (0..self.records_per_page()).rev().find_map(|index| {
+            let address = self.record_address(page, index);
+            if self.flash.read_half_word(address) == key {
+                Some(self.flash.read_half_word(address + 2))
+            } else {
+                None
+            }
+        })
+    }
+    
+    /// 查找页中第一个键和值都未写入的记录位置
+    unsafe fn free_slot(&self, page: usize) -> Option<u32> {
+        (0..self.records_per_page()).map(|index| self.record_address(page, index)).find(|&address| {
+            self.flash.read_half_word(address) == EEPROM_EMPTY_KEY
+                && self.flash.read_half_word(address + 2) == EEPROM_EMPTY_KEY
+        })
+    }
+    
+    /// 读取页状态
+    unsafe fn page_status(&self, page: usize) -> u16 {
+        self.flash.read_half_word(self.pages[page])
+    }
+    
+    /// 每页可容纳的记录数
+    pub fn records_per_page(&self) -> u32 {
+        eeprom_records_per_page(self.flash.page_size())
+    }
+    
+    /// 获取页中第`index`条记录的地址
+    fn record_address(&self, page: usize, index: u32) -> u32 {
+        self.pages[page] + EEPROM_HEADER_SIZE + index * EEPROM_RECORD_SIZE
+    }
+}
+
+/// 检查一段区域是否位于主存储器内且起始地址半字对齐
+/// 
+/// # 参数
+/// * `address` - 起始地址
+/// * `len` - 区域长度（字节），为0时只检查起始地址
+/// * `size` - 主存储器容量（字节）
+pub const fn check_range(address: u32, len: u32, size: u32) -> Result<(), FlashError> {
+    if address < FLASH_START || address - FLASH_START >= size {
+        return Err(FlashError::OutOfRange);
+    }
+    if len > size - (address - FLASH_START) {
+        return Err(FlashError::OutOfRange);
+    }
+    if !address.is_multiple_of(2) {
+        return Err(FlashError::Unaligned);
+    }
+    Ok(())
+}
+
+/// 获取地址所在页的起始地址
+/// 
+/// # 参数
+/// * `address` - 页内任意地址
+/// * `page_size` - 页大小（字节），必须为2的幂
+pub const fn page_address(address: u32, page_size: u32) -> u32 {
+    address & !(page_size - 1)
+}
+
+/// 由FLASH_SR计算操作结果，写保护错误优先
+pub const fn status_result(sr: u32) -> Result<(), FlashError> {
+    if sr & SR_WRPRTERR != 0 {
+        Err(FlashError::WriteProtected)
+    } else if sr & SR_PGERR != 0 {
+        Err(FlashError::Program)
+    } else {
+        Ok(())
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    
+    /// EEPROM测试使用的两页起始地址
+    const EEPROM_PAGE0: u32 = 0x0800_F800;
+    const EEPROM_PAGE1: u32 = 0x0800_FC00;
+    
+    /// 内存模拟的两页闪存，编程规则与硬件一致
+    /// 
+    /// `PAGE`为页大小（字节），两页共`PAGE`个半字，第一页从`EEPROM_PAGE0`开始
+    struct RamFlash<const PAGE: usize = 1024> {
+        data: RefCell<[u16; PAGE]>,
+    }
+    
+    impl<const PAGE: usize> RamFlash<PAGE> {
+        fn new() -> Self {
+            Self { data: RefCell::new([0xFFFF; PAGE]) }
+        }
+        
+        fn index(address: u32) -> usize {
+            ((address - EEPROM_PAGE0) / 2) as usize
+        }
+    }
+    
+    impl<const PAGE: usize> FlashStorage for RamFlash<PAGE> {
+        unsafe fn read_half_word(&self, address: u32) -> u16 {
+            self.data.borrow()[Self::index(address)]
+        }
+        
+        unsafe fn program_half_word(&self, address: u32, data: u16) -> Result<(), FlashError> {
+            let mut cells = self.data.borrow_mut();
+            let cell = &mut cells[Self::index(address)];
+            if *cell != 0xFFFF && data != 0 {
+                return Err(FlashError::Program);
+            }
+            *cell = data;
+            Ok(())
+        }
+        
+        unsafe fn erase_page(&self, address: u32) -> Result<(), FlashError> {
+            let start = Self::index(page_address(address, PAGE as u32));
+            self.data.borrow_mut()[start..start + PAGE / 2].fill(0xFFFF);
+            Ok(())
+        }
+        
+        fn page_size(&self) -> u32 {
+            PAGE as u32
+        }
+    }
+    
+    /// 测试EEPROM模拟的读写和写满后的整理
+    #[test]
+    fn test_eeprom_write_and_compact() {
+        let mut eeprom = EepromEmulation::new(RamFlash::<1024>::new(), EEPROM_PAGE0, EEPROM_PAGE1);
+        unsafe {
+            eeprom.init().unwrap();
+            assert_eq!(eeprom.page_status(0), PAGE_VALID, "空白闪存应被格式化");
+            assert_eq!(eeprom.read(1), None, "未写入的键应返回None");
+            
+            eeprom.write(1, 100).unwrap();
+            eeprom.write(2, 200).unwrap();
+            eeprom.write(1, 101).unwrap();
+            assert_eq!(eeprom.read(1), Some(101), "应读到最新值");
+            assert_eq!(eeprom.read(2), Some(200), "其他键不受影响");
+            assert_eq!(eeprom.write(EEPROM_EMPTY_KEY, 0), Err(EepromError::InvalidKey), "保留键应被拒绝");
+            
+            // 相同值不写入闪存
+            let before = eeprom.free_slot(0);
+            eeprom.write(1, 101).unwrap();
+            assert_eq!(eeprom.free_slot(0), before, "相同值不应追加记录");
+            
+            // 写满第一页后整理到第二页
+            assert_eq!(eeprom.records_per_page(), 255, "1KB页应容纳255条记录");
+            for value in 0..eeprom.records_per_page() as u16 {
+                eeprom.write(3, value).unwrap();
+            }
+            assert_eq!(eeprom.active, 1, "写满后应切换到第二页");
+            assert_eq!(eeprom.page_status(0), PAGE_ERASED, "原页应被擦除");
+            assert_eq!(eeprom.page_status(1), PAGE_VALID, "新页应标记为有效");
+            assert_eq!(eeprom.read(1), Some(101), "整理后应保留键1的最新值");
+            assert_eq!(eeprom.read(2), Some(200), "整理后应保留键2的最新值");
+            assert_eq!(eeprom.read(3), Some(eeprom.records_per_page() as u16 - 1), "整理后应保留键3的最新值");
+        }
+        
+        // 大容量器件2KB页：第二页从第一页之后2KB开始
+        let mut eeprom = EepromEmulation::new(RamFlash::<2048>::new(), EEPROM_PAGE0, EEPROM_PAGE0 + 2048);
+        unsafe {
+            eeprom.format().unwrap();
+            assert_eq!(eeprom.records_per_page(), 511, "2KB页应容纳511条记录");
+            for value in 0..=eeprom.records_per_page() as u16 {
+                eeprom.write(4, value).unwrap();
+            }
+            assert_eq!(eeprom.active, 1, "写满2KB页后应切换到第二页");
+            assert_eq!(eeprom.read(4), Some(eeprom.records_per_page() as u16), "整理后应保留最新值");
+        }
+    }
+    
+    /// 测试整理过程被打断后的恢复
+    #[test]
+    fn test_eeprom_recover_interrupted_transfer() {
+        let mut eeprom = EepromEmulation::new(RamFlash::<1024>::new(), EEPROM_PAGE0, EEPROM_PAGE1);
+        unsafe {
+            eeprom.format().unwrap();
+            eeprom.write(7, 70).unwrap();
+            
+            // 目标页刚标记为接收状态时掉电：丢弃目标页，继续使用原页
+            eeprom.flash.program_half_word(EEPROM_PAGE1, PAGE_RECEIVE).unwrap();
+            eeprom.flash.program_half_word(EEPROM_PAGE1 + 6, 71).unwrap();
+            eeprom.flash.program_half_word(EEPROM_PAGE1 + 4, 7).unwrap();
+            let mut recovered = EepromEmulation::new(eeprom.flash, EEPROM_PAGE0, EEPROM_PAGE1);
+            recovered.init().unwrap();
+            assert_eq!(recovered.active, 0, "原页完整时应继续使用原页");
+            assert_eq!(recovered.page_status(1), PAGE_ERASED, "未完成的目标页应被擦除");
+            assert_eq!(recovered.read(7), Some(70), "应读到原页中的值");
+            
+            // 原页已擦除但目标页未标记为有效：完成标记
+            recovered.flash.program_half_word(EEPROM_PAGE1, PAGE_RECEIVE).unwrap();
+            recovered.flash.program_half_word(EEPROM_PAGE1 + 6, 71).unwrap();
+            recovered.flash.program_half_word(EEPROM_PAGE1 + 4, 7).unwrap();
+            recovered.flash.erase_page(EEPROM_PAGE0).unwrap();
+            let mut recovered = EepromEmulation::new(recovered.flash, EEPROM_PAGE0, EEPROM_PAGE1);
+            recovered.init().unwrap();
+            assert_eq!(recovered.active, 1, "应使用复制完成的目标页");
+            assert_eq!(recovered.page_status(1), PAGE_VALID, "目标页应标记为有效");
+            assert_eq!(recovered.read(7), Some(71), "应读到目标页中的值");
+        }
+    }
+    
+    /// 测试编程地址的范围和对齐检查
+    #[test]
+    fn test_flash_range_check() {
+        assert_eq!(check_range(FLASH_START, 2, FLASH_SIZE), Ok(()), "起始地址应有效");
+        assert_eq!(check_range(0x0800_FFFE, 2, FLASH_SIZE), Ok(()), "最后一个半字应有效");
+        assert_eq!(check_range(0x0800_FFFE, 4, FLASH_SIZE), Err(FlashError::OutOfRange), "跨越末尾应超出范围");
+        assert_eq!(check_range(0x0801_0000, 2, FLASH_SIZE), Err(FlashError::OutOfRange), "末尾之后应超出范围");
+        assert_eq!(check_range(0x2000_0000, 2, FLASH_SIZE), Err(FlashError::OutOfRange), "SRAM地址应超出范围");
+        assert_eq!(check_range(0x07FF_FFFE, 2, FLASH_SIZE), Err(FlashError::OutOfRange), "起始之前应超出范围");
+        assert_eq!(check_range(0x0800_0401, 2, FLASH_SIZE), Err(FlashError::Unaligned), "奇数地址应未对齐");
+        assert_eq!(check_range(0x0801_0000, 2, 512 * 1024), Ok(()), "512KB器件上64KB之后的地址应有效");
+        
+        assert_eq!(page_address(0x0800_FC10, 1024), 0x0800_FC00, "应对齐到1KB页");
+        assert_eq!(page_address(0x0800_FC11, 1024), 0x0800_FC00, "奇数地址也应对齐到所在页");
+        assert_eq!(page_address(0x0800_FC10, 2048), 0x0800_F800, "应对齐到2KB页");
+        assert_eq!(page_address(FLASH_START, 1024), FLASH_START, "页起始地址应不变");
+    }
+    
+    /// 测试按容量判断容量类别和页大小
+    #[test]
+    fn test_flash_density() {
+        assert_eq!(FlashDensity::from_size_kb(32), FlashDensity::Low, "32KB应为小容量");
+        assert_eq!(FlashDensity::from_size_kb(64), FlashDensity::Medium, "64KB应为中容量");
+        assert_eq!(FlashDensity::from_size_kb(128), FlashDensity::Medium, "128KB应为中容量");
+        assert_eq!(FlashDensity::from_size_kb(512), FlashDensity::High, "512KB应为大容量");
+        assert_eq!(FlashDensity::from_size_kb(1024), FlashDensity::XL, "1MB应为超大容量");
+        
+        assert_eq!(FlashDriver::new().page_size(), 1024, "默认应为1KB页");
+        assert_eq!(FlashDriver::with_size_kb(256).page_size(), 2048, "大容量器件应为2KB页");
+        assert_eq!(FlashDriver::with_size_kb(512).size(), 512 * 1024, "容量换算错误");
+    }
+    
+    /// 测试状态寄存器到操作结果的换算
+    #[test]
+    fn test_flash_status_result() {
+        assert_eq!(status_result(SR_EOP), Ok(()), "只有EOP时应成功");
+        assert_eq!(status_result(SR_PGERR | SR_EOP), Err(FlashError::Program), "PGERR应为编程错误");
+        assert_eq!(status_result(SR_WRPRTERR), Err(FlashError::WriteProtected), "WRPRTERR应为写保护错误");
+        assert_eq!(status_result(SR_WRPRTERR | SR_PGERR), Err(FlashError::WriteProtected), "写保护错误应优先");
+    }
+}
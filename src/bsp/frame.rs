@@ -0,0 +1,305 @@
+//! 帧协议模块
+//! 提供串口数据帧的封装和解析：起始字节 + 长度 + 数据 + CRC-16，使用字节填充转义起始字节
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::serial::{Serial, SerialError};
+
+/// 帧起始字节
+pub const FRAME_START: u8 = 0x7E;
+
+/// 转义字节，后跟的字节需与`FRAME_ESCAPE_XOR`异或还原
+pub const FRAME_ESCAPE: u8 = 0x7D;
+
+/// 转义异或值
+pub const FRAME_ESCAPE_XOR: u8 = 0x20;
+
+/// 最大数据长度
+pub const FRAME_MAX_PAYLOAD: usize = 255;
+
+/// 编码后帧的最大长度：起始字节 + (长度 + 数据 + CRC)全部转义
+pub const FRAME_MAX_ENCODED: usize = 1 + 2 * (1 + FRAME_MAX_PAYLOAD + 2);
+
+/// 帧错误枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameError {
+    CrcMismatch,            // CRC校验失败
+    Overflow,               // 数据长度超过接收缓冲区
+    Serial(SerialError),    // 串口接收错误
+}
+
+/// 计算CRC-16/CCITT-FALSE（多项式0x1021，初值0xFFFF）
+pub const fn crc16(data: &[u8]) -> u16 {
+    crc16_update(0xFFFF, data)
+}
+
+/// 在已有CRC的基础上继续计算CRC-16/CCITT-FALSE
+pub const fn crc16_update(mut crc: u16, data: &[u8]) -> u16 {
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= (data[i] as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// 帧发送器
+/// 
+/// 帧格式（转义前）：`0x7E | 长度 | 数据 | CRC高字节 | CRC低字节`，CRC覆盖长度和数据。
+/// 起始字节之后出现的0x7E和0x7D都转义为`0x7D, 字节 ^ 0x20`，因此0x7E只会出现在帧头。
+pub struct FrameTx;
+
+impl FrameTx {
+    /// 将数据编码为帧
+    /// 
+    /// # 参数
+    /// * `payload` - 数据，最长`FRAME_MAX_PAYLOAD`字节
+    /// * `out` - 输出缓冲区，长度为`FRAME_MAX_ENCODED`时一定够用
+    /// 
+    /// # 返回值
+    /// 编码后的长度，输出缓冲区不足时返回None
+    pub fn encode(payload: &[u8], out: &mut [u8]) -> Option<usize> {
+        assert!(payload.len() <= FRAME_MAX_PAYLOAD, "Frame payload must be at most 255 bytes");
+        
+        let length = [payload.len() as u8];
+        let crc = crc16_update(crc16(&length), payload);
+        
+        let mut writer = StuffingWriter { out, len: 0 };
+        writer.raw(FRAME_START)?;
+        for &byte in length.iter().chain(payload.iter()).chain(crc.to_be_bytes().iter()) {
+            writer.stuffed(byte)?;
+        }
+        Some(writer.len)
+    }
+    
+    /// 编码并通过串口发送一帧
+    pub fn send(serial: &Serial, payload: &[u8]) {
+        let mut buffer = [0u8; FRAME_MAX_ENCODED];
+        if let Some(len) = Self::encode(payload, &mut buffer) {
+            serial.write_bytes(&buffer[..len]);
+        }
+    }
+}
+
+/// 带字节填充的输出缓冲区写入器
+struct StuffingWriter<'a> {
+    out: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> StuffingWriter<'a> {
+    /// 写入不转义的字节
+    fn raw(&mut self, byte: u8) -> Option<()> {
+        *self.out.get_mut(self.len)? = byte;
+        self.len += 1;
+        Some(())
+    }
+    
+    /// 写入需要时转义的字节
+    fn stuffed(&mut self, byte: u8) -> Option<()> {
+        if byte == FRAME_START || byte == FRAME_ESCAPE {
+            self.raw(FRAME_ESCAPE)?;
+            self.raw(byte ^ FRAME_ESCAPE_XOR)
+        } else {
+            self.raw(byte)
+        }
+    }
+}
+
+/// 接收状态枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RxState {
+    Idle,       // 等待起始字节
+    Length,     // 等待长度字节
+    Payload,    // 接收数据
+    CrcHigh,    // 等待CRC高字节
+    CrcLow,     // 等待CRC低字节
+}
+
+/// 帧接收器
+/// 
+/// 逐字节解析帧，收到起始字节时总是重新开始，因此丢字节或半帧不会影响后续帧的同步。
+/// 数据保存在长度为N的内部缓冲区中，长度超过N的帧被丢弃。
+pub struct FrameRx<const N: usize> {
+    buffer: [u8; N],
+    state: RxState,
+    escaped: bool,
+    length: usize,
+    received: usize,
+    crc: u16,
+}
+
+impl<const N: usize> FrameRx<N> {
+    /// 创建帧接收器
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            state: RxState::Idle,
+            escaped: false,
+            length: 0,
+            received: 0,
+            crc: 0,
+        }
+    }
+    
+    /// 从串口接收缓冲区读取所有可用字节并解析
+    /// 
+    /// 串口应使用`Serial::new_with_buffer`创建并启用接收中断。
+    /// 
+    /// # 返回值
+    /// 收到完整帧时返回`Some(Ok(数据长度))`，数据通过`payload`获取；
+    /// 帧错误时返回`Some(Err(..))`；没有完整帧时返回None
+    pub fn poll(&mut self, serial: &Serial) -> Option<Result<usize, FrameError>> {
+        loop {
+            match serial.read_nb() {
+                Ok(byte) => {
+                    if let Some(result) = self.push(byte) {
+                        return Some(result);
+                    }
+                },
+                Err(nb::Error::WouldBlock) => return None,
+                Err(nb::Error::Other(error)) => {
+                    self.state = RxState::Idle;
+                    return Some(Err(FrameError::Serial(error)));
+                },
+            }
+        }
+    }
+    
+    /// 解析一个接收到的字节
+    /// 
+    /// # 返回值
+    /// 同`poll`
+    pub fn push(&mut self, byte: u8) -> Option<Result<usize, FrameError>> {
+        if byte == FRAME_START {
+            self.state = RxState::Length;
+            self.escaped = false;
+            return None;
+        }
+        if self.state == RxState::Idle {
+            return None;
+        }
+        if byte == FRAME_ESCAPE {
+            self.escaped = true;
+            return None;
+        }
+        let byte = if self.escaped { byte ^ FRAME_ESCAPE_XOR } else { byte };
+        self.escaped = false;
+        
+        match self.state {
+            RxState::Idle => None,
+            RxState::Length => {
+                self.received = 0;
+                // 先检查长度再保存，避免超长帧之后`payload`越界
+                if byte as usize > N {
+                    self.length = 0;
+                    self.state = RxState::Idle;
+                    return Some(Err(FrameError::Overflow));
+                }
+                self.length = byte as usize;
+                self.crc = crc16(&[byte]);
+                self.state = if self.length == 0 { RxState::CrcHigh } else { RxState::Payload };
+                None
+            },
+            RxState::Payload => {
+                self.buffer[self.received] = byte;
+                self.received += 1;
+                if self.received == self.length {
+                    self.state = RxState::CrcHigh;
+                }
+                None
+            },
+            RxState::CrcHigh => {
+                self.crc = crc16_update(self.crc, &self.buffer[..self.length]) ^ ((byte as u16) << 8);
+                self.state = RxState::CrcLow;
+                None
+            },
+            RxState::CrcLow => {
+                self.state = RxState::Idle;
+                if self.crc ^ byte as u16 == 0 {
+                    Some(Ok(self.length))
+                } else {
+                    Some(Err(FrameError::CrcMismatch))
+                }
+            },
+        }
+    }
+    
+    /// 获取最近一次收到的完整帧的数据
+    pub fn payload(&self) -> &[u8] {
+        &self.buffer[..self.length]
+    }
+}
+
+impl<const N: usize> Default for FrameRx<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试帧编码后解码得到原始数据
+    #[test]
+    fn test_frame_round_trip() {
+        assert_eq!(crc16(b"123456789"), 0x29B1, "CRC-16/CCITT-FALSE校验值错误");
+        
+        // 数据中包含起始字节和转义字节
+        let payload = [0x01, FRAME_START, 0x42, FRAME_ESCAPE, 0xFF];
+        let mut encoded = [0u8; FRAME_MAX_ENCODED];
+        let len = FrameTx::encode(&payload, &mut encoded).unwrap();
+        assert_eq!(encoded[0], FRAME_START, "帧应以起始字节开头");
+        assert!(!encoded[1..len].contains(&FRAME_START), "帧头之后不应出现未转义的起始字节");
+        
+        let mut rx: FrameRx<16> = FrameRx::new();
+        // 帧前的噪声应被忽略
+        assert_eq!(rx.push(0x55), None, "空闲时应忽略非起始字节");
+        let mut result = None;
+        for &byte in &encoded[..len] {
+            if let Some(r) = rx.push(byte) {
+                result = Some(r);
+            }
+        }
+        assert_eq!(result, Some(Ok(payload.len())), "应解析出完整帧");
+        assert_eq!(rx.payload(), &payload, "解码数据应与原始数据一致");
+        
+        // 空帧
+        let len = FrameTx::encode(&[], &mut encoded).unwrap();
+        let result = encoded[..len].iter().filter_map(|&byte| rx.push(byte)).next();
+        assert_eq!(result, Some(Ok(0)), "应支持空数据帧");
+        
+        // 输出缓冲区不足
+        assert_eq!(FrameTx::encode(&payload, &mut [0u8; 4]), None, "输出缓冲区不足时应返回None");
+    }
+    
+    /// 测试CRC错误和超长帧被拒绝
+    #[test]
+    fn test_frame_rejects_corruption() {
+        let mut encoded = [0u8; FRAME_MAX_ENCODED];
+        let len = FrameTx::encode(b"hello", &mut encoded).unwrap();
+        encoded[3] ^= 0x01;
+        
+        let mut rx: FrameRx<16> = FrameRx::new();
+        let result = encoded[..len].iter().filter_map(|&byte| rx.push(byte)).next();
+        assert_eq!(result, Some(Err(FrameError::CrcMismatch)), "数据损坏时应报告CRC错误");
+        
+        let len = FrameTx::encode(&[0xAA; 32], &mut encoded).unwrap();
+        let result = encoded[..len].iter().filter_map(|&byte| rx.push(byte)).next();
+        assert_eq!(result, Some(Err(FrameError::Overflow)), "超过接收缓冲区的帧应被拒绝");
+        assert!(rx.payload().is_empty(), "超长帧之后读取数据不应越界");
+        
+        // 错误之后仍能接收下一帧
+        let len = FrameTx::encode(b"ok", &mut encoded).unwrap();
+        let result = encoded[..len].iter().filter_map(|&byte| rx.push(byte)).next();
+        assert_eq!(result, Some(Ok(2)), "错误之后应能重新同步");
+    }
+}
@@ -8,6 +8,9 @@
 use library::*;
 use core::marker::PhantomData;
 use core::fmt::Debug;
+use core::convert::Infallible;
+use core::sync::atomic::{AtomicBool, Ordering};
+use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
 
 /// GPIO速度枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -37,6 +40,13 @@ pub enum PushPullType {
     OpenDrain,
 }
 
+/// 输出引脚初始电平
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InitialState {
+    Low,    // 低电平
+    High,   // 高电平
+}
+
 /// 上拉下拉类型
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PullType {
@@ -65,6 +75,10 @@ impl PinMode for PullDown {}
 pub struct Analog;
 impl PinMode for Analog {}
 
+/// 唤醒输入（作为STOP模式EXTI唤醒源的输入）
+pub struct WakeupInput;
+impl PinMode for WakeupInput {}
+
 /// 推挽输出
 pub struct PushPull;
 impl PinMode for PushPull {}
@@ -110,6 +124,21 @@ pub enum GpioPort {
     G,
 }
 
+/// 获取端口在RCC_APB2ENR中的时钟使能位（IOPAEN~IOPGEN为位2~8）
+/// 
+/// 显式列出每个端口，不依赖`GpioPort`的判别值顺序
+pub const fn port_rcc_bit(port: GpioPort) -> u32 {
+    match port {
+        GpioPort::A => 1 << 2,
+        GpioPort::B => 1 << 3,
+        GpioPort::C => 1 << 4,
+        GpioPort::D => 1 << 5,
+        GpioPort::E => 1 << 6,
+        GpioPort::F => 1 << 7,
+        GpioPort::G => 1 << 8,
+    }
+}
+
 /// GPIO端口结构体（向后兼容）
 #[derive(Debug, Clone, Copy)]
 pub struct GpioPortStruct {
@@ -151,7 +180,7 @@ impl GpioPortStruct {
         // 使能时钟
         let rcc_ptr = 0x4002_1000 as *mut u32;
         let apb2enr = (rcc_ptr as usize + 0x18) as *mut u32; // APB2ENR寄存器
-        let clock_bit = 1 << (2 + self.port as u32);
+        let clock_bit = port_rcc_bit(self.port);
         *apb2enr |= clock_bit;
         
         // 配置为推挽输出
@@ -186,7 +215,7 @@ impl GpioPortStruct {
         // 使能时钟
         let rcc_ptr = 0x4002_1000 as *mut u32;
         let apb2enr = (rcc_ptr as usize + 0x18) as *mut u32; // APB2ENR寄存器
-        let clock_bit = 1 << (2 + self.port as u32);
+        let clock_bit = port_rcc_bit(self.port);
         *apb2enr |= clock_bit;
         
         // 配置为复用推挽输出
@@ -220,7 +249,7 @@ impl GpioPortStruct {
         // 使能时钟
         let rcc_ptr = 0x4002_1000 as *mut u32;
         let apb2enr = (rcc_ptr as usize + 0x18) as *mut u32; // APB2ENR寄存器
-        let clock_bit = 1 << (2 + self.port as u32);
+        let clock_bit = port_rcc_bit(self.port);
         *apb2enr |= clock_bit;
         
         // 配置为浮动输入
@@ -273,6 +302,47 @@ impl GpioPortStruct {
         let brr = (port_ptr as usize + 0x14) as *mut u32; // BRR寄存器
         *brr = 1 << self.pin;
     }
+    
+    /// 转换为开漏输出（50MHz）
+    /// # Safety
+    /// - 调用者必须确保引脚未被其他代码或外设占用
+    pub unsafe fn into_open_drain_output(self) {
+        self.configure(0b0111); // CNF=01, MODE=11 (50MHz)
+    }
+    
+    /// 转换为复用开漏输出（50MHz），用于I2C等开漏总线外设
+    /// # Safety
+    /// - 调用者必须确保引脚未被其他代码或外设占用
+    /// - 调用者必须确保已正确配置相关外设的复用功能
+    pub unsafe fn into_alternate_open_drain(self) {
+        self.configure(0b1111); // CNF=11, MODE=11 (50MHz)
+    }
+    
+    /// 读取引脚输入电平（高电平返回true）
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn is_high(self) -> bool {
+        GpioPortBatch::new(self.port).read_input_data() & (1 << self.pin) != 0
+    }
+    
+    /// 读取引脚输入电平（低电平返回true）
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn is_low(self) -> bool {
+        !self.is_high()
+    }
+    
+    /// 启用端口时钟并写入引脚的4位配置值
+    unsafe fn configure(self, config: u32) {
+        let port_base = 0x4001_0800 + 0x400 * self.port as usize;
+        
+        // 使能时钟
+        let apb2enr = (0x4002_1000 + 0x18) as *mut u32; // APB2ENR寄存器
+        apb2enr.write_volatile(apb2enr.read_volatile() | port_rcc_bit(self.port));
+        
+        let cr_ptr = (port_base + if self.pin < 8 { 0x00 } else { 0x04 }) as *mut u32;
+        cr_ptr.write_volatile(replace_config_nibble(cr_ptr.read_volatile(), self.pin, config));
+    }
 }
 
 /// 为GpioPortBatch实现批量操作方法
@@ -404,6 +474,30 @@ impl GpioPortBatch {
         let _ = *lckr; // 读取确认
         let _ = *lckr; // 再次读取确认
     }
+    
+    /// 一次性采样多个输入引脚并按指定顺序打包
+    /// 
+    /// 只读取一次IDR，保证所有引脚在同一时刻采样，适合读取并行ADC或状态总线。
+    /// 结果中第i位对应`pins[i]`的电平。
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn read_group(&self, pins: &[u8]) -> u32 {
+        Self::pack_bits(self.read_input_data(), pins)
+    }
+    
+    /// 从端口数据快照中按指定引脚顺序提取位并打包
+    pub fn pack_bits(data: u16, pins: &[u8]) -> u32 {
+        assert!(pins.len() <= 32, "At most 32 pins can be packed");
+        
+        let mut value = 0u32;
+        for (index, &pin) in pins.iter().enumerate() {
+            assert!(pin < 16, "Pin number must be less than 16");
+            if (data >> pin) & 0x01 != 0 {
+                value |= 1 << index;
+            }
+        }
+        value
+    }
 }
 
 /// GPIO引脚结构体
@@ -468,7 +562,9 @@ use core::ops::Deref;
 /// 为Pin实现基础方法
 impl<P: GpioPortType, M: PinMode> Pin<P, M> {
     /// 创建新的Pin实例
-    pub const unsafe fn new(port: P, pin: u8) -> Self {
+    /// 
+    /// 外部代码应通过`Gpio::take().split()`获取引脚，保证每个引脚只有一个所有者
+    pub(crate) const unsafe fn new(port: P, pin: u8) -> Self {
         Self {
             port,
             pin,
@@ -476,6 +572,11 @@ impl<P: GpioPortType, M: PinMode> Pin<P, M> {
         }
     }
     
+    /// 获取引脚编号（0~15）
+    pub const fn pin_number(&self) -> u8 {
+        self.pin
+    }
+    
     /// 获取端口实例
     pub unsafe fn get_port(&self) -> &'static P::Periph {
         match P::PORT {
@@ -489,24 +590,11 @@ impl<P: GpioPortType, M: PinMode> Pin<P, M> {
         }
     }
     
-    /// 获取端口时钟使能位
-    fn clock_en_bit(&self) -> u32 {
-        match P::PORT {
-            GpioPort::A => 1 << 2,
-            GpioPort::B => 1 << 3,
-            GpioPort::C => 1 << 4,
-            GpioPort::D => 1 << 5,
-            GpioPort::E => 1 << 6,
-            GpioPort::F => 1 << 7,
-            GpioPort::G => 1 << 8,
-        }
-    }
-    
     /// 启用端口时钟
     unsafe fn enable_clock(&self) {
         let rcc = &mut *(0x4002_1000 as *mut rcc::RegisterBlock);
         let mut value = rcc.apb2enr().read().bits();
-        value |= self.clock_en_bit();
+        value |= port_rcc_bit(P::PORT);
         rcc.apb2enr().write(|w| unsafe { w.bits(value) });
     }
     
@@ -698,12 +786,55 @@ macro_rules! impl_input_methods {
             pub unsafe fn read_input_raw(&self) -> u8 {
                 self.get_input()
             }
+            
+            /// 原地修改输入引脚的上下拉
+            /// 
+            /// 只更新ODR位，仅在浮空/模拟与上下拉输入之间切换时才改写CNF，
+            /// 不需要先回到浮空输入。引脚的类型标记保持不变。
+            /// 
+            /// # 参数
+            /// * `pull` - 新的上下拉类型
+            /// 
+            /// # Safety
+            /// - 调用者必须确保相应GPIO端口时钟已启用
+            pub unsafe fn set_pull(&mut self, pull: PullType) {
+                let port = self.get_port();
+                let cr_value = if self.pin < 8 { port.crl().read().bits() } else { port.crh().read().bits() };
+                let (value, bsrr) = set_pull_registers(cr_value, self.pin, pull);
+                
+                // 先切换ODR再改CNF，避免短暂出现反向的上下拉
+                if let Some(bsrr) = bsrr {
+                    port.bsrr().write(|w| unsafe { w.bits(bsrr) });
+                }
+                if value != cr_value {
+                    if self.pin < 8 {
+                        port.crl().write(|w| unsafe { w.bits(value) });
+                    } else {
+                        port.crh().write(|w| unsafe { w.bits(value) });
+                    }
+                }
+            }
+            
+            /// 多次采样后按多数表决读取引脚电平，用于按键等需要消抖的输入
+            /// 
+            /// # 参数
+            /// * `samples` - 采样次数，至少3次（1~2按3次处理）；为0时只读取一次原始电平
+            /// * `delay_us` - 相邻两次采样的间隔（微秒）
+            /// 
+            /// # 返回值
+            /// 多数采样为高电平时返回true
+            /// 
+            /// # Safety
+            /// - 调用者必须确保SysTick延时已初始化
+            pub unsafe fn debounced_read(&self, samples: u8, delay_us: u32) -> bool {
+                majority_sample(samples, || unsafe { self.is_high() }, || unsafe { crate::bsp::delay::delay_us(delay_us) })
+            }
         })*
     };
 }
 
 // 为所有输入模式实现通用方法
-impl_input_methods!(Floating, PullUp, PullDown, Analog);
+impl_input_methods!(Floating, PullUp, PullDown, Analog, WakeupInput);
 
 /// 浮动输入模式扩展
 impl<P: GpioPortType> Pin<P, Floating> {
@@ -737,7 +868,54 @@ impl<P: GpioPortType> Pin<P, Floating> {
         }
     }
     
+    /// 配置为STOP模式的EXTI唤醒输入
+    /// 
+    /// STOP模式下GPIO配置和ODR保持不变，上下拉电阻由CNF=10时的ODR位选择，
+    /// 因此先写ODR再切换CNF，保证引脚在任何时刻都不会处于未定义的上下拉状态。
+    /// 同时把引脚所在的EXTI线映射到该端口（AFIO_EXTICRx），触发边沿和
+    /// 中断/事件使能仍需通过`Exti`配置。模拟输入会关闭施密特触发器，EXTI无法检测边沿，因此不允许。
+    /// 
+    /// # 参数
+    /// * `pull` - 上下拉类型，外部有确定驱动时可用`PullType::Floating`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保AFIO时钟已启用
+    /// - 调用者必须确保同一EXTI线未被其他端口的引脚占用
+    pub unsafe fn configure_wakeup_input(self, pull: PullType) -> Pin<P, WakeupInput> {
+        let (config, odr) = wakeup_input_config(pull);
+        self.enable_clock();
+        
+        let port = self.get_port();
+        match odr {
+            Some(true) => {
+                port.bsrr().write(|w| unsafe { w.bits(1 << self.pin) });
+            },
+            Some(false) => {
+                port.brr().write(|w| unsafe { w.bits(1 << self.pin) });
+            },
+            None => {},
+        }
+        if self.pin < 8 {
+            let value = replace_config_nibble(port.crl().read().bits(), self.pin, config);
+            port.crl().write(|w| unsafe { w.bits(value) });
+        } else {
+            let value = replace_config_nibble(port.crh().read().bits(), self.pin, config);
+            port.crh().write(|w| unsafe { w.bits(value) });
+        }
+        
+        gpio_exti_line_config(P::PORT, self.pin);
+        
+        Pin {
+            port: self.port,
+            pin: self.pin,
+            _mode: PhantomData,
+        }
+    }
+    
     /// 转换为推挽输出
+    /// 
+    /// 输出电平沿用ODR中的残留值，上电后驱动继电器等负载时应使用
+    /// `into_push_pull_output_low`/`into_push_pull_output_high`明确初始电平。
     pub unsafe fn into_push_pull_output(self, speed: GpioSpeed) -> Pin<P, PushPull> {
         self.configure_push_pull_output(speed);
         Pin {
@@ -747,6 +925,34 @@ impl<P: GpioPortType> Pin<P, Floating> {
         }
     }
     
+    /// 转换为推挽输出并指定初始电平
+    /// 
+    /// 先通过BSRR写入ODR再切换为输出模式，引脚从输入变为输出时直接输出指定电平，不会产生毛刺。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn into_push_pull_output_with_state(self, speed: GpioSpeed, state: InitialState) -> Pin<P, PushPull> {
+        self.enable_clock();
+        self.get_port().bsrr().write(|w| unsafe { w.bits(initial_state_bsrr(self.pin, state)) });
+        self.into_push_pull_output(speed)
+    }
+    
+    /// 转换为推挽输出，初始为低电平
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn into_push_pull_output_low(self, speed: GpioSpeed) -> Pin<P, PushPull> {
+        self.into_push_pull_output_with_state(speed, InitialState::Low)
+    }
+    
+    /// 转换为推挽输出，初始为高电平
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn into_push_pull_output_high(self, speed: GpioSpeed) -> Pin<P, PushPull> {
+        self.into_push_pull_output_with_state(speed, InitialState::High)
+    }
+    
     /// 转换为开漏输出
     pub unsafe fn into_open_drain_output(self, speed: GpioSpeed) -> Pin<P, OpenDrain> {
         self.configure_open_drain_output(speed);
@@ -780,6 +986,251 @@ impl<P: GpioPortType> Pin<P, Floating> {
     }
 }
 
+/// 浮动输入模式到双向引脚的转换
+impl<P: GpioPortType> Pin<P, Floating> {
+    /// 转换为可快速切换方向的双向引脚
+    /// 
+    /// 初始为浮空输入，输出时使用指定的推挽/开漏类型和速度
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn into_bidirectional(self, output_type: PushPullType, speed: GpioSpeed) -> Bidirectional<P> {
+        let mode_bits = match speed {
+            GpioSpeed::Speed10MHz => 0b01,
+            GpioSpeed::Speed2MHz => 0b10,
+            GpioSpeed::Speed50MHz => 0b11,
+        };
+        let output_config = match output_type {
+            PushPullType::PushPull => mode_bits,            // CNF=00, MODE=xx
+            PushPullType::OpenDrain => 0b0100 | mode_bits,  // CNF=01, MODE=xx
+        };
+        
+        // 时钟使能和初始配置只在此处做一次
+        self.configure_floating();
+        Bidirectional {
+            port: self.port,
+            pin: self.pin,
+            input_config: 0b0100, // CNF=01, MODE=00
+            output_config,
+            is_output: false,
+        }
+    }
+}
+
+/// 计算只替换指定引脚配置半字节后的CRL/CRH值
+/// 
+/// # 参数
+/// * `value` - 当前CRL/CRH寄存器值
+/// * `pin` - 引脚号（0-15）
+/// * `config` - 4位配置值（CNF[1:0] MODE[1:0]）
+pub const fn replace_config_nibble(value: u32, pin: u8, config: u32) -> u32 {
+    let pin_pos = (pin % 8) as u32;
+    let pin_mask = 0x0F << (pin_pos * 4);
+    (value & !pin_mask) | ((config & 0x0F) << (pin_pos * 4))
+}
+
+/// 计算设置初始电平的BSRR值
+/// 
+/// BSRR低16位置位ODR，高16位清除ODR
+pub const fn initial_state_bsrr(pin: u8, state: InitialState) -> u32 {
+    match state {
+        InitialState::Low => 1 << (pin as u32 + 16),
+        InitialState::High => 1 << pin as u32,
+    }
+}
+
+/// 计算翻转引脚电平的BSRR值
+/// 
+/// # 参数
+/// * `odr` - 当前ODR值，只使用`pin`对应的位
+/// * `pin` - 引脚号（0-15）
+pub const fn toggle_bsrr(odr: u32, pin: u8) -> u32 {
+    if odr & (1 << pin as u32) != 0 {
+        initial_state_bsrr(pin, InitialState::Low)
+    } else {
+        initial_state_bsrr(pin, InitialState::High)
+    }
+}
+
+/// 计算唤醒输入的配置半字节和ODR位
+/// 
+/// # 返回值
+/// (CNF[1:0] MODE[1:0]配置值, ODR位：上拉为Some(true)，下拉为Some(false)，浮空为None)
+pub const fn wakeup_input_config(pull: PullType) -> (u32, Option<bool>) {
+    assert!(is_exti_wakeup_compatible(pull), "Analog input cannot be used as an EXTI wakeup source");
+    input_pull_config(pull)
+}
+
+/// 计算输入模式的配置半字节和ODR位
+/// 
+/// # 返回值
+/// (CNF[1:0] MODE[1:0]配置值, ODR位：上拉为Some(true)，下拉为Some(false)，浮空和模拟为None)
+pub const fn input_pull_config(pull: PullType) -> (u32, Option<bool>) {
+    match pull {
+        PullType::PullUp => (0b1000, Some(true)),       // CNF=10, MODE=00, ODR=1
+        PullType::PullDown => (0b1000, Some(false)),    // CNF=10, MODE=00, ODR=0
+        PullType::Floating => (0b0100, None),           // CNF=01, MODE=00
+        PullType::Analog => (0b0000, None),             // CNF=00, MODE=00
+    }
+}
+
+/// 计算修改输入上下拉后的CRL/CRH值和需要写入的BSRR值
+/// 
+/// # 参数
+/// * `cr_value` - 引脚所在的CRL/CRH当前值
+/// * `pin` - 引脚号（0-15）
+/// * `pull` - 新的上下拉类型
+/// 
+/// # 返回值
+/// (新的CRL/CRH值, BSRR写入值：上下拉时为Some，浮空和模拟不改ODR为None)
+pub const fn set_pull_registers(cr_value: u32, pin: u8, pull: PullType) -> (u32, Option<u32>) {
+    let (config, odr) = input_pull_config(pull);
+    let bsrr = match odr {
+        Some(true) => Some(initial_state_bsrr(pin, InitialState::High)),
+        Some(false) => Some(initial_state_bsrr(pin, InitialState::Low)),
+        None => None,
+    };
+    (replace_config_nibble(cr_value, pin, config), bsrr)
+}
+
+/// 检查输入类型能否作为EXTI唤醒源
+/// 
+/// 模拟输入模式下施密特触发器关闭，IDR恒为0，EXTI检测不到边沿
+pub const fn is_exti_wakeup_compatible(pull: PullType) -> bool {
+    !matches!(pull, PullType::Analog)
+}
+
+/// 消抖读取的最少采样次数
+pub const DEBOUNCE_MIN_SAMPLES: u8 = 3;
+
+/// 多次采样并返回多数值
+/// 
+/// # 参数
+/// * `samples` - 采样次数，少于`DEBOUNCE_MIN_SAMPLES`时按最少次数处理；为0时只调用一次`read`
+/// * `read` - 读取一次电平
+/// * `delay` - 两次采样之间的等待，最后一次采样后不调用
+pub fn majority_sample<R: FnMut() -> bool, D: FnMut()>(samples: u8, mut read: R, mut delay: D) -> bool {
+    if samples == 0 {
+        return read();
+    }
+    
+    let samples = if samples < DEBOUNCE_MIN_SAMPLES { DEBOUNCE_MIN_SAMPLES } else { samples };
+    let mut high = 0u8;
+    for i in 0..samples {
+        if i != 0 {
+            delay();
+        }
+        if read() {
+            high += 1;
+        }
+    }
+    // 偶数次采样时高低各半按低电平处理
+    high > samples / 2
+}
+
+/// 双向引脚
+/// 
+/// 用于1-Wire、双向并行总线等需要在传输过程中频繁切换方向的场合。
+/// 方向切换只改写CRL/CRH中该引脚的配置半字节，不再重复时钟使能等初始化步骤，以减小切换延迟。
+#[derive(Debug)]
+pub struct Bidirectional<P: GpioPortType> {
+    port: P,
+    pin: u8,
+    input_config: u32,
+    output_config: u32,
+    is_output: bool,
+}
+
+impl<P: GpioPortType> Bidirectional<P> {
+    /// 获取端口实例
+    unsafe fn get_port(&self) -> &'static P::Periph {
+        match P::PORT {
+            GpioPort::A => &*(0x4001_0800 as *const P::Periph),
+            GpioPort::B => &*(0x4001_0C00 as *const P::Periph),
+            GpioPort::C => &*(0x4001_1000 as *const P::Periph),
+            GpioPort::D => &*(0x4001_1400 as *const P::Periph),
+            GpioPort::E => &*(0x4001_1800 as *const P::Periph),
+            GpioPort::F => &*(0x4001_1C00 as *const P::Periph),
+            GpioPort::G => &*(0x4001_2000 as *const P::Periph),
+        }
+    }
+    
+    /// 改写该引脚的配置半字节
+    #[inline(always)]
+    unsafe fn write_config(&self, config: u32) {
+        let port = self.get_port();
+        if self.pin < 8 {
+            let value = replace_config_nibble(port.crl().read().bits(), self.pin, config);
+            port.crl().write(|w| unsafe { w.bits(value) });
+        } else {
+            let value = replace_config_nibble(port.crh().read().bits(), self.pin, config);
+            port.crh().write(|w| unsafe { w.bits(value) });
+        }
+    }
+    
+    /// 快速切换为输入（浮空输入）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    #[inline(always)]
+    pub unsafe fn into_input_fast(&mut self) {
+        self.write_config(self.input_config);
+        self.is_output = false;
+    }
+    
+    /// 快速切换为输出
+    /// 
+    /// 输出电平由ODR决定，切换前可先调用`set_high`/`set_low`预设电平以避免毛刺
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    #[inline(always)]
+    pub unsafe fn into_output_fast(&mut self) {
+        self.write_config(self.output_config);
+        self.is_output = true;
+    }
+    
+    /// 当前是否为输出方向
+    pub fn is_output(&self) -> bool {
+        self.is_output
+    }
+    
+    /// 设置输出高电平（输入方向时仅预设ODR）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn set_high(&mut self) {
+        let port = self.get_port();
+        port.bsrr().write(|w| unsafe { w.bits(1 << self.pin) });
+    }
+    
+    /// 设置输出低电平（输入方向时仅预设ODR）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相应GPIO端口时钟已启用
+    pub unsafe fn set_low(&mut self) {
+        let port = self.get_port();
+        port.brr().write(|w| unsafe { w.bits(1 << self.pin) });
+    }
+    
+    /// 读取引脚实际电平（IDR，两个方向均有效）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_high(&self) -> bool {
+        let port = self.get_port();
+        (port.idr().read().bits() & (1 << self.pin)) != 0
+    }
+    
+    /// 读取引脚实际电平（低电平返回true）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
 /// 输出模式的通用方法
 macro_rules! impl_output_methods {
     ($($mode:ty),*) => {
@@ -797,10 +1248,13 @@ macro_rules! impl_output_methods {
             }
             
             /// 切换引脚状态
+            /// 
+            /// 只读取本引脚的ODR位，再通过一次BSRR写入置位或清除，
+            /// 不会覆盖中断中对同一端口其他引脚的修改
             pub unsafe fn toggle(&mut self) {
                 let port = self.get_port();
-                let current = port.odr().read().bits();
-                port.odr().write(|w| unsafe { w.bits(current ^ (1 << self.pin)) });
+                let bsrr = toggle_bsrr(port.odr().read().bits(), self.pin);
+                port.bsrr().write(|w| unsafe { w.bits(bsrr) });
             }
             
             /// 获取引脚输出状态（高电平返回true）
@@ -857,7 +1311,69 @@ macro_rules! impl_output_methods {
 // 为所有输出模式实现通用方法
 impl_output_methods!(PushPull, OpenDrain, AlternatePushPull, AlternateOpenDrain);
 
-/// 预定义的GPIO引脚常量
+/// 为输出模式实现embedded-hal数字输出特性
+/// 
+/// 引脚只能通过`unsafe`的构造和模式转换得到，持有引脚即代表独占该引脚，
+/// 因此特性方法不再要求`unsafe`，可直接传给通用驱动
+macro_rules! impl_embedded_hal_output {
+    ($($mode:ty),*) => {
+        $(impl<P: GpioPortType> OutputPin for Pin<P, $mode> {
+            type Error = Infallible;
+            
+            fn set_low(&mut self) -> Result<(), Infallible> {
+                unsafe { Pin::<P, $mode>::set_low(self) };
+                Ok(())
+            }
+            
+            fn set_high(&mut self) -> Result<(), Infallible> {
+                unsafe { Pin::<P, $mode>::set_high(self) };
+                Ok(())
+            }
+        }
+        
+        impl<P: GpioPortType> StatefulOutputPin for Pin<P, $mode> {
+            fn is_set_high(&self) -> Result<bool, Infallible> {
+                Ok(unsafe { Pin::<P, $mode>::is_high(self) })
+            }
+            
+            fn is_set_low(&self) -> Result<bool, Infallible> {
+                Ok(unsafe { Pin::<P, $mode>::is_low(self) })
+            }
+        }
+        
+        impl<P: GpioPortType> ToggleableOutputPin for Pin<P, $mode> {
+            type Error = Infallible;
+            
+            fn toggle(&mut self) -> Result<(), Infallible> {
+                unsafe { Pin::<P, $mode>::toggle(self) };
+                Ok(())
+            }
+        })*
+    };
+}
+
+/// 为输入模式实现embedded-hal数字输入特性
+macro_rules! impl_embedded_hal_input {
+    ($($mode:ty),*) => {
+        $(impl<P: GpioPortType> InputPin for Pin<P, $mode> {
+            type Error = Infallible;
+            
+            fn is_high(&self) -> Result<bool, Infallible> {
+                Ok(unsafe { Pin::<P, $mode>::is_high(self) })
+            }
+            
+            fn is_low(&self) -> Result<bool, Infallible> {
+                Ok(unsafe { Pin::<P, $mode>::is_low(self) })
+            }
+        })*
+    };
+}
+
+impl_embedded_hal_output!(PushPull, OpenDrain, AlternatePushPull, AlternateOpenDrain);
+// 模拟输入的施密特触发器关闭，IDR恒为0，不实现InputPin
+impl_embedded_hal_input!(Floating, PullUp, PullDown, WakeupInput);
+
+/// 引脚构造函数，外部代码应通过`Gpio::take().split()`获取引脚
 pub mod pins {
     use super::*;
     
@@ -866,7 +1382,7 @@ pub mod pins {
     /// - 调用者必须确保GPIOA外设时钟已启用
     /// - 调用者必须确保引脚未被其他代码或外设占用
     /// - 此函数会修改寄存器状态，可能影响其他使用同一端口的代码
-    pub unsafe fn pa0() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa0() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 0);
         pin.configure_floating();
         pin
@@ -876,7 +1392,7 @@ pub mod pins {
     /// - 调用者必须确保GPIOA外设时钟已启用
     /// - 调用者必须确保引脚未被其他代码或外设占用
     /// - 此函数会修改寄存器状态，可能影响其他使用同一端口的代码
-    pub unsafe fn pa1() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa1() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 1);
         pin.configure_floating();
         pin
@@ -886,7 +1402,7 @@ pub mod pins {
     /// - 调用者必须确保GPIOA外设时钟已启用
     /// - 调用者必须确保引脚未被其他代码或外设占用
     /// - 此函数会修改寄存器状态，可能影响其他使用同一端口的代码
-    pub unsafe fn pa2() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa2() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 2);
         pin.configure_floating();
         pin
@@ -896,404 +1412,651 @@ pub mod pins {
     /// - 调用者必须确保GPIOA外设时钟已启用
     /// - 调用者必须确保引脚未被其他代码或外设占用
     /// - 此函数会修改寄存器状态，可能影响其他使用同一端口的代码
-    pub unsafe fn pa3() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa3() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 3);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa4() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa4() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 4);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa5() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa5() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 5);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa6() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa6() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 6);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa7() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa7() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 7);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa8() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa8() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 8);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa9() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa9() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 9);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa10() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa10() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 10);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa11() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa11() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 11);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa12() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa12() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 12);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa13() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa13() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 13);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa14() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa14() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 14);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pa15() -> Pin<Gpioa, Floating> {
+    pub(crate) unsafe fn pa15() -> Pin<Gpioa, Floating> {
         let pin = Pin::new(Gpioa::steal(), 15);
         pin.configure_floating();
         pin
     }
     
     // 端口B引脚
-    pub unsafe fn pb0() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb0() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 0);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb1() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb1() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 1);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb2() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb2() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 2);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb3() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb3() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 3);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb4() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb4() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 4);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb5() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb5() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 5);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb6() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb6() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 6);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb7() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb7() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 7);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb8() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb8() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 8);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb9() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb9() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 9);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb10() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb10() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 10);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb11() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb11() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 11);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb12() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb12() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 12);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb13() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb13() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 13);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb14() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb14() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 14);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pb15() -> Pin<Gpiob, Floating> {
+    pub(crate) unsafe fn pb15() -> Pin<Gpiob, Floating> {
         let pin = Pin::new(Gpiob::steal(), 15);
         pin.configure_floating();
         pin
     }
     
     // 端口C引脚
-    pub unsafe fn pc0() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc0() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 0);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc1() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc1() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 1);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc2() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc2() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 2);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc3() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc3() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 3);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc4() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc4() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 4);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc5() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc5() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 5);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc6() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc6() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 6);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc7() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc7() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 7);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc8() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc8() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 8);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc9() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc9() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 9);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc10() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc10() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 10);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc11() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc11() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 11);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc12() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc12() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 12);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc13() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc13() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 13);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc14() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc14() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 14);
         pin.configure_floating();
         pin
     }
     
-    pub unsafe fn pc15() -> Pin<Gpioc, Floating> {
+    pub(crate) unsafe fn pc15() -> Pin<Gpioc, Floating> {
         let pin = Pin::new(Gpioc::steal(), 15);
         pin.configure_floating();
         pin
     }
 }
 
+/// GPIO所有权令牌是否已被取走
+static GPIO_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// GPIO所有权令牌
+/// 
+/// 全局只能取得一次，通过`split`把端口A~C的每个引脚按值交给调用者，
+/// 引脚从`GpioParts`中移出后由借用检查保证只有一个所有者，避免两处代码同时改写CRL/ODR。
+pub struct Gpio {
+    _private: (),
+}
+
+impl Gpio {
+    /// 取得GPIO所有权令牌
+    /// 
+    /// # 返回值
+    /// 第一次调用返回Some，之后返回None
+    pub fn take() -> Option<Self> {
+        if GPIO_TAKEN.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(Self { _private: () })
+        }
+    }
+    
+    /// 拆分为各个引脚
+    /// 
+    /// 启用端口A~C的时钟，并把所有引脚配置为浮空输入（复位状态）
+    pub fn split(self) -> GpioParts {
+        unsafe {
+            GpioParts {
+                pa0: pins::pa0(),
+                pa1: pins::pa1(),
+                pa2: pins::pa2(),
+                pa3: pins::pa3(),
+                pa4: pins::pa4(),
+                pa5: pins::pa5(),
+                pa6: pins::pa6(),
+                pa7: pins::pa7(),
+                pa8: pins::pa8(),
+                pa9: pins::pa9(),
+                pa10: pins::pa10(),
+                pa11: pins::pa11(),
+                pa12: pins::pa12(),
+                pa13: pins::pa13(),
+                pa14: pins::pa14(),
+                pa15: pins::pa15(),
+                pb0: pins::pb0(),
+                pb1: pins::pb1(),
+                pb2: pins::pb2(),
+                pb3: pins::pb3(),
+                pb4: pins::pb4(),
+                pb5: pins::pb5(),
+                pb6: pins::pb6(),
+                pb7: pins::pb7(),
+                pb8: pins::pb8(),
+                pb9: pins::pb9(),
+                pb10: pins::pb10(),
+                pb11: pins::pb11(),
+                pb12: pins::pb12(),
+                pb13: pins::pb13(),
+                pb14: pins::pb14(),
+                pb15: pins::pb15(),
+                pc0: pins::pc0(),
+                pc1: pins::pc1(),
+                pc2: pins::pc2(),
+                pc3: pins::pc3(),
+                pc4: pins::pc4(),
+                pc5: pins::pc5(),
+                pc6: pins::pc6(),
+                pc7: pins::pc7(),
+                pc8: pins::pc8(),
+                pc9: pins::pc9(),
+                pc10: pins::pc10(),
+                pc11: pins::pc11(),
+                pc12: pins::pc12(),
+                pc13: pins::pc13(),
+                pc14: pins::pc14(),
+                pc15: pins::pc15(),
+            }
+        }
+    }
+}
+
+/// GPIO引脚集合，每个字段只能被移出一次
+pub struct GpioParts {
+    pub pa0: Pin<Gpioa, Floating>,
+    pub pa1: Pin<Gpioa, Floating>,
+    pub pa2: Pin<Gpioa, Floating>,
+    pub pa3: Pin<Gpioa, Floating>,
+    pub pa4: Pin<Gpioa, Floating>,
+    pub pa5: Pin<Gpioa, Floating>,
+    pub pa6: Pin<Gpioa, Floating>,
+    pub pa7: Pin<Gpioa, Floating>,
+    pub pa8: Pin<Gpioa, Floating>,
+    pub pa9: Pin<Gpioa, Floating>,
+    pub pa10: Pin<Gpioa, Floating>,
+    pub pa11: Pin<Gpioa, Floating>,
+    pub pa12: Pin<Gpioa, Floating>,
+    pub pa13: Pin<Gpioa, Floating>,
+    pub pa14: Pin<Gpioa, Floating>,
+    pub pa15: Pin<Gpioa, Floating>,
+    pub pb0: Pin<Gpiob, Floating>,
+    pub pb1: Pin<Gpiob, Floating>,
+    pub pb2: Pin<Gpiob, Floating>,
+    pub pb3: Pin<Gpiob, Floating>,
+    pub pb4: Pin<Gpiob, Floating>,
+    pub pb5: Pin<Gpiob, Floating>,
+    pub pb6: Pin<Gpiob, Floating>,
+    pub pb7: Pin<Gpiob, Floating>,
+    pub pb8: Pin<Gpiob, Floating>,
+    pub pb9: Pin<Gpiob, Floating>,
+    pub pb10: Pin<Gpiob, Floating>,
+    pub pb11: Pin<Gpiob, Floating>,
+    pub pb12: Pin<Gpiob, Floating>,
+    pub pb13: Pin<Gpiob, Floating>,
+    pub pb14: Pin<Gpiob, Floating>,
+    pub pb15: Pin<Gpiob, Floating>,
+    pub pc0: Pin<Gpioc, Floating>,
+    pub pc1: Pin<Gpioc, Floating>,
+    pub pc2: Pin<Gpioc, Floating>,
+    pub pc3: Pin<Gpioc, Floating>,
+    pub pc4: Pin<Gpioc, Floating>,
+    pub pc5: Pin<Gpioc, Floating>,
+    pub pc6: Pin<Gpioc, Floating>,
+    pub pc7: Pin<Gpioc, Floating>,
+    pub pc8: Pin<Gpioc, Floating>,
+    pub pc9: Pin<Gpioc, Floating>,
+    pub pc10: Pin<Gpioc, Floating>,
+    pub pc11: Pin<Gpioc, Floating>,
+    pub pc12: Pin<Gpioc, Floating>,
+    pub pc13: Pin<Gpioc, Floating>,
+    pub pc14: Pin<Gpioc, Floating>,
+    pub pc15: Pin<Gpioc, Floating>,
+}
+
 /// 向后兼容的GPIO引脚常量
 // 端口A引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA0: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA1: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA2: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA3: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA4: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA5: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA6: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA7: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA8: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA9: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA10: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA11: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA12: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA13: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA14: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PA15: GpioPortStruct = GpioPortStruct { port: GpioPort::A, pin: 15 };
 
 // 端口B引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB0: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB1: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB2: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB3: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB4: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB5: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB6: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB7: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB8: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB9: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB10: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB11: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB12: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB13: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB14: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PB15: GpioPortStruct = GpioPortStruct { port: GpioPort::B, pin: 15 };
 
 // 端口C引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC0: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC1: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC2: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC3: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC4: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC5: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC6: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC7: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC8: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC9: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC10: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC11: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC12: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC13: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC14: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PC15: GpioPortStruct = GpioPortStruct { port: GpioPort::C, pin: 15 };
 
 // 端口D引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD0: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD1: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD2: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD3: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD4: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD5: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD6: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD7: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD8: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD9: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD10: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD11: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD12: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD13: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD14: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PD15: GpioPortStruct = GpioPortStruct { port: GpioPort::D, pin: 15 };
 
 // 端口E引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE0: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE1: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE2: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE3: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE4: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE5: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE6: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE7: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE8: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE9: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE10: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE11: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE12: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE13: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE14: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PE15: GpioPortStruct = GpioPortStruct { port: GpioPort::E, pin: 15 };
 
 // 端口F引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF0: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF1: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF2: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF3: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF4: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF5: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF6: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF7: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF8: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF9: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF10: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF11: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF12: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF13: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF14: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PF15: GpioPortStruct = GpioPortStruct { port: GpioPort::F, pin: 15 };
 
 // 端口G引脚
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG0: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 0 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG1: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 1 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG2: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 2 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG3: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 3 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG4: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 4 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG5: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 5 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG6: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 6 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG7: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 7 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG8: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 8 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG9: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 9 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG10: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 10 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG11: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 11 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG12: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 12 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG13: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 13 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG14: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 14 };
+#[deprecated(note = "use Gpio::take().split() for exclusively owned pins")]
 pub const PG15: GpioPortStruct = GpioPortStruct { port: GpioPort::G, pin: 15 };
 
 /// GPIO重映射配置函数
@@ -1748,6 +2511,111 @@ pub enum GpioRemap {
     RemapMisc,
 }
 
+/// F1系列可从MAPR回读的重映射
+/// 
+/// SWJ_CFG位为只写，读出值无定义，因此不参与解码
+const DECODABLE_REMAPS: [GpioRemap; 24] = [
+    GpioRemap::RemapSPI1,
+    GpioRemap::RemapI2C1,
+    GpioRemap::RemapUSART1,
+    GpioRemap::RemapUSART2,
+    GpioRemap::PartialRemapUSART3,
+    GpioRemap::FullRemapUSART3,
+    GpioRemap::PartialRemapTIM1,
+    GpioRemap::FullRemapTIM1,
+    GpioRemap::PartialRemap1TIM2,
+    GpioRemap::PartialRemap2TIM2,
+    GpioRemap::FullRemapTIM2,
+    GpioRemap::PartialRemapTIM3,
+    GpioRemap::FullRemapTIM3,
+    GpioRemap::RemapTIM4,
+    GpioRemap::Remap1CAN1,
+    GpioRemap::Remap2CAN1,
+    GpioRemap::RemapPD01,
+    GpioRemap::RemapTim5Ch4Lsi,
+    GpioRemap::RemapAdc1EtrgInj,
+    GpioRemap::RemapAdc1EtrgReg,
+    GpioRemap::RemapAdc2EtrgInj,
+    GpioRemap::RemapAdc2EtrgReg,
+    GpioRemap::RemapEth,
+    GpioRemap::RemapCan2,
+];
+
+impl GpioRemap {
+    /// 获取重映射在MAPR中对应的位域
+    /// 
+    /// # 返回值
+    /// (位域掩码, 该重映射生效时位域的值)，F1系列中不可回读的重映射返回None
+    pub const fn mapr_field(self) -> Option<(u32, u32)> {
+        match self {
+            GpioRemap::RemapSPI1 => Some((1 << 0, 1 << 0)),
+            GpioRemap::RemapI2C1 => Some((1 << 1, 1 << 1)),
+            GpioRemap::RemapUSART1 => Some((1 << 2, 1 << 2)),
+            GpioRemap::RemapUSART2 => Some((1 << 3, 1 << 3)),
+            GpioRemap::PartialRemapUSART3 => Some((0b11 << 4, 0b01 << 4)),
+            GpioRemap::FullRemapUSART3 => Some((0b11 << 4, 0b11 << 4)),
+            GpioRemap::PartialRemapTIM1 => Some((0b11 << 6, 0b01 << 6)),
+            GpioRemap::FullRemapTIM1 => Some((0b11 << 6, 0b11 << 6)),
+            GpioRemap::PartialRemap1TIM2 => Some((0b11 << 8, 0b01 << 8)),
+            GpioRemap::PartialRemap2TIM2 => Some((0b11 << 8, 0b10 << 8)),
+            GpioRemap::FullRemapTIM2 => Some((0b11 << 8, 0b11 << 8)),
+            GpioRemap::PartialRemapTIM3 => Some((0b11 << 10, 0b10 << 10)),
+            GpioRemap::FullRemapTIM3 => Some((0b11 << 10, 0b11 << 10)),
+            GpioRemap::RemapTIM4 => Some((1 << 12, 1 << 12)),
+            GpioRemap::Remap1CAN1 => Some((0b11 << 13, 0b10 << 13)),
+            GpioRemap::Remap2CAN1 => Some((0b11 << 13, 0b11 << 13)),
+            GpioRemap::RemapPD01 => Some((1 << 15, 1 << 15)),
+            GpioRemap::RemapTim5Ch4Lsi => Some((1 << 16, 1 << 16)),
+            GpioRemap::RemapAdc1EtrgInj => Some((1 << 17, 1 << 17)),
+            GpioRemap::RemapAdc1EtrgReg => Some((1 << 18, 1 << 18)),
+            GpioRemap::RemapAdc2EtrgInj => Some((1 << 19, 1 << 19)),
+            GpioRemap::RemapAdc2EtrgReg => Some((1 << 20, 1 << 20)),
+            GpioRemap::RemapEth => Some((1 << 21, 1 << 21)),
+            GpioRemap::RemapCan2 => Some((1 << 22, 1 << 22)),
+            _ => None,
+        }
+    }
+}
+
+/// 当前生效的重映射状态（由AFIO_MAPR解码）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RemapState {
+    mapr: u32,
+}
+
+impl RemapState {
+    /// 从MAPR寄存器值创建
+    pub const fn from_bits(mapr: u32) -> Self {
+        Self { mapr }
+    }
+    
+    /// 获取原始MAPR寄存器值
+    pub const fn bits(&self) -> u32 {
+        self.mapr
+    }
+    
+    /// 检查指定重映射是否生效
+    pub fn contains(&self, remap: GpioRemap) -> bool {
+        match remap.mapr_field() {
+            Some((mask, value)) => self.mapr & mask == value,
+            None => false,
+        }
+    }
+    
+    /// 获取所有生效的重映射
+    pub fn active(&self) -> heapless::Vec<GpioRemap, 24> {
+        DECODABLE_REMAPS.iter().copied().filter(|&remap| self.contains(remap)).collect()
+    }
+}
+
+/// 读取当前的重映射状态
+/// # Safety
+/// - 调用者必须确保AFIO外设时钟已启用
+pub unsafe fn current_remaps() -> RemapState {
+    let afio = &*(0x40010000 as *const library::afio::RegisterBlock);
+    RemapState::from_bits(afio.mapr().read().bits())
+}
+
 /// 复位AFIO寄存器到默认状态
 /// # Safety
 /// - 调用者必须确保RCC外设时钟已启用
@@ -1769,7 +2637,7 @@ pub unsafe fn gpio_afio_deinit() {
 pub unsafe fn gpio_init(port: GpioPort, config: GpioInitConfig) {
     // 使能GPIO时钟
     let rcc = &mut *(0x4002_1000 as *mut rcc::RegisterBlock);
-    let clock_bit = 1 << (2 + port as u32);
+    let clock_bit = port_rcc_bit(port);
     rcc.apb2enr().write(|w| unsafe { w.bits(rcc.apb2enr().read().bits() | clock_bit) });
     
     // 获取GPIO端口寄存器指针
@@ -1915,3 +2783,189 @@ pub unsafe fn gpio_exti_line_config(port_source: GpioPort, pin_source: u8) {
         _ => unreachable!(),
     }
 }
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试按指定顺序从IDR快照中打包引脚电平
+    #[test]
+    fn test_read_group_pack_bits() {
+        // 模拟IDR：PA0、PA3、PA7、PA15为高电平
+        let idr: u16 = 0b1000_0000_1000_1001;
+        
+        assert_eq!(GpioPortBatch::pack_bits(idr, &[0, 1, 2, 3]), 0b1001, "顺序引脚打包错误");
+        assert_eq!(GpioPortBatch::pack_bits(idr, &[15, 7, 3, 0]), 0b1111, "乱序引脚打包错误");
+        assert_eq!(GpioPortBatch::pack_bits(idr, &[1, 15, 2, 7]), 0b1010, "混合电平打包错误");
+        assert_eq!(GpioPortBatch::pack_bits(idr, &[]), 0, "空引脚列表应返回0");
+    }
+    
+    /// 测试方向快速切换只改写目标引脚的配置半字节
+    #[test]
+    fn test_replace_config_nibble() {
+        // 模拟CRL：所有引脚为推挽50MHz（0x3）
+        let crl: u32 = 0x3333_3333;
+        
+        // PA2切换为浮空输入
+        let value = replace_config_nibble(crl, 2, 0b0100);
+        assert_eq!(value, 0x3333_3433, "只应改写引脚2的半字节");
+        
+        // 再切换回开漏输出50MHz
+        let value = replace_config_nibble(value, 2, 0b0111);
+        assert_eq!(value, 0x3333_3733, "只应改写引脚2的半字节");
+        
+        // PB13位于CRH的第5个半字节
+        let crh: u32 = 0x4444_4444;
+        let value = replace_config_nibble(crh, 13, 0b0011);
+        assert_eq!(value, 0x4434_4444, "CRH中只应改写引脚13的半字节");
+        assert_eq!(value & !0x00F0_0000, crh & !0x00F0_0000, "其他半字节不应变化");
+    }
+    
+    /// 测试MAPR值解码为生效的重映射集合
+    #[test]
+    fn test_current_remaps_decode() {
+        // USART1重映射、TIM2部分重映射2、CAN1重映射到PD0/PD1，SWJ_CFG位不参与解码
+        let state = RemapState::from_bits((1 << 2) | (0b10 << 8) | (0b11 << 13) | (0b010 << 24));
+        let active = state.active();
+        assert_eq!(
+            &active[..],
+            &[GpioRemap::RemapUSART1, GpioRemap::PartialRemap2TIM2, GpioRemap::Remap2CAN1],
+            "解码出的重映射集合错误"
+        );
+        assert!(!state.contains(GpioRemap::FullRemapTIM2), "同一位域不同取值不应视为生效");
+        assert!(!state.contains(GpioRemap::RemapSWJJTAGDisable), "SWJ_CFG为只写位，不应解码");
+        
+        assert!(RemapState::from_bits(0).active().is_empty(), "复位值不应有任何重映射");
+    }
+    
+    /// 测试唤醒输入的上下拉配置位和EXTI兼容性
+    #[test]
+    fn test_wakeup_input_config() {
+        assert_eq!(wakeup_input_config(PullType::PullUp), (0b1000, Some(true)), "上拉应为CNF=10且ODR置位");
+        assert_eq!(wakeup_input_config(PullType::PullDown), (0b1000, Some(false)), "下拉应为CNF=10且ODR清零");
+        assert_eq!(wakeup_input_config(PullType::Floating), (0b0100, None), "浮空输入应为CNF=01且不改ODR");
+        
+        // PB9配置为上拉唤醒输入，只改写CRH中引脚9的半字节
+        let (config, _) = wakeup_input_config(PullType::PullUp);
+        assert_eq!(replace_config_nibble(0x4444_4444, 9, config), 0x4444_4484, "引脚9配置半字节错误");
+        
+        assert!(is_exti_wakeup_compatible(PullType::PullUp), "上拉输入应可作为唤醒源");
+        assert!(is_exti_wakeup_compatible(PullType::Floating), "浮空输入应可作为唤醒源");
+        assert!(!is_exti_wakeup_compatible(PullType::Analog), "模拟输入不能作为唤醒源");
+    }
+    
+    /// 测试原地修改上下拉只切换ODR位，不改动已为上下拉输入的配置半字节
+    #[test]
+    fn test_set_pull_odr_only() {
+        let apply = |odr: u32, bsrr: u32| (odr | (bsrr & 0xFFFF)) & !(bsrr >> 16);
+        
+        // PA3已为上下拉输入（CNF=10, MODE=00）
+        let crl = 0x4444_8444;
+        let (value, bsrr) = set_pull_registers(crl, 3, PullType::PullUp);
+        assert_eq!(value, crl, "已为上下拉输入时不应改动CRL");
+        assert_eq!(apply(0x0000, bsrr.unwrap()), 0x0008, "上拉应置位ODR3");
+        
+        let (value, bsrr) = set_pull_registers(crl, 3, PullType::PullDown);
+        assert_eq!(value, crl, "已为上下拉输入时不应改动CRL");
+        assert_eq!(apply(0x0009, bsrr.unwrap()), 0x0001, "下拉应只清除ODR3");
+        
+        // 从浮空切换到上拉需要改写CNF，切换到模拟不改ODR
+        assert_eq!(set_pull_registers(0x4444_4444, 3, PullType::PullUp).0, 0x4444_8444, "浮空到上拉应改写CNF");
+        assert_eq!(set_pull_registers(crl, 3, PullType::Analog), (0x4444_0444, None), "模拟输入不应改写ODR");
+    }
+    
+    /// 测试各模式引脚实现了对应的embedded-hal数字特性
+    #[test]
+    fn test_embedded_hal_digital_impls() {
+        fn assert_output<T: OutputPin<Error = Infallible> + StatefulOutputPin + ToggleableOutputPin<Error = Infallible>>() {}
+        fn assert_input<T: InputPin<Error = Infallible>>() {}
+        
+        assert_output::<Pin<Gpioa, PushPull>>();
+        assert_output::<Pin<Gpiob, OpenDrain>>();
+        assert_output::<Pin<Gpioc, AlternatePushPull>>();
+        assert_output::<Pin<Gpioa, AlternateOpenDrain>>();
+        assert_input::<Pin<Gpioa, Floating>>();
+        assert_input::<Pin<Gpiob, PullUp>>();
+        assert_input::<Pin<Gpioc, PullDown>>();
+    }
+    
+    /// 测试通过BSRR翻转引脚不影响同一端口的其他引脚
+    #[test]
+    fn test_toggle_leaves_other_pins() {
+        let apply = |odr: u32, bsrr: u32| (odr | (bsrr & 0xFFFF)) & !(bsrr >> 16);
+        
+        // PA1为高电平，翻转PA3
+        let odr = 1 << 1;
+        let bsrr = toggle_bsrr(odr, 3);
+        assert_eq!(bsrr, 1 << 3, "PA3为低时应只置位PA3");
+        let odr = apply(odr, bsrr);
+        assert_eq!(odr, (1 << 1) | (1 << 3), "PA1应保持高电平");
+        
+        // 再次翻转PA3
+        let odr = apply(odr, toggle_bsrr(odr, 3));
+        assert_eq!(odr, 1 << 1, "PA3应变回低电平且PA1不受影响");
+    }
+    
+    /// 测试GPIO所有权令牌只能取得一次
+    #[test]
+    fn test_gpio_take_once() {
+        assert!(Gpio::take().is_some(), "第一次应能取得令牌");
+        assert!(Gpio::take().is_none(), "令牌不应被重复取得");
+    }
+    
+    /// 测试指定初始电平时写入的BSRR使ODR位正确
+    #[test]
+    fn test_initial_state_odr() {
+        // 按BSRR语义模拟ODR：低16位置位，高16位清除
+        let apply = |odr: u32, bsrr: u32| (odr | (bsrr & 0xFFFF)) & !(bsrr >> 16);
+        
+        // PB5上电残留为高电平
+        let odr = 0x0000_0021;
+        let odr_low = apply(odr, initial_state_bsrr(5, InitialState::Low));
+        assert_eq!(odr_low & (1 << 5), 0, "初始低电平应清除ODR位");
+        assert_eq!(odr_low, 0x0000_0001, "其他引脚的ODR位不应变化");
+        
+        let odr_high = apply(0, initial_state_bsrr(15, InitialState::High));
+        assert_eq!(odr_high, 1 << 15, "初始高电平应置位ODR位");
+    }
+    
+    /// 测试消抖读取的多数表决和采样次数
+    #[test]
+    fn test_majority_sample() {
+        let pattern = [true, false, true, true, false];
+        let mut reads = 0;
+        let mut delays = 0;
+        let value = majority_sample(5, || { reads += 1; pattern[reads - 1] }, || delays += 1);
+        assert!(value, "5次采样中3次为高应返回高电平");
+        assert_eq!((reads, delays), (5, 4), "应采样5次，间隔4次");
+        
+        let pattern = [false, true, false];
+        let mut reads = 0;
+        assert!(!majority_sample(3, || { reads += 1; pattern[reads - 1] }, || {}), "3次采样中1次为高应返回低电平");
+        
+        let mut reads = 0;
+        majority_sample(1, || { reads += 1; true }, || {});
+        assert_eq!(reads, DEBOUNCE_MIN_SAMPLES as usize, "少于3次应按3次采样");
+        
+        let mut reads = 0;
+        let mut delays = 0;
+        assert!(majority_sample(0, || { reads += 1; true }, || delays += 1), "0次采样应返回原始电平");
+        assert_eq!((reads, delays), (1, 0), "0次采样应只读取一次且不延时");
+        
+        let pattern = [true, false, true, false];
+        let mut reads = 0;
+        assert!(!majority_sample(4, || { reads += 1; pattern[reads - 1] }, || {}), "高低各半应返回低电平");
+    }
+    
+    /// 测试端口A~G对应RCC_APB2ENR的位2~8
+    #[test]
+    fn test_port_rcc_bit() {
+        let ports = [GpioPort::A, GpioPort::B, GpioPort::C, GpioPort::D, GpioPort::E, GpioPort::F, GpioPort::G];
+        for (i, port) in ports.iter().enumerate() {
+            assert_eq!(port_rcc_bit(*port), 1 << (2 + i), "端口{:?}的时钟使能位错误", port);
+        }
+        assert_eq!(port_rcc_bit(Gpiod::PORT), 1 << 5, "类型状态端口D应使用位5");
+        assert_eq!(port_rcc_bit(Gpiog::PORT), 1 << 8, "类型状态端口G应使用位8");
+    }
+}
@@ -1,11 +1,16 @@
 ﻿//! IIC模块
 //! 提供硬件IIC和软件IIC的封装
+//! 
+//! 硬件IIC主机请使用`I2c`（7位地址，支持I2C1/I2C2，实现embedded-hal特性）；
+//! 旧的`HardwareIic`只支持I2C1并使用8位地址，已弃用，仅为兼容`IicDevice`保留。
 
 // 屏蔽未使用代码警告
 #![allow(unused)]
 
-use crate::bsp::gpio::{GpioPin, GpioMode};
+use crate::bsp::gpio::{GpioPort, GpioPortStruct};
 use crate::bsp::delay::*;
+use crate::bsp::regs::{RegisterAccess, Mmio};
+use embedded_hal::blocking;
 
 // 导入内部生成的设备驱动库
 use library::*;
@@ -33,24 +38,20 @@ impl IicPin {
     /// 
     /// # Returns
     /// 返回对应的GPIO引脚，用于底层GPIO操作
-    pub fn to_gpio_pin(&self) -> GpioPin {
-        match self {
-            IicPin::PB6 => crate::bsp::gpio::PB6,
-            IicPin::PB7 => crate::bsp::gpio::PB7,
-            IicPin::PB10 => crate::bsp::gpio::PB10,
-            IicPin::PB11 => crate::bsp::gpio::PB11,
-        }
+    pub fn to_gpio_pin(&self) -> GpioPortStruct {
+        GpioPortStruct::from(*self)
     }
 }
 
-impl From<IicPin> for GpioPin {
+impl From<IicPin> for GpioPortStruct {
     fn from(pin: IicPin) -> Self {
-        match pin {
-            IicPin::PB6 => crate::bsp::gpio::PB6,
-            IicPin::PB7 => crate::bsp::gpio::PB7,
-            IicPin::PB10 => crate::bsp::gpio::PB10,
-            IicPin::PB11 => crate::bsp::gpio::PB11,
-        }
+        let pin = match pin {
+            IicPin::PB6 => 6,
+            IicPin::PB7 => 7,
+            IicPin::PB10 => 10,
+            IicPin::PB11 => 11,
+        };
+        GpioPortStruct { port: GpioPort::B, pin }
     }
 }
 
@@ -169,21 +170,23 @@ pub struct IicClockConfig {
     pclk1: u32,
 }
 
-impl IicClockConfig {
+impl Default for IicClockConfig {
     /// 创建默认时钟配置
     /// 
     /// 默认使用PLL作为时钟源，系统时钟72MHz，APB1时钟36MHz
     /// 
     /// # Returns
     /// 默认的IIC时钟配置
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             source: IicClockSource::Pll,
             sysclk: 72_000_000,
             pclk1: 36_000_000,
         }
     }
-    
+}
+
+impl IicClockConfig {
     /// 从系统寄存器动态计算时钟配置
     /// 
     /// 读取RCC寄存器，自动检测当前系统时钟源和频率，无需手动配置
@@ -224,15 +227,14 @@ impl IicClockConfig {
         
         // 根据PPRE1位计算APB1时钟频率
         let ppre1 = (rcc_cfgr >> 8) & 0x07;
-        let pclk1: u32;
-        match ppre1 {
-            0x00..=0x03 => pclk1 = sysclk, // 不分频
-            0x04 => pclk1 = sysclk / 2, // 二分频
-            0x05 => pclk1 = sysclk / 4, // 四分频
-            0x06 => pclk1 = sysclk / 8, // 八分频
-            0x07 => pclk1 = sysclk / 16, // 十六分频
-            _ => pclk1 = sysclk / 2, // 默认二分频
-        }
+        let pclk1 = match ppre1 {
+            0x00..=0x03 => sysclk, // 不分频
+            0x04 => sysclk / 2, // 二分频
+            0x05 => sysclk / 4, // 四分频
+            0x06 => sysclk / 8, // 八分频
+            0x07 => sysclk / 16, // 十六分频
+            _ => sysclk / 2, // 默认二分频
+        };
         
         Self {
             source,
@@ -292,11 +294,107 @@ pub enum IicError {
     Other,          // 其他未分类错误
 }
 
+/// I2C1寄存器基地址
+const I2C1_BASE: u32 = 0x4000_5400;
+/// CR1寄存器偏移
+const I2C_CR1: u32 = 0x00;
+/// SR1寄存器偏移
+const I2C_SR1: u32 = 0x14;
+/// CR1.START：生成起始信号
+const CR1_START: u32 = 1 << 8;
+/// SR1.SB：起始信号已发送
+const SR1_SB: u32 = 1 << 0;
+
+/// 生成起始信号并等待SB标志置位
+/// 
+/// # 参数
+/// * `regs` - 寄存器访问接口
+/// * `base` - I2C寄存器基地址
+/// * `wait` - 等待函数，条件满足返回false、超时返回true（与`wait_with_timeout`约定相同）
+unsafe fn generate_start<R, W>(regs: &R, base: u32, wait: W) -> IicResult<()>
+where
+    R: RegisterAccess,
+    W: Fn(&dyn Fn() -> bool) -> bool,
+{
+    regs.modify(base + I2C_CR1, 0, CR1_START);
+    if wait(&|| regs.read(base + I2C_SR1) & SR1_SB != 0) {
+        Err(IicError::Timeout)
+    } else {
+        Ok(())
+    }
+}
+
 /// IIC结果类型
 /// 
 /// 使用标准Rust Result类型，封装IIC操作的成功或失败状态
 pub type IicResult<T> = Result<T, IicError>;
 
+/// IIC事务操作枚举
+/// 
+/// 用于`transaction`，与embedded-hal的`I2c::transaction`语义一致
+#[derive(Debug, PartialEq)]
+pub enum I2cOp<'a> {
+    Write(&'a [u8]),        // 写入数据
+    Read(&'a mut [u8]),     // 读取数据到缓冲区
+}
+
+/// IIC事务的总线级操作，由`run_transaction`按事务顺序调用
+trait TransactionBus {
+    /// 生成起始信号，`repeated`为true时为重复起始信号（不经过停止信号）
+    unsafe fn bus_start(&self, repeated: bool) -> IicResult<()>;
+    
+    /// 发送设备地址和读写方向
+    unsafe fn bus_address(&self, addr: u8, read: bool) -> IicResult<()>;
+    
+    /// 发送数据
+    unsafe fn bus_write(&self, data: &[u8]) -> IicResult<()>;
+    
+    /// 接收数据，`nack_last`为true时最后一个字节回复NACK
+    unsafe fn bus_read(&self, buffer: &mut [u8], nack_last: bool) -> IicResult<()>;
+    
+    /// 生成停止信号
+    unsafe fn bus_stop(&self);
+}
+
+/// 执行IIC事务
+/// 
+/// 第一个操作前发送起始信号，读写方向改变时发送重复起始信号和地址，
+/// 相邻的同方向操作合并为一段连续传输，最后只发送一次停止信号。
+/// 出错时同样发送停止信号释放总线。
+unsafe fn run_transaction<B: TransactionBus>(bus: &B, addr: u8, ops: &mut [I2cOp]) -> IicResult<()> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+    
+    let result = run_transaction_ops(bus, addr, ops);
+    bus.bus_stop();
+    result
+}
+
+/// 按顺序执行事务中的各个操作，不发送停止信号
+unsafe fn run_transaction_ops<B: TransactionBus>(bus: &B, addr: u8, ops: &mut [I2cOp]) -> IicResult<()> {
+    let mut previous_read: Option<bool> = None;
+    
+    for i in 0..ops.len() {
+        let read = matches!(ops[i], I2cOp::Read(_));
+        // 下一个操作也是读取时，本段最后一个字节仍需回复ACK
+        let next_read = matches!(ops.get(i + 1), Some(I2cOp::Read(_)));
+        
+        if previous_read != Some(read) {
+            bus.bus_start(previous_read.is_some())?;
+            bus.bus_address(addr, read)?;
+        }
+        
+        match &mut ops[i] {
+            I2cOp::Write(data) => bus.bus_write(data)?,
+            I2cOp::Read(buffer) => bus.bus_read(buffer, !next_read)?,
+        }
+        previous_read = Some(read);
+    }
+    
+    Ok(())
+}
+
 /// IIC操作Trait，定义通用的IIC操作接口
 /// 
 /// 统一硬件和软件IIC的操作接口，提供一致的编程体验
@@ -341,6 +439,23 @@ pub trait I2cOps {
     /// 直接访问硬件寄存器或GPIO，需要确保在正确的上下文中调用
     unsafe fn read(&self, addr: u8, buffer: &mut [u8]) -> IicResult<()>;
     
+    /// 执行IIC事务
+    /// 
+    /// 各操作之间使用重复起始信号连接，最后只发送一次停止信号，
+    /// 适用于先写寄存器地址再读数据、中间不允许停止信号的设备
+    /// 
+    /// # Arguments
+    /// * `addr` - 设备的8位IIC地址
+    /// * `ops` - 按顺序执行的读写操作
+    /// 
+    /// # Returns
+    /// * `Ok(())` - 事务成功，读取的数据已写入各缓冲区
+    /// * `Err(IicError)` - 事务失败，包含具体错误信息
+    /// 
+    /// # Safety
+    /// 直接访问硬件寄存器或GPIO，需要确保在正确的上下文中调用
+    unsafe fn transaction(&self, addr: u8, ops: &mut [I2cOp]) -> IicResult<()>;
+    
     /// 重置IIC，恢复总线通信
     /// 
     /// 当总线出现异常（如卡死、溢出等）时，重置IIC外设和引脚，恢复正常通信
@@ -410,6 +525,9 @@ pub enum IicMode {
 /// 硬件IIC结构体
 /// 
 /// 封装STM32的硬件IIC外设，提供类型安全的硬件IIC操作
+/// 
+/// 已被`I2c`取代：迁移时把8位地址右移1位作为`I2c`的7位地址
+#[deprecated(note = "use `I2c` instead; it takes 7-bit addresses")]
 pub struct HardwareIic {
     config: IicConfig, // 类型安全的IIC配置
 }
@@ -430,10 +548,12 @@ pub struct SoftwareIic {
 pub struct IicDevice {
     addr: IicAddress, // 类型安全的IIC地址
     mode: IicMode,
+    #[allow(deprecated)]
     hardware: Option<HardwareIic>,
     software: Option<SoftwareIic>,
 }
 
+#[allow(deprecated)]
 impl HardwareIic {
     /// 创建新的硬件IIC实例（通用版本，允许灵活配置）
     pub fn new(config: IicConfig) -> Self {
@@ -489,16 +609,16 @@ impl HardwareIic {
         
         // 2. 配置SCL和SDA引脚为复用开漏输出，使用指定的引脚
         if let Some((scl_pin, sda_pin)) = self.config.pins {
-            let scl: GpioPin = scl_pin.into();
-            let sda: GpioPin = sda_pin.into();
-            scl.into_mode(crate::bsp::gpio::GpioMode::AlternateOpenDrain, crate::bsp::gpio::GpioSpeed::Speed50MHz);
-            sda.into_mode(crate::bsp::gpio::GpioMode::AlternateOpenDrain, crate::bsp::gpio::GpioSpeed::Speed50MHz);
+            let scl: GpioPortStruct = scl_pin.into();
+            let sda: GpioPortStruct = sda_pin.into();
+            scl.into_alternate_open_drain();
+            sda.into_alternate_open_drain();
         } else {
             // 默认使用PB6和PB7作为IIC引脚
-            let scl: GpioPin = IicPin::PB6.into();
-            let sda: GpioPin = IicPin::PB7.into();
-            scl.into_mode(crate::bsp::gpio::GpioMode::AlternateOpenDrain, crate::bsp::gpio::GpioSpeed::Speed50MHz);
-            sda.into_mode(crate::bsp::gpio::GpioMode::AlternateOpenDrain, crate::bsp::gpio::GpioSpeed::Speed50MHz);
+            let scl: GpioPortStruct = IicPin::PB6.into();
+            let sda: GpioPortStruct = IicPin::PB7.into();
+            scl.into_alternate_open_drain();
+            sda.into_alternate_open_drain();
         }
         
         // 3. 启用I2C1时钟
@@ -592,15 +712,8 @@ impl HardwareIic {
 
     /// 生成I2C起始信号（完全按照STM32F10x_StdPeriph_Driver库的I2C_GenerateSTART函数实现）
     unsafe fn start(&self) -> bool {
-        let i2c = &mut *(0x40005400 as *mut library::i2c1::RegisterBlock);
-        
-        // 生成起始信号
-        i2c.cr1().modify(|_, w: &mut library::i2c1::cr1::W| w.start().set_bit());
-        
-        // 等待SB标志置位，使用基于系统时钟的超时机制
-        !wait_with_timeout(self.config.timeout_us, || {
-            i2c.sr1().read().sb().bit()
-        })
+        // 生成起始信号并等待SB标志置位，使用基于系统时钟的超时机制
+        generate_start(&Mmio, I2C1_BASE, |condition| wait_with_timeout(self.config.timeout_us, condition)).is_ok()
     }
 
     /// 生成I2C停止信号
@@ -612,17 +725,11 @@ impl HardwareIic {
     }
 
     /// 发送设备地址
+    /// 
+    /// 起始信号发出后总线即处于BUSY状态，总线空闲检查由调用者在起始信号之前完成
     unsafe fn send_addr(&self, addr: u8, read: bool) -> bool {
         let i2c = &mut *(0x40005400 as *mut library::i2c1::RegisterBlock);
         
-        // 检查总线是否空闲，使用基于系统时钟的超时机制，配置中的超时时间
-        let bus_free = !wait_with_timeout(self.config.timeout_us, || {
-            !i2c.sr2().read().busy().bit()
-        });
-        if !bus_free {
-            return false;
-        }
-        
         // 处理地址：直接使用传入的地址，STM32硬件IIC会自动处理
         // 对于8位地址，直接写入，硬件会自动提取7位地址并添加R/W位
         let addr_byte = if read {
@@ -809,9 +916,9 @@ impl HardwareIic {
         }
         
         // 读取数据
-        for i in 0..len {
+        for (i, byte) in buffer.iter_mut().enumerate() {
             let ack = i < len - 1;
-            buffer[i] = self.recv_data(ack);
+            *byte = self.recv_data(ack);
             
             // 检查是否有错误
             let sr1 = i2c.sr1().read();
@@ -835,6 +942,9 @@ impl HardwareIic {
     }
     
     /// 重置IIC控制器，恢复总线通信
+    /// 
+    /// # Safety
+    /// - 调用者必须确保没有正在进行的I2C传输，且I2C外设未被其他代码使用
     pub unsafe fn reset(&self) {
         let i2c = &mut *(0x40005400 as *mut library::i2c1::RegisterBlock);
         
@@ -852,6 +962,7 @@ impl HardwareIic {
 }
 
 /// 实现I2cOps Trait for HardwareIic
+#[allow(deprecated)]
 impl I2cOps for HardwareIic {
     unsafe fn init(&self) {
         HardwareIic::init(self)
@@ -865,11 +976,84 @@ impl I2cOps for HardwareIic {
         HardwareIic::read(self, addr, buffer)
     }
     
+    unsafe fn transaction(&self, addr: u8, ops: &mut [I2cOp]) -> IicResult<()> {
+        run_transaction(self, addr, ops)
+    }
+    
     unsafe fn reset(&self) {
         HardwareIic::reset(self)
     }
 }
 
+#[allow(deprecated)]
+impl TransactionBus for HardwareIic {
+    unsafe fn bus_start(&self, repeated: bool) -> IicResult<()> {
+        if !repeated {
+            let i2c = &mut *(0x40005400 as *mut library::i2c1::RegisterBlock);
+            // 仅在首个起始信号前检查总线空闲，重复起始时总线由本机占用
+            let bus_free = !wait_with_timeout(self.config.timeout_us, || {
+                !i2c.sr2().read().busy().bit()
+            });
+            if !bus_free {
+                return Err(IicError::Busy);
+            }
+        }
+        
+        if self.start() {
+            Ok(())
+        } else {
+            Err(IicError::Timeout)
+        }
+    }
+    
+    unsafe fn bus_address(&self, addr: u8, read: bool) -> IicResult<()> {
+        if self.send_addr(addr, read) {
+            Ok(())
+        } else {
+            Err(IicError::NoAcknowledge)
+        }
+    }
+    
+    unsafe fn bus_write(&self, data: &[u8]) -> IicResult<()> {
+        for (i, &byte) in data.iter().enumerate() {
+            if !self.send_data(byte, i == data.len() - 1) {
+                return Err(IicError::Timeout);
+            }
+        }
+        Ok(())
+    }
+    
+    unsafe fn bus_read(&self, buffer: &mut [u8], nack_last: bool) -> IicResult<()> {
+        let i2c = &mut *(0x40005400 as *mut library::i2c1::RegisterBlock);
+        let len = buffer.len();
+        
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            // 在字节接收完成前设置本字节的应答
+            let ack = !(nack_last && i == len - 1);
+            i2c.cr1().modify(|_, w: &mut library::i2c1::cr1::W| {
+                if ack {
+                    w.ack().set_bit()
+                } else {
+                    w.ack().clear_bit()
+                }
+            });
+            
+            let rxne_set = !wait_with_timeout(self.config.timeout_us, || {
+                i2c.sr1().read().rx_ne().bit()
+            });
+            if !rxne_set {
+                return Err(IicError::Timeout);
+            }
+            *byte = i2c.dr().read().bits() as u8;
+        }
+        Ok(())
+    }
+    
+    unsafe fn bus_stop(&self) {
+        self.stop();
+    }
+}
+
 impl SoftwareIic {
     /// 创建新的软件IIC实例
     pub fn new(scl: IicPin, sda: IicPin, speed: u32) -> Self {
@@ -881,22 +1065,16 @@ impl SoftwareIic {
         
         // 校验speed参数，确保在合法范围内
         // 软件IIC通常支持10KHz到100KHz，过高的速率会导致通信失败
-        let validated_speed = if speed < 10_000 {
-            100_000 // 最低10KHz，默认100KHz
-        } else if speed > 200_000 {
-            100_000 // 最高200KHz，默认100KHz
-        } else {
+        let validated_speed = if (10_000..=200_000).contains(&speed) {
             speed
+        } else {
+            100_000 // 有效范围10KHz~200KHz，超出范围时默认100KHz
         };
         
         // 根据speed计算合适的delay_us值
         // 假设每个时钟周期需要两个延时（高电平+低电平）
         // 例如：100KHz需要每个时钟周期10us，每个电平保持5us
-        let delay_us = if validated_speed > 0 {
-            (500_000 / validated_speed) as u32 // 500,000 / speed 计算出每个电平需要的微秒数
-        } else {
-            5 // 默认5us
-        };
+        let delay_us = 500_000 / validated_speed; // 500,000 / speed 计算出每个电平需要的微秒数
         
         let mut new_config = config;
         new_config.speed = validated_speed;
@@ -923,8 +1101,8 @@ impl SoftwareIic {
     /// 初始化软件IIC
     unsafe fn init(&self) {
         // 配置SCL和SDA为开漏输出
-        let scl: GpioPin = self.scl.into();
-        let sda: GpioPin = self.sda.into();
+        let scl: GpioPortStruct = self.scl.into();
+        let sda: GpioPortStruct = self.sda.into();
         
         scl.into_open_drain_output();
         sda.into_open_drain_output();
@@ -941,8 +1119,8 @@ impl SoftwareIic {
 
     /// 生成起始信号
     unsafe fn start(&self) {
-        let scl: GpioPin = self.scl.into();
-        let sda: GpioPin = self.sda.into();
+        let scl: GpioPortStruct = self.scl.into();
+        let sda: GpioPortStruct = self.sda.into();
         
         sda.set_high();
         scl.set_high();
@@ -952,8 +1130,8 @@ impl SoftwareIic {
 
     /// 生成停止信号
     unsafe fn stop(&self) {
-        let scl: GpioPin = self.scl.into();
-        let sda: GpioPin = self.sda.into();
+        let scl: GpioPortStruct = self.scl.into();
+        let sda: GpioPortStruct = self.sda.into();
         
         sda.set_low();
         scl.set_high();
@@ -962,8 +1140,8 @@ impl SoftwareIic {
 
     /// 发送一个字节
     unsafe fn send_byte(&self, byte: u8) -> IicResult<bool> {
-        let scl: GpioPin = self.scl.into();
-        let sda: GpioPin = self.sda.into();
+        let scl: GpioPortStruct = self.scl.into();
+        let sda: GpioPortStruct = self.sda.into();
         
         for i in 0..8 {
             // 发送数据位
@@ -1000,8 +1178,8 @@ impl SoftwareIic {
 
     /// 接收一个字节
     unsafe fn recv_byte(&self, ack: bool) -> u8 {
-        let scl: GpioPin = self.scl.into();
-        let sda: GpioPin = self.sda.into();
+        let scl: GpioPortStruct = self.scl.into();
+        let sda: GpioPortStruct = self.sda.into();
         
         let mut byte = 0;
         
@@ -1091,9 +1269,9 @@ impl SoftwareIic {
         }
         
         // 读取数据
-        for i in 0..len {
+        for (i, byte) in buffer.iter_mut().enumerate() {
             let ack = i < len - 1;
-            buffer[i] = self.recv_byte(ack);
+            *byte = self.recv_byte(ack);
         }
         
         // 生成停止信号
@@ -1102,6 +1280,9 @@ impl SoftwareIic {
     }
     
     /// 重置IIC，恢复总线通信
+    /// 
+    /// # Safety
+    /// - 调用者必须确保没有正在进行的I2C传输，且I2C外设未被其他代码使用
     pub unsafe fn reset(&self) {
         // 软件IIC重置，重新初始化引脚
         self.init();
@@ -1122,11 +1303,52 @@ impl I2cOps for SoftwareIic {
         SoftwareIic::read(self, addr, buffer)
     }
     
+    unsafe fn transaction(&self, addr: u8, ops: &mut [I2cOp]) -> IicResult<()> {
+        run_transaction(self, addr, ops)
+    }
+    
     unsafe fn reset(&self) {
         SoftwareIic::reset(self)
     }
 }
 
+impl TransactionBus for SoftwareIic {
+    unsafe fn bus_start(&self, _repeated: bool) -> IicResult<()> {
+        // SCL为低时先释放SDA再拉高SCL，起始信号时序同样适用于重复起始
+        self.start();
+        Ok(())
+    }
+    
+    unsafe fn bus_address(&self, addr: u8, read: bool) -> IicResult<()> {
+        let addr_byte = if read { addr | 1 } else { addr & !1 };
+        if self.send_byte(addr_byte)? {
+            Ok(())
+        } else {
+            Err(IicError::NoAcknowledge)
+        }
+    }
+    
+    unsafe fn bus_write(&self, data: &[u8]) -> IicResult<()> {
+        for &byte in data {
+            self.send_byte(byte)?;
+        }
+        Ok(())
+    }
+    
+    unsafe fn bus_read(&self, buffer: &mut [u8], nack_last: bool) -> IicResult<()> {
+        let len = buffer.len();
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = self.recv_byte(!(nack_last && i == len - 1));
+        }
+        Ok(())
+    }
+    
+    unsafe fn bus_stop(&self) {
+        self.stop();
+    }
+}
+
+#[allow(deprecated)]
 impl IicDevice {
     /// 创建硬件IIC设备（通用版本，允许指定引脚）
     /// 
@@ -1268,6 +1490,24 @@ impl IicDevice {
         }
     }
     
+    /// 执行IIC事务（安全API）
+    /// 
+    /// 各操作之间使用重复起始信号连接，最后只发送一次停止信号，
+    /// 例如先写寄存器地址再读数据：`[I2cOp::Write(&[reg]), I2cOp::Read(&mut buf)]`
+    /// 
+    /// # Arguments
+    /// * `ops` - 按顺序执行的读写操作
+    /// 
+    /// # Returns
+    /// * `Ok(())` - 事务成功，读取的数据已写入各缓冲区
+    /// * `Err(IicError)` - 事务失败，包含具体错误信息
+    pub fn transaction(&self, ops: &mut [I2cOp]) -> Result<(), IicError> {
+        unsafe {
+            let i2c_ops = self.get_i2c_ops()?;
+            i2c_ops.transaction(self.addr.get_hw_address(), ops)
+        }
+    }
+    
     /// 重置IIC设备，恢复总线通信
     /// 
     /// 当总线出现异常（如卡死、溢出等）时，调用此方法重置IIC外设和引脚，恢复正常通信
@@ -1355,3 +1595,617 @@ pub mod devices {
         IicDevice::new_software(iic_addr, scl, sda, speed)
     }
 }
+
+/// I2C外设编号枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum I2cNumber {
+    I2C1,   // PB6(SCL)/PB7(SDA)
+    I2C2,   // PB10(SCL)/PB11(SDA)
+}
+
+/// I2C总线速度枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum I2cSpeed {
+    Standard,   // 标准模式，100kHz
+    Fast,       // 快速模式，400kHz
+}
+
+impl I2cSpeed {
+    /// 获取SCL频率（Hz）
+    pub const fn frequency(&self) -> u32 {
+        match self {
+            I2cSpeed::Standard => I2C_SPEED_100K,
+            I2cSpeed::Fast => I2C_SPEED_400K,
+        }
+    }
+}
+
+/// I2C主机错误枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum I2cError {
+    Nack,               // 地址或数据未被应答
+    BusError,           // 总线错误，检测到错位的起始/停止信号
+    ArbitrationLost,    // 仲裁丢失
+    Timeout,            // 等待超时，如总线被占用或从机一直拉低SCL
+    InvalidClock,       // PCLK1不在该速度允许的范围内（标准模式2~36MHz，快速模式4~36MHz）
+}
+
+/// I2C等待标志的默认超时时间（微秒）
+/// 
+/// 从机可以拉低SCL延长时钟（clock stretching），超时需覆盖从机最长的处理时间，
+/// 如EEPROM的页写入周期
+pub const I2C_DEFAULT_TIMEOUT_US: u32 = 10_000;
+
+/// I2C CR1.PE：外设使能
+const I2C_CR1_PE: u32 = 1 << 0;
+/// I2C CR1.STOP：生成停止信号
+const I2C_CR1_STOP: u32 = 1 << 9;
+/// I2C CR1.ACK：应答使能
+const I2C_CR1_ACK: u32 = 1 << 10;
+/// I2C CR1.POS：应答位置（用于2字节接收）
+const I2C_CR1_POS: u32 = 1 << 11;
+/// I2C CR1.SWRST：软件复位
+const I2C_CR1_SWRST: u32 = 1 << 15;
+/// I2C SR1.ADDR：地址已发送
+const I2C_SR1_ADDR: u32 = 1 << 1;
+/// I2C SR1.BTF：字节传输完成
+const I2C_SR1_BTF: u32 = 1 << 2;
+/// I2C SR1.RXNE：数据寄存器非空
+const I2C_SR1_RXNE: u32 = 1 << 6;
+/// I2C SR1.TXE：数据寄存器空
+const I2C_SR1_TXE: u32 = 1 << 7;
+/// I2C SR1.BERR：总线错误
+const I2C_SR1_BERR: u32 = 1 << 8;
+/// I2C SR1.ARLO：仲裁丢失
+const I2C_SR1_ARLO: u32 = 1 << 9;
+/// I2C SR1.AF：应答失败
+const I2C_SR1_AF: u32 = 1 << 10;
+/// I2C SR2.BUSY：总线忙
+const I2C_SR2_BUSY: u32 = 1 << 1;
+/// I2C CCR.F/S：快速模式
+const I2C_CCR_FS: u32 = 1 << 15;
+
+/// I2C主机
+/// 
+/// 支持I2C1/I2C2的7位地址阻塞读写。每次等待标志都有超时，
+/// 从机无应答、总线错误或一直拉低SCL时返回错误而不是死等。
+/// 
+/// 实现了embedded-hal的`blocking::i2c::{Read, Write, WriteRead}`，错误类型为`I2cError`，
+/// 可直接交给bme280、ssd1306等驱动。例如读取地址0x76的BME280芯片ID：
+/// 
+/// ```no_run
+/// # use crate::bsp::iic::{I2cError, I2cSpeed, I2C1};
+/// use embedded_hal::blocking::i2c::WriteRead;
+/// 
+/// # fn read_bme280_id() -> Result<u8, I2cError> {
+/// let mut i2c = I2C1;
+/// unsafe { i2c.init(I2cSpeed::Fast)? };
+/// let mut id = [0u8; 1];
+/// // 写寄存器地址0xD0后以重复起始信号读取，中间没有停止信号
+/// WriteRead::write_read(&mut i2c, 0x76, &[0xD0], &mut id)?;
+/// assert_eq!(id[0], 0x60);
+/// # Ok(id[0])
+/// # }
+/// ```
+/// 
+/// 本crate为二进制crate，rustdoc不运行其文档测试，测试模块中的`read_bme280_id`
+/// 与此示例保持一致，由`cargo test`进行类型检查。
+pub struct I2c {
+    number: I2cNumber,
+    timeout_us: u32,
+}
+
+impl I2c {
+    /// 创建新的I2C实例
+    pub const fn new(number: I2cNumber) -> Self {
+        Self {
+            number,
+            timeout_us: I2C_DEFAULT_TIMEOUT_US,
+        }
+    }
+    
+    /// 设置等待标志的超时时间（微秒）
+    pub const fn with_timeout(self, timeout_us: u32) -> Self {
+        Self {
+            number: self.number,
+            timeout_us,
+        }
+    }
+    
+    /// 获取对应的I2C寄存器块
+    unsafe fn get_i2c(&self) -> &'static library::i2c1::RegisterBlock {
+        match self.number {
+            I2cNumber::I2C1 => &*(0x4000_5400 as *const library::i2c1::RegisterBlock),
+            I2cNumber::I2C2 => &*(0x4000_5800 as *const library::i2c1::RegisterBlock),
+        }
+    }
+    
+    /// 初始化I2C主机
+    /// 
+    /// 把默认引脚配置为复用开漏输出（I2C1为PB6/PB7，I2C2为PB10/PB11），
+    /// 按当前PCLK1频率计算CCR和TRISE。修改系统时钟后需要重新初始化。
+    /// 
+    /// # 参数
+    /// * `speed` - 总线速度
+    /// 
+    /// # 返回值
+    /// PCLK1不满足该速度的要求时返回`I2cError::InvalidClock`，此时不修改任何配置
+    /// 
+    /// # Safety
+    /// - 调用者必须确保I2C外设和引脚未被其他代码使用，且总线上有上拉电阻
+    pub unsafe fn init(&self, speed: I2cSpeed) -> Result<(), I2cError> {
+        let pclk1 = crate::bsp::rcc::RccDriver::new().get_clocks_freq().pclk1_frequency;
+        let (freq, ccr, trise) = Self::timing(pclk1, speed)?;
+        
+        let rcc = &*(0x4002_1000 as *const library::rcc::RegisterBlock);
+        let (scl, sda, clock_bit) = match self.number {
+            I2cNumber::I2C1 => (6, 7, 1 << 21),
+            I2cNumber::I2C2 => (10, 11, 1 << 22),
+        };
+        GpioPortStruct { port: GpioPort::B, pin: scl }.into_alternate_open_drain();
+        GpioPortStruct { port: GpioPort::B, pin: sda }.into_alternate_open_drain();
+        rcc.apb1enr().modify(|r, w| unsafe { w.bits(r.bits() | clock_bit) });
+        
+        // 软件复位清除可能残留的BUSY状态
+        let i2c = self.get_i2c();
+        i2c.cr1().write(|w| unsafe { w.bits(I2C_CR1_SWRST) });
+        i2c.cr1().write(|w| unsafe { w.bits(0) });
+        
+        // CCR和TRISE只能在PE=0时配置
+        i2c.cr2().write(|w| unsafe { w.bits(freq) });
+        i2c.ccr().write(|w| unsafe { w.bits(ccr) });
+        i2c.trise().write(|w| unsafe { w.bits(trise) });
+        i2c.cr1().write(|w| unsafe { w.bits(I2C_CR1_PE) });
+        Ok(())
+    }
+    
+    /// 计算CR2.FREQ、CCR和TRISE
+    /// 
+    /// CCR向上取整，保证SCL频率不超过目标速度。快速模式使用2:1占空比（DUTY=0）。
+    /// 
+    /// # 参数
+    /// * `pclk1` - APB1时钟频率（Hz），标准模式至少2MHz，快速模式至少4MHz
+    /// * `speed` - 总线速度
+    /// 
+    /// # 返回值
+    /// (CR2.FREQ, CCR, TRISE)；PCLK1超出范围时返回`I2cError::InvalidClock`
+    pub const fn timing(pclk1: u32, speed: I2cSpeed) -> Result<(u32, u32, u32), I2cError> {
+        let freq = pclk1 / 1_000_000;
+        let min_freq = match speed {
+            I2cSpeed::Standard => 2,
+            I2cSpeed::Fast => 4,
+        };
+        if freq < min_freq || freq > 36 {
+            return Err(I2cError::InvalidClock);
+        }
+        match speed {
+            I2cSpeed::Standard => {
+                // Thigh = Tlow = CCR × Tpclk1，CCR最小为4
+                let ccr = pclk1.div_ceil(2 * I2C_SPEED_100K);
+                let ccr = if ccr < 4 { 4 } else { ccr };
+                // 最大上升时间1000ns
+                Ok((freq, ccr, freq + 1))
+            },
+            I2cSpeed::Fast => {
+                // Thigh = CCR × Tpclk1，Tlow = 2 × CCR × Tpclk1
+                let ccr = pclk1.div_ceil(3 * I2C_SPEED_400K);
+                let ccr = if ccr < 1 { 1 } else { ccr };
+                // 最大上升时间300ns
+                Ok((freq, I2C_CCR_FS | ccr, freq * 300 / 1000 + 1))
+            },
+        }
+    }
+    
+    /// 解析SR1中的错误标志和等待的标志
+    /// 
+    /// # 返回值
+    /// 有错误标志时返回错误，否则返回`flag`是否已置位
+    pub const fn sr1_status(sr1: u32, flag: u32) -> Result<bool, I2cError> {
+        if sr1 & I2C_SR1_ARLO != 0 {
+            Err(I2cError::ArbitrationLost)
+        } else if sr1 & I2C_SR1_BERR != 0 {
+            Err(I2cError::BusError)
+        } else if sr1 & I2C_SR1_AF != 0 {
+            Err(I2cError::Nack)
+        } else {
+            Ok(sr1 & flag != 0)
+        }
+    }
+    
+    /// 等待SR1中的标志置位，期间检查错误标志
+    unsafe fn wait_flag(&self, flag: u32) -> Result<(), I2cError> {
+        let i2c = self.get_i2c();
+        let status = core::cell::Cell::new(Ok(false));
+        wait_with_timeout(self.timeout_us, || {
+            status.set(Self::sr1_status(i2c.sr1().read().bits(), flag));
+            status.get() != Ok(false)
+        });
+        match status.get() {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(I2cError::Timeout),
+            Err(error) => Err(error),
+        }
+    }
+    
+    /// 出错后清除错误标志并释放总线
+    unsafe fn recover(&self, error: I2cError) -> I2cError {
+        let i2c = self.get_i2c();
+        // 错误标志为写0清除
+        i2c.sr1().write(|w| unsafe { w.bits(!(I2C_SR1_AF | I2C_SR1_ARLO | I2C_SR1_BERR) & 0xFFFF) });
+        // 仲裁丢失时硬件已自动释放总线，其余情况发出停止信号
+        if error != I2cError::ArbitrationLost {
+            self.stop();
+        }
+        i2c.cr1().modify(|r, w| unsafe { w.bits(r.bits() & !I2C_CR1_POS) });
+        error
+    }
+    
+    /// 生成停止信号并等待完成
+    unsafe fn stop(&self) {
+        let i2c = self.get_i2c();
+        i2c.cr1().modify(|r, w| unsafe { w.bits(r.bits() | I2C_CR1_STOP) });
+        // 硬件发出停止信号后清除STOP位，之后才能开始下一次传输
+        wait_with_timeout(self.timeout_us, || i2c.cr1().read().bits() & I2C_CR1_STOP == 0);
+    }
+    
+    /// 生成起始信号并发送地址
+    unsafe fn start(&self, addr: u8, read: bool) -> Result<(), I2cError> {
+        let i2c = self.get_i2c();
+        let base = match self.number {
+            I2cNumber::I2C1 => I2C1_BASE,
+            I2cNumber::I2C2 => 0x4000_5800,
+        };
+        generate_start(&Mmio, base, |condition| wait_with_timeout(self.timeout_us, condition))
+            .map_err(|_| I2cError::Timeout)?;
+        
+        i2c.dr().write(|w| unsafe { w.bits(((addr as u32) << 1) | read as u32) });
+        self.wait_flag(I2C_SR1_ADDR)
+    }
+    
+    /// 清除ADDR标志（依次读SR1和SR2）
+    unsafe fn clear_addr(&self) {
+        let i2c = self.get_i2c();
+        let _ = i2c.sr1().read().bits();
+        let _ = i2c.sr2().read().bits();
+    }
+    
+    /// 等待总线空闲
+    unsafe fn wait_idle(&self) -> Result<(), I2cError> {
+        let i2c = self.get_i2c();
+        if wait_with_timeout(self.timeout_us, || i2c.sr2().read().bits() & I2C_SR2_BUSY == 0) {
+            Err(I2cError::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// 发送地址和数据，不生成停止信号
+    unsafe fn write_bytes(&self, addr: u8, bytes: &[u8]) -> Result<(), I2cError> {
+        let i2c = self.get_i2c();
+        self.start(addr, false)?;
+        self.clear_addr();
+        for &byte in bytes {
+            self.wait_flag(I2C_SR1_TXE)?;
+            i2c.dr().write(|w| unsafe { w.bits(byte as u32) });
+        }
+        // 等待最后一个字节移出并收到应答
+        self.wait_flag(I2C_SR1_BTF)
+    }
+    
+    /// 发送地址并接收数据，最后生成停止信号
+    /// 
+    /// 按参考手册的轮询接收流程处理1字节、2字节和多字节的NACK/STOP时机
+    unsafe fn read_bytes(&self, addr: u8, buf: &mut [u8]) -> Result<(), I2cError> {
+        let i2c = self.get_i2c();
+        let len = buf.len();
+        let set_cr1 = |bits: u32| i2c.cr1().modify(|r, w| unsafe { w.bits(r.bits() | bits) });
+        let clear_cr1 = |bits: u32| i2c.cr1().modify(|r, w| unsafe { w.bits(r.bits() & !bits) });
+        let read_dr = || i2c.dr().read().bits() as u8;
+        
+        if len == 2 {
+            set_cr1(I2C_CR1_ACK | I2C_CR1_POS);
+        } else {
+            set_cr1(I2C_CR1_ACK);
+        }
+        self.start(addr, true)?;
+        
+        match len {
+            1 => {
+                // 清除ADDR前关闭ACK，清除后立即请求停止
+                clear_cr1(I2C_CR1_ACK);
+                self.clear_addr();
+                set_cr1(I2C_CR1_STOP);
+                self.wait_flag(I2C_SR1_RXNE)?;
+                buf[0] = read_dr();
+            },
+            2 => {
+                // POS=1：ACK位控制的是移位寄存器中下一个字节的应答
+                self.clear_addr();
+                clear_cr1(I2C_CR1_ACK);
+                self.wait_flag(I2C_SR1_BTF)?;
+                set_cr1(I2C_CR1_STOP);
+                buf[0] = read_dr();
+                buf[1] = read_dr();
+                clear_cr1(I2C_CR1_POS);
+            },
+            _ => {
+                self.clear_addr();
+                for byte in buf[..len - 3].iter_mut() {
+                    self.wait_flag(I2C_SR1_RXNE)?;
+                    *byte = read_dr();
+                }
+                // 剩余3字节：DR中为N-2，移位寄存器中为N-1，此时关闭ACK使第N个字节回NACK
+                self.wait_flag(I2C_SR1_BTF)?;
+                clear_cr1(I2C_CR1_ACK);
+                buf[len - 3] = read_dr();
+                self.wait_flag(I2C_SR1_BTF)?;
+                set_cr1(I2C_CR1_STOP);
+                buf[len - 2] = read_dr();
+                self.wait_flag(I2C_SR1_RXNE)?;
+                buf[len - 1] = read_dr();
+            },
+        }
+        
+        // 等待停止信号发出
+        wait_with_timeout(self.timeout_us, || i2c.cr1().read().bits() & I2C_CR1_STOP == 0);
+        Ok(())
+    }
+    
+    /// 向从机写入数据
+    /// 
+    /// # 参数
+    /// * `addr` - 7位从机地址
+    /// * `bytes` - 要写入的数据，为空时只发送地址（可用于探测设备）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn write(&self, addr: u8, bytes: &[u8]) -> Result<(), I2cError> {
+        self.wait_idle()?;
+        match self.write_bytes(addr, bytes) {
+            Ok(()) => {
+                self.stop();
+                Ok(())
+            },
+            Err(error) => Err(self.recover(error)),
+        }
+    }
+    
+    /// 从从机读取数据
+    /// 
+    /// # 参数
+    /// * `addr` - 7位从机地址
+    /// * `buf` - 接收缓冲区，为空时直接返回
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn read(&self, addr: u8, buf: &mut [u8]) -> Result<(), I2cError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        self.wait_idle()?;
+        self.read_bytes(addr, buf).map_err(|error| self.recover(error))
+    }
+    
+    /// 先写后读，中间使用重复起始信号，常用于读取传感器寄存器
+    /// 
+    /// # 参数
+    /// * `addr` - 7位从机地址
+    /// * `bytes` - 要写入的数据（如寄存器地址）
+    /// * `buf` - 接收缓冲区，为空时等同于`write`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn write_read(&self, addr: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), I2cError> {
+        if buf.is_empty() {
+            return self.write(addr, bytes);
+        }
+        self.wait_idle()?;
+        let result = match self.write_bytes(addr, bytes) {
+            // BTF置位时SCL被拉低，直接发出重复起始信号
+            Ok(()) => self.read_bytes(addr, buf),
+            Err(error) => Err(error),
+        };
+        result.map_err(|error| self.recover(error))
+    }
+}
+
+/// 实现embedded-hal阻塞写入特性
+impl blocking::i2c::Write for I2c {
+    type Error = I2cError;
+    
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), I2cError> {
+        unsafe { I2c::write(self, address, bytes) }
+    }
+}
+
+/// 实现embedded-hal阻塞读取特性
+impl blocking::i2c::Read for I2c {
+    type Error = I2cError;
+    
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), I2cError> {
+        unsafe { I2c::read(self, address, buffer) }
+    }
+}
+
+/// 实现embedded-hal先写后读特性，写和读之间使用重复起始信号
+impl blocking::i2c::WriteRead for I2c {
+    type Error = I2cError;
+    
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I2cError> {
+        unsafe { I2c::write_read(self, address, bytes, buffer) }
+    }
+}
+
+/// 预定义的I2C实例
+pub const I2C1: I2c = I2c::new(I2cNumber::I2C1);
+pub const I2C2: I2c = I2c::new(I2cNumber::I2C2);
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    
+    /// 总线事件
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum BusEvent {
+        Start,
+        RepeatedStart,
+        Address(u8, bool),
+        Write(usize),
+        Read(usize, bool),
+        Stop,
+    }
+    
+    /// 记录总线事件的模拟总线
+    struct MockBus {
+        events: RefCell<heapless::Vec<BusEvent, 16>>,
+    }
+    
+    impl TransactionBus for MockBus {
+        unsafe fn bus_start(&self, repeated: bool) -> IicResult<()> {
+            let event = if repeated { BusEvent::RepeatedStart } else { BusEvent::Start };
+            self.events.borrow_mut().push(event).unwrap();
+            Ok(())
+        }
+        
+        unsafe fn bus_address(&self, addr: u8, read: bool) -> IicResult<()> {
+            self.events.borrow_mut().push(BusEvent::Address(addr, read)).unwrap();
+            Ok(())
+        }
+        
+        unsafe fn bus_write(&self, data: &[u8]) -> IicResult<()> {
+            self.events.borrow_mut().push(BusEvent::Write(data.len())).unwrap();
+            Ok(())
+        }
+        
+        unsafe fn bus_read(&self, buffer: &mut [u8], nack_last: bool) -> IicResult<()> {
+            buffer.fill(0x5A);
+            self.events.borrow_mut().push(BusEvent::Read(buffer.len(), nack_last)).unwrap();
+            Ok(())
+        }
+        
+        unsafe fn bus_stop(&self) {
+            self.events.borrow_mut().push(BusEvent::Stop).unwrap();
+        }
+    }
+    
+    /// 测试先写后读事务的起始/重复起始/停止信号顺序
+    #[test]
+    fn test_write_then_read_transaction_sequencing() {
+        let bus = MockBus { events: RefCell::new(heapless::Vec::new()) };
+        let mut buffer = [0u8; 2];
+        let mut ops = [I2cOp::Write(&[0x3B]), I2cOp::Read(&mut buffer)];
+        
+        unsafe { run_transaction(&bus, 0xD0, &mut ops) }.unwrap();
+        
+        assert_eq!(
+            bus.events.borrow().as_slice(),
+            &[
+                BusEvent::Start,
+                BusEvent::Address(0xD0, false),
+                BusEvent::Write(1),
+                BusEvent::RepeatedStart,
+                BusEvent::Address(0xD0, true),
+                BusEvent::Read(2, true),
+                BusEvent::Stop,
+            ],
+            "写后读事务应使用重复起始信号且只在最后发送一次停止信号"
+        );
+        assert_eq!(buffer, [0x5A, 0x5A], "读取的数据应写入缓冲区");
+        
+        // 相邻的同方向操作合并，不产生新的起始信号
+        let bus = MockBus { events: RefCell::new(heapless::Vec::new()) };
+        let mut first = [0u8; 1];
+        let mut second = [0u8; 3];
+        let mut ops = [I2cOp::Read(&mut first), I2cOp::Read(&mut second)];
+        unsafe { run_transaction(&bus, 0xA0, &mut ops) }.unwrap();
+        assert_eq!(
+            bus.events.borrow().as_slice(),
+            &[
+                BusEvent::Start,
+                BusEvent::Address(0xA0, true),
+                BusEvent::Read(1, false),
+                BusEvent::Read(3, true),
+                BusEvent::Stop,
+            ],
+            "连续读取应合并为一段且只有最后一个字节回复NACK"
+        );
+    }
+    
+    /// 测试注入故障使SB标志永不置位时起始信号返回超时
+    #[test]
+    fn test_start_timeout_with_fault_injection() {
+        use crate::bsp::regs::{MockRegisters, Fault};
+        
+        let polls = |condition: &dyn Fn() -> bool| !(0..100).any(|_| condition());
+        let regs: MockRegisters<4> = MockRegisters::new();
+        
+        // 正常情况：SB已置位
+        regs.set(I2C1_BASE + I2C_SR1, SR1_SB);
+        assert_eq!(unsafe { generate_start(&regs, I2C1_BASE, polls) }, Ok(()), "SB置位时应成功");
+        assert_ne!(regs.get(I2C1_BASE + I2C_CR1) & CR1_START, 0, "应置位CR1.START");
+        
+        // SB永不置位
+        regs.inject(Fault::StuckLow { addr: I2C1_BASE + I2C_SR1, mask: SR1_SB });
+        assert_eq!(unsafe { generate_start(&regs, I2C1_BASE, polls) }, Err(IicError::Timeout), "SB永不置位时应超时");
+        
+        // START写入丢失
+        regs.clear_faults();
+        regs.set(I2C1_BASE + I2C_CR1, 0);
+        regs.inject(Fault::DropWrite { addr: I2C1_BASE + I2C_CR1 });
+        unsafe { generate_start(&regs, I2C1_BASE, polls) }.ok();
+        assert_eq!(regs.get(I2C1_BASE + I2C_CR1), 0, "被丢弃的写入不应改变CR1");
+    }
+    
+    /// 测试由PCLK1计算CCR和TRISE
+    #[test]
+    fn test_i2c_timing() {
+        assert_eq!(I2c::timing(36_000_000, I2cSpeed::Standard), Ok((36, 180, 37)), "36MHz标准模式配置错误");
+        assert_eq!(I2c::timing(36_000_000, I2cSpeed::Fast), Ok((36, 0x8000 | 30, 11)), "36MHz快速模式配置错误");
+        assert_eq!(I2c::timing(8_000_000, I2cSpeed::Standard), Ok((8, 40, 9)), "8MHz标准模式配置错误");
+        
+        assert_eq!(I2c::timing(1_000_000, I2cSpeed::Standard), Err(I2cError::InvalidClock), "PCLK1低于2MHz应报错");
+        assert_eq!(I2c::timing(2_000_000, I2cSpeed::Fast), Err(I2cError::InvalidClock), "快速模式PCLK1低于4MHz应报错");
+        assert_eq!(I2c::timing(72_000_000, I2cSpeed::Standard), Err(I2cError::InvalidClock), "PCLK1超过36MHz应报错");
+        
+        // 8MHz下400kHz不能整除，CCR向上取整保证不超速
+        let (_, ccr, trise) = I2c::timing(8_000_000, I2cSpeed::Fast).unwrap();
+        assert_eq!(ccr & 0x0FFF, 7, "CCR应向上取整");
+        assert!(8_000_000 / (3 * (ccr & 0x0FFF)) <= I2C_SPEED_400K, "SCL频率不应超过400kHz");
+        assert_eq!(trise, 3, "快速模式TRISE应按300ns计算");
+    }
+    
+    /// 测试SR1错误标志到I2cError的映射
+    #[test]
+    fn test_i2c_sr1_status() {
+        assert_eq!(I2c::sr1_status(0, I2C_SR1_TXE), Ok(false), "标志未置位时应继续等待");
+        assert_eq!(I2c::sr1_status(I2C_SR1_TXE, I2C_SR1_TXE), Ok(true), "标志置位时应结束等待");
+        assert_eq!(I2c::sr1_status(I2C_SR1_AF, I2C_SR1_ADDR), Err(I2cError::Nack), "AF应映射为Nack");
+        assert_eq!(I2c::sr1_status(I2C_SR1_BERR | I2C_SR1_TXE, I2C_SR1_TXE), Err(I2cError::BusError), "错误应优先于标志");
+        assert_eq!(I2c::sr1_status(I2C_SR1_ARLO | I2C_SR1_AF, I2C_SR1_BTF), Err(I2cError::ArbitrationLost), "仲裁丢失优先级最高");
+    }
+    
+    /// 测试I2c实现了embedded-hal阻塞I2C特性
+    #[test]
+    fn test_embedded_hal_i2c_impls() {
+        fn assert_i2c<T>()
+        where
+            T: blocking::i2c::Read<Error = I2cError> + blocking::i2c::Write<Error = I2cError> + blocking::i2c::WriteRead<Error = I2cError>,
+        {}
+        assert_i2c::<I2c>();
+    }
+    
+    /// `I2c`文档示例，只做类型检查，不在主机上运行
+    #[allow(dead_code)]
+    fn read_bme280_id() -> Result<u8, I2cError> {
+        use embedded_hal::blocking::i2c::WriteRead;
+        
+        let mut i2c = I2C1;
+        unsafe { i2c.init(I2cSpeed::Fast)? };
+        let mut id = [0u8; 1];
+        WriteRead::write_read(&mut i2c, 0x76, &[0xD0], &mut id)?;
+        assert_eq!(id[0], 0x60);
+        Ok(id[0])
+    }
+}
@@ -5,12 +5,16 @@
 
 // 导入内部生成的设备驱动库
 use library::*;
+use crate::bsp::rcc::LSI_FREQUENCY;
 
 // 键值
 const IWDG_KEY_ENABLE: u16 = 0xCCCC;
 const IWDG_KEY_FEED: u16 = 0xAAAA;
 const IWDG_KEY_WRITE_ACCESS_ENABLE: u16 = 0x5555;
 
+/// 重载值最大值（RLR为12位）
+pub const IWDG_MAX_RELOAD: u16 = 0x0FFF;
+
 /// IWDG预分频系数枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum IwdgPrescaler {
@@ -23,6 +27,27 @@ pub enum IwdgPrescaler {
     Div256 = 6,
 }
 
+impl IwdgPrescaler {
+    /// 获取分频系数
+    pub const fn divider(&self) -> u32 {
+        4 << (*self as u32)
+    }
+    
+    /// 由PR字段值获取预分频，超出范围时返回None
+    const fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(IwdgPrescaler::Div4),
+            1 => Some(IwdgPrescaler::Div8),
+            2 => Some(IwdgPrescaler::Div16),
+            3 => Some(IwdgPrescaler::Div32),
+            4 => Some(IwdgPrescaler::Div64),
+            5 => Some(IwdgPrescaler::Div128),
+            6 => Some(IwdgPrescaler::Div256),
+            _ => None,
+        }
+    }
+}
+
 /// IWDG结构体
 pub struct Iwdg;
 
@@ -37,10 +62,40 @@ impl Iwdg {
         &mut *(0x40003000 as *mut library::iwdg::RegisterBlock)
     }
     
-    /// 初始化IWDG
+    /// 按超时时间启动IWDG
+    /// 
+    /// 由LSI（约40kHz）选择最小的预分频和对应的重载值，使超时时间不小于`timeout_ms`。
+    /// LSI频率在30~60kHz之间变化，实际超时可能比计算值短约三分之一，应留出余量。
+    /// 
+    /// 注意：IWDG启动后无法停止，只有系统复位才能关闭，之后必须在超时前周期调用`feed`。
+    /// 
+    /// # 参数
+    /// * `timeout_ms` - 超时时间（毫秒），限制在1~`max_timeout_ms()`之间，
+    ///   为0时按1ms处理，超过最长超时时按最长超时处理
+    /// 
+    /// # Safety
+    /// - 调用者必须确保启动后按时喂狗，否则会触发系统复位
+    pub unsafe fn start(&self, timeout_ms: u32) {
+        let (prescaler, reload) = Self::timeout_config(timeout_ms);
+        self.init(prescaler, reload);
+    }
+    
+    /// 初始化并启动IWDG
+    /// 
+    /// 先写入0xCCCC启动看门狗（同时由硬件启动LSI），再写入0x5555解除PR/RLR写保护，
+    /// 等待配置生效后喂狗，使新的重载值立即生效。启动后无法停止。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保启动后按时喂狗，否则会触发系统复位
     pub unsafe fn init(&self, prescaler: IwdgPrescaler, reload: u16) {
+        assert!(reload <= IWDG_MAX_RELOAD, "IWDG reload value must fit in 12 bits");
         let iwdg = self.iwdg();
         
+        // 启用IWDG
+        iwdg.kr().write(|w| w
+            .key().bits(IWDG_KEY_ENABLE)
+        );
+        
         // 启用写入访问
         iwdg.kr().write(|w| w
             .key().bits(IWDG_KEY_WRITE_ACCESS_ENABLE)
@@ -56,16 +111,19 @@ impl Iwdg {
             .rl().bits(reload)
         );
         
-        // 重载计数器
-        self.feed();
+        // 等待预分频和重载值同步到LSI时钟域
+        while self.is_prescaler_busy() || self.is_reload_busy() {
+            core::hint::spin_loop();
+        }
         
-        // 启用IWDG
-        iwdg.kr().write(|w| w
-            .key().bits(IWDG_KEY_ENABLE)
-        );
+        // 重载计数器，同时恢复写保护
+        self.feed();
     }
     
     /// 喂狗（重载计数器）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn feed(&self) {
         let iwdg = self.iwdg();
         iwdg.kr().write(|w| w
@@ -74,12 +132,18 @@ impl Iwdg {
     }
     
     /// 检查预分频寄存器是否正在更新
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_prescaler_busy(&self) -> bool {
         let iwdg = self.iwdg();
         iwdg.sr().read().pvu().bit_is_set()
     }
     
     /// 检查重载寄存器是否正在更新
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_reload_busy(&self) -> bool {
         let iwdg = self.iwdg();
         iwdg.sr().read().rvu().bit_is_set()
@@ -87,33 +151,82 @@ impl Iwdg {
     
     /// 计算看门狗超时时间
     /// 
+    /// 计数器从重载值递减到0时复位，共计`reload + 1`个计数周期
+    /// 
     /// # 参数
     /// * `prescaler` - 预分频系数
     /// * `reload` - 重载值
     /// 
     /// # 返回值
-    /// 超时时间（毫秒）
-    pub fn calculate_timeout(prescaler: IwdgPrescaler, reload: u16) -> u32 {
-        // 独立看门狗时钟频率为40kHz
-        let clk_freq = 40_000;
-        
-        // 计算预分频后的频率
-        let prescaler_value = match prescaler {
-            IwdgPrescaler::Div4 => 4,
-            IwdgPrescaler::Div8 => 8,
-            IwdgPrescaler::Div16 => 16,
-            IwdgPrescaler::Div32 => 32,
-            IwdgPrescaler::Div64 => 64,
-            IwdgPrescaler::Div128 => 128,
-            IwdgPrescaler::Div256 => 256,
-        };
-        
-        let tick_freq = clk_freq / prescaler_value;
-        let timeout_ms = (reload as u32 * 1000) / tick_freq;
+    /// 超时时间（毫秒，按LSI典型频率40kHz计算，向下取整）
+    pub const fn calculate_timeout(prescaler: IwdgPrescaler, reload: u16) -> u32 {
+        ((reload as u64 + 1) * prescaler.divider() as u64 * 1000 / LSI_FREQUENCY as u64) as u32
+    }
+    
+    /// 可设置的最长超时时间（毫秒），256分频、重载值0xFFF时约26.2秒
+    pub const fn max_timeout_ms() -> u32 {
+        Self::calculate_timeout(IwdgPrescaler::Div256, IWDG_MAX_RELOAD)
+    }
+    
+    /// 由超时时间计算预分频和重载值
+    /// 
+    /// 选择能满足超时时间的最小预分频以获得最高的分辨率，重载值向上取整
+    /// 
+    /// # 参数
+    /// * `timeout_ms` - 超时时间（毫秒），限制在1~`max_timeout_ms()`之间
+    pub const fn timeout_config(timeout_ms: u32) -> (IwdgPrescaler, u16) {
+        let max = Self::max_timeout_ms();
+        let timeout_ms = if timeout_ms == 0 { 1 } else if timeout_ms > max { max } else { timeout_ms };
         
-        timeout_ms
+        let mut bits = 0;
+        loop {
+            let prescaler = match IwdgPrescaler::from_bits(bits) {
+                Some(prescaler) => prescaler,
+                None => return (IwdgPrescaler::Div256, IWDG_MAX_RELOAD),
+            };
+            let divider = prescaler.divider() as u64;
+            // 计数周期数向上取整
+            let ticks = (timeout_ms as u64 * LSI_FREQUENCY as u64).div_ceil(divider * 1000);
+            if ticks <= IWDG_MAX_RELOAD as u64 + 1 {
+                return (prescaler, (ticks - 1) as u16);
+            }
+            bits += 1;
+        }
+    }
+}
+
+impl Default for Iwdg {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// 预定义的IWDG实例
 pub const IWDG: Iwdg = Iwdg::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试由超时时间选择预分频和重载值
+    #[test]
+    fn test_iwdg_timeout_config() {
+        assert_eq!(Iwdg::max_timeout_ms(), 26_214, "最长超时应约为26.2秒");
+        assert_eq!(Iwdg::calculate_timeout(IwdgPrescaler::Div4, 0), 0, "单个计数周期为0.1ms");
+        assert_eq!(Iwdg::calculate_timeout(IwdgPrescaler::Div32, 1249), 1000, "32分频1250个周期应为1秒");
+        
+        assert_eq!(Iwdg::timeout_config(1), (IwdgPrescaler::Div4, 9), "1ms应使用4分频");
+        assert_eq!(Iwdg::timeout_config(409), (IwdgPrescaler::Div4, 4089), "409ms仍可使用4分频");
+        assert_eq!(Iwdg::timeout_config(500), (IwdgPrescaler::Div8, 2499), "500ms应使用8分频");
+        assert_eq!(Iwdg::timeout_config(1000), (IwdgPrescaler::Div16, 2499), "1秒应使用16分频");
+        assert_eq!(Iwdg::timeout_config(26_214), (IwdgPrescaler::Div256, 4095), "最长超时应使用256分频");
+        assert_eq!(Iwdg::timeout_config(0), Iwdg::timeout_config(1), "0ms应按1ms处理");
+        assert_eq!(Iwdg::timeout_config(60_000), (IwdgPrescaler::Div256, 4095), "超过最长超时应按最长超时处理");
+        
+        for timeout_ms in [1, 7, 100, 999, 5000, 26_000] {
+            let (prescaler, reload) = Iwdg::timeout_config(timeout_ms);
+            assert!(Iwdg::calculate_timeout(prescaler, reload) >= timeout_ms, "实际超时不应小于请求值");
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! 旋律播放模块
+//! 提供基于定时器PWM的无源蜂鸣器音符/旋律非阻塞播放
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::timer::{Timer, PwmChannel};
+
+/// 音符枚举（十二平均律，A4 = 440Hz）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Note {
+    Rest,   // 休止符
+    C4,
+    Cs4,
+    D4,
+    Ds4,
+    E4,
+    F4,
+    Fs4,
+    G4,
+    Gs4,
+    A4,
+    As4,
+    B4,
+    C5,
+    Cs5,
+    D5,
+    Ds5,
+    E5,
+    F5,
+    Fs5,
+    G5,
+    Gs5,
+    A5,
+    As5,
+    B5,
+    C6,
+}
+
+impl Note {
+    /// 获取音符频率（Hz），休止符为0
+    pub const fn frequency(&self) -> u32 {
+        match self {
+            Note::Rest => 0,
+            Note::C4 => 262,
+            Note::Cs4 => 277,
+            Note::D4 => 294,
+            Note::Ds4 => 311,
+            Note::E4 => 330,
+            Note::F4 => 349,
+            Note::Fs4 => 370,
+            Note::G4 => 392,
+            Note::Gs4 => 415,
+            Note::A4 => 440,
+            Note::As4 => 466,
+            Note::B4 => 494,
+            Note::C5 => 523,
+            Note::Cs5 => 554,
+            Note::D5 => 587,
+            Note::Ds5 => 622,
+            Note::E5 => 659,
+            Note::F5 => 698,
+            Note::Fs5 => 740,
+            Note::G5 => 784,
+            Note::Gs5 => 831,
+            Note::A5 => 880,
+            Note::As5 => 932,
+            Note::B5 => 988,
+            Note::C6 => 1047,
+        }
+    }
+}
+
+/// 旋律播放器
+/// 
+/// 按`(音符, 时长毫秒)`序列非阻塞播放：在周期性的定时器节拍（如1ms的SysTick或定时器更新中断）中
+/// 调用`on_tick`，时长到达后切换到下一个音符并重新配置PWM频率，播放结束后静音。
+pub struct MelodyPlayer {
+    timer: Timer,
+    channel: PwmChannel,
+    melody: &'static [(Note, u16)],
+    index: usize,
+    remaining_ms: u32,
+    playing: bool,
+}
+
+impl MelodyPlayer {
+    /// 创建旋律播放器
+    /// 
+    /// # 参数
+    /// * `timer` - 驱动蜂鸣器的定时器，通道引脚需已配置为复用推挽输出
+    /// * `channel` - PWM通道
+    pub const fn new(timer: Timer, channel: PwmChannel) -> Self {
+        Self {
+            timer,
+            channel,
+            melody: &[],
+            index: 0,
+            remaining_ms: 0,
+            playing: false,
+        }
+    }
+    
+    /// 开始播放旋律，正在播放的旋律会被打断
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn play(&mut self, melody: &'static [(Note, u16)]) {
+        self.start(melody);
+        self.output_current();
+    }
+    
+    /// 停止播放并静音
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn stop(&mut self) {
+        self.playing = false;
+        self.output_current();
+    }
+    
+    /// 定时器节拍处理，应在周期性中断中调用
+    /// 
+    /// # 参数
+    /// * `elapsed_ms` - 距上次调用经过的毫秒数
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn on_tick(&mut self, elapsed_ms: u32) {
+        if self.advance(elapsed_ms) {
+            self.output_current();
+        }
+    }
+    
+    /// 检查是否正在播放
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+    
+    /// 获取当前正在播放的音符，未播放时返回None
+    pub fn current_note(&self) -> Option<Note> {
+        if self.playing {
+            Some(self.melody[self.index].0)
+        } else {
+            None
+        }
+    }
+    
+    /// 载入旋律并定位到第一个音符
+    fn start(&mut self, melody: &'static [(Note, u16)]) {
+        self.melody = melody;
+        self.index = 0;
+        self.playing = !melody.is_empty();
+        if self.playing {
+            self.remaining_ms = melody[0].1 as u32;
+            if self.remaining_ms == 0 {
+                self.advance(0);
+            }
+        }
+    }
+    
+    /// 推进播放进度
+    /// 
+    /// # 返回值
+    /// 当前音符发生变化（包括播放结束）时返回true
+    fn advance(&mut self, mut elapsed_ms: u32) -> bool {
+        if !self.playing {
+            return false;
+        }
+        
+        let mut changed = false;
+        while elapsed_ms >= self.remaining_ms {
+            elapsed_ms -= self.remaining_ms;
+            self.index += 1;
+            changed = true;
+            
+            if self.index >= self.melody.len() {
+                self.playing = false;
+                self.remaining_ms = 0;
+                return true;
+            }
+            self.remaining_ms = self.melody[self.index].1 as u32;
+        }
+        self.remaining_ms -= elapsed_ms;
+        changed
+    }
+    
+    /// 按当前音符配置PWM输出
+    unsafe fn output_current(&self) {
+        match self.current_note() {
+            Some(note) if note != Note::Rest => {
+                self.timer.set_pwm_frequency(self.channel, note.frequency(), 50);
+            },
+            _ => {
+                self.timer.set_pwm_duty(self.channel, 0);
+            },
+        }
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsp::timer::TIM3;
+    
+    /// 测试用旋律
+    static DOORBELL: [(Note, u16); 4] = [(Note::E5, 100), (Note::Rest, 20), (Note::C5, 0), (Note::G4, 200)];
+    
+    /// 测试模拟节拍下音符序列正确推进
+    #[test]
+    fn test_melody_advances_over_ticks() {
+        let mut player = MelodyPlayer::new(TIM3, PwmChannel::Channel1);
+        assert!(!player.is_playing(), "新建时不应在播放");
+        
+        player.start(&DOORBELL);
+        assert_eq!(player.current_note(), Some(Note::E5), "应从第一个音符开始");
+        
+        assert!(!player.advance(99), "时长未到时不应切换音符");
+        assert_eq!(player.current_note(), Some(Note::E5), "时长未到时应保持当前音符");
+        
+        assert!(player.advance(1), "时长到达时应切换音符");
+        assert_eq!(player.current_note(), Some(Note::Rest), "应切换到休止符");
+        
+        // 一次节拍跨过休止符和时长为0的音符
+        assert!(player.advance(30), "跨过多个音符时应切换");
+        assert_eq!(player.current_note(), Some(Note::G4), "时长为0的音符应被跳过");
+        
+        // 上次节拍多出的10ms计入当前音符
+        assert!(!player.advance(189), "剩余时长应扣除多出的时间");
+        assert!(player.advance(1), "最后一个音符结束时应报告变化");
+        assert!(!player.is_playing(), "播放完毕后应停止");
+        assert_eq!(player.current_note(), None, "停止后不应有当前音符");
+        assert!(!player.advance(100), "停止后节拍不应有效果");
+    }
+}
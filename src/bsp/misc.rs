@@ -1,5 +1,7 @@
 ﻿#![allow(unused)]
 
+use library::Interrupt;
+
 const SCB_BASE: u32 = 0xE000ED00;
 const NVIC_BASE: u32 = 0xE000E100;
 const SYSTICK_BASE: u32 = 0xE000E010;
@@ -10,11 +12,17 @@ const SCB_SCR: *mut u32 = (SCB_BASE + 0x04) as *mut u32;
 
 const NVIC_ISER: *mut u32 = NVIC_BASE as *mut u32;
 const NVIC_ICER: *mut u32 = (NVIC_BASE + 0x080) as *mut u32;
+const NVIC_ISPR: *mut u32 = (NVIC_BASE + 0x100) as *mut u32;
+const NVIC_ICPR: *mut u32 = (NVIC_BASE + 0x180) as *mut u32;
 const NVIC_IP: *mut u32 = (NVIC_BASE + 0x300) as *mut u32;
 
 const SYSTICK_CTRL: *mut u32 = SYSTICK_BASE as *mut u32;
 
 const AIRCR_VECTKEY_MASK: u32 = 0x05FA0000;
+const AIRCR_PRIGROUP_MASK: u32 = 0x700;
+
+/// STM32F103实现的优先级位数（IP寄存器高4位）
+pub const NVIC_PRIO_BITS: u8 = 4;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(u32)]
@@ -63,42 +71,210 @@ impl Misc {
         Self
     }
 
+    /// 设置NVIC优先级分组（AIRCR.PRIGROUP）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
     pub unsafe fn nvic_priority_group_config(&self, priority_group: NvicPriorityGroup) {
         *SCB_AIRCR = AIRCR_VECTKEY_MASK | (priority_group as u32);
     }
 
+    /// 设置NVIC优先级分组
+    /// 
+    /// 应在使能任何中断之前调用一次，之后修改分组会改变已设置优先级的含义
+    /// 
+    /// # 参数
+    /// * `group` - 抢占优先级位数（0~4），其余位为子优先级；
+    ///   例如2表示4级抢占优先级、4级子优先级
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
+    pub unsafe fn set_priority_grouping(&self, group: u8) {
+        *SCB_AIRCR = Self::aircr_priority_grouping(group);
+    }
+
+    /// 读取当前的抢占优先级位数（0~4）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn priority_grouping(&self) -> u8 {
+        Self::preemption_bits((*SCB_AIRCR & AIRCR_PRIGROUP_MASK) >> 8)
+    }
+
+    /// 计算设置优先级分组时写入AIRCR的值
+    /// 
+    /// AIRCR的写入必须在高16位带VECTKEY（0x05FA），否则写入被忽略
+    /// 
+    /// # 参数
+    /// * `group` - 抢占优先级位数（0~4）
+    pub const fn aircr_priority_grouping(group: u8) -> u32 {
+        assert!(group <= NVIC_PRIO_BITS, "Priority grouping must be 0-4 preemption bits");
+        // PRIGROUP为子优先级的起始位，4位优先级时抢占位数n对应PRIGROUP=7-n
+        AIRCR_VECTKEY_MASK | ((7 - group as u32) << 8)
+    }
+
+    /// 由AIRCR.PRIGROUP字段计算抢占优先级位数
+    const fn preemption_bits(prigroup: u32) -> u8 {
+        // PRIGROUP小于3时优先级位全部用作抢占优先级
+        if prigroup < 3 { NVIC_PRIO_BITS } else { (7 - prigroup) as u8 }
+    }
+
+    /// 按优先级分组把抢占优先级和子优先级编码为IP寄存器字节
+    /// 
+    /// # 参数
+    /// * `group` - 抢占优先级位数（0~4）
+    /// * `preemption_priority` - 抢占优先级，超出位数的高位被截断
+    /// * `sub_priority` - 子优先级，超出位数的高位被截断
+    pub const fn encode_priority(group: u8, preemption_priority: u8, sub_priority: u8) -> u8 {
+        let sub_bits = NVIC_PRIO_BITS - group;
+        let preempt = (preemption_priority as u32) & ((1 << group) - 1);
+        let sub = (sub_priority as u32) & ((1 << sub_bits) - 1);
+        (((preempt << sub_bits) | sub) << (8 - NVIC_PRIO_BITS)) as u8
+    }
+
+    /// 按初始化结构体配置中断的优先级并使能或禁用中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
     pub unsafe fn nvic_init(&self, init_struct: NvicInitStruct) {
         if init_struct.enable {
-            let priority_group = ((*SCB_AIRCR) & 0x700) >> 8;
-            let pre_bits = 4 - priority_group;
-            let sub_bits = priority_group;
-            
-            let mut priority = (init_struct.preemption_priority as u32) << sub_bits;
-            priority |= (init_struct.sub_priority as u32) & ((1 << sub_bits) - 1);
-            priority <<= 4;
-            
-            let ip_index = init_struct.irq_channel as usize;
-            let ip_register = NVIC_IP.add(ip_index / 4);
-            let shift = (ip_index % 4) * 8 + 4;
-            *ip_register &= !(0xFF << shift);
-            *ip_register |= priority << shift;
+            let priority = Self::encode_priority(
+                self.priority_grouping(),
+                init_struct.preemption_priority,
+                init_struct.sub_priority,
+            ) as u32;
             
-            let iser_index = init_struct.irq_channel as usize / 32;
-            let iser_bit = init_struct.irq_channel % 32;
-            let iser_register = NVIC_ISER.add(iser_index);
-            *iser_register |= 1 << iser_bit;
+            Self::write_ip(init_struct.irq_channel as u16, priority as u8);
+            Self::write_bit(NVIC_ISER, init_struct.irq_channel as u16);
         } else {
-            let icer_index = init_struct.irq_channel as usize / 32;
-            let icer_bit = init_struct.irq_channel % 32;
-            let icer_register = NVIC_ICER.add(icer_index);
-            *icer_register |= 1 << icer_bit;
+            Self::write_bit(NVIC_ICER, init_struct.irq_channel as u16);
         }
     }
 
+    /// 使能中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保中断服务函数已就绪，使能中断可能打破依赖屏蔽中断的临界区
+    pub unsafe fn enable_irq(&self, irq: Interrupt) {
+        Self::write_bit(NVIC_ISER, irq as u16);
+    }
+
+    /// 禁用中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
+    pub unsafe fn disable_irq(&self, irq: Interrupt) {
+        Self::write_bit(NVIC_ICER, irq as u16);
+    }
+
+    /// 检查中断是否已使能
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_irq_enabled(&self, irq: Interrupt) -> bool {
+        Self::read_bit(NVIC_ISER, irq as u16)
+    }
+
+    /// 设置中断优先级
+    /// 
+    /// # 参数
+    /// * `irq` - 中断
+    /// * `priority` - 优先级（0~15，数值越小优先级越高），按当前优先级分组拆分为抢占优先级和子优先级；
+    ///   需要分别指定时可用`encode_priority`计算后调用`set_priority_raw`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
+    pub unsafe fn set_priority(&self, irq: Interrupt, priority: u8) {
+        Self::write_ip(irq as u16, Self::priority_byte(priority));
+    }
+
+    /// 直接设置中断的IP寄存器字节（只有高4位有效）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
+    pub unsafe fn set_priority_raw(&self, irq: Interrupt, priority: u8) {
+        Self::write_ip(irq as u16, priority);
+    }
+
+    /// 读取中断优先级（0~15）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn get_priority(&self, irq: Interrupt) -> u8 {
+        let irq = irq as usize;
+        let value = *NVIC_IP.add(irq / 4) >> ((irq % 4) * 8);
+        (value as u8) >> (8 - NVIC_PRIO_BITS)
+    }
+
+    /// 挂起中断，中断使能时会立即进入服务函数
+    /// 
+    /// # Safety
+    /// - 调用者必须确保中断服务函数已就绪
+    pub unsafe fn pend(&self, irq: Interrupt) {
+        Self::write_bit(NVIC_ISPR, irq as u16);
+    }
+
+    /// 清除中断挂起状态
+    /// 
+    /// # Safety
+    /// - 调用者必须确保修改NVIC配置不会破坏正在运行的中断处理的优先级和屏蔽关系
+    pub unsafe fn clear_pending(&self, irq: Interrupt) {
+        Self::write_bit(NVIC_ICPR, irq as u16);
+    }
+
+    /// 检查中断是否挂起
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_pending(&self, irq: Interrupt) -> bool {
+        Self::read_bit(NVIC_ISPR, irq as u16)
+    }
+
+    /// 计算中断在ISER/ICER/ISPR/ICPR中的寄存器序号和位掩码
+    /// 
+    /// # 返回值
+    /// (寄存器序号, 位掩码)
+    pub const fn irq_bit(irq: u16) -> (usize, u32) {
+        ((irq / 32) as usize, 1 << (irq % 32))
+    }
+
+    /// 把0~15的优先级转换为IP寄存器字节，超出范围的高位被截断
+    pub const fn priority_byte(priority: u8) -> u8 {
+        (priority & ((1 << NVIC_PRIO_BITS) - 1)) << (8 - NVIC_PRIO_BITS)
+    }
+
+    /// 写入置位/清除寄存器组中的对应位
+    /// 
+    /// ISER/ICER/ISPR/ICPR写0无效，只写入目标位，不需要读-改-写
+    unsafe fn write_bit(base: *mut u32, irq: u16) {
+        let (index, mask) = Self::irq_bit(irq);
+        base.add(index).write_volatile(mask);
+    }
+
+    /// 读取置位/清除寄存器组中的对应位
+    unsafe fn read_bit(base: *mut u32, irq: u16) -> bool {
+        let (index, mask) = Self::irq_bit(irq);
+        base.add(index).read_volatile() & mask != 0
+    }
+
+    /// 写入中断的IP寄存器字节
+    unsafe fn write_ip(irq: u16, priority: u8) {
+        // IP寄存器支持字节访问，避免读-改-写影响同一字内的其他中断
+        (NVIC_IP as *mut u8).add(irq as usize).write_volatile(priority);
+    }
+
+    /// 设置中断向量表的位置（SCB.VTOR）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保新的向量表已完整写入，且偏移按128字节对齐
     pub unsafe fn nvic_set_vector_table(&self, vect_tab: NvicVectTab, offset: u32) {
         *SCB_VTOR = (vect_tab as u32) | (offset & 0x1FFFFF80);
     }
 
+    /// 设置或清除SCB.SCR中的低功耗配置位
+    /// 
+    /// # Safety
+    /// - 调用者必须确保低功耗配置与唤醒源相匹配，否则内核可能无法被唤醒
     pub unsafe fn nvic_system_lp_config(&self, low_power_mode: LowPowerMode, new_state: bool) {
         if new_state {
             *SCB_SCR |= low_power_mode as u32;
@@ -107,6 +283,10 @@ impl Misc {
         }
     }
 
+    /// 选择SysTick时钟源
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SysTick未被其他代码同时使用，切换时钟源会改变延时和时基的频率
     pub unsafe fn systick_clk_source_config(&self, clk_source: SysTickClkSource) {
         if clk_source == SysTickClkSource::Hclk {
             *SYSTICK_CTRL |= SysTickClkSource::Hclk as u32;
@@ -116,4 +296,51 @@ impl Misc {
     }
 }
 
+impl Default for Misc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub const MISC: Misc = Misc::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试优先级分组写入AIRCR的编码
+    #[test]
+    fn test_aircr_priority_grouping() {
+        assert_eq!(Misc::aircr_priority_grouping(0), 0x05FA_0700, "0位抢占优先级应为PRIGROUP=7");
+        assert_eq!(Misc::aircr_priority_grouping(2), 0x05FA_0500, "2位抢占优先级应为PRIGROUP=5");
+        assert_eq!(Misc::aircr_priority_grouping(4), 0x05FA_0300, "4位抢占优先级应为PRIGROUP=3");
+        for group in 0..=4u8 {
+            let aircr = Misc::aircr_priority_grouping(group);
+            assert_eq!(aircr >> 16, 0x05FA, "写入AIRCR必须带VECTKEY");
+            assert_eq!(Misc::preemption_bits((aircr >> 8) & 0x7), group, "PRIGROUP应能还原抢占位数");
+        }
+        assert_eq!(Misc::aircr_priority_grouping(2), NvicPriorityGroup::Group2 as u32 | AIRCR_VECTKEY_MASK, "应与分组枚举一致");
+    }
+
+    /// 测试抢占优先级和子优先级的编码
+    #[test]
+    fn test_encode_priority() {
+        assert_eq!(Misc::encode_priority(2, 1, 3), 0x70, "2位抢占时1/3应编码为0x70");
+        assert_eq!(Misc::encode_priority(4, 15, 0), 0xF0, "4位抢占时子优先级无效");
+        assert_eq!(Misc::encode_priority(0, 0, 9), 0x90, "0位抢占时全部为子优先级");
+        assert_eq!(Misc::encode_priority(1, 3, 0), 0x80, "超出位数的抢占优先级应被截断");
+    }
+
+    /// 测试中断号到NVIC寄存器位和优先级字节的换算
+    #[test]
+    fn test_irq_bit() {
+        assert_eq!(Misc::irq_bit(Interrupt::WWDG as u16), (0, 1 << 0), "WWDG应在ISER0位0");
+        assert_eq!(Misc::irq_bit(Interrupt::USART3 as u16), (1, 1 << 7), "USART3（39）应在ISER1位7");
+        assert_eq!(Misc::irq_bit(Interrupt::DMA2_Channel4_5 as u16), (1, 1 << 27), "最后一个中断（59）应在ISER1位27");
+        
+        assert_eq!(Misc::priority_byte(0), 0x00, "优先级0应为0x00");
+        assert_eq!(Misc::priority_byte(5), 0x50, "优先级应写入高4位");
+        assert_eq!(Misc::priority_byte(0x1F), 0xF0, "超出4位的优先级应被截断");
+    }
+}
@@ -3,27 +3,47 @@
 //! 包含板级支持包
 
 pub mod adc;
-// pub mod bkp;
-// pub mod can;
-// pub mod crc;
-// pub mod dac;
+pub mod battery;
+pub mod bkp;
+pub mod can;
+pub mod control;
+pub mod crc;
+pub mod dac;
+pub mod debounce;
 pub mod delay;
-// pub mod dma;
-// pub mod exti;
-// pub mod flash;
+pub mod diag;
+pub mod dma;
+pub mod encoder;
+pub mod error;
+pub mod executor;
+pub mod exti;
+pub mod fan;
+pub mod flash;
+pub mod frame;
 pub mod gpio;
-// pub mod iic;
-// pub mod iwdg;
-// pub mod pwr;
+pub mod iic;
+pub mod iwdg;
+pub mod melody;
+pub mod pwr;
 pub mod rcc;
-// pub mod rtc;
+pub mod regs;
+pub mod selftest;
+pub mod rtc;
 pub mod serial;
-// pub mod spi;
+pub mod servo;
+pub mod soft_uart;
+pub mod spi;
 pub mod system;
 pub mod timer;
-// pub mod wwdg;
+pub mod wwdg;
 // pub mod cec;
-// pub mod dbg;
+pub mod dbg;
 // pub mod fsmc;
 // pub mod sdio;
-// pub mod misc;
+pub mod misc;
+
+/// 中断服务函数属性宏和中断枚举
+/// 
+/// 用`#[interrupt] fn USART3() {}`声明中断服务函数，函数名必须是`Interrupt`的变体，
+/// 拼写错误会在编译时报错，而不是静默地使用`DefaultHandler`
+pub use library::{interrupt, Interrupt};
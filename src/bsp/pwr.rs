@@ -1,10 +1,37 @@
 ﻿//! PWR模块
 //! 提供电源控制功能封装
+//! 
+//! 低功耗模式：
+//! - 睡眠：只停止CPU时钟，任意中断唤醒
+//! - 停止：1.8V域时钟全部停止，SRAM和寄存器保持，EXTI线（含RTC闹钟、PVD）唤醒；
+//!   唤醒后系统时钟为HSI，需重新配置时钟树
+//! - 待机：1.8V域断电，只保留备份域和待机电路，WKUP引脚上升沿、RTC闹钟、NRST或IWDG唤醒；
+//!   唤醒过程相当于复位，可通过待机标志判断
 
 #![allow(unused)]
 
 // 导入内部生成的设备驱动库
 use library::*;
+use cortex_m::peripheral::SCB;
+use crate::bsp::rcc::{ClockConfig, RccClocks, RccDriver, RccError};
+
+/// PWR_CR.LPDS：停止模式下电压调节器进入低功耗
+const CR_LPDS: u32 = 1 << 0;
+/// PWR_CR.PDDS：深睡眠时进入待机模式
+const CR_PDDS: u32 = 1 << 1;
+/// PWR_CR.CWUF：清除唤醒标志
+const CR_CWUF: u32 = 1 << 2;
+/// PWR_CR.CSBF：清除待机标志
+const CR_CSBF: u32 = 1 << 3;
+/// SCB_SCR.SLEEPDEEP
+const SCR_SLEEPDEEP: u32 = 1 << 2;
+
+/// 停止模式下的电压调节器模式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopRegulator {
+    On,         // 调节器正常运行，唤醒更快
+    LowPower,   // 调节器低功耗运行，电流更小但唤醒延时更长
+}
 
 /// PWR结构体
 pub struct Pwr;
@@ -26,6 +53,9 @@ impl Pwr {
     }
     
     /// 初始化PWR
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn init(&self) {
         let rcc = self.rcc();
         
@@ -36,6 +66,9 @@ impl Pwr {
     }
     
     /// 使能对备份域的访问
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn enable_backup_domain_access(&self) {
         let pwr = self.pwr();
         pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
@@ -44,6 +77,9 @@ impl Pwr {
     }
     
     /// 禁用对备份域的访问
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn disable_backup_domain_access(&self) {
         let pwr = self.pwr();
         pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
@@ -52,6 +88,9 @@ impl Pwr {
     }
     
     /// 启用PVD（可编程电压监测器）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn enable_pvd(&self) {
         let pwr = self.pwr();
         pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
@@ -60,6 +99,9 @@ impl Pwr {
     }
     
     /// 禁用PVD（可编程电压监测器）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn disable_pvd(&self) {
         let pwr = self.pwr();
         pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
@@ -68,6 +110,9 @@ impl Pwr {
     }
     
     /// 设置PVD阈值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn set_pvd_level(&self, level: u8) {
         let pwr = self.pwr();
         let level_clamped = if level > 7 { 7 } else { level };
@@ -76,65 +121,106 @@ impl Pwr {
         );
     }
     
-    /// 进入睡眠模式
-    pub unsafe fn enter_sleep_mode(&self, wait_for_interrupt: bool) {
-        if wait_for_interrupt {
-            // WFI指令
-            core::arch::asm!("wfi");
-        } else {
-            // WFE指令
-            core::arch::asm!("wfe");
-        }
+    /// 获取SCB寄存器块
+    unsafe fn scb(&self) -> &'static mut cortex_m::peripheral::scb::RegisterBlock {
+        &mut *(SCB::PTR as *mut cortex_m::peripheral::scb::RegisterBlock)
+    }
+    
+    /// 设置或清除SLEEPDEEP位
+    unsafe fn set_sleep_deep(&self, enable: bool) {
+        let scb = self.scb();
+        scb.scr.modify(|r| if enable { r | SCR_SLEEPDEEP } else { r & !SCR_SLEEPDEEP });
+    }
+    
+    /// 进入睡眠模式，任意中断唤醒后返回
+    /// 
+    /// # Safety
+    /// - 调用者必须确保至少有一个已使能的中断能够唤醒内核
+    pub unsafe fn enter_sleep(&self) {
+        self.set_sleep_deep(false);
+        cortex_m::asm::wfi();
     }
     
-    /// 进入停止模式
-    pub unsafe fn enter_stop_mode(&self, regulator_low_power: bool) {
+    /// 进入停止模式，EXTI中断唤醒后返回
+    /// 
+    /// 唤醒后系统时钟为HSI（8MHz），HSE和PLL已关闭，需重新配置时钟树，
+    /// 或使用`enter_stop_and_restore`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保唤醒源已配置为EXTI中断，且所有EXTI挂起位和RTC闹钟标志已清除，否则无法进入停止模式
+    pub unsafe fn enter_stop(&self, regulator: StopRegulator) {
+        self.init();
         let pwr = self.pwr();
+        pwr.cr().modify(|r, w: &mut library::pwr::cr::W| unsafe { w.bits(Self::stop_cr(r.bits(), regulator)) });
         
-        // 设置LPDS位
-        if regulator_low_power {
-            pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
-                .lpds().set_bit()
-            );
-        } else {
-            pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
-                .lpds().clear_bit()
-            );
-        }
-        
-        // 设置PDDS位为0（停止模式）
-        pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
-            .pdds().clear_bit()
-        );
-        
-        // 设置CWUF位
-        pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
-            .cwuf().set_bit()
-        );
+        self.set_sleep_deep(true);
+        cortex_m::asm::wfi();
         
-        // WFI指令
-        core::arch::asm!("wfi");
+        // 唤醒后恢复为普通睡眠，避免之后的WFI再次进入停止模式
+        self.set_sleep_deep(false);
+    }
+    
+    /// 进入停止模式，唤醒后按`config`重新配置时钟树
+    /// 
+    /// # 返回值
+    /// 重新配置后的各总线时钟频率
+    /// 
+    /// # Safety
+    /// - 同`enter_stop`和`RccDriver::configure`
+    pub unsafe fn enter_stop_and_restore(&self, regulator: StopRegulator, config: ClockConfig) -> Result<RccClocks, RccError> {
+        self.enter_stop(regulator);
+        RccDriver::new().configure(config)
     }
     
-    /// 进入待机模式
-    pub unsafe fn enter_standby_mode(&self) {
+    /// 进入待机模式，不会返回
+    /// 
+    /// 唤醒后从复位开始执行，`get_standby_flag`为true
+    /// 
+    /// # Safety
+    /// - 调用者必须确保RTC闹钟标志已清除，需要WKUP引脚唤醒时已调用`enable_wakeup_pin`
+    pub unsafe fn enter_standby(&self) -> ! {
+        self.init();
         let pwr = self.pwr();
+        pwr.cr().modify(|r, w: &mut library::pwr::cr::W| unsafe { w.bits(Self::standby_cr(r.bits())) });
         
-        // 设置PDDS位为1（待机模式）
-        pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
-            .pdds().set_bit()
-        );
-        
-        // 设置CWUF位
-        pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
-            .cwuf().set_bit()
+        self.set_sleep_deep(true);
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+    
+    /// 计算进入停止模式的CR值：PDDS清零，按调节器模式设置LPDS，清除唤醒标志
+    pub const fn stop_cr(cr: u32, regulator: StopRegulator) -> u32 {
+        let cr = (cr & !(CR_PDDS | CR_LPDS | CR_CSBF)) | CR_CWUF;
+        match regulator {
+            StopRegulator::On => cr,
+            StopRegulator::LowPower => cr | CR_LPDS,
+        }
+    }
+    
+    /// 计算进入待机模式的CR值：PDDS置位，清除唤醒标志
+    pub const fn standby_cr(cr: u32) -> u32 {
+        (cr & !CR_CSBF) | CR_PDDS | CR_CWUF
+    }
+    
+    /// 启用或禁用WKUP引脚（PA0）
+    /// 
+    /// 启用后PA0被强制为下拉输入，上升沿将系统从待机模式唤醒
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PA0未被用作其他功能，启用后PA0强制为下拉输入
+    pub unsafe fn enable_wakeup_pin(&self, enable: bool) {
+        self.init();
+        let pwr = self.pwr();
+        pwr.csr().modify(|_, w: &mut library::pwr::csr::W| w
+            .ewup().bit(enable)
         );
-        
-        // WFI指令
-        core::arch::asm!("wfi");
     }
     
     /// 清除Wake-Up标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn clear_wakeup_flag(&self) {
         let pwr = self.pwr();
         pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
@@ -143,6 +229,9 @@ impl Pwr {
     }
     
     /// 清除待机标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保PWR寄存器未被其他代码同时修改
     pub unsafe fn clear_standby_flag(&self) {
         let pwr = self.pwr();
         pwr.cr().modify(|_, w: &mut library::pwr::cr::W| w
@@ -151,23 +240,53 @@ impl Pwr {
     }
     
     /// 检查Wake-Up标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_wakeup_flag(&self) -> bool {
         let pwr = self.pwr();
         pwr.csr().read().wuf().bit_is_set()
     }
     
     /// 检查待机标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_standby_flag(&self) -> bool {
         let pwr = self.pwr();
         pwr.csr().read().sbf().bit_is_set()
     }
     
     /// 检查PVD输出
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_pvd_output(&self) -> bool {
         let pwr = self.pwr();
         pwr.csr().read().pvdo().bit_is_set()
     }
 }
 
+impl Default for Pwr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的PWR实例
 pub const PWR: Pwr = Pwr::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试进入停止/待机模式时的CR配置
+    #[test]
+    fn test_low_power_cr_bits() {
+        assert_eq!(Pwr::stop_cr(0x0000_0102, StopRegulator::On), 0x0000_0104, "停止模式应清除PDDS和LPDS并保留DBP");
+        assert_eq!(Pwr::stop_cr(0, StopRegulator::LowPower), 0x0000_0005, "低功耗调节器应置位LPDS");
+        assert_eq!(Pwr::standby_cr(0x0000_0001), 0x0000_0007, "待机模式应置位PDDS和CWUF");
+        assert_eq!(Pwr::standby_cr(0x0000_0008) & CR_CSBF, 0, "不应同时清除待机标志");
+    }
+}
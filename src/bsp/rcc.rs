@@ -5,6 +5,8 @@
 
 // 使用生成的设备驱动库
 use library::*;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use crate::bsp::flash::{FlashDriver, FlashLatency};
 
 /// RCC时钟源枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -14,6 +16,90 @@ pub enum RccClockSource {
     PLL,    // 锁相环时钟
 }
 
+/// BDCR寄存器BDRST位：备份域软件复位
+pub const BDCR_BDRST: u32 = 1 << 16;
+
+/// PWR_CR寄存器DBP位：取消备份域写保护
+const PWR_CR_DBP: u32 = 1 << 8;
+
+/// CFGR寄存器MCO字段位置（位26:24）
+pub const CFGR_MCO_SHIFT: u32 = 24;
+
+/// CFGR寄存器MCO字段掩码
+pub const CFGR_MCO_MASK: u32 = 0b111 << CFGR_MCO_SHIFT;
+
+/// 计算备份域复位时依次写入BDCR的值
+/// 
+/// # 返回值
+/// (置位BDRST的值, 清除BDRST的值)
+pub const fn backup_reset_bdcr(bdcr: u32) -> (u32, u32) {
+    (bdcr | BDCR_BDRST, bdcr & !BDCR_BDRST)
+}
+
+/// 计算MCO时钟源和分频对应的CFGR.MCO字段值（已移位）
+/// 
+/// # 返回值
+/// HSI/1为0b101，HSE/1为0b110，PLL/2为0b111；STM32F103不支持的组合返回None
+pub const fn mco_field(source: RccClockSource, div: u32) -> Option<u32> {
+    match McoSource::from_source_div(source, div) {
+        Some(mco) => Some(mco.cfgr_field()),
+        None => None,
+    }
+}
+
+/// MCO输出时钟源枚举（CFGR.MCO）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum McoSource {
+    Disabled = 0b000,   // 不输出时钟
+    Sysclk = 0b100,     // 系统时钟
+    Hsi = 0b101,        // 内部高速时钟
+    Hse = 0b110,        // 外部高速时钟
+    PllDiv2 = 0b111,    // PLL时钟2分频
+}
+
+impl McoSource {
+    /// 由时钟源和分频系数得到MCO输出
+    /// 
+    /// # 返回值
+    /// HSI/1、HSE/1、PLL/2分别对应`Hsi`、`Hse`、`PllDiv2`，其他组合返回None
+    pub const fn from_source_div(source: RccClockSource, div: u32) -> Option<Self> {
+        match (source, div) {
+            (RccClockSource::HSI, 1) => Some(McoSource::Hsi),
+            (RccClockSource::HSE, 1) => Some(McoSource::Hse),
+            (RccClockSource::PLL, 2) => Some(McoSource::PllDiv2),
+            _ => None,
+        }
+    }
+    
+    /// 获取对应的CFGR.MCO字段值（已移位）
+    pub const fn cfgr_field(self) -> u32 {
+        (self as u32) << CFGR_MCO_SHIFT
+    }
+}
+
+/// 用新的MCO字段替换CFGR值中的MCO位，其余位保持不变
+pub const fn mco_cfgr_value(cfgr: u32, field: u32) -> u32 {
+    (cfgr & !CFGR_MCO_MASK) | (field & CFGR_MCO_MASK)
+}
+
+/// APB1RSTR中可复位的外设位
+/// 
+/// 不包含BKP和PWR：复位它们会清除DBP等备份域访问配置，软复位外设时应保持不变
+pub const APB1_RESET_MASK: u32 = 0x22FE_C9FF;
+
+/// APB2RSTR中可复位的外设位
+pub const APB2_RESET_MASK: u32 = 0x0038_FFFD;
+
+/// 由外设时钟使能寄存器计算需要复位的外设位
+/// 
+/// RSTR与ENR的外设位一一对应，只复位时钟已启用的外设
+/// 
+/// # 返回值
+/// (APB1RSTR值, APB2RSTR值)
+pub const fn peripheral_reset_masks(apb1enr: u32, apb2enr: u32) -> (u32, u32) {
+    (apb1enr & APB1_RESET_MASK, apb2enr & APB2_RESET_MASK)
+}
+
 /// RCC PLL输入源枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RccPllSource {
@@ -103,7 +189,186 @@ pub struct SystemClockConfig {
     pub apb2_prescaler: RccApb2Prescaler,
 }
 
+/// HSI频率，单位Hz
+pub const HSI_FREQUENCY: u32 = 8_000_000;
+
+/// 系统时钟最大频率，单位Hz
+pub const SYSCLK_MAX: u32 = 72_000_000;
+
+/// APB1时钟最大频率，单位Hz
+pub const PCLK1_MAX: u32 = 36_000_000;
+
+/// ADC时钟最大频率，单位Hz
+pub const ADCCLK_MAX: u32 = 14_000_000;
+
+/// ADC预分频系数枚举（CFGR.ADCPRE）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdcPrescaler {
+    Div2 = 0b00,    // PCLK2 2分频
+    Div4 = 0b01,    // PCLK2 4分频
+    Div6 = 0b10,    // PCLK2 6分频
+    Div8 = 0b11,    // PCLK2 8分频
+}
+
+impl AdcPrescaler {
+    /// 获取分频系数
+    pub const fn divider(self) -> u32 {
+        (self as u32 + 1) * 2
+    }
+    
+    /// 由分频系数（2、4、6、8）得到预分频枚举，其他值返回None
+    pub const fn from_divider(divider: u32) -> Option<Self> {
+        match divider {
+            2 => Some(AdcPrescaler::Div2),
+            4 => Some(AdcPrescaler::Div4),
+            6 => Some(AdcPrescaler::Div6),
+            8 => Some(AdcPrescaler::Div8),
+            _ => None,
+        }
+    }
+    
+    /// 选择使ADC时钟不超过14MHz的最小分频
+    /// 
+    /// PCLK2超过112MHz时8分频也无法满足，返回`Div8`
+    pub const fn for_pclk2(pclk2: u32) -> Self {
+        if pclk2 <= 2 * ADCCLK_MAX {
+            AdcPrescaler::Div2
+        } else if pclk2 <= 4 * ADCCLK_MAX {
+            AdcPrescaler::Div4
+        } else if pclk2 <= 6 * ADCCLK_MAX {
+            AdcPrescaler::Div6
+        } else {
+            AdcPrescaler::Div8
+        }
+    }
+}
+
+/// RCC错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RccError {
+    SysclkTooHigh,  // 系统时钟超过72MHz
+    Pclk1TooHigh,   // APB1时钟超过36MHz
+    UnsupportedMco, // MCO不支持该时钟源和分频的组合
+}
+
+/// 时钟树配置，用于`RccDriver::configure`
+/// 
+/// Flash等待周期由得到的系统时钟频率自动确定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockConfig {
+    pub source: RccClockSource,             // 系统时钟源
+    pub pll_source: RccPllSource,           // PLL输入，仅系统时钟源为PLL时使用
+    pub pll_mul: RccPllMul,                 // PLL倍频系数，仅系统时钟源为PLL时使用
+    pub ahb_prescaler: RccAhbPrescaler,     // AHB预分频
+    pub apb1_prescaler: RccApb1Prescaler,   // APB1预分频
+    pub apb2_prescaler: RccApb2Prescaler,   // APB2预分频
+}
+
+impl ClockConfig {
+    /// 8MHz外部晶振经PLL 9倍频得到72MHz，APB1 2分频为36MHz
+    pub const fn hse_72mhz() -> Self {
+        Self {
+            source: RccClockSource::PLL,
+            pll_source: RccPllSource::Hse,
+            pll_mul: RccPllMul::Mul9,
+            ahb_prescaler: RccAhbPrescaler::Div1,
+            apb1_prescaler: RccApb1Prescaler::Div2,
+            apb2_prescaler: RccApb2Prescaler::Div1,
+        }
+    }
+    
+    /// 只使用内部8MHz HSI，适用于没有外部晶振的板子
+    pub const fn hsi_8mhz() -> Self {
+        Self {
+            source: RccClockSource::HSI,
+            pll_source: RccPllSource::HsiDiv2,
+            pll_mul: RccPllMul::Mul2,
+            ahb_prescaler: RccAhbPrescaler::Div1,
+            apb1_prescaler: RccApb1Prescaler::Div1,
+            apb2_prescaler: RccApb2Prescaler::Div1,
+        }
+    }
+    
+    /// HSI/2经PLL 6倍频得到24MHz，Flash无需等待周期，适用于低功耗场合
+    pub const fn hsi_24mhz() -> Self {
+        Self {
+            source: RccClockSource::PLL,
+            pll_source: RccPllSource::HsiDiv2,
+            pll_mul: RccPllMul::Mul6,
+            ahb_prescaler: RccAhbPrescaler::Div1,
+            apb1_prescaler: RccApb1Prescaler::Div1,
+            apb2_prescaler: RccApb2Prescaler::Div1,
+        }
+    }
+    
+    /// 计算配置得到的系统时钟频率
+    /// 
+    /// # 参数
+    /// * `hse_freq` - HSE频率（Hz），不使用HSE时忽略
+    pub const fn sysclk_frequency(&self, hse_freq: u32) -> u32 {
+        match self.source {
+            RccClockSource::HSI => HSI_FREQUENCY,
+            RccClockSource::HSE => hse_freq,
+            RccClockSource::PLL => {
+                let input = match self.pll_source {
+                    RccPllSource::HsiDiv2 => HSI_FREQUENCY / 2,
+                    RccPllSource::Hse => hse_freq,
+                    RccPllSource::HseDiv2 => hse_freq / 2,
+                };
+                input * (self.pll_mul as u32 + 2)
+            }
+        }
+    }
+    
+    /// 计算配置得到的各总线时钟频率
+    /// 
+    /// ADC时钟按ADCPRE复位值（PCLK2 2分频）计算
+    pub const fn clocks(&self, hse_freq: u32) -> RccClocks {
+        let sysclk_frequency = self.sysclk_frequency(hse_freq);
+        let hclk_frequency = sysclk_frequency / ahb_divider(self.ahb_prescaler as u32);
+        let pclk1_frequency = hclk_frequency / apb_divider(self.apb1_prescaler as u32);
+        let pclk2_frequency = hclk_frequency / apb_divider(self.apb2_prescaler as u32);
+        RccClocks {
+            sysclk_frequency,
+            hclk_frequency,
+            pclk1_frequency,
+            pclk2_frequency,
+            adcclk_frequency: pclk2_frequency / 2,
+        }
+    }
+    
+    /// 检查配置是否超出芯片的频率限制
+    pub const fn validate(&self, hse_freq: u32) -> Result<(), RccError> {
+        let clocks = self.clocks(hse_freq);
+        if clocks.sysclk_frequency > SYSCLK_MAX {
+            return Err(RccError::SysclkTooHigh);
+        }
+        if clocks.pclk1_frequency > PCLK1_MAX {
+            return Err(RccError::Pclk1TooHigh);
+        }
+        Ok(())
+    }
+}
+
+/// 由HPRE字段计算AHB分频系数
+const fn ahb_divider(hpre: u32) -> u32 {
+    if hpre < 8 {
+        1
+    } else if hpre < 12 {
+        2 << (hpre - 8)
+    } else {
+        // 0b1100~0b1111对应64~512分频，跳过了32分频
+        64 << (hpre - 12)
+    }
+}
+
+/// 由PPRE字段计算APB分频系数
+const fn apb_divider(ppre: u32) -> u32 {
+    if ppre < 4 { 1 } else { 2 << (ppre - 4) }
+}
+
 /// 时钟频率结构体
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RccClocks {
     pub sysclk_frequency: u32,  // 系统时钟频率，单位Hz
     pub hclk_frequency: u32,    // AHB时钟频率，单位Hz
@@ -112,13 +377,102 @@ pub struct RccClocks {
     pub adcclk_frequency: u32,  // ADC时钟频率，单位Hz
 }
 
+/// 时钟变化回调表容量
+pub const CLOCK_CHANGE_HOOK_CAPACITY: usize = 8;
+
+/// 时钟变化回调表
+/// 
+/// 回调以函数指针形式保存在原子指针中，注册和通知都不需要关中断。
+pub struct ClockChangeHooks {
+    hooks: [AtomicPtr<()>; CLOCK_CHANGE_HOOK_CAPACITY],
+}
+
+impl ClockChangeHooks {
+    /// 创建空的回调表
+    pub const fn new() -> Self {
+        Self {
+            hooks: [const { AtomicPtr::new(core::ptr::null_mut()) }; CLOCK_CHANGE_HOOK_CAPACITY],
+        }
+    }
+    
+    /// 注册回调，占用第一个空位
+    /// 
+    /// # 返回值
+    /// 回调表已满时返回false
+    pub fn register(&self, hook: fn(&RccClocks)) -> bool {
+        let ptr = hook as *mut ();
+        self.hooks.iter().any(|slot| {
+            slot.compare_exchange(core::ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire).is_ok()
+        })
+    }
+    
+    /// 注销回调
+    pub fn unregister(&self, hook: fn(&RccClocks)) {
+        let ptr = hook as *mut ();
+        for slot in self.hooks.iter() {
+            let _ = slot.compare_exchange(ptr, core::ptr::null_mut(), Ordering::AcqRel, Ordering::Acquire);
+        }
+    }
+    
+    /// 以新的时钟频率调用所有已注册的回调
+    pub fn notify(&self, clocks: &RccClocks) {
+        for slot in self.hooks.iter() {
+            let ptr = slot.load(Ordering::Acquire);
+            if !ptr.is_null() {
+                // 非空指针只可能由register从fn(&RccClocks)转换而来
+                let hook: fn(&RccClocks) = unsafe { core::mem::transmute(ptr) };
+                hook(clocks);
+            }
+        }
+    }
+}
+
+impl Default for ClockChangeHooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 全局时钟变化回调表
+pub static CLOCK_CHANGE_HOOKS: ClockChangeHooks = ClockChangeHooks::new();
+
+/// LSE频率（32.768kHz晶振）
+pub const LSE_FREQUENCY: u32 = 32_768;
+
+/// LSI典型频率（30~60kHz，随温度和芯片变化）
+pub const LSI_FREQUENCY: u32 = 40_000;
+
 /// RTC时钟源枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RtcClockSource {
     LSE,            // 外部低速时钟
     LSI,            // 内部低速时钟
     HseDiv128,      // HSE除以128
 }
 
+impl RtcClockSource {
+    /// 获取BDCR.RTCSEL字段值（未移位）
+    pub const fn rtcsel(&self) -> u32 {
+        match self {
+            RtcClockSource::LSE => 0b01,
+            RtcClockSource::LSI => 0b10,
+            RtcClockSource::HseDiv128 => 0b11,
+        }
+    }
+    
+    /// 计算RTC时钟频率
+    /// 
+    /// # 参数
+    /// * `hse_freq` - HSE频率（Hz），不使用HSE时忽略
+    pub const fn frequency(&self, hse_freq: u32) -> u32 {
+        match self {
+            RtcClockSource::LSE => LSE_FREQUENCY,
+            RtcClockSource::LSI => LSI_FREQUENCY,
+            RtcClockSource::HseDiv128 => hse_freq / 128,
+        }
+    }
+}
+
 /// RCC中断枚举
 pub enum RccInterrupt {
     LsiRdy = 0x00000002,    // LSI就绪中断
@@ -406,6 +760,72 @@ impl RccDriver {
         
         // 4. 设置系统时钟源
         self.set_system_clock_source(config.clock_source);
+        
+        // 5. 通知依赖时钟的外设重新计算配置
+        CLOCK_CHANGE_HOOKS.notify(&self.get_clocks_freq());
+    }
+    
+    /// 按配置切换系统时钟
+    /// 
+    /// 先切换到HSI，再启用所需的振荡器和PLL、设置总线预分频，最后切换系统时钟源。
+    /// 切换期间Flash等待周期取最大值，切换完成后按新的系统时钟频率设置：
+    /// ≤24MHz为0，≤48MHz为1，其余为2。完成后通知已注册的时钟变化回调。
+    /// 
+    /// # 参数
+    /// * `config` - 时钟树配置，使用HSE时按`hse_frequency`计算频率
+    /// 
+    /// # 返回值
+    /// 切换后的各总线时钟频率；系统时钟超过72MHz或APB1超过36MHz时返回错误，且不修改任何寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保切换时钟时没有外设正在使用旧的时钟频率，使用HSE时外部晶振必须存在
+    pub unsafe fn configure(&self, config: ClockConfig) -> Result<RccClocks, RccError> {
+        config.validate(self.hse_frequency)?;
+        let flash = FlashDriver::new();
+        
+        // 1. 切换到HSI，之后才能重新配置PLL
+        self.enable_hsi();
+        self.set_system_clock_source(RccClockSource::HSI);
+        flash.enable_prefetch();
+        flash.set_latency(FlashLatency::Latency2);
+        
+        // 2. 启用所需的时钟源
+        let uses_hse = match config.source {
+            RccClockSource::HSI => false,
+            RccClockSource::HSE => true,
+            RccClockSource::PLL => !matches!(config.pll_source, RccPllSource::HsiDiv2),
+        };
+        if uses_hse {
+            self.enable_hse();
+        }
+        if config.source == RccClockSource::PLL {
+            self.configure_pll(config.pll_source, config.pll_mul);
+            self.enable_pll();
+        }
+        
+        // 3. 配置预分频器并切换系统时钟源
+        self.set_ahb_prescaler(config.ahb_prescaler);
+        self.set_apb1_prescaler(config.apb1_prescaler);
+        self.set_apb2_prescaler(config.apb2_prescaler);
+        self.set_system_clock_source(config.source);
+        
+        // 4. 按新频率设置Flash等待周期
+        flash.set_latency(FlashLatency::for_sysclk(config.sysclk_frequency(self.hse_frequency)));
+        
+        let clocks = self.get_clocks_freq();
+        CLOCK_CHANGE_HOOKS.notify(&clocks);
+        Ok(clocks)
+    }
+    
+    /// 注册时钟变化回调
+    /// 
+    /// `configure_system_clock`完成后以新的时钟频率调用所有回调，
+    /// 外设可在回调中重新计算波特率、定时器分频、ADC预分频或SysTick重装载值。
+    /// 
+    /// # 返回值
+    /// 注册成功返回true，回调表已满（`CLOCK_CHANGE_HOOK_CAPACITY`）时返回false
+    pub fn on_clock_change(&self, hook: fn(&RccClocks)) -> bool {
+        CLOCK_CHANGE_HOOKS.register(hook)
     }
     
     /// 配置AHB预分频系数
@@ -512,6 +932,26 @@ impl RccDriver {
         rcc.apb2rstr().write(|w: &mut library::rcc::apb2rstr::W| unsafe { w.bits(0) });
     }
     
+    /// 复位所有已启用时钟的APB外设，使其寄存器恢复默认值
+    /// 
+    /// 按APB1ENR/APB2ENR中已置位的使能位（由`enable_apb1_peripheral`等设置）产生复位脉冲，
+    /// 外设时钟保持启用；BKP和PWR不复位，AHB外设（DMA、CRC等）没有复位寄存器。
+    /// 
+    /// # 返回值
+    /// (APB1RSTR值, APB2RSTR值)，即实际复位的外设位
+    /// 
+    /// # Safety
+    /// - 调试串口等正在使用的外设也会被复位，调用后需要重新初始化
+    pub unsafe fn reset_all_peripherals(&self) -> (u32, u32) {
+        let rcc = self.get_rcc();
+        let (apb1, apb2) = peripheral_reset_masks(rcc.apb1enr().read().bits(), rcc.apb2enr().read().bits());
+        rcc.apb1rstr().write(|w: &mut library::rcc::apb1rstr::W| unsafe { w.bits(apb1) });
+        rcc.apb1rstr().write(|w: &mut library::rcc::apb1rstr::W| unsafe { w.bits(0) });
+        rcc.apb2rstr().write(|w: &mut library::rcc::apb2rstr::W| unsafe { w.bits(apb2) });
+        rcc.apb2rstr().write(|w: &mut library::rcc::apb2rstr::W| unsafe { w.bits(0) });
+        (apb1, apb2)
+    }
+    
     /// 获取系统时钟频率
     pub unsafe fn get_system_clock_frequency(&self) -> u32 {
         let rcc = self.get_rcc();
@@ -699,93 +1139,85 @@ impl RccDriver {
     }
     
     /// 配置ADC时钟
-    /// prescaler: ADC预分频系数，可选值：2, 4, 6, 8
+    /// prescaler: ADC预分频系数，可选值：2, 4, 6, 8，其他值不修改ADCPRE
+    #[deprecated(note = "use `set_adc_prescaler` with `AdcPrescaler`")]
     pub unsafe fn configure_adc_clock(&self, prescaler: u32) {
-        let rcc = self.get_rcc();
-        
-        // 配置ADC预分频器
-        let mut value = rcc.cfgr().read().bits();
-        // 清除ADCPRE位（位14-15）
-        value &= !0xC000;
-        
-        // 设置ADCPRE位
-        match prescaler {
-            2 => {
-                // ADC时钟 = PCLK2 / 2
-                value |= 0x0000;
-            }
-            4 => {
-                // ADC时钟 = PCLK2 / 4
-                value |= 0x4000;
-            }
-            6 => {
-                // ADC时钟 = PCLK2 / 6
-                value |= 0x8000;
-            }
-            8 => {
-                // ADC时钟 = PCLK2 / 8
-                value |= 0xC000;
-            }
-            _ => {
-                // 默认使用2分频
-                value |= 0x0000;
-            }
+        if let Some(prescaler) = AdcPrescaler::from_divider(prescaler) {
+            self.set_adc_prescaler(prescaler);
         }
-        
+    }
+    
+    /// 设置ADC预分频系数（CFGR.ADCPRE）
+    /// 
+    /// ADC时钟不得超过14MHz，PCLK2为72MHz时至少需要6分频
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC未在转换时修改预分频
+    pub unsafe fn set_adc_prescaler(&self, prescaler: AdcPrescaler) {
+        let rcc = self.get_rcc();
+        let value = (rcc.cfgr().read().bits() & !0xC000) | ((prescaler as u32) << 14);
         rcc.cfgr().write(|w: &mut library::rcc::cfgr::W| unsafe { w.bits(value) });
     }
     
+    /// 按当前PCLK2选择最小的ADC预分频，使ADC时钟不超过14MHz
+    /// 
+    /// # 返回值
+    /// 设置后的ADC时钟频率（Hz）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保ADC未在转换时修改预分频
+    pub unsafe fn limit_adc_clock(&self) -> u32 {
+        let pclk2 = self.get_clocks_freq().pclk2_frequency;
+        let prescaler = AdcPrescaler::for_pclk2(pclk2);
+        self.set_adc_prescaler(prescaler);
+        pclk2 / prescaler.divider()
+    }
+    
     /// 配置MCO（微控制器时钟输出）
     /// source: MCO时钟源
-    /// prescaler: MCO预分频系数，可选值：1, 2, 4, 8
+    /// prescaler: MCO分频系数，STM32F103只支持HSI/HSE不分频（1）和PLL 2分频（2），其他组合不修改MCO
+    #[deprecated(note = "use `set_mco` with `McoSource`")]
     pub unsafe fn configure_mco(&self, source: RccClockSource, prescaler: u32) {
-        let rcc = self.get_rcc();
-        
-        // 配置MCO
-        let mut value = rcc.cfgr().read().bits();
-        // 清除MCO位
-        value &= !0x7F000000;
-        
-        // 设置MCO源
-        match source {
-            RccClockSource::HSI => {
-                // HSI作为MCO源
-                value |= 0x00000000;
-            }
-            RccClockSource::HSE => {
-                // HSE作为MCO源
-                value |= 0x40000000;
-            }
-            RccClockSource::PLL => {
-                // PLL作为MCO源
-                value |= 0x80000000;
-            }
-        }
-        
-        // 设置MCO预分频系数
-        match prescaler {
-            1 => {
-                // MCO不分频
-                value &= !0x30000000;
-            }
-            2 => {
-                // MCO 2分频
-                value |= 0x10000000;
-            }
-            4 => {
-                // MCO 4分频
-                value |= 0x20000000;
-            }
-            8 => {
-                // MCO 8分频
-                value |= 0x30000000;
-            }
-            _ => {
-                // 默认不分频
-                value &= !0x30000000;
-            }
+        if let Some(mco) = McoSource::from_source_div(source, prescaler) {
+            self.set_mco(mco);
         }
-        
+    }
+    
+    /// 配置带分频的MCO时钟输出
+    /// 
+    /// STM32F103的MCO没有独立的预分频器，可选输出为HSI、HSE和PLL/2。
+    /// 输出引脚为PA8，调用者需将其配置为复用推挽输出（50MHz）；
+    /// MCO引脚最高输出50MHz，72MHz的PLL时钟必须经过2分频输出。
+    /// 
+    /// # 参数
+    /// * `source` - MCO时钟源
+    /// * `div` - 分频系数，HSI/HSE为1，PLL为2
+    /// 
+    /// # 返回值
+    /// 不支持的组合返回`UnsupportedMco`，不修改MCO
+    /// 
+    /// # Safety
+    /// - 调用者必须确保所选时钟源已启用
+    #[deprecated(note = "use `set_mco` with `McoSource`")]
+    pub unsafe fn set_mco_with_div(&self, source: RccClockSource, div: u32) -> Result<(), RccError> {
+        let mco = McoSource::from_source_div(source, div).ok_or(RccError::UnsupportedMco)?;
+        self.set_mco(mco);
+        Ok(())
+    }
+    
+    /// 选择MCO输出的时钟
+    /// 
+    /// 输出引脚为PA8，调用者需将其配置为复用推挽输出（50MHz）。
+    /// MCO引脚最高输出50MHz，系统时钟高于50MHz时不应选择`Sysclk`。
+    /// 
+    /// # 参数
+    /// * `source` - MCO时钟源，`Disabled`关闭输出
+    /// 
+    /// # Safety
+    /// - 调用者必须确保所选时钟源已启用
+    pub unsafe fn set_mco(&self, source: McoSource) {
+        let rcc = self.get_rcc();
+        let value = mco_cfgr_value(rcc.cfgr().read().bits(), source.cfgr_field());
         rcc.cfgr().write(|w: &mut library::rcc::cfgr::W| unsafe { w.bits(value) });
     }
     
@@ -961,26 +1393,19 @@ impl RccDriver {
         self.enable_apb1_peripheral(Apb1Peripheral::PWR);
         self.enable_apb1_peripheral(Apb1Peripheral::BKP);
         
-        // 解锁备份域访问
-        let pwr = &mut *(0x40007000 as *mut library::Pwr);
-        pwr.cr().write(|w: &mut library::pwr::cr::W| unsafe { w.bits(0x10) });
+        // 解锁备份域访问，只修改DBP，保留PVD和LPDS配置
+        let pwr = &mut *(0x40007000 as *mut library::pwr::RegisterBlock);
+        pwr.cr().modify(|r, w: &mut library::pwr::cr::W| unsafe { w.bits(r.bits() | PWR_CR_DBP) });
         
         let rcc = self.get_rcc();
         
-        // 复位备份域
-        rcc.bdcr().modify(|r, w| {
-            w.bdrst().set_bit();
-            w.bits(r.bits())
-        });
-        
-        // 清除复位标志
-        rcc.bdcr().modify(|r, w| {
-            w.bdrst().clear_bit();
-            w.bits(r.bits())
-        });
+        // 置位再清除BDRST，产生一次备份域复位
+        let (assert, release) = backup_reset_bdcr(rcc.bdcr().read().bits());
+        rcc.bdcr().write(|w| unsafe { w.bits(assert) });
+        rcc.bdcr().write(|w| unsafe { w.bits(release) });
         
         // 锁定备份域访问
-        pwr.cr().write(|w: &mut library::pwr::cr::W| unsafe { w.bits(0x00) });
+        pwr.cr().modify(|r, w: &mut library::pwr::cr::W| unsafe { w.bits(r.bits() & !PWR_CR_DBP) });
     }
     
     /// 启用LSI（内部低速时钟）
@@ -1121,3 +1546,143 @@ pub enum Apb2Peripheral {
 pub const RCC_DRIVER: RccDriver = RccDriver {
     hse_frequency: 8_000_000,
 };
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicU32;
+    
+    static LAST_PCLK1: AtomicU32 = AtomicU32::new(0);
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+    
+    fn record_pclk1(clocks: &RccClocks) {
+        LAST_PCLK1.store(clocks.pclk1_frequency, Ordering::Relaxed);
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    fn noop(_: &RccClocks) {}
+    
+    /// 测试时钟变化后已注册的回调收到新的时钟频率
+    #[test]
+    fn test_clock_change_hooks() {
+        let hooks = ClockChangeHooks::new();
+        assert!(hooks.register(record_pclk1), "空回调表应能注册");
+        
+        // 从HSI 8MHz切换到PLL 72MHz
+        let clocks = RccClocks {
+            sysclk_frequency: 72_000_000,
+            hclk_frequency: 72_000_000,
+            pclk1_frequency: 36_000_000,
+            pclk2_frequency: 72_000_000,
+            adcclk_frequency: 12_000_000,
+        };
+        hooks.notify(&clocks);
+        assert_eq!(LAST_PCLK1.load(Ordering::Relaxed), 36_000_000, "回调应收到新的PCLK1频率");
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1, "回调应只调用一次");
+        
+        hooks.unregister(record_pclk1);
+        hooks.notify(&clocks);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1, "注销后不应再调用");
+        
+        for _ in 0..CLOCK_CHANGE_HOOK_CAPACITY {
+            assert!(hooks.register(noop), "容量内应能注册");
+        }
+        assert!(!hooks.register(noop), "回调表满时应注册失败");
+    }
+    
+    /// 测试MCO字段和分频组合
+    #[test]
+    fn test_mco_field_with_divider() {
+        assert_eq!(mco_field(RccClockSource::HSI, 1), Some(0b101 << 24), "HSI不分频应为0b101");
+        assert_eq!(mco_field(RccClockSource::HSE, 1), Some(0b110 << 24), "HSE不分频应为0b110");
+        assert_eq!(mco_field(RccClockSource::PLL, 2), Some(0b111 << 24), "PLL 2分频应为0b111");
+        assert_eq!(mco_field(RccClockSource::PLL, 1), None, "PLL不支持不分频输出");
+        assert_eq!(mco_field(RccClockSource::HSE, 4), None, "F103的MCO没有其他预分频");
+        
+        // 72MHz配置下的CFGR：只替换MCO位
+        let cfgr = 0x001D_0402;
+        let value = mco_cfgr_value(cfgr | (0b110 << 24), mco_field(RccClockSource::PLL, 2).unwrap());
+        assert_eq!(value, cfgr | (0b111 << 24), "应只修改MCO字段");
+        assert_eq!(value & !CFGR_MCO_MASK, cfgr, "其他位应保持不变");
+        
+        assert_eq!(McoSource::Sysclk.cfgr_field(), 0b100 << 24, "SYSCLK应为0b100");
+        assert_eq!(McoSource::PllDiv2.cfgr_field(), mco_field(RccClockSource::PLL, 2).unwrap(), "PLL/2应与mco_field一致");
+        assert_eq!(McoSource::from_source_div(RccClockSource::HSE, 1), Some(McoSource::Hse), "HSE不分频应对应Hse");
+        assert_eq!(McoSource::from_source_div(RccClockSource::PLL, 1), None, "PLL不分频不受支持");
+        assert_eq!(mco_cfgr_value(cfgr | CFGR_MCO_MASK, McoSource::Disabled.cfgr_field()), cfgr, "关闭输出应清除MCO字段");
+    }
+    
+    /// 测试只复位时钟已启用的外设
+    #[test]
+    fn test_reset_only_enabled_peripherals() {
+        assert_eq!(peripheral_reset_masks(0, 0), (0, 0), "没有启用的外设时不应复位");
+        
+        // TIM2、USART2、I2C1启用；GPIOA、GPIOC、USART1启用
+        let apb1enr = Apb1Peripheral::TIM2 as u32 | Apb1Peripheral::USART2 as u32 | Apb1Peripheral::I2C1 as u32;
+        let apb2enr = Apb2Peripheral::GPIOA as u32 | Apb2Peripheral::GPIOC as u32 | Apb2Peripheral::USART1 as u32;
+        assert_eq!(peripheral_reset_masks(apb1enr, apb2enr), (apb1enr, apb2enr), "应复位全部已启用的外设");
+        
+        // PWR和BKP保持不变，保留位不写入
+        let (apb1, apb2) = peripheral_reset_masks(apb1enr | Apb1Peripheral::PWR as u32 | Apb1Peripheral::BKP as u32 | 1 << 9, apb2enr | 1 << 1);
+        assert_eq!((apb1, apb2), (apb1enr, apb2enr), "不应复位PWR、BKP和保留位");
+        
+        let (apb1, apb2) = peripheral_reset_masks(0xFFFF_FFFF, 0xFFFF_FFFF);
+        assert_eq!(apb1 & Apb1Peripheral::DAC as u32, Apb1Peripheral::DAC as u32, "DAC应可复位");
+        assert_eq!(apb1 & Apb1Peripheral::TIM14 as u32, Apb1Peripheral::TIM14 as u32, "TIM14应可复位");
+        assert_eq!(apb2 & Apb2Peripheral::TIM11 as u32, Apb2Peripheral::TIM11 as u32, "TIM11应可复位");
+        assert_eq!(apb2 & Apb2Peripheral::AFIO as u32, Apb2Peripheral::AFIO as u32, "AFIO应可复位");
+    }
+    
+    /// 测试时钟配置的频率计算和限制检查
+    #[test]
+    fn test_clock_config() {
+        let config = ClockConfig::hse_72mhz();
+        let clocks = config.clocks(8_000_000);
+        assert_eq!(clocks.sysclk_frequency, 72_000_000, "8MHz HSE 9倍频应为72MHz");
+        assert_eq!(clocks.pclk1_frequency, 36_000_000, "APB1 2分频应为36MHz");
+        assert_eq!(clocks.pclk2_frequency, 72_000_000, "APB2不分频应为72MHz");
+        assert_eq!(config.validate(8_000_000), Ok(()), "72MHz配置应有效");
+        
+        assert_eq!(ClockConfig::hsi_8mhz().sysclk_frequency(0), 8_000_000, "HSI配置应为8MHz");
+        assert_eq!(ClockConfig::hsi_24mhz().sysclk_frequency(0), 24_000_000, "HSI/2 6倍频应为24MHz");
+        assert_eq!(ClockConfig::hsi_24mhz().validate(0), Ok(()), "24MHz配置应有效");
+        
+        let mut config = ClockConfig::hse_72mhz();
+        config.apb1_prescaler = RccApb1Prescaler::Div1;
+        assert_eq!(config.validate(8_000_000), Err(RccError::Pclk1TooHigh), "APB1为72MHz时应报错");
+        config.pll_mul = RccPllMul::Mul16;
+        assert_eq!(config.validate(8_000_000), Err(RccError::SysclkTooHigh), "128MHz应报错");
+        
+        let mut config = ClockConfig::hse_72mhz();
+        config.ahb_prescaler = RccAhbPrescaler::Div64;
+        assert_eq!(config.clocks(8_000_000).hclk_frequency, 1_125_000, "AHB 64分频计算错误");
+        
+        assert_eq!(FlashLatency::for_sysclk(24_000_000), FlashLatency::Latency0, "24MHz应为0等待周期");
+        assert_eq!(FlashLatency::for_sysclk(36_000_000), FlashLatency::Latency1, "36MHz应为1等待周期");
+        assert_eq!(FlashLatency::for_sysclk(72_000_000), FlashLatency::Latency2, "72MHz应为2等待周期");
+    }
+    
+    /// 测试ADC预分频选择
+    #[test]
+    fn test_adc_prescaler_for_pclk2() {
+        assert_eq!(AdcPrescaler::for_pclk2(72_000_000), AdcPrescaler::Div6, "72MHz应6分频得到12MHz");
+        assert_eq!(AdcPrescaler::for_pclk2(56_000_000), AdcPrescaler::Div4, "56MHz应4分频得到14MHz");
+        assert_eq!(AdcPrescaler::for_pclk2(8_000_000), AdcPrescaler::Div2, "8MHz应2分频");
+        assert_eq!(AdcPrescaler::Div6.divider(), 6, "分频系数错误");
+        assert_eq!(AdcPrescaler::Div8.divider(), 8, "分频系数错误");
+        assert_eq!(AdcPrescaler::from_divider(6), Some(AdcPrescaler::Div6), "6应对应6分频");
+        assert_eq!(AdcPrescaler::from_divider(3), None, "不支持的分频系数应返回None");
+    }
+    
+    /// 测试备份域复位时BDRST的置位和清除
+    #[test]
+    fn test_backup_reset_bdcr() {
+        // LSEON、RTCSEL=LSE、RTCEN
+        let bdcr = 0x0000_8101;
+        let (assert, release) = backup_reset_bdcr(bdcr);
+        assert_eq!(assert, 0x0001_8101, "第一次写入应置位BDRST");
+        assert_eq!(release, bdcr, "第二次写入应清除BDRST");
+        assert_eq!(backup_reset_bdcr(BDCR_BDRST).1, 0, "已置位的BDRST应被清除");
+    }
+}
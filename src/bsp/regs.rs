@@ -0,0 +1,155 @@
+//! 寄存器访问模块
+//! 提供可替换的寄存器读写接口，以及用于主机测试的带故障注入的模拟寄存器
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use core::cell::RefCell;
+use heapless::{LinearMap, Vec};
+
+/// 可同时生效的故障注入数量
+pub const MAX_FAULTS: usize = 8;
+
+/// 寄存器读写接口
+/// 
+/// 驱动中需要在主机上测试的寄存器交互通过此接口访问，
+/// 目标硬件上使用`Mmio`，主机测试中使用`MockRegisters`
+pub trait RegisterAccess {
+    /// 读取寄存器
+    /// # Safety
+    /// - `Mmio`实现会直接按地址读取，调用者必须确保地址有效
+    unsafe fn read(&self, addr: u32) -> u32;
+    
+    /// 写入寄存器
+    /// # Safety
+    /// - `Mmio`实现会直接按地址写入，调用者必须确保地址有效且写入不会破坏其他外设状态
+    unsafe fn write(&self, addr: u32, value: u32);
+    
+    /// 读-改-写寄存器
+    /// # Safety
+    /// - 同`read`和`write`
+    unsafe fn modify(&self, addr: u32, clear: u32, set: u32) {
+        let value = self.read(addr);
+        self.write(addr, (value & !clear) | set);
+    }
+}
+
+/// 内存映射寄存器访问（目标硬件）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mmio;
+
+impl RegisterAccess for Mmio {
+    unsafe fn read(&self, addr: u32) -> u32 {
+        (addr as *const u32).read_volatile()
+    }
+    
+    unsafe fn write(&self, addr: u32, value: u32) {
+        (addr as *mut u32).write_volatile(value)
+    }
+}
+
+/// 注入的故障类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    StuckLow { addr: u32, mask: u32 },     // 读取时指定位恒为0（标志永不置位）
+    StuckHigh { addr: u32, mask: u32 },    // 读取时指定位恒为1（标志永不清除）
+    DropWrite { addr: u32 },               // 对该地址的写入被丢弃
+}
+
+/// 带故障注入的模拟寄存器
+/// 
+/// 未写入过的地址读数为0；注入的故障在读取或写入时生效，用于在主机测试中
+/// 驱动超时、错误标志等难以在硬件上复现的分支。
+pub struct MockRegisters<const N: usize> {
+    regs: RefCell<LinearMap<u32, u32, N>>,
+    faults: RefCell<Vec<Fault, MAX_FAULTS>>,
+}
+
+impl<const N: usize> MockRegisters<N> {
+    /// 创建空的模拟寄存器
+    pub const fn new() -> Self {
+        Self {
+            regs: RefCell::new(LinearMap::new()),
+            faults: RefCell::new(Vec::new()),
+        }
+    }
+    
+    /// 直接设置寄存器值，不受故障影响
+    pub fn set(&self, addr: u32, value: u32) {
+        let mut regs = self.regs.borrow_mut();
+        assert!(regs.insert(addr, value).is_ok(), "Mock register map is full");
+    }
+    
+    /// 直接读取寄存器的存储值，不受故障影响
+    pub fn get(&self, addr: u32) -> u32 {
+        self.regs.borrow().get(&addr).copied().unwrap_or(0)
+    }
+    
+    /// 注入故障
+    pub fn inject(&self, fault: Fault) {
+        assert!(self.faults.borrow_mut().push(fault).is_ok(), "Too many injected faults");
+    }
+    
+    /// 清除所有故障
+    pub fn clear_faults(&self) {
+        self.faults.borrow_mut().clear();
+    }
+}
+
+impl<const N: usize> Default for MockRegisters<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RegisterAccess for MockRegisters<N> {
+    unsafe fn read(&self, addr: u32) -> u32 {
+        let mut value = self.get(addr);
+        for fault in self.faults.borrow().iter() {
+            match *fault {
+                Fault::StuckLow { addr: a, mask } if a == addr => value &= !mask,
+                Fault::StuckHigh { addr: a, mask } if a == addr => value |= mask,
+                _ => {},
+            }
+        }
+        value
+    }
+    
+    unsafe fn write(&self, addr: u32, value: u32) {
+        let dropped = self.faults.borrow().contains(&Fault::DropWrite { addr });
+        if !dropped {
+            self.set(addr, value);
+        }
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试故障注入对读写的影响
+    #[test]
+    fn test_fault_injection() {
+        let regs: MockRegisters<4> = MockRegisters::new();
+        regs.set(0x10, 0x0000_00F0);
+        
+        regs.inject(Fault::StuckLow { addr: 0x10, mask: 0x10 });
+        regs.inject(Fault::StuckHigh { addr: 0x10, mask: 0x01 });
+        regs.inject(Fault::DropWrite { addr: 0x20 });
+        
+        unsafe {
+            assert_eq!(regs.read(0x10), 0x0000_00E1, "读取时应应用固定位故障");
+            regs.write(0x20, 0x1234);
+            assert_eq!(regs.read(0x20), 0, "被丢弃的写入不应生效");
+            regs.modify(0x24, 0, 0x8);
+            assert_eq!(regs.read(0x24), 0x8, "其他地址的写入应正常生效");
+        }
+        assert_eq!(regs.get(0x10), 0x0000_00F0, "故障不应改变存储值");
+        
+        regs.clear_faults();
+        unsafe {
+            assert_eq!(regs.read(0x10), 0x0000_00F0, "清除故障后应读到存储值");
+        }
+    }
+}
@@ -5,8 +5,40 @@
 
 // 导入内部生成的设备驱动库
 use library::*;
+use crate::bsp::rcc::{RccDriver, RtcClockSource};
+
+/// RCC_BDCR位定义
+const BDCR_LSEON: u32 = 1 << 0;
+const BDCR_LSERDY: u32 = 1 << 1;
+const BDCR_RTCSEL_SHIFT: u32 = 8;
+const BDCR_RTCSEL_MASK: u32 = 0b11 << BDCR_RTCSEL_SHIFT;
+const BDCR_RTCEN: u32 = 1 << 15;
+const BDCR_BDRST: u32 = 1 << 16;
+
+/// 一天的秒数
+const SECONDS_PER_DAY: u32 = 86_400;
+
+/// 日期时间（UTC）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateTime {
+    pub year: u16,      // 年（1970~2106）
+    pub month: u8,      // 月（1~12）
+    pub day: u8,        // 日（1~31）
+    pub hour: u8,       // 时（0~23）
+    pub minute: u8,     // 分（0~59）
+    pub second: u8,     // 秒（0~59）
+}
+
+/// RTC错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RtcError {
+    InvalidDateTime, // 日期时间字段无效，或超出32位计数器可表示的范围
+}
 
 /// RTC结构体
+/// 
+/// STM32F1的RTC只有一个32位秒计数器，这里把计数器作为Unix时间戳使用，
+/// 日期换算由`to_datetime`/`from_datetime`完成
 pub struct Rtc;
 
 impl Rtc {
@@ -30,8 +62,18 @@ impl Rtc {
         &mut *(0x40007000 as *mut library::pwr::RegisterBlock)
     }
     
-    /// 初始化RTC
-    pub unsafe fn init(&self, prescaler: u32) {
+    /// 初始化RTC，计数器频率为1Hz
+    /// 
+    /// RTC位于备份域，VBAT供电时复位后仍继续计数。时钟源已按`source`配置并运行时
+    /// 只同步寄存器，不修改计数器；否则复位备份域（RTCSEL只能在备份域复位后修改，
+    /// 会清除备份寄存器），启动时钟源并设置预分频。
+    /// 
+    /// # 参数
+    /// * `source` - RTC时钟源，使用HSE/128时HSE必须已启动
+    /// 
+    /// # Safety
+    /// - 使用LSE时调用者必须确保已焊接32.768kHz晶振，否则会一直等待LSE就绪
+    pub unsafe fn init(&self, source: RtcClockSource) {
         let rcc = self.rcc();
         let pwr = self.pwr();
         
@@ -46,54 +88,71 @@ impl Rtc {
             .dbp().set_bit()
         );
         
-        // 重置备份域
-        rcc.bdcr().modify(|_, w: &mut library::rcc::bdcr::W| w
-            .bdrst().set_bit()
-        );
-        rcc.bdcr().modify(|_, w: &mut library::rcc::bdcr::W| w
-            .bdrst().clear_bit()
-        );
-        
-        // 启用LSE振荡器
-        rcc.bdcr().modify(|_, w: &mut library::rcc::bdcr::W| w
-            .lseon().set_bit()
-        );
+        // LSI不属于备份域，每次复位后都需要重新启动
+        if source == RtcClockSource::LSI {
+            RccDriver::new().enable_lsi();
+        }
         
-        // 等待LSE就绪
-        while rcc.bdcr().read().lserdy().bit_is_clear() {
-            core::hint::spin_loop();
+        let bdcr = rcc.bdcr().read().bits();
+        if Self::is_clock_configured(bdcr, source) {
+            self.wait_for_sync();
+            return;
         }
         
-        // 选择LSE作为RTC时钟源
-        rcc.bdcr().modify(|_, w: &mut library::rcc::bdcr::W| w
-            .rtcsel().bits(0b10)
-        );
+        // 已选择过其他时钟源时，必须复位备份域才能修改RTCSEL
+        if bdcr & BDCR_RTCSEL_MASK != 0 {
+            rcc.bdcr().write(|w: &mut library::rcc::bdcr::W| unsafe { w.bits(BDCR_BDRST) });
+            rcc.bdcr().write(|w: &mut library::rcc::bdcr::W| unsafe { w.bits(0) });
+        }
         
-        // 启用RTC时钟
-        rcc.bdcr().modify(|_, w: &mut library::rcc::bdcr::W| w
-            .rtcen().set_bit()
-        );
+        if source == RtcClockSource::LSE {
+            rcc.bdcr().modify(|r, w| unsafe { w.bits(r.bits() | BDCR_LSEON) });
+            while rcc.bdcr().read().bits() & BDCR_LSERDY == 0 {
+                core::hint::spin_loop();
+            }
+        }
         
-        // 进入配置模式
-        self.enter_config_mode();
+        // 选择时钟源并启用RTC时钟
+        rcc.bdcr().modify(|r, w| unsafe {
+            w.bits((r.bits() & !BDCR_RTCSEL_MASK) | (source.rtcsel() << BDCR_RTCSEL_SHIFT) | BDCR_RTCEN)
+        });
+        self.wait_for_sync();
         
-        // 设置预分频值
+        // 设置预分频值，RTC_CNT每秒加1
+        let prescaler = Self::prescaler(source.frequency(RccDriver::new().get_hse_frequency()));
         let rtc = self.rtc();
+        self.enter_config_mode();
         rtc.prlh().write(|w: &mut library::rtc::prlh::W| unsafe { w.bits((prescaler >> 16) & 0x0F) });
         rtc.prll().write(|w: &mut library::rtc::prll::W| unsafe { w.bits(prescaler & 0xFFFF) });
-        
-        // 退出配置模式
         self.exit_config_mode();
-        
-        // 等待RTC寄存器同步
-        self.wait_for_sync();
-        
-        // 等待RTC寄存器写入操作完成
-        self.wait_for_last_task();
+    }
+    
+    /// 检查备份域中的RTC时钟是否已按指定时钟源配置并启用
+    /// 
+    /// # 参数
+    /// * `bdcr` - RCC_BDCR寄存器值
+    /// * `source` - 期望的时钟源
+    pub const fn is_clock_configured(bdcr: u32, source: RtcClockSource) -> bool {
+        bdcr & BDCR_RTCEN != 0 && (bdcr & BDCR_RTCSEL_MASK) >> BDCR_RTCSEL_SHIFT == source.rtcsel()
+    }
+    
+    /// 计算1Hz计数所需的预分频值（RTC_PRL）
+    /// 
+    /// # 参数
+    /// * `rtc_clk` - RTC时钟频率（Hz）
+    pub const fn prescaler(rtc_clk: u32) -> u32 {
+        assert!(rtc_clk >= 1 && rtc_clk <= 0x10_0000, "RTC clock must fit the 20-bit prescaler");
+        rtc_clk - 1
     }
     
     /// 进入配置模式
+    /// 
+    /// 先等待上一次写操作完成（RTOFF=1），再置位CNF
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn enter_config_mode(&self) {
+        self.wait_for_last_task();
         let rtc = self.rtc();
         rtc.crl().modify(|_, w: &mut library::rtc::crl::W| w
             .cnf().set_bit()
@@ -105,14 +164,25 @@ impl Rtc {
     }
     
     /// 退出配置模式
+    /// 
+    /// 清除CNF后写入才真正开始，等待RTOFF=1表示写入完成
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn exit_config_mode(&self) {
         let rtc = self.rtc();
         rtc.crl().modify(|_, w: &mut library::rtc::crl::W| w
             .cnf().clear_bit()
         );
+        self.wait_for_last_task();
     }
     
     /// 等待RTC寄存器同步
+    /// 
+    /// 系统复位或APB1时钟停止（如STOP模式）后，读取RTC寄存器前必须调用
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn wait_for_sync(&self) {
         let rtc = self.rtc();
         rtc.crl().modify(|_, w: &mut library::rtc::crl::W| w
@@ -124,6 +194,9 @@ impl Rtc {
     }
     
     /// 等待RTC寄存器写入操作完成
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn wait_for_last_task(&self) {
         let rtc = self.rtc();
         // rtoff是只读字段，由硬件自动设置
@@ -134,6 +207,9 @@ impl Rtc {
     }
     
     /// 设置RTC计数器值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn set_counter(&self, counter: u32) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -142,21 +218,69 @@ impl Rtc {
         rtc.cntl().write(|w: &mut library::rtc::cntl::W| unsafe { w.bits(counter & 0xFFFF) });
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 获取RTC计数器值
+    /// 
+    /// 两个半字分别读取，高半字在两次读取之间变化时重新读取低半字
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
     pub unsafe fn get_counter(&self) -> u32 {
-        self.wait_for_sync();
-        
         let rtc = self.rtc();
-        let cnth = rtc.cnth().read().bits();
-        let cntl = rtc.cntl().read().bits();
+        let mut cnth = rtc.cnth().read().bits();
+        let mut cntl = rtc.cntl().read().bits();
+        let cnth_again = rtc.cnth().read().bits();
+        if cnth_again != cnth {
+            cnth = cnth_again;
+            cntl = rtc.cntl().read().bits();
+        }
         
-        ((cnth as u32) << 16) | (cntl as u32)
+        (cnth << 16) | cntl
+    }
+    
+    /// 设置Unix时间戳（秒）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
+    pub unsafe fn set_unix_time(&self, timestamp: u32) {
+        self.set_counter(timestamp);
+    }
+    
+    /// 获取Unix时间戳（秒）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn get_unix_time(&self) -> u32 {
+        self.get_counter()
+    }
+    
+    /// 设置日期时间
+    /// 
+    /// # 返回值
+    /// 日期时间无效时返回`InvalidDateTime`，不修改计数器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
+    pub unsafe fn set_datetime(&self, datetime: &DateTime) -> Result<(), RtcError> {
+        self.set_counter(from_datetime(datetime)?);
+        Ok(())
+    }
+    
+    /// 获取当前日期时间
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn get_datetime(&self) -> DateTime {
+        to_datetime(self.get_counter())
     }
     
     /// 设置RTC闹钟值
+    /// 
+    /// 计数器到达该值时置位闹钟标志（`get_alarm_flag`），启用闹钟中断时产生RTC/RTCAlarm中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn set_alarm(&self, alarm: u32) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -165,10 +289,12 @@ impl Rtc {
         rtc.alrl().write(|w: &mut library::rtc::alrl::W| unsafe { w.bits(alarm & 0xFFFF) });
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 获取RTC闹钟值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
     pub unsafe fn get_alarm(&self) -> u32 {
         // 注意：ALRH和ALRL是只写寄存器，不能读取
         // 这个方法实际上无法获取当前闹钟值，返回0作为占位
@@ -176,6 +302,9 @@ impl Rtc {
     }
     
     /// 启用RTC秒中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn enable_second_interrupt(&self) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -185,10 +314,12 @@ impl Rtc {
         );
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 禁用RTC秒中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn disable_second_interrupt(&self) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -198,10 +329,12 @@ impl Rtc {
         );
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 启用RTC闹钟中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn enable_alarm_interrupt(&self) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -211,10 +344,12 @@ impl Rtc {
         );
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 禁用RTC闹钟中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn disable_alarm_interrupt(&self) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -224,10 +359,12 @@ impl Rtc {
         );
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 启用RTC溢出中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn enable_overflow_interrupt(&self) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -237,10 +374,12 @@ impl Rtc {
         );
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 禁用RTC溢出中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn disable_overflow_interrupt(&self) {
         let rtc = self.rtc();
         self.enter_config_mode();
@@ -250,10 +389,12 @@ impl Rtc {
         );
         
         self.exit_config_mode();
-        self.wait_for_last_task();
     }
     
     /// 清除RTC秒中断标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn clear_second_flag(&self) {
         let rtc = self.rtc();
         rtc.crl().modify(|_, w: &mut library::rtc::crl::W| w
@@ -262,6 +403,9 @@ impl Rtc {
     }
     
     /// 清除RTC闹钟中断标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn clear_alarm_flag(&self) {
         let rtc = self.rtc();
         rtc.crl().modify(|_, w: &mut library::rtc::crl::W| w
@@ -270,6 +414,9 @@ impl Rtc {
     }
     
     /// 清除RTC溢出中断标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init`，且RTC寄存器未被其他代码同时访问
     pub unsafe fn clear_overflow_flag(&self) {
         let rtc = self.rtc();
         rtc.crl().modify(|_, w: &mut library::rtc::crl::W| w
@@ -278,23 +425,182 @@ impl Rtc {
     }
     
     /// 检查RTC秒中断标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_second_flag(&self) -> bool {
         let rtc = self.rtc();
         rtc.crl().read().secf().bit_is_set()
     }
     
     /// 检查RTC闹钟中断标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_alarm_flag(&self) -> bool {
         let rtc = self.rtc();
         rtc.crl().read().alrf().bit_is_set()
     }
     
     /// 检查RTC溢出中断标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_overflow_flag(&self) -> bool {
         let rtc = self.rtc();
         rtc.crl().read().owf().bit_is_set()
     }
 }
 
+impl Default for Rtc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 预定义的RTC实例
 pub const RTC: Rtc = Rtc::new();
+
+/// 由Unix时间戳计算日期时间（UTC）
+pub const fn to_datetime(timestamp: u32) -> DateTime {
+    let days = timestamp / SECONDS_PER_DAY;
+    let secs = timestamp % SECONDS_PER_DAY;
+    
+    // 以0000-03-01为起点按400年周期换算，闰日位于每年末尾
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    
+    DateTime {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+        hour: (secs / 3600) as u8,
+        minute: (secs / 60 % 60) as u8,
+        second: (secs % 60) as u8,
+    }
+}
+
+/// 是否为闰年
+const fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// 指定月份的天数，月份无效时返回0
+const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 => if is_leap_year(year) { 29 } else { 28 },
+        _ => 0,
+    }
+}
+
+/// 由日期时间（UTC）计算Unix时间戳
+/// 
+/// # 返回值
+/// 日期必须在1970-01-01 00:00:00到2106-02-07 06:28:15之间，且各字段有效，
+/// 否则返回`InvalidDateTime`
+pub const fn from_datetime(datetime: &DateTime) -> Result<u32, RtcError> {
+    if datetime.year < 1970
+        || datetime.day < 1
+        || datetime.day > days_in_month(datetime.year, datetime.month)
+        || datetime.hour > 23
+        || datetime.minute > 59
+        || datetime.second > 59
+    {
+        return Err(RtcError::InvalidDateTime);
+    }
+    
+    let month = datetime.month as u64;
+    let year = datetime.year as u64 - if month <= 2 { 1 } else { 0 };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + datetime.day as u64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    
+    let secs = days * SECONDS_PER_DAY as u64
+        + datetime.hour as u64 * 3600
+        + datetime.minute as u64 * 60
+        + datetime.second as u64;
+    if secs > u32::MAX as u64 {
+        return Err(RtcError::InvalidDateTime);
+    }
+    Ok(secs as u32)
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 构造日期时间
+    const fn datetime(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        DateTime { year, month, day, hour, minute, second }
+    }
+    
+    /// 测试Unix时间戳与日期时间的相互换算
+    #[test]
+    fn test_datetime_conversion() {
+        let cases = [
+            (0, datetime(1970, 1, 1, 0, 0, 0)),
+            (951_782_400, datetime(2000, 2, 29, 0, 0, 0)),
+            (951_955_199, datetime(2000, 3, 1, 23, 59, 59)),
+            (1_700_000_000, datetime(2023, 11, 14, 22, 13, 20)),
+            (1_709_251_199, datetime(2024, 2, 29, 23, 59, 59)),
+            (4_102_444_800, datetime(2100, 1, 1, 0, 0, 0)),
+            (u32::MAX, datetime(2106, 2, 7, 6, 28, 15)),
+        ];
+        for (timestamp, expected) in cases {
+            assert_eq!(to_datetime(timestamp), expected, "时间戳{}换算日期错误", timestamp);
+            assert_eq!(from_datetime(&expected), Ok(timestamp), "日期{:?}换算时间戳错误", expected);
+        }
+        
+        // 2100年不是闰年，2月28日之后是3月1日
+        assert_eq!(to_datetime(4_107_542_400), datetime(2100, 3, 1, 0, 0, 0), "2100年不应有2月29日");
+    }
+    
+    /// 测试无效日期时间返回错误而不是换算出错误的时间戳
+    #[test]
+    fn test_invalid_datetime() {
+        let invalid = [
+            datetime(1969, 12, 31, 23, 59, 59),
+            datetime(2024, 0, 1, 0, 0, 0),
+            datetime(2024, 13, 1, 0, 0, 0),
+            datetime(2024, 1, 0, 0, 0, 0),
+            datetime(2024, 4, 31, 0, 0, 0),
+            datetime(2023, 2, 29, 0, 0, 0),
+            datetime(2100, 2, 29, 0, 0, 0),
+            datetime(2024, 1, 1, 24, 0, 0),
+            datetime(2024, 1, 1, 0, 60, 0),
+            datetime(2024, 1, 1, 0, 0, 60),
+            datetime(2106, 2, 7, 6, 28, 16),
+        ];
+        for datetime in invalid {
+            assert_eq!(from_datetime(&datetime), Err(RtcError::InvalidDateTime), "日期{:?}应被拒绝", datetime);
+        }
+        assert_eq!(from_datetime(&datetime(2000, 2, 29, 0, 0, 0)), Ok(951_782_400), "2000年是闰年，应允许2月29日");
+    }
+    
+    /// 测试RTC时钟源配置检查和预分频
+    #[test]
+    fn test_rtc_clock_config() {
+        let lse_running = BDCR_LSEON | BDCR_LSERDY | (0b01 << BDCR_RTCSEL_SHIFT) | BDCR_RTCEN;
+        assert!(Rtc::is_clock_configured(lse_running, RtcClockSource::LSE), "LSE已运行时不应重新配置");
+        assert!(!Rtc::is_clock_configured(lse_running, RtcClockSource::LSI), "时钟源不同时应重新配置");
+        assert!(!Rtc::is_clock_configured(lse_running & !BDCR_RTCEN, RtcClockSource::LSE), "RTC时钟未启用时应重新配置");
+        assert!(!Rtc::is_clock_configured(0, RtcClockSource::LSE), "备份域复位后应重新配置");
+        
+        assert_eq!(Rtc::prescaler(RtcClockSource::LSE.frequency(8_000_000)), 32_767, "LSE预分频应为32767");
+        assert_eq!(Rtc::prescaler(RtcClockSource::LSI.frequency(8_000_000)), 39_999, "LSI预分频应为39999");
+        assert_eq!(Rtc::prescaler(RtcClockSource::HseDiv128.frequency(8_000_000)), 62_499, "HSE/128预分频应为62499");
+    }
+}
@@ -0,0 +1,140 @@
+//! 自检模块
+//! 提供上电自检（POST）辅助函数，用于在启动时确认ADC、定时器和GPIO工作正常
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::adc::{Adc, AdcChannel, AdcSampleTime};
+use crate::bsp::timer::Timer;
+use crate::bsp::gpio::{GpioPortStruct, GpioPortBatch};
+use crate::bsp::delay::delay_us;
+
+/// VREFINT原始值下限
+/// 
+/// VREFINT为1.16V~1.24V，按VDDA = 3.3V ± 10%计算：1.16V / 3.63V × 4095 ≈ 1308
+pub const VREFINT_MIN_RAW: u16 = 1300;
+
+/// VREFINT原始值上限：1.24V / 2.97V × 4095 ≈ 1710
+pub const VREFINT_MAX_RAW: u16 = 1710;
+
+/// 定时器自检时读取计数值的最大次数
+pub const TIMER_SELFTEST_POLLS: u32 = 1000;
+
+/// ADC自检：读取内部参考电压VREFINT并检查是否在合理范围内
+/// 
+/// 会启用温度传感器和VREFINT（TSVREFE），VREFINT要求采样时间不少于17.1us，
+/// 因此使用最长的239.5周期采样时间。
+/// 
+/// # 参数
+/// * `adc` - ADC实例，必须为ADC1（VREFINT只连接到ADC1）
+/// 
+/// # 返回值
+/// VREFINT读数在`VREFINT_MIN_RAW`~`VREFINT_MAX_RAW`之间返回true
+/// 
+/// # Safety
+/// - 调用者必须确保ADC1已初始化和校准，且SysTick已初始化（`delay::init_systick`）
+pub unsafe fn adc_selftest(adc: &Adc) -> bool {
+    adc.temp_sensor_vrefint_cmd(true);
+    // 等待VREFINT稳定（tSTART最大10us）
+    delay_us(10);
+    
+    adc.regular_channel_config(AdcChannel::Channel17, 1, AdcSampleTime::Cycles239_5);
+    adc.software_start_conv_cmd(true);
+    while !adc.is_conversion_complete() {
+        core::hint::spin_loop();
+    }
+    
+    vrefint_in_range(adc.read_result())
+}
+
+/// 检查VREFINT原始读数是否在合理范围内
+pub const fn vrefint_in_range(raw: u16) -> bool {
+    raw >= VREFINT_MIN_RAW && raw <= VREFINT_MAX_RAW
+}
+
+/// 定时器自检：启动定时器并检查计数器是否前进
+/// 
+/// 自检结束后定时器保持停止状态。
+/// 
+/// # 参数
+/// * `timer` - 定时器实例，应已用`init`配置预分频和周期
+/// 
+/// # 返回值
+/// 在`TIMER_SELFTEST_POLLS`次读取内计数值发生变化返回true
+/// 
+/// # Safety
+/// - 调用者必须确保定时器已初始化，且未被其他代码同时使用
+pub unsafe fn timer_selftest(timer: &Timer) -> bool {
+    timer.set_count(0);
+    timer.start();
+    let advanced = counter_advances(|| timer.get_count(), TIMER_SELFTEST_POLLS);
+    timer.stop();
+    advanced
+}
+
+/// 检查计数器在有限次读取内是否前进
+/// 
+/// # 参数
+/// * `read` - 读取计数值的函数
+/// * `polls` - 第一次读取之后最多再读取的次数
+pub fn counter_advances<F: FnMut() -> u16>(mut read: F, polls: u32) -> bool {
+    let start = read();
+    (0..polls).any(|_| read() != start)
+}
+
+/// GPIO回环自检：驱动输出引脚并在连接到一起的输入引脚上读回
+/// 
+/// 依次输出高、低电平，两次读回都与输出一致才算通过。
+/// 自检结束后输出引脚保持低电平。
+/// 
+/// # 参数
+/// * `out_pin` - 输出引脚，配置为推挽输出
+/// * `in_pin` - 输入引脚，配置为浮空输入，需在板上与输出引脚相连
+/// 
+/// # Safety
+/// - 调用者必须确保两个引脚未被其他代码或外设占用，且SysTick已初始化
+pub unsafe fn gpio_loopback_test(out_pin: GpioPortStruct, in_pin: GpioPortStruct) -> bool {
+    out_pin.into_push_pull_output();
+    in_pin.into_floating_input();
+    
+    let read_input = || GpioPortBatch::new(in_pin.port).read_input_data() & (1 << in_pin.pin) != 0;
+    
+    out_pin.set_high();
+    delay_us(10);
+    let high_ok = read_input();
+    
+    out_pin.set_low();
+    delay_us(10);
+    let low_ok = !read_input();
+    
+    high_ok && low_ok
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试VREFINT读数范围检查
+    #[test]
+    fn test_vrefint_bounds() {
+        assert!(vrefint_in_range(1489), "3.3V供电下的典型读数应通过");
+        assert!(vrefint_in_range(VREFINT_MIN_RAW), "下限应包含在范围内");
+        assert!(vrefint_in_range(VREFINT_MAX_RAW), "上限应包含在范围内");
+        assert!(!vrefint_in_range(0), "读数为0说明ADC或通道异常");
+        assert!(!vrefint_in_range(4095), "满量程读数说明ADC或通道异常");
+    }
+    
+    /// 测试计数器前进检查
+    #[test]
+    fn test_timer_advance_check() {
+        let mut count = 0u16;
+        assert!(counter_advances(|| { count += 1; count }, 10), "计数递增时应通过");
+        
+        assert!(!counter_advances(|| 42, 10), "计数不变时应失败");
+        
+        // 在最后一次读取时才变化也应通过
+        let mut reads = 0;
+        assert!(counter_advances(|| { reads += 1; if reads > 10 { 1 } else { 0 } }, 10), "最后一次读取变化也应通过");
+    }
+}
@@ -7,6 +7,10 @@
 use core::fmt;
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::cell::UnsafeCell;
+use crate::bsp::rcc::RccDriver;
+use core::convert::Infallible;
+use embedded_hal::serial;
+use crate::bsp::regs::{RegisterAccess, Mmio};
 
 // 导入内部生成的设备驱动库
 use library::*;
@@ -14,11 +18,11 @@ use library::*;
 /// 串口波特率枚举
 #[derive(Debug, Clone, Copy)]
 pub enum BaudRate {
-    B9600,
-    B19200,
-    B38400,
-    B57600,
-    B115200,
+    B9600 = 9600,
+    B19200 = 19200,
+    B38400 = 38400,
+    B57600 = 57600,
+    B115200 = 115200,
 }
 
 /// 串口枚举
@@ -123,6 +127,36 @@ pub enum WakeUpMode {
 /// 串口接收缓冲区大小
 const RX_BUFFER_SIZE: usize = 256;
 
+/// USART寄存器偏移
+const USART_SR: u32 = 0x00;     // 状态寄存器
+const USART_DR: u32 = 0x04;     // 数据寄存器
+const USART_CR1: u32 = 0x0C;    // 控制寄存器1
+
+/// USART_CR1帧格式位
+const CR1_PCE: u32 = 1 << 10;   // 校验使能
+const CR1_M: u32 = 1 << 12;     // 字长（0：8位，1：9位，均包含校验位）
+
+/// USART_SR状态位
+const SR_PE: u32 = 1 << 0;      // 校验错误
+const SR_FE: u32 = 1 << 1;      // 帧错误
+const SR_NE: u32 = 1 << 2;      // 噪声错误
+const SR_ORE: u32 = 1 << 3;     // 接收溢出
+const SR_RXNE: u32 = 1 << 5;    // 接收数据寄存器非空
+const SR_ERRORS: u32 = SR_PE | SR_FE | SR_NE | SR_ORE;
+
+/// 各串口的软件回环模式标志
+static LOOPBACK_ENABLED: [AtomicBool; 3] = [AtomicBool::new(false), AtomicBool::new(false), AtomicBool::new(false)];
+
+/// 串口错误类型枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SerialError {
+    Overrun,    // 接收溢出（ORE）
+    Noise,      // 噪声错误（NE）
+    Framing,    // 帧错误（FE）
+    Parity,     // 校验错误（PE）
+    UnachievableBaud,   // 当前外设时钟下无法以2%以内的误差得到请求的波特率
+}
+
 /// 串口接收缓冲区
 pub struct RxBuffer {
     buffer: UnsafeCell<[u8; RX_BUFFER_SIZE]>,
@@ -288,6 +322,15 @@ pub struct Serial {
 }
 
 impl SerialPort {
+    /// 获取串口寄存器基地址
+    const fn base_address(&self) -> u32 {
+        match self {
+            SerialPort::USART1 => 0x4001_3800,
+            SerialPort::USART2 => 0x4000_4400,
+            SerialPort::USART3 => 0x4000_4800,
+        }
+    }
+    
     /// 获取串口寄存器
     fn get_usart(&self) -> &'static mut Usart1 {
         match self {
@@ -310,6 +353,15 @@ impl SerialPort {
     fn clock_reg(&self) -> &'static mut Rcc {
         unsafe { &mut *(0x40021000 as *mut Rcc) }
     }
+    
+    /// 获取软件回环标志
+    fn loopback_flag(&self) -> &'static AtomicBool {
+        match self {
+            SerialPort::USART1 => &LOOPBACK_ENABLED[0],
+            SerialPort::USART2 => &LOOPBACK_ENABLED[1],
+            SerialPort::USART3 => &LOOPBACK_ENABLED[2],
+        }
+    }
 }
 
 impl Serial {
@@ -334,25 +386,98 @@ impl Serial {
         self.port.get_usart()
     }
     
-    /// 获取波特率寄存器值
-    fn baud_rate_value(&self, baud: BaudRate) -> u32 {
-        // 获取串口时钟频率
-        // USART1挂载在APB2上，时钟频率为72MHz
-        // USART2和USART3挂载在APB1上，时钟频率为36MHz
-        let fck = match self.port {
-            SerialPort::USART1 => 72_000_000,
-            SerialPort::USART2 | SerialPort::USART3 => 36_000_000,
+    /// 计算波特率寄存器值
+    /// 
+    /// BRR = fck / baud四舍五入，即USARTDIV × 16，高12位为整数部分、低4位为小数部分，
+    /// 小数部分进位时自动进入整数部分
+    /// 
+    /// # 参数
+    /// * `fck` - 串口外设时钟频率，单位Hz
+    /// * `baud` - 波特率
+    pub const fn brr_value(fck: u32, baud: u32) -> u32 {
+        (fck + baud / 2) / baud
+    }
+    
+    /// 获取实际配置的波特率相对请求值的误差（千分比）
+    /// 
+    /// 由当前BRR寄存器值和外设时钟计算实际波特率，误差超过±20‰（2%）时通信通常不可靠。
+    /// 
+    /// # 参数
+    /// * `requested` - 请求的波特率
+    /// 
+    /// # 返回值
+    /// 带符号误差，正值表示实际波特率偏高
+    pub fn baud_error_permille(&self, requested: u32) -> i16 {
+        let brr = self.get_usart().brr().read().bits();
+        Self::baud_error_from_brr(self.get_clock_frequency(), brr, requested)
+    }
+    
+    /// 由BRR值计算波特率误差（千分比，四舍五入）
+    pub const fn baud_error_from_brr(fck: u32, brr: u32, requested: u32) -> i16 {
+        if brr == 0 || requested == 0 {
+            return i16::MIN;
+        }
+        // 实际波特率 = fck / BRR，误差 = (fck - requested × BRR) / (requested × BRR)
+        let actual = fck as i64;
+        let target = requested as i64 * brr as i64;
+        let error = (actual - target) * 1000;
+        let permille = if error >= 0 {
+            (error + target / 2) / target
+        } else {
+            (error - target / 2) / target
         };
-        
-        // 精确计算波特率，参考标准库实现
-        let integer_divider = fck / (16 * baud as u32);
-        let fractional_divider = ((fck % (16 * baud as u32)) * 16 + baud as u32 / 2) / baud as u32;
-        
-        (integer_divider << 4) | fractional_divider
+        permille as i16
+    }
+    
+    /// 检查波特率能否实现并计算BRR值
+    /// 
+    /// BRR需在16~0xFFFF之间（USARTDIV ≥ 1），且量化误差不超过±20‰（2%）
+    /// 
+    /// # 参数
+    /// * `fck` - 串口外设时钟频率，单位Hz
+    /// * `baud` - 波特率
+    pub const fn checked_brr(fck: u32, baud: u32) -> Result<u32, SerialError> {
+        if baud == 0 {
+            return Err(SerialError::UnachievableBaud);
+        }
+        let brr = Self::brr_value(fck, baud);
+        if brr < 16 || brr > 0xFFFF {
+            return Err(SerialError::UnachievableBaud);
+        }
+        let error = Self::baud_error_from_brr(fck, brr, baud);
+        if error > 20 || error < -20 {
+            return Err(SerialError::UnachievableBaud);
+        }
+        Ok(brr)
+    }
+    
+    /// 以任意波特率初始化串口（8N1，其余为默认配置）
+    /// 
+    /// 由RCC当前的APB时钟计算BRR（USART1使用PCLK2，USART2/3使用PCLK1），
+    /// 72MHz下USART1可用1200~4.5M波特率。
+    /// 
+    /// # 返回值
+    /// 误差超过2%或超出分频范围时返回`SerialError::UnachievableBaud`，此时不修改任何寄存器
+    pub fn init_baud(&self, baud: u32) -> Result<(), SerialError> {
+        let brr = Self::checked_brr(self.get_clock_frequency(), baud)?;
+        self.configure(SerialConfig::default(), brr);
+        Ok(())
     }
     
     /// 初始化串口
-    pub fn init(&self, config: SerialConfig) {
+    /// 
+    /// 与`init_baud`一样经`checked_brr`计算BRR，`config.baud_rate`只是常用波特率的简写。
+    /// 
+    /// # 返回值
+    /// 当前外设时钟下误差超过2%时返回`SerialError::UnachievableBaud`，此时不修改任何寄存器
+    pub fn init(&self, config: SerialConfig) -> Result<(), SerialError> {
+        let brr = Self::checked_brr(self.get_clock_frequency(), config.baud_rate as u32)?;
+        self.configure(config, brr);
+        Ok(())
+    }
+    
+    /// 按配置和BRR值初始化串口，`config.baud_rate`被忽略
+    fn configure(&self, config: SerialConfig, brr: u32) {
         let rcc = unsafe { &mut *(0x40021000 as *mut Rcc) };
         let usart = self.get_usart();
         
@@ -372,14 +497,6 @@ impl Serial {
         }
         
         // 2. 配置波特率
-        let brr = match config.baud_rate {
-            BaudRate::B9600 => self.baud_rate_value(BaudRate::B9600),
-            BaudRate::B19200 => self.baud_rate_value(BaudRate::B19200),
-            BaudRate::B38400 => self.baud_rate_value(BaudRate::B38400),
-            BaudRate::B57600 => self.baud_rate_value(BaudRate::B57600),
-            BaudRate::B115200 => self.baud_rate_value(BaudRate::B115200),
-        };
-        
         unsafe {
             usart.brr().write(|w| w.bits(brr));
         }
@@ -518,8 +635,113 @@ impl Serial {
     }
     
     /// 初始化串口（使用默认配置）
-    pub fn init_default(&self) {
-        self.init(SerialConfig::default());
+    pub fn init_default(&self) -> Result<(), SerialError> {
+        self.init(SerialConfig::default())
+    }
+    
+    /// 按给定波特率和帧格式初始化串口
+    /// 
+    /// 帧格式取`config`中的`word_length`、`parity`、`stop_bits`等字段，`config.baud_rate`被`baud`覆盖。
+    /// 字长包含校验位：8位字长加校验（如8E1中的"8"应配置为`Bits9`）时有效数据只有7位，
+    /// 接收时会去掉校验位，见`data_mask`。
+    /// 
+    /// # 参数
+    /// * `baud` - 波特率
+    /// * `config` - 帧格式等其他配置
+    /// 
+    /// # 返回值
+    /// 波特率无法实现时返回`SerialError::UnachievableBaud`，见`init`
+    pub fn init_with_config(&self, baud: BaudRate, config: SerialConfig) -> Result<(), SerialError> {
+        self.init(SerialConfig {
+            baud_rate: baud,
+            ..config
+        })
+    }
+    
+    /// 根据CR1的字长和校验配置计算接收数据的有效位掩码
+    /// 
+    /// 校验位占用字长的最高位：8位字长加校验为7位数据，9位字长加校验为8位数据，
+    /// 9位字长无校验为9位数据（`read_byte`只能返回低8位）
+    pub const fn data_mask(cr1: u32) -> u16 {
+        match (cr1 & CR1_M != 0, cr1 & CR1_PCE != 0) {
+            (false, false) => 0x0FF,
+            (false, true) => 0x07F,
+            (true, true) => 0x0FF,
+            (true, false) => 0x1FF,
+        }
+    }
+    
+    /// 以中断缓冲接收方式初始化串口
+    /// 
+    /// 清空接收缓冲区，按默认配置（8N1）和给定波特率初始化，并启用接收中断。
+    /// 之后在串口中断服务函数中调用`on_rxne_interrupt`，主循环用`read_buffered`/`available`读取，
+    /// 两次轮询之间到达的字节不会丢失（缓冲区满时丢弃新字节并置位溢出标志）。
+    /// 
+    /// # 参数
+    /// * `baud` - 波特率
+    /// 
+    /// # 返回值
+    /// 波特率无法实现时返回`SerialError::UnachievableBaud`，见`init`
+    pub fn init_buffered(&self, baud: BaudRate) -> Result<(), SerialError> {
+        let buffer = match self.rx_buffer {
+            Some(buffer) => buffer,
+            None => panic!("Buffered receive needs a serial created with new_with_buffer"),
+        };
+        buffer.clear();
+        self.init(SerialConfig {
+            baud_rate: baud,
+            rx_interrupt: true,
+            ..SerialConfig::default()
+        })
+    }
+    
+    /// 接收中断处理，应在串口中断服务函数中调用
+    /// 
+    /// RXNE置位时读取DR并写入接收缓冲区；有噪声、帧或校验错误时丢弃该字节，读DR同时清除错误标志
+    /// 
+    /// # 返回值
+    /// 是否有字节写入缓冲区
+    pub fn on_rxne_interrupt(&self) -> bool {
+        match self.rx_buffer {
+            Some(buffer) => cortex_m::interrupt::free(|_| unsafe {
+                Self::receive_regs(&Mmio, self.port.base_address(), buffer)
+            }),
+            None => {
+                self.handle_rx_interrupt();
+                false
+            }
+        }
+    }
+    
+    /// 从接收缓冲区读取一个字节，缓冲区为空时返回None
+    pub fn read_buffered(&self) -> Option<u8> {
+        let buffer = self.rx_buffer?;
+        cortex_m::interrupt::free(|_| buffer.pop())
+    }
+    
+    /// 获取接收缓冲区中可读取的字节数
+    pub fn available(&self) -> usize {
+        self.buffer_len()
+    }
+    
+    /// 通过寄存器访问接口接收一个字节到缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保`base`为有效的串口寄存器基地址，且在临界区中调用
+    pub unsafe fn receive_regs<R: RegisterAccess>(regs: &R, base: u32, buffer: &RxBuffer) -> bool {
+        let sr = regs.read(base + USART_SR);
+        if sr & (SR_RXNE | SR_ERRORS) == 0 {
+            return false;
+        }
+        
+        // 先读SR再读DR，同时清除RXNE和错误标志；ORE时DR中仍是溢出前的有效字节
+        let dr = regs.read(base + USART_DR);
+        if sr & SR_RXNE == 0 || sr & (SR_PE | SR_FE | SR_NE) != 0 {
+            return false;
+        }
+        let mask = Self::data_mask(regs.read(base + USART_CR1)) as u32;
+        buffer.push((dr & mask) as u8);
+        true
     }
     
     /// 启用串口
@@ -697,8 +919,37 @@ impl Serial {
         }
     }
     
+    /// 设置软件回环模式
+    /// 
+    /// F1的USART没有内部回环功能，这里用软件实现：回环模式下发送的字节不经过硬件，
+    /// 直接写入本串口的接收缓冲区，可以在没有外部连线的情况下测试协议解析代码。
+    /// 
+    /// # 参数
+    /// * `on` - true为启用回环模式
+    pub fn set_loopback(&self, on: bool) {
+        assert!(!on || self.rx_buffer.is_some(), "Loopback mode requires an RX buffer");
+        self.port.loopback_flag().store(on, Ordering::SeqCst);
+    }
+    
+    /// 检查是否处于软件回环模式
+    pub fn is_loopback(&self) -> bool {
+        self.port.loopback_flag().load(Ordering::SeqCst)
+    }
+    
+    /// 回环模式下将发送的字节写入接收缓冲区
+    fn loop_back(&self, byte: u8) {
+        if let Some(buffer) = &self.rx_buffer {
+            buffer.push(byte);
+        }
+    }
+    
     /// 发送一个字节
     pub fn write_byte(&self, byte: u8) {
+        if self.is_loopback() {
+            self.loop_back(byte);
+            return;
+        }
+        
         let usart = self.get_usart();
         
         // 等待发送缓冲区为空
@@ -733,6 +984,10 @@ impl Serial {
     
     /// 接收一个字节
     pub fn read_byte(&self) -> u8 {
+        if self.is_loopback() {
+            return nb::block!(self.read_nb()).unwrap_or(0);
+        }
+        
         let usart = self.get_usart();
         
         // 等待接收数据
@@ -740,9 +995,95 @@ impl Serial {
             core::hint::spin_loop();
         }
         
-        // 读取数据
+        // 读取数据，去掉校验位
+        let mask = Self::data_mask(usart.cr1().read().bits()) as u32;
         unsafe {
-            (usart.dr().read().bits() & 0xFF) as u8
+            (usart.dr().read().bits() & mask) as u8
+        }
+    }
+    
+    /// 接收一个字节并检查接收错误（阻塞式）
+    /// 
+    /// 等待RXNE或错误标志置位，按先读SR再读DR的顺序清除标志。
+    /// 线路断开或干扰时会收到帧错误/噪声错误，应用可据此丢弃数据而不是处理错误字节。
+    /// 
+    /// # 返回值
+    /// 成功时返回数据；有错误时返回`Overrun`、`Framing`、`Noise`或`Parity`，该字节已被丢弃
+    pub fn read_byte_checked(&self) -> Result<u8, SerialError> {
+        nb::block!(self.read_nb())
+    }
+    
+    /// 非阻塞接收一个字节
+    /// 
+    /// # 返回值
+    /// 无数据时返回`WouldBlock`；有接收错误时读取DR清除错误标志并返回对应错误
+    pub fn read_nb(&self) -> nb::Result<u8, SerialError> {
+        if self.is_loopback() {
+            return self.read_from_buffer().ok_or(nb::Error::WouldBlock);
+        }
+        
+        unsafe { Self::read_nb_regs(&Mmio, self.port.base_address()) }
+    }
+    
+    /// 通过寄存器访问接口非阻塞接收一个字节
+    /// 
+    /// # 参数
+    /// * `regs` - 寄存器访问接口
+    /// * `base` - 串口寄存器基地址
+    /// 
+    /// # Safety
+    /// - 调用者必须确保`base`为有效的串口寄存器基地址
+    pub unsafe fn read_nb_regs<R: RegisterAccess>(regs: &R, base: u32) -> nb::Result<u8, SerialError> {
+        let sr = regs.read(base + USART_SR);
+        
+        if sr & (SR_RXNE | SR_ERRORS) == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        
+        // 先读SR再读DR，同时清除RXNE和错误标志；启用校验时去掉校验位
+        let mask = Self::data_mask(regs.read(base + USART_CR1)) as u32;
+        let dr = regs.read(base + USART_DR);
+        Self::decode_rx(sr, dr & mask)
+    }
+    
+    /// 非阻塞发送一个字节
+    /// 
+    /// # 返回值
+    /// 发送数据寄存器非空时返回`WouldBlock`
+    pub fn write_nb(&self, byte: u8) -> nb::Result<(), Infallible> {
+        if self.is_loopback() {
+            self.loop_back(byte);
+            return Ok(());
+        }
+        
+        let usart = self.get_usart();
+        
+        if usart.sr().read().txe().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        
+        unsafe {
+            usart.dr().write(|w| w.bits(byte as u32));
+        }
+        Ok(())
+    }
+    
+    /// 根据SR和DR的值解析接收结果
+    /// 
+    /// 错误优先于数据：按溢出、帧错误、噪声、校验错误的顺序报告
+    pub fn decode_rx(sr: u32, dr: u32) -> nb::Result<u8, SerialError> {
+        if sr & SR_ORE != 0 {
+            Err(nb::Error::Other(SerialError::Overrun))
+        } else if sr & SR_FE != 0 {
+            Err(nb::Error::Other(SerialError::Framing))
+        } else if sr & SR_NE != 0 {
+            Err(nb::Error::Other(SerialError::Noise))
+        } else if sr & SR_PE != 0 {
+            Err(nb::Error::Other(SerialError::Parity))
+        } else if sr & SR_RXNE != 0 {
+            Ok((dr & 0xFF) as u8)
+        } else {
+            Err(nb::Error::WouldBlock)
         }
     }
     
@@ -908,26 +1249,114 @@ impl Serial {
     pub fn disable_irda_mode(&self) {
         let usart = self.get_usart();
         unsafe {
-            usart.cr3().modify(|_, w| w.iren().clear_bit());
+            usart.cr3().modify(|_, w| w.iren().clear_bit().irlp().clear_bit());
+        }
+    }
+    
+    /// 启用IrDA模式并配置低功耗选项和预分频器
+    /// 
+    /// IrDA SIR编码要求GTPR.PSC配合工作模式设置：
+    /// - 普通模式：PSC必须为1
+    /// - 低功耗模式：PSC对外设时钟分频得到约1.8432MHz的低功耗频率（允许1.42~2.12MHz）
+    /// 
+    /// IrDA模式下CR2的LINEN、STOP、CLKEN以及CR3的SCEN、HDSEL必须保持清零。
+    /// 
+    /// # 参数
+    /// * `low_power` - 是否启用IrDA低功耗模式
+    pub fn enable_irda(&self, low_power: bool) {
+        let usart = self.get_usart();
+        let psc = Self::irda_prescaler(self.get_clock_frequency(), low_power);
+        let bits = Self::irda_cr3_bits(low_power);
+        
+        unsafe {
+            usart.gtpr().modify(|_, w| w.psc().bits(psc));
+            usart.cr3().modify(|r, w| {
+                let mut value = r.bits();
+                value &= !0x0000_0006; // 清除IREN和IRLP位
+                value |= bits;
+                w.bits(value)
+            });
+        }
+    }
+    
+    /// 计算IrDA模式下CR3中IREN/IRLP位的值
+    pub const fn irda_cr3_bits(low_power: bool) -> u32 {
+        let mut value = 1 << 1; // IREN
+        if low_power {
+            value |= 1 << 2;    // IRLP
+        }
+        value
+    }
+    
+    /// 计算IrDA模式下GTPR.PSC的值
+    /// 
+    /// # 参数
+    /// * `fck` - 串口外设时钟频率，单位Hz
+    /// * `low_power` - 是否为低功耗模式
+    pub fn irda_prescaler(fck: u32, low_power: bool) -> u8 {
+        if !low_power {
+            return 1;
+        }
+        
+        // 低功耗频率目标值为1.8432MHz，四舍五入后限制在1~255之间
+        let psc = (fck + 1_843_200 / 2) / 1_843_200;
+        psc.clamp(1, 255) as u8
+    }
+    
+    /// 获取串口外设时钟频率
+    /// 
+    /// USART1挂载在APB2上，USART2和USART3挂载在APB1上
+    fn get_clock_frequency(&self) -> u32 {
+        let clocks = unsafe { RccDriver::new().get_clocks_freq() };
+        match self.port {
+            SerialPort::USART1 => clocks.pclk2_frequency,
+            SerialPort::USART2 | SerialPort::USART3 => clocks.pclk1_frequency,
         }
     }
 }
 
 /// 实现fmt::Write特性，支持使用write!宏
+/// 
+/// 例如`writeln!(USART3, "adc={}", value)`。按UTF-8字节逐个阻塞发送（等待TXE），不会丢字符
 impl fmt::Write for Serial {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            self.write_char(c)?;
-        }
+        self.write_bytes(s.as_bytes());
         Ok(())
     }
     
     fn write_char(&mut self, c: char) -> fmt::Result {
-        self.write_byte(c as u8);
+        let mut utf8 = [0u8; 4];
+        self.write_bytes(c.encode_utf8(&mut utf8).as_bytes());
         Ok(())
     }
 }
 
+/// 实现embedded-hal非阻塞串口读取特性
+impl serial::Read<u8> for Serial {
+    type Error = SerialError;
+    
+    fn read(&mut self) -> nb::Result<u8, SerialError> {
+        self.read_nb()
+    }
+}
+
+/// 实现embedded-hal非阻塞串口发送特性
+impl serial::Write<u8> for Serial {
+    type Error = Infallible;
+    
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.write_nb(byte)
+    }
+    
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        if self.is_tx_complete() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
 /// 预定义的串口接收缓冲区
 pub static USART1_RX_BUFFER: RxBuffer = RxBuffer::new();
 pub static USART2_RX_BUFFER: RxBuffer = RxBuffer::new();
@@ -942,3 +1371,243 @@ pub const USART3: Serial = Serial::new(SerialPort::USART3);
 pub const USART1_WITH_BUFFER: Serial = Serial::new_with_buffer(SerialPort::USART1, &USART1_RX_BUFFER);
 pub const USART2_WITH_BUFFER: Serial = Serial::new_with_buffer(SerialPort::USART2, &USART2_RX_BUFFER);
 pub const USART3_WITH_BUFFER: Serial = Serial::new_with_buffer(SerialPort::USART3, &USART3_RX_BUFFER);
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试IrDA模式的CR3配置位
+    #[test]
+    fn test_irda_cr3_bits() {
+        assert_eq!(Serial::irda_cr3_bits(false), 0x0000_0002, "普通模式应只设置IREN");
+        assert_eq!(Serial::irda_cr3_bits(true), 0x0000_0006, "低功耗模式应同时设置IREN和IRLP");
+    }
+    
+    /// 测试IrDA模式的GTPR预分频计算
+    #[test]
+    fn test_irda_prescaler() {
+        assert_eq!(Serial::irda_prescaler(36_000_000, false), 1, "普通模式PSC必须为1");
+        assert_eq!(Serial::irda_prescaler(36_000_000, true), 20, "36MHz低功耗模式PSC应为20");
+        assert_eq!(Serial::irda_prescaler(72_000_000, true), 39, "72MHz低功耗模式PSC应为39");
+        
+        // 分频后的低功耗频率应落在1.42~2.12MHz之间
+        let psc = Serial::irda_prescaler(8_000_000, true) as u32;
+        let freq = 8_000_000 / psc;
+        assert!(freq >= 1_420_000 && freq <= 2_120_000, "低功耗频率超出范围: {}Hz", freq);
+    }
+    
+    /// 模拟USART状态寄存器，用于验证trait约束的读取逻辑
+    struct MockUsart {
+        sr: u32,
+        dr: u32,
+    }
+    
+    impl serial::Read<u8> for MockUsart {
+        type Error = SerialError;
+        
+        fn read(&mut self) -> nb::Result<u8, SerialError> {
+            Serial::decode_rx(self.sr, self.dr)
+        }
+    }
+    
+    /// 只依赖serial::Read特性的轮询读取
+    fn poll_read<R: serial::Read<u8>>(reader: &mut R) -> nb::Result<u8, R::Error> {
+        reader.read()
+    }
+    
+    /// 测试非阻塞读取：RXNE清零时返回WouldBlock
+    #[test]
+    fn test_read_nb_would_block() {
+        let mut idle = MockUsart { sr: 0x00C0, dr: 0x55 };  // TXE|TC，RXNE清零
+        assert_eq!(poll_read(&mut idle), Err(nb::Error::WouldBlock), "RXNE清零时应返回WouldBlock");
+        
+        let mut ready = MockUsart { sr: SR_RXNE, dr: 0x1A5 };
+        assert_eq!(poll_read(&mut ready), Ok(0xA5), "RXNE置位时应返回DR低8位");
+        
+        let mut overrun = MockUsart { sr: SR_RXNE | SR_ORE, dr: 0x00 };
+        assert_eq!(poll_read(&mut overrun), Err(nb::Error::Other(SerialError::Overrun)), "溢出时应返回错误");
+    }
+    
+    /// 测试软件回环模式下发送的字节可以被读回
+    #[test]
+    fn test_loopback_write_read() {
+        static BUFFER: RxBuffer = RxBuffer::new();
+        let mut serial = Serial::new_with_buffer(SerialPort::USART3, &BUFFER);
+        
+        serial.set_loopback(true);
+        assert!(serial.is_loopback(), "应处于回环模式");
+        
+        serial.write_byte(0x5A);
+        serial.write_str("OK");
+        assert_eq!(serial::Read::read(&mut serial), Ok(0x5A), "回环模式下写入的字节应可读回");
+        assert_eq!(serial.read_byte(), b'O', "应按写入顺序读回");
+        assert_eq!(serial.read_nb(), Ok(b'K'), "应按写入顺序读回");
+        assert_eq!(serial.read_nb(), Err(nb::Error::WouldBlock), "缓冲区为空时应返回WouldBlock");
+        
+        serial.set_loopback(false);
+        assert!(!serial.is_loopback(), "应退出回环模式");
+    }
+    
+    /// 测试BRR量化造成的波特率误差计算
+    #[test]
+    fn test_baud_error_permille() {
+        // 72MHz下115200波特率可精确分频：USARTDIV = 39.0625
+        let brr = Serial::brr_value(72_000_000, 115200);
+        assert_eq!(brr, 0x271, "72MHz下115200的BRR错误");
+        assert_eq!(Serial::baud_error_from_brr(72_000_000, brr, 115200), 0, "精确分频时误差应为0");
+        
+        // 36MHz下9600：BRR = 3750，误差为0
+        assert_eq!(Serial::baud_error_from_brr(36_000_000, Serial::brr_value(36_000_000, 9600), 9600), 0, "36MHz下9600误差应为0");
+        
+        // HSI 8MHz下115200：BRR = 69，实际约115942，误差+6‰
+        let brr = Serial::brr_value(8_000_000, 115200);
+        assert_eq!(brr, 69, "8MHz下115200的BRR错误");
+        assert_eq!(Serial::baud_error_from_brr(8_000_000, brr, 115200), 6, "8MHz下误差应为+6‰");
+        
+        // 2MHz下115200：BRR = 17，实际约117647，误差+21‰，超过2%
+        let error = Serial::baud_error_from_brr(2_000_000, Serial::brr_value(2_000_000, 115200), 115200);
+        assert_eq!(error, 21, "2MHz下误差应为+21‰");
+        assert!(error.abs() > 20, "误差应超过2%");
+        
+        // 实际波特率偏低时误差为负：BRR = 70时约114286
+        assert_eq!(Serial::baud_error_from_brr(8_000_000, 70, 115200), -8, "实际波特率偏低时误差应为负");
+    }
+    
+    /// 测试通过故障注入触发接收溢出和数据永不到达
+    #[test]
+    fn test_read_nb_fault_injection() {
+        use crate::bsp::regs::{MockRegisters, Fault};
+        
+        let base = SerialPort::USART2.base_address();
+        let regs: MockRegisters<4> = MockRegisters::new();
+        regs.set(base + USART_SR, SR_RXNE);
+        regs.set(base + USART_DR, 0x42);
+        assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Ok(0x42), "无故障时应读到数据");
+        
+        // 强制ORE置位
+        regs.inject(Fault::StuckHigh { addr: base + USART_SR, mask: SR_ORE });
+        assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Err(nb::Error::Other(SerialError::Overrun)), "ORE置位时应返回溢出错误");
+        
+        // RXNE永不置位时一直返回WouldBlock
+        regs.clear_faults();
+        regs.inject(Fault::StuckLow { addr: base + USART_SR, mask: SR_RXNE });
+        assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Err(nb::Error::WouldBlock), "RXNE永不置位时应返回WouldBlock");
+    }
+    
+    /// 测试接收中断把字节写入缓冲区
+    #[test]
+    fn test_buffered_receive() {
+        use crate::bsp::regs::MockRegisters;
+        
+        let buffer = RxBuffer::new();
+        let base = SerialPort::USART3.base_address();
+        let regs: MockRegisters<4> = MockRegisters::new();
+        
+        assert!(!unsafe { Serial::receive_regs(&regs, base, &buffer) }, "RXNE清零时不应写入缓冲区");
+        
+        regs.set(base + USART_SR, SR_RXNE);
+        for byte in [0x31, 0x32, 0x33] {
+            regs.set(base + USART_DR, byte);
+            assert!(unsafe { Serial::receive_regs(&regs, base, &buffer) }, "RXNE置位时应写入缓冲区");
+        }
+        
+        // 帧错误的字节被丢弃，溢出时DR中的字节仍有效
+        regs.set(base + USART_SR, SR_RXNE | SR_FE);
+        assert!(!unsafe { Serial::receive_regs(&regs, base, &buffer) }, "帧错误的字节应被丢弃");
+        regs.set(base + USART_SR, SR_RXNE | SR_ORE);
+        regs.set(base + USART_DR, 0x34);
+        assert!(unsafe { Serial::receive_regs(&regs, base, &buffer) }, "溢出时DR中的字节应保留");
+        
+        assert_eq!(buffer.len(), 4, "缓冲区应有4个字节");
+        for expected in [0x31, 0x32, 0x33, 0x34] {
+            assert_eq!(buffer.pop(), Some(expected), "应按接收顺序读出");
+        }
+        assert_eq!(buffer.pop(), None, "读完后缓冲区应为空");
+    }
+    
+    /// 测试write!格式化输出按UTF-8字节发送
+    #[test]
+    fn test_fmt_write_bytes() {
+        use core::fmt::Write;
+        
+        static BUFFER: RxBuffer = RxBuffer::new();
+        let mut serial = Serial::new_with_buffer(SerialPort::USART1, &BUFFER);
+        serial.set_loopback(true);
+        
+        writeln!(serial, "adc={}", 1234).unwrap();
+        serial.write_char('°').unwrap();
+        
+        let mut out = [0u8; 16];
+        let count = serial.read_from_buffer_multiple(&mut out);
+        assert_eq!(&out[..count], "adc=1234\n°".as_bytes(), "应按格式化结果的UTF-8字节发送");
+        serial.set_loopback(false);
+    }
+    
+    /// 测试启用校验时接收数据去掉校验位
+    #[test]
+    fn test_parity_data_mask() {
+        use crate::bsp::regs::MockRegisters;
+        
+        assert_eq!(Serial::data_mask(0), 0xFF, "8N1应为8位数据");
+        assert_eq!(Serial::data_mask(CR1_PCE), 0x7F, "8位字长加校验应为7位数据");
+        assert_eq!(Serial::data_mask(CR1_M | CR1_PCE), 0xFF, "9位字长加校验（8E1）应为8位数据");
+        assert_eq!(Serial::data_mask(CR1_M), 0x1FF, "9位字长无校验应为9位数据");
+        
+        // 7E1：DR第7位为校验位
+        let base = SerialPort::USART1.base_address();
+        let regs: MockRegisters<4> = MockRegisters::new();
+        regs.set(base + USART_CR1, CR1_PCE);
+        regs.set(base + USART_SR, SR_RXNE);
+        regs.set(base + USART_DR, 0xC1);
+        assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Ok(0x41), "应去掉校验位");
+        
+        // 8E1：DR第8位为校验位
+        regs.set(base + USART_CR1, CR1_M | CR1_PCE);
+        regs.set(base + USART_DR, 0x1C1);
+        assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Ok(0xC1), "应保留8位数据");
+    }
+    
+    /// 测试任意波特率的BRR计算和误差检查
+    #[test]
+    fn test_checked_brr() {
+        assert_eq!(Serial::checked_brr(72_000_000, 115200), Ok(0x271), "72MHz下115200应可实现");
+        assert_eq!(Serial::checked_brr(72_000_000, 4_500_000), Ok(16), "72MHz下4.5M应为BRR=16");
+        assert_eq!(Serial::checked_brr(72_000_000, 1200), Ok(60000), "72MHz下1200应可实现");
+        assert_eq!(Serial::checked_brr(36_000_000, 250_000), Ok(144), "非标准波特率也应可实现");
+        
+        assert_eq!(Serial::checked_brr(72_000_000, 1000), Err(SerialError::UnachievableBaud), "BRR超过0xFFFF应报错");
+        assert_eq!(Serial::checked_brr(36_000_000, 4_500_000), Err(SerialError::UnachievableBaud), "USARTDIV小于1应报错");
+        assert_eq!(Serial::checked_brr(2_000_000, 115200), Err(SerialError::UnachievableBaud), "误差超过2%应报错");
+        assert_eq!(Serial::checked_brr(72_000_000, 0), Err(SerialError::UnachievableBaud), "波特率为0应报错");
+        
+        // 枚举波特率与任意波特率走同一检查
+        assert_eq!(Serial::checked_brr(72_000_000, BaudRate::B115200 as u32), Ok(625), "72MHz下115200的BRR应为625");
+        assert_eq!(Serial::checked_brr(2_000_000, BaudRate::B115200 as u32), Err(SerialError::UnachievableBaud), "枚举波特率误差超过2%也应报错");
+    }
+    
+    /// 测试接收错误标志的报告顺序
+    #[test]
+    fn test_receive_error_flags() {
+        use crate::bsp::regs::MockRegisters;
+        
+        let base = SerialPort::USART3.base_address();
+        let regs: MockRegisters<4> = MockRegisters::new();
+        regs.set(base + USART_DR, 0x55);
+        
+        let cases = [
+            (SR_RXNE | SR_FE, SerialError::Framing),
+            (SR_RXNE | SR_NE, SerialError::Noise),
+            (SR_RXNE | SR_PE, SerialError::Parity),
+            (SR_ORE, SerialError::Overrun),
+            (SR_FE | SR_PE, SerialError::Framing),
+        ];
+        for (sr, expected) in cases {
+            regs.set(base + USART_SR, sr);
+            assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Err(nb::Error::Other(expected)), "错误标志报告错误");
+        }
+        
+        regs.set(base + USART_SR, SR_RXNE);
+        assert_eq!(unsafe { Serial::read_nb_regs(&regs, base) }, Ok(0x55), "无错误时应返回数据");
+    }
+}
@@ -0,0 +1,158 @@
+//! 舵机控制模块
+//! 提供标准模拟舵机的50Hz PWM输出和角度/脉宽设置
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use embedded_hal::PwmPin;
+use crate::bsp::timer::{Timer, PwmChannel, PwmChannelHandle, PwmMode, PwmPolarity};
+
+/// 舵机PWM频率
+pub const SERVO_PWM_FREQUENCY_HZ: u32 = 50;
+
+/// PWM周期（微秒）
+const SERVO_PERIOD_US: u32 = 1_000_000 / SERVO_PWM_FREQUENCY_HZ;
+
+/// 最小脉宽（微秒），对应0度
+pub const SERVO_MIN_PULSE_US: u16 = 500;
+
+/// 最大脉宽（微秒），对应180度
+pub const SERVO_MAX_PULSE_US: u16 = 2500;
+
+/// 最大角度
+pub const SERVO_MAX_ANGLE: u8 = 180;
+
+/// 标准舵机
+/// 
+/// 定时器以约1MHz计数、周期20ms输出PWM，脉宽500~2500µs线性对应0~180度。
+/// 输出通过`PwmChannelHandle`完成，比较值按当前ARR从脉宽换算。
+/// 同一定时器的其他通道共用50Hz周期，可以再接3个舵机。
+pub struct Servo {
+    pwm: PwmChannelHandle,
+    pulse_us: u16,
+}
+
+impl Servo {
+    /// 创建舵机并配置50Hz PWM输出，初始脉宽为1500µs（90度）
+    /// 
+    /// # 参数
+    /// * `timer` - 输出PWM的定时器
+    /// * `channel` - PWM通道，对应引脚需预先配置为复用推挽输出
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码使用，且会覆盖定时器原有的PSC、ARR配置
+    pub unsafe fn new(timer: Timer, channel: PwmChannel) -> Self {
+        let (prescaler, period) = Self::pwm_config(timer.get_timer_clock());
+        let pulse_us = Self::pulse_for_angle(SERVO_MAX_ANGLE / 2);
+        let duty = Self::duty_for_pulse(pulse_us, period);
+        timer.init_pwm(channel, PwmMode::Mode1, PwmPolarity::High, period, prescaler, duty);
+        Self { pwm: PwmChannelHandle::new(timer, channel), pulse_us }
+    }
+    
+    /// 设置角度
+    /// 
+    /// # 参数
+    /// * `degrees` - 0~180，超过180按180处理
+    pub fn set_angle(&mut self, degrees: u8) {
+        self.set_pulse_us(Self::pulse_for_angle(degrees));
+    }
+    
+    /// 设置脉宽
+    /// 
+    /// # 参数
+    /// * `us` - 脉宽（微秒），限制在500~2500之间
+    pub fn set_pulse_us(&mut self, us: u16) {
+        self.pulse_us = Self::clamp_pulse(us);
+        let duty = Self::duty_for_pulse(self.pulse_us, self.pwm.get_max_duty());
+        self.pwm.set_duty(duty);
+    }
+    
+    /// 获取当前设置的脉宽（微秒）
+    pub fn pulse_us(&self) -> u16 {
+        self.pulse_us
+    }
+    
+    /// 释放舵机，返回定时器，PWM输出保持不变
+    pub fn release(self) -> Timer {
+        self.pwm.release()
+    }
+    
+    /// 计算50Hz PWM的预分频和自动重装载值
+    /// 
+    /// 预分频与`Timer::delay_prescaler`相同，取最接近1MHz计数频率的值，
+    /// ARR按实际计数频率换算20ms周期，定时器时钟不是1MHz整数倍或低于1MHz时输出仍为50Hz。
+    /// 
+    /// # 返回值
+    /// (PSC, ARR)，72MHz定时器时钟下为(71, 19999)，4.5MHz下为(4, 17999)
+    pub const fn pwm_config(timer_clk: u32) -> (u16, u16) {
+        let prescaler = Timer::delay_prescaler(timer_clk);
+        let ticks = Timer::delay_ticks_for_us(SERVO_PERIOD_US as u64, timer_clk, prescaler);
+        let period = if ticks == 0 { 0 } else if ticks > 0x1_0000 { 0xFFFF } else { ticks - 1 };
+        (prescaler, period as u16)
+    }
+    
+    /// 按自动重装载值把脉宽换算为比较值，四舍五入
+    /// 
+    /// # 参数
+    /// * `pulse_us` - 脉宽（微秒）
+    /// * `arr` - 自动重装载值，一个周期为`arr + 1`个计数、20ms
+    pub const fn duty_for_pulse(pulse_us: u16, arr: u16) -> u16 {
+        let counts = arr as u32 + 1;
+        let duty = (pulse_us as u32 * counts + SERVO_PERIOD_US / 2) / SERVO_PERIOD_US;
+        if duty > arr as u32 { arr } else { duty as u16 }
+    }
+    
+    /// 将脉宽限制在500~2500µs之间
+    pub const fn clamp_pulse(us: u16) -> u16 {
+        if us < SERVO_MIN_PULSE_US {
+            SERVO_MIN_PULSE_US
+        } else if us > SERVO_MAX_PULSE_US {
+            SERVO_MAX_PULSE_US
+        } else {
+            us
+        }
+    }
+    
+    /// 由角度计算脉宽（微秒），四舍五入，超过180度按180度处理
+    pub const fn pulse_for_angle(degrees: u8) -> u16 {
+        let degrees = if degrees > SERVO_MAX_ANGLE { SERVO_MAX_ANGLE } else { degrees };
+        let span = (SERVO_MAX_PULSE_US - SERVO_MIN_PULSE_US) as u32;
+        let offset = (degrees as u32 * span + SERVO_MAX_ANGLE as u32 / 2) / SERVO_MAX_ANGLE as u32;
+        SERVO_MIN_PULSE_US + offset as u16
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试50Hz PWM配置和角度/脉宽换算
+    #[test]
+    fn test_servo_pulse_mapping() {
+        assert_eq!(Servo::pwm_config(72_000_000), (71, 19999), "72MHz下应为PSC=71、ARR=19999");
+        assert_eq!(Servo::pwm_config(8_000_000), (7, 19999), "8MHz下应为PSC=7、ARR=19999");
+        assert_eq!(Servo::pwm_config(4_500_000), (4, 17999), "4.5MHz下应为PSC=4、ARR=17999");
+        assert_eq!(Servo::pwm_config(500_000), (0, 9999), "0.5MHz下应不分频、ARR=9999");
+        
+        for clk in [72_000_000, 4_500_000, 500_000] {
+            let (psc, arr) = Servo::pwm_config(clk);
+            assert_eq!(clk / ((psc as u32 + 1) * (arr as u32 + 1)), SERVO_PWM_FREQUENCY_HZ, "输出频率应为50Hz");
+        }
+        
+        assert_eq!(Servo::duty_for_pulse(1500, 19999), 1500, "1MHz计数下比较值应等于脉宽");
+        assert_eq!(Servo::duty_for_pulse(1500, 17999), 1350, "0.9MHz计数下1500µs应为1350个计数");
+        assert_eq!(Servo::duty_for_pulse(2500, 9999), 1250, "0.5MHz计数下2500µs应为1250个计数");
+        
+        assert_eq!(Servo::pulse_for_angle(0), 500, "0度应为500µs");
+        assert_eq!(Servo::pulse_for_angle(90), 1500, "90度应为1500µs");
+        assert_eq!(Servo::pulse_for_angle(45), 1000, "45度应为1000µs");
+        assert_eq!(Servo::pulse_for_angle(1), 511, "1度应四舍五入为511µs");
+        assert_eq!(Servo::pulse_for_angle(180), 2500, "180度应为2500µs");
+        assert_eq!(Servo::pulse_for_angle(255), 2500, "超过180度应按180度处理");
+        
+        assert_eq!(Servo::clamp_pulse(100), 500, "脉宽过小应限制为500µs");
+        assert_eq!(Servo::clamp_pulse(1200), 1200, "范围内的脉宽应保持不变");
+        assert_eq!(Servo::clamp_pulse(3000), 2500, "脉宽过大应限制为2500µs");
+    }
+}
@@ -0,0 +1,197 @@
+//! 软件串口模块
+//! 提供基于GPIO位操作（bit-bang）的8N1软件串口，适用于硬件USART不足的场合
+
+// 屏蔽未使用代码警告
+#![allow(unused)]
+
+use crate::bsp::gpio::{GpioPortStruct, GpioPortBatch};
+use crate::bsp::delay::get_uptime_us;
+
+/// 软件串口支持的最高波特率
+pub const SOFT_UART_MAX_BAUD: u32 = 38_400;
+
+/// 一帧的位数：1个起始位 + 8个数据位 + 1个停止位
+pub const SOFT_UART_FRAME_BITS: u32 = 10;
+
+/// 软件串口结构体
+/// 
+/// 位定时基于SysTick微秒计时，每一位的边沿按帧起点计算绝对偏移，避免逐位累积误差。
+/// 发送或接收期间较长的中断服务函数会引入抖动，波特率越高影响越大。
+#[derive(Debug, Clone, Copy)]
+pub struct SoftUart {
+    tx: GpioPortStruct,
+    rx: GpioPortStruct,
+    baud: u32,
+}
+
+impl SoftUart {
+    /// 创建软件串口实例
+    /// 
+    /// # 参数
+    /// * `tx` - 发送引脚
+    /// * `rx` - 接收引脚
+    /// * `baud` - 波特率（不超过38400）
+    pub const fn new(tx: GpioPortStruct, rx: GpioPortStruct, baud: u32) -> Self {
+        assert!(baud > 0 && baud <= SOFT_UART_MAX_BAUD, "Soft UART baud rate out of range");
+        Self { tx, rx, baud }
+    }
+    
+    /// 初始化软件串口引脚
+    /// 
+    /// 发送引脚配置为推挽输出并保持空闲高电平，接收引脚配置为浮空输入
+    /// # Safety
+    /// - 调用者必须确保SysTick已初始化（`delay::init_systick`）
+    /// - 调用者必须确保引脚未被其他代码或外设占用
+    pub unsafe fn init(&self) {
+        self.tx.set_high();
+        self.tx.into_push_pull_output();
+        self.tx.set_high();
+        self.rx.into_floating_input();
+    }
+    
+    /// 获取波特率
+    pub const fn baud(&self) -> u32 {
+        self.baud
+    }
+    
+    /// 发送一个字节
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn write_byte(&self, byte: u8) {
+        let frame = Self::frame_bits(byte);
+        let start = get_uptime_us();
+        
+        for bit in 0..SOFT_UART_FRAME_BITS {
+            if (frame >> bit) & 0x01 != 0 {
+                self.tx.set_high();
+            } else {
+                self.tx.set_low();
+            }
+            Self::wait_until(start + Self::bit_offset_us(self.baud, bit + 1) as u64);
+        }
+    }
+    
+    /// 发送多个字节
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn write_bytes(&self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_byte(byte);
+        }
+    }
+    
+    /// 发送字符串
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn write_str(&self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+    
+    /// 接收一个字节
+    /// 
+    /// 等待起始位下降沿，然后在每一位的中点采样
+    /// 
+    /// # 参数
+    /// * `timeout_us` - 等待起始位的超时时间，单位微秒
+    /// 
+    /// # 返回值
+    /// 成功接收返回`Some(byte)`，超时或帧错误（停止位不为高）返回`None`
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn read_byte(&self, timeout_us: u32) -> Option<u8> {
+        // 等待起始位
+        let wait_start = get_uptime_us();
+        while self.rx_level() {
+            if get_uptime_us() - wait_start >= timeout_us as u64 {
+                return None;
+            }
+        }
+        
+        let start = get_uptime_us();
+        let mut frame: u16 = 0;
+        for bit in 0..SOFT_UART_FRAME_BITS {
+            Self::wait_until(start + Self::sample_offset_us(self.baud, bit) as u64);
+            if self.rx_level() {
+                frame |= 1 << bit;
+            }
+        }
+        
+        Self::decode_frame(frame)
+    }
+    
+    /// 计算一位的持续时间（微秒，四舍五入）
+    pub const fn bit_time_us(baud: u32) -> u32 {
+        (1_000_000 + baud / 2) / baud
+    }
+    
+    /// 计算第`bit`位的起始时刻相对帧起点的偏移（微秒）
+    /// 
+    /// 按帧起点直接计算，不会把每一位的舍入误差累积到后面的位
+    pub const fn bit_offset_us(baud: u32, bit: u32) -> u32 {
+        ((bit as u64 * 1_000_000 + baud as u64 / 2) / baud as u64) as u32
+    }
+    
+    /// 计算第`bit`位的中点采样时刻相对起始位下降沿的偏移（微秒）
+    pub const fn sample_offset_us(baud: u32, bit: u32) -> u32 {
+        (((2 * bit as u64 + 1) * 1_000_000 + baud as u64) / (2 * baud as u64)) as u32
+    }
+    
+    /// 组装8N1帧，第0位为起始位，依次为LSB先发送的数据位和停止位
+    pub const fn frame_bits(byte: u8) -> u16 {
+        // 起始位为0，停止位为1
+        ((byte as u16) << 1) | (1 << 9)
+    }
+    
+    /// 解析8N1帧
+    /// 
+    /// # 返回值
+    /// 起始位为低且停止位为高时返回数据字节，否则返回None
+    pub const fn decode_frame(frame: u16) -> Option<u8> {
+        if frame & 0x0001 != 0 || frame & 0x0200 == 0 {
+            None
+        } else {
+            Some(((frame >> 1) & 0xFF) as u8)
+        }
+    }
+    
+    /// 读取接收引脚电平
+    unsafe fn rx_level(&self) -> bool {
+        GpioPortBatch::new(self.rx.port).read_input_data() & (1 << self.rx.pin) != 0
+    }
+    
+    /// 忙等待直到指定的系统时间（微秒）
+    fn wait_until(deadline_us: u64) {
+        while get_uptime_us() < deadline_us {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试9600波特率下的位定时计算
+    #[test]
+    fn test_bit_timing_9600() {
+        assert_eq!(SoftUart::bit_time_us(9600), 104, "9600波特率每位约104us");
+        assert_eq!(SoftUart::bit_offset_us(9600, 1), 104, "第1位边沿应在104us");
+        assert_eq!(SoftUart::bit_offset_us(9600, 10), 1042, "整帧应持续约1042us，不累积舍入误差");
+        assert_eq!(SoftUart::sample_offset_us(9600, 0), 52, "起始位应在52us处采样");
+        assert_eq!(SoftUart::sample_offset_us(9600, 9), 990, "停止位应在990us处采样");
+    }
+    
+    /// 测试8N1帧的组装与解析
+    #[test]
+    fn test_frame_assembly() {
+        let frame = SoftUart::frame_bits(0x55);
+        assert_eq!(frame & 0x01, 0, "起始位应为低电平");
+        assert_eq!((frame >> 9) & 0x01, 1, "停止位应为高电平");
+        assert_eq!(frame, 0b10_1010_1010, "数据位应LSB先发送");
+        
+        assert_eq!(SoftUart::decode_frame(SoftUart::frame_bits(0xA3)), Some(0xA3), "帧往返解析错误");
+        assert_eq!(SoftUart::decode_frame(0x0000), None, "停止位为低应判为帧错误");
+        assert_eq!(SoftUart::decode_frame(0x03FF), None, "起始位为高应判为帧错误");
+    }
+}
@@ -1,10 +1,89 @@
 ﻿//! SPI模块
 //! 提供串行外设接口功能封装
+//! 
+//! `Spi`实现了embedded-hal的`spi::FullDuplex<u8>`和`blocking::spi::{Transfer, Write}`，
+//! 可直接交给基于这些特性编写的显示屏、闪存等驱动。例如读取外部SPI闪存的JEDEC ID：
+//! 
+//! ```no_run
+//! # use crate::bsp::spi::{BitOrder, SpiBaudRatePrescaler, SpiError, SpiMode, SPI1};
+//! use embedded_hal::blocking::spi::Transfer;
+//! use embedded_hal::digital::v2::OutputPin;
+//! 
+//! fn read_jedec_id<S: Transfer<u8>, P: OutputPin>(spi: &mut S, cs: &mut P) -> Result<[u8; 3], S::Error> {
+//!     let mut buf = [0x9F, 0, 0, 0];
+//!     cs.set_low().ok();
+//!     let result = spi.transfer(&mut buf).map(|id| [id[1], id[2], id[3]]);
+//!     cs.set_high().ok();
+//!     result
+//! }
+//! 
+//! # fn example<P: OutputPin>(cs: &mut P) -> Result<[u8; 3], SpiError> {
+//! let mut spi = SPI1;
+//! unsafe { spi.init(SpiMode::Mode0, SpiBaudRatePrescaler::Div4, BitOrder::MsbFirst) };
+//! let id = read_jedec_id(&mut spi, cs)?;  // W25Q64为[0xEF, 0x40, 0x17]
+//! # Ok(id)
+//! # }
+//! ```
+//! 
+//! 本crate为二进制crate，rustdoc不运行其文档测试，测试模块中的`read_jedec_id`
+//! 与此示例保持一致，由`cargo test`进行类型检查。
 
 #![allow(unused)]
 
 // 使用内部生成的设备驱动库
 use library::*;
+use heapless::Vec;
+use embedded_hal::{blocking, spi};
+use crate::bsp::dma::{
+    Dma, DmaChannel, DmaDirection, DmaPeripheralIncrementMode, DmaMemoryIncrementMode,
+    DmaPeripheralDataSize, DmaMemoryDataSize, DmaChannelPriority, DmaCircularMode, DmaInterrupt,
+};
+
+/// CR1.MSTR：主模式
+pub const CR1_MSTR: u32 = 1 << 2;
+/// CR1.SPE：SPI使能
+pub const CR1_SPE: u32 = 1 << 6;
+/// CR1.LSBFIRST：低位先行
+pub const CR1_LSBFIRST: u32 = 1 << 7;
+/// CR1.SSI：内部从选择电平
+pub const CR1_SSI: u32 = 1 << 8;
+/// CR1.SSM：软件从选择管理
+pub const CR1_SSM: u32 = 1 << 9;
+/// CR1.RXONLY：只接收模式，从模式下不驱动MISO
+pub const CR1_RXONLY: u32 = 1 << 10;
+/// CR1.BIDIOE：双向模式下输出使能
+pub const CR1_BIDIOE: u32 = 1 << 14;
+/// CR1.BIDIMODE：单线双向模式
+pub const CR1_BIDIMODE: u32 = 1 << 15;
+/// CR1.DFF：16位数据帧
+pub const CR1_DFF: u32 = 1 << 11;
+/// CR2.RXDMAEN：接收缓冲区DMA使能
+pub const CR2_RXDMAEN: u32 = 1 << 0;
+/// CR2.TXDMAEN：发送缓冲区DMA使能
+pub const CR2_TXDMAEN: u32 = 1 << 1;
+/// CR2.SSOE：NSS输出使能
+pub const CR2_SSOE: u32 = 1 << 2;
+/// CR2.RXNEIE：接收缓冲区非空中断使能
+pub const CR2_RXNEIE: u32 = 1 << 6;
+/// CR2.TXEIE：发送缓冲区空中断使能
+pub const CR2_TXEIE: u32 = 1 << 7;
+/// SR.RXNE：接收缓冲区非空
+pub const SR_RXNE: u32 = 1 << 0;
+/// SR.TXE：发送缓冲区空
+pub const SR_TXE: u32 = 1 << 1;
+/// SR.MODF：模式错误
+pub const SR_MODF: u32 = 1 << 5;
+/// SR.OVR：接收溢出
+pub const SR_OVR: u32 = 1 << 6;
+/// SR.BSY：总线忙
+pub const SR_BSY: u32 = 1 << 7;
+
+/// SPI错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpiError {
+    Overrun,    // 接收溢出，上一个字节未读出就收到了新字节
+    ModeFault,  // 模式错误，主机的NSS被拉低，SPI已被硬件关闭
+}
 
 /// SPI枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -59,6 +138,13 @@ pub enum SpiNssMode {
     Hardware = 0,
 }
 
+/// SPI数据位顺序枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOrder {
+    MsbFirst,   // 高位先行
+    LsbFirst,   // 低位先行
+}
+
 /// SPI结构体
 pub struct Spi {
     number: SpiNumber,
@@ -73,6 +159,27 @@ impl SpiNumber {
             SpiNumber::SPI3 => 1 << 15,  // APB1
         }
     }
+    
+    /// 获取数据寄存器（DR）地址
+    pub const fn dr_address(&self) -> u32 {
+        match self {
+            SpiNumber::SPI1 => 0x4001_3000 + 0x0C,
+            SpiNumber::SPI2 => 0x4000_3800 + 0x0C,
+            SpiNumber::SPI3 => 0x4000_3C00 + 0x0C,
+        }
+    }
+    
+    /// 获取固定映射的DMA通道
+    /// 
+    /// # 返回值
+    /// (接收通道, 发送通道)：SPI1为DMA1通道2/3，SPI2为DMA1通道4/5，SPI3为DMA2通道1/2
+    pub const fn dma_channels(&self) -> (Dma, Dma) {
+        match self {
+            SpiNumber::SPI1 => (Dma::new(1, DmaChannel::Channel2), Dma::new(1, DmaChannel::Channel3)),
+            SpiNumber::SPI2 => (Dma::new(1, DmaChannel::Channel4), Dma::new(1, DmaChannel::Channel5)),
+            SpiNumber::SPI3 => (Dma::new(2, DmaChannel::Channel1), Dma::new(2, DmaChannel::Channel2)),
+        }
+    }
 }
 
 impl Spi {
@@ -92,119 +199,230 @@ impl Spi {
         }
     }
     
-    /// 初始化SPI
-    pub unsafe fn init(
+    /// 初始化SPI为8位全双工主模式
+    /// 
+    /// 使用软件NSS（SSM=1、SSI=1），片选由用户通过GPIO引脚自行控制，
+    /// 可在`is_busy`返回false后再释放片选。
+    /// 
+    /// # 参数
+    /// * `mode` - 时钟极性和相位
+    /// * `prescaler` - 时钟预分频（SPI1为PCLK2，SPI2/SPI3为PCLK1）
+    /// * `bit_order` - 数据位顺序
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SCK、MOSI已配置为复用推挽输出，MISO已配置为输入
+    pub unsafe fn init(&self, mode: SpiMode, prescaler: SpiBaudRatePrescaler, bit_order: BitOrder) {
+        self.init_with_options(
+            mode,
+            SpiDataSize::Bits8,
+            prescaler,
+            SpiDirection::TwoLinesFullDuplex,
+            SpiNssMode::Software,
+            bit_order,
+        );
+    }
+    
+    /// 按完整参数初始化SPI主模式
+    /// 
+    /// # Safety
+    /// - 调用者必须确保相关引脚已正确配置
+    pub unsafe fn init_with_options(
         &self,
         mode: SpiMode,
         data_size: SpiDataSize,
         baud_rate: SpiBaudRatePrescaler,
         direction: SpiDirection,
         nss_mode: SpiNssMode,
+        bit_order: BitOrder,
     ) {
-        // 启用SPI时钟
+        self.enable_clock();
+        
+        let spi = self.get_spi();
+        let (cr1, cr2) = Self::master_config(mode, data_size, baud_rate, direction, nss_mode, bit_order);
+        
+        // 先关闭SPI再修改配置
+        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(cr1 & !CR1_SPE) });
+        spi.cr2().write(|w: &mut library::spi1::cr2::W| unsafe { w.bits(cr2) });
+        // 丢弃残留数据
+        let _ = spi.dr().read().bits();
+        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(cr1) });
+    }
+    
+    /// 计算主模式的CR1和CR2值
+    /// 
+    /// 软件NSS时同时置位SSI，否则主机会检测到模式错误（MODF）而退回从模式；
+    /// 硬件NSS时启用SSOE，由SPI在使能期间输出片选。
+    /// 
+    /// # 返回值
+    /// (CR1, CR2)
+    pub const fn master_config(
+        mode: SpiMode,
+        data_size: SpiDataSize,
+        baud_rate: SpiBaudRatePrescaler,
+        direction: SpiDirection,
+        nss_mode: SpiNssMode,
+        bit_order: BitOrder,
+    ) -> (u32, u32) {
+        // CPHA为位0，CPOL为位1
+        let mut cr1 = CR1_MSTR | CR1_SPE | ((mode as u32) & 0x03) | ((baud_rate as u32) << 3);
+        let mut cr2 = 0;
+        
+        if let SpiDataSize::Bits16 = data_size {
+            cr1 |= CR1_DFF;
+        }
+        if let BitOrder::LsbFirst = bit_order {
+            cr1 |= CR1_LSBFIRST;
+        }
+        cr1 |= match direction {
+            SpiDirection::TwoLinesFullDuplex => 0,
+            SpiDirection::TwoLinesRxOnly => CR1_RXONLY,
+            SpiDirection::OneLineRx => CR1_BIDIMODE,
+            SpiDirection::OneLineTx => CR1_BIDIMODE | CR1_BIDIOE,
+        };
+        match nss_mode {
+            SpiNssMode::Software => cr1 |= CR1_SSM | CR1_SSI,
+            SpiNssMode::Hardware => cr2 |= CR2_SSOE,
+        }
+        (cr1, cr2)
+    }
+    
+    /// 启用SPI外设时钟
+    unsafe fn enable_clock(&self) {
         let rcc = &mut *(0x40021000 as *mut library::rcc::RegisterBlock);
         match self.number {
             SpiNumber::SPI1 => {
-                let mut value = rcc.apb2enr().read().bits();
-                value |= 1 << 12;
-                rcc.apb2enr().write(|w: &mut library::rcc::apb2enr::W| unsafe { w.bits(value) });
-            },
-            SpiNumber::SPI2 => {
-                let mut value = rcc.apb1enr().read().bits();
-                value |= 1 << 14;
-                rcc.apb1enr().write(|w: &mut library::rcc::apb1enr::W| unsafe { w.bits(value) });
+                rcc.apb2enr().modify(|r, w| unsafe { w.bits(r.bits() | self.number.clock_en_bit()) });
             },
-            SpiNumber::SPI3 => {
-                let mut value = rcc.apb1enr().read().bits();
-                value |= 1 << 15;
-                rcc.apb1enr().write(|w: &mut library::rcc::apb1enr::W| unsafe { w.bits(value) });
+            SpiNumber::SPI2 | SpiNumber::SPI3 => {
+                rcc.apb1enr().modify(|r, w| unsafe { w.bits(r.bits() | self.number.clock_en_bit()) });
             },
         }
-        
+    }
+    
+    /// 发送数据，等待发送完成
+    /// 
+    /// 同时读出收到的数据，避免接收缓冲区溢出（OVR）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn send(&self, data: u16) {
+        self.transfer_word(data);
+        self.wait_idle();
+    }
+    
+    /// 接收数据
+    /// 
+    /// 只等待接收缓冲区非空，用于只接收模式；全双工主模式下需要用`transfer`发送哑元数据产生时钟
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn receive(&self) -> u16 {
         let spi = self.get_spi();
+        // 等待接收缓冲区非空
+        while (spi.sr().read().bits() & SR_RXNE) == 0 {
+            core::hint::spin_loop();
+        }
         
-        // 禁用SPI
-        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(spi.cr1().read().bits() & !(1 << 6)) });
-        
-        // 配置SPI
-        let mut cr1 = 0;
-        
-        // 设置主模式
-        cr1 |= (1 << 2);
-        
-        // 设置SPI模式
-        cr1 |= ((mode as u32) & 0x03) << 0;
-        
-        // 设置数据大小
-        cr1 |= (data_size as u32) << 11;
-        
-        // 设置时钟预分频
-        cr1 |= (baud_rate as u32) << 3;
-        
-        // 设置数据方向
-        cr1 |= ((direction as u32) & 0x03) << 14;
-        
-        // 设置NSS管理模式
-        cr1 |= (nss_mode as u32) << 9;
-        
-        // 启用SPI
-        cr1 |= (1 << 6);
-        
-        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(cr1) });
-        
-        // 配置CR2
-        let mut cr2 = 0;
-        // 启用接收缓冲区非空中断
-        cr2 |= (1 << 6);
-        spi.cr2().write(|w: &mut library::spi1::cr2::W| unsafe { w.bits(cr2) });
+        // 读取数据
+        spi.dr().read().bits() as u16
     }
     
-    /// 发送数据
-    pub unsafe fn send(&self, data: u16) {
+    /// 发送并接收一帧数据（全双工），8位和16位数据帧均可使用
+    /// 
+    /// 单线发送模式下不会收到数据（RXNE不会置位），等待发送完成后返回0
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn transfer_word(&self, data: u16) -> u16 {
         let spi = self.get_spi();
         // 等待发送缓冲区为空
-        while (spi.sr().read().bits() & (1 << 1)) == 0 {
+        while (spi.sr().read().bits() & SR_TXE) == 0 {
             core::hint::spin_loop();
         }
-        
-        // 发送数据
         spi.dr().write(|w: &mut library::spi1::dr::W| unsafe { w.bits(data as u32) });
         
-        // 等待传输完成
-        while (spi.sr().read().bits() & (1 << 7)) == 0 {
+        if self.is_tx_only() {
+            self.wait_tx_complete();
+            return 0;
+        }
+        
+        // 等待对应的接收数据
+        while (spi.sr().read().bits() & SR_RXNE) == 0 {
             core::hint::spin_loop();
         }
+        spi.dr().read().bits() as u16
     }
     
-    /// 接收数据
-    pub unsafe fn receive(&self) -> u16 {
-        let spi = self.get_spi();
-        // 等待接收缓冲区非空
-        while (spi.sr().read().bits() & (1 << 0)) == 0 {
-            core::hint::spin_loop();
+    /// 发送并接收一个字节（全双工）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn transfer(&self, byte: u8) -> u8 {
+        self.transfer_word(byte as u16) as u8
+    }
+    
+    /// 发送多个字节，丢弃收到的数据，返回时最后一个字节已发送完成
+    /// 
+    /// 单线发送模式下只等待TXE连续写入，不读取接收缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn write_bytes(&self, bytes: &[u8]) {
+        if !self.is_tx_only() {
+            for &byte in bytes {
+                self.transfer_word(byte as u16);
+            }
+            self.wait_idle();
+            return;
         }
         
-        // 读取数据
-        spi.dr().read().bits() as u16
+        let spi = self.get_spi();
+        for &byte in bytes {
+            while (spi.sr().read().bits() & SR_TXE) == 0 {
+                core::hint::spin_loop();
+            }
+            spi.dr().write(|w: &mut library::spi1::dr::W| unsafe { w.bits(byte as u32) });
+        }
+        self.wait_tx_complete();
     }
     
-    /// 发送并接收数据（全双工）
-    pub unsafe fn transfer(&self, data: u16) -> u16 {
-        // 发送数据
-        self.send(data);
-        
-        // 接收数据
-        self.receive()
+    /// 检查是否配置为单线发送模式（BIDIMODE和BIDIOE均置位）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_tx_only(&self) -> bool {
+        Self::cr1_tx_only(self.get_spi().cr1().read().bits())
+    }
+    
+    /// 由CR1值判断是否为单线发送模式
+    pub const fn cr1_tx_only(cr1: u32) -> bool {
+        cr1 & (CR1_BIDIMODE | CR1_BIDIOE) == CR1_BIDIMODE | CR1_BIDIOE
+    }
+    
+    /// 等待发送缓冲区为空且最后一帧移出（TXE置位后BSY清零）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn wait_tx_complete(&self) {
+        let spi = self.get_spi();
+        while (spi.sr().read().bits() & SR_TXE) == 0 {
+            core::hint::spin_loop();
+        }
+        self.wait_idle();
     }
     
     /// 发送数据缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
     pub unsafe fn send_buffer(&self, buffer: &[u8]) {
-        for &byte in buffer {
-            self.send(byte as u16);
-        }
+        self.write_bytes(buffer);
     }
     
     /// 接收数据缓冲区
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
     pub unsafe fn receive_buffer(&self, buffer: &mut [u8]) {
         for byte in buffer {
             *byte = self.receive() as u8;
@@ -212,44 +430,418 @@ impl Spi {
     }
     
     /// 传输数据缓冲区（全双工）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
     pub unsafe fn transfer_buffer(&self, tx_buffer: &[u8], rx_buffer: &mut [u8]) {
         for (i, &byte) in tx_buffer.iter().enumerate() {
             if i < rx_buffer.len() {
-                rx_buffer[i] = self.transfer(byte as u16) as u8;
+                rx_buffer[i] = self.transfer(byte);
             } else {
-                self.send(byte as u16);
+                self.transfer(byte);
             }
         }
+        self.wait_idle();
+    }
+    
+    /// 使用DMA原地全双工传输
+    /// 
+    /// 发送通道从`buf`读出字节发送，接收通道把收到的字节写回`buf`的同一位置，
+    /// 传输完成后`buf`中即为读回的数据，适用于支持回读的显示屏帧缓冲区等场合。
+    /// 接收通道的优先级高于发送通道，且先于发送通道启动，保证每个字节在被下一个字节覆盖前读出。
+    /// 
+    /// 使用`SpiInPlaceTransfer::wait`等待完成并取回缓冲区，SPI需已初始化为8位主模式。
+    /// 
+    /// # 参数
+    /// * `buf` - 发送数据，传输完成后被接收数据覆盖，长度不超过65535
+    /// 
+    /// # Safety
+    /// - 调用者必须确保该SPI对应的两个DMA通道未被其他代码占用
+    pub unsafe fn transfer_in_place_dma(&self, buf: &'static mut [u8]) -> SpiInPlaceTransfer {
+        assert!(buf.len() <= u16::MAX as usize, "SPI DMA transfer length must not exceed 65535");
+        
+        let (rx, tx) = self.number.dma_channels();
+        let dr = self.number.dr_address();
+        let addr = buf.as_mut_ptr() as u32;
+        let len = buf.len() as u16;
+        
+        let (rx_ccr, tx_ccr) = in_place_dma_ccr();
+        for (dma, config) in [(&rx, rx_ccr), (&tx, tx_ccr)] {
+            dma.init_with_ccr(config);
+            dma.clear_interrupt(DmaInterrupt::TransferComplete);
+            dma.configure_transfer(dr, addr, len);
+        }
+        
+        let spi = self.get_spi();
+        // 丢弃残留数据，避免第一个字节错位
+        let _ = spi.dr().read().bits();
+        
+        // 先启动接收再启动发送
+        rx.enable();
+        spi.cr2().modify(|r, w| unsafe { w.bits(r.bits() | CR2_RXDMAEN) });
+        tx.enable();
+        spi.cr2().modify(|r, w| unsafe { w.bits(r.bits() | CR2_TXDMAEN) });
+        
+        SpiInPlaceTransfer {
+            spi: Spi::new(self.number),
+            rx,
+            tx,
+            buf,
+        }
+    }
+    
+    /// 初始化为只监听的嗅探模式
+    /// 
+    /// 配置为软件NSS（SSI=0，始终被选中）的只接收从模式：SCK和MOSI作为输入，不驱动MISO，
+    /// 也不输出NSS，因此可以直接挂到正在通信的总线上而不影响总线。
+    /// 要抓取MISO上的数据，把总线MISO接到本机的MOSI引脚即可。
+    /// 
+    /// SCK和MOSI引脚需配置为浮空输入，MISO和NSS引脚保持为输入或不连接。
+    /// 启用RXNE中断，需在SPI中断服务函数中调用`SpiSniffer::on_rx_interrupt`。
+    /// 
+    /// # 参数
+    /// * `mode` - 与被监听总线相同的时钟极性和相位
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SCK和MOSI引脚已配置为浮空输入，且SPI未被其他代码使用
+    pub unsafe fn init_sniffer(&self, mode: SpiMode) {
+        self.enable_clock();
+        
+        let spi = self.get_spi();
+        let (cr1, cr2) = Self::sniffer_config(mode);
+        
+        // 先关闭SPI再修改配置
+        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(cr1 & !CR1_SPE) });
+        spi.cr2().write(|w: &mut library::spi1::cr2::W| unsafe { w.bits(cr2) });
+        // 丢弃残留数据
+        let _ = spi.dr().read().bits();
+        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(cr1) });
+    }
+    
+    /// 计算嗅探模式的CR1和CR2值
+    /// 
+    /// # 返回值
+    /// (CR1, CR2)
+    pub const fn sniffer_config(mode: SpiMode) -> (u32, u32) {
+        // 从模式（MSTR=0），只接收，软件NSS且SSI=0，8位数据，MSB先行
+        let cr1 = ((mode as u32) & 0x03) | CR1_RXONLY | CR1_SSM | CR1_SPE;
+        // 只启用接收中断，不输出NSS
+        let cr2 = CR2_RXNEIE;
+        (cr1, cr2)
     }
     
     /// 检查SPI是否忙
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_busy(&self) -> bool {
         let spi = self.get_spi();
-        (spi.sr().read().bits() & (1 << 7)) != 0
+        (spi.sr().read().bits() & SR_BSY) != 0
+    }
+    
+    /// 等待当前传输完成（BSY清零）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn wait_idle(&self) {
+        while self.is_busy() {
+            core::hint::spin_loop();
+        }
     }
     
     /// 检查接收缓冲区是否非空
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_rx_not_empty(&self) -> bool {
         let spi = self.get_spi();
-        (spi.sr().read().bits() & (1 << 0)) != 0
+        (spi.sr().read().bits() & SR_RXNE) != 0
     }
     
     /// 检查发送缓冲区是否为空
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn is_tx_empty(&self) -> bool {
         let spi = self.get_spi();
-        (spi.sr().read().bits() & (1 << 1)) != 0
+        (spi.sr().read().bits() & SR_TXE) != 0
     }
     
     /// 启用SPI
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，相关引脚已正确配置
     pub unsafe fn enable(&self) {
         let spi = self.get_spi();
-        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(spi.cr1().read().bits() | (1 << 6)) });
+        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(spi.cr1().read().bits() | CR1_SPE) });
     }
     
     /// 禁用SPI
+    /// 
+    /// 全双工模式下先等待发送缓冲区为空且传输完成，避免截断正在发送的数据
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
     pub unsafe fn disable(&self) {
         let spi = self.get_spi();
-        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(spi.cr1().read().bits() & !(1 << 6)) });
+        while (spi.sr().read().bits() & SR_TXE) == 0 {
+            core::hint::spin_loop();
+        }
+        self.wait_idle();
+        spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(spi.cr1().read().bits() & !CR1_SPE) });
+    }
+}
+
+impl Spi {
+    /// 非阻塞读取收到的字节
+    /// 
+    /// 发生溢出或模式错误时先按手册顺序清除标志（溢出：读DR再读SR；模式错误：读SR再写CR1），
+    /// 模式错误后SPE已被硬件清零，需要重新初始化。
+    /// 只有采样到的SR中RXNE或OVR置位时才读DR，避免读走SR采样之后才到达的字节
+    /// 
+    /// # 返回值
+    /// 接收缓冲区为空时返回`WouldBlock`，不访问DR
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn read_nb(&self) -> nb::Result<u8, SpiError> {
+        let spi = self.get_spi();
+        let sr = spi.sr().read().bits();
+        let dr = if sr & (SR_RXNE | SR_OVR) != 0 { spi.dr().read().bits() } else { 0 };
+        let result = Self::decode_rx(sr, dr);
+        match result {
+            Err(nb::Error::Other(SpiError::Overrun)) => {
+                spi.sr().read();
+            },
+            Err(nb::Error::Other(SpiError::ModeFault)) => {
+                spi.cr1().write(|w: &mut library::spi1::cr1::W| unsafe { w.bits(spi.cr1().read().bits()) });
+            },
+            _ => {},
+        }
+        result
+    }
+    
+    /// 非阻塞发送一个字节
+    /// 
+    /// # 返回值
+    /// 发送缓冲区非空时返回`WouldBlock`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保SPI已初始化，且未被其他代码同时使用
+    pub unsafe fn send_nb(&self, byte: u8) -> nb::Result<(), SpiError> {
+        let spi = self.get_spi();
+        let sr = spi.sr().read().bits();
+        if sr & SR_MODF != 0 {
+            return Err(nb::Error::Other(SpiError::ModeFault));
+        }
+        if sr & SR_TXE == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        spi.dr().write(|w: &mut library::spi1::dr::W| unsafe { w.bits(byte as u32) });
+        Ok(())
+    }
+    
+    /// 根据SR和DR的值解析接收结果
+    /// 
+    /// 模式错误优先于溢出；RXNE未置位时DR的值无效
+    pub fn decode_rx(sr: u32, dr: u32) -> nb::Result<u8, SpiError> {
+        if sr & SR_MODF != 0 {
+            Err(nb::Error::Other(SpiError::ModeFault))
+        } else if sr & SR_OVR != 0 {
+            Err(nb::Error::Other(SpiError::Overrun))
+        } else if sr & SR_RXNE != 0 {
+            Ok(dr as u8)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+/// 实现embedded-hal非阻塞全双工特性
+/// 
+/// 每次`send`后必须调用`read`取回对应的字节，否则下一次接收会溢出
+impl spi::FullDuplex<u8> for Spi {
+    type Error = SpiError;
+    
+    fn read(&mut self) -> nb::Result<u8, SpiError> {
+        unsafe { self.read_nb() }
+    }
+    
+    fn send(&mut self, byte: u8) -> nb::Result<(), SpiError> {
+        unsafe { self.send_nb(byte) }
+    }
+}
+
+/// 实现embedded-hal阻塞全双工传输特性，收到的字节覆盖`words`
+/// 
+/// 逐字节调用`transfer`；单线发送模式下收不到数据，`words`保持不变
+impl blocking::spi::Transfer<u8> for Spi {
+    type Error = SpiError;
+    
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], SpiError> {
+        let tx_only = unsafe { self.is_tx_only() };
+        for word in words.iter_mut() {
+            let received = unsafe { Spi::transfer(self, *word) };
+            if !tx_only {
+                *word = received;
+            }
+        }
+        unsafe { self.wait_idle() };
+        Ok(words)
+    }
+}
+
+/// 实现embedded-hal阻塞发送特性，丢弃收到的字节
+/// 
+/// 基于`send_nb`/`read_nb`，溢出和模式错误会中止传输并返回错误
+impl blocking::spi::Write<u8> for Spi {
+    type Error = SpiError;
+    
+    fn write(&mut self, words: &[u8]) -> Result<(), SpiError> {
+        let tx_only = unsafe { self.is_tx_only() };
+        for &word in words {
+            nb::block!(unsafe { self.send_nb(word) })?;
+            if !tx_only {
+                nb::block!(unsafe { self.read_nb() })?;
+            }
+        }
+        unsafe { self.wait_tx_complete() };
+        Ok(())
+    }
+}
+
+/// 计算原地全双工DMA传输两个通道的CCR配置值
+/// 
+/// 两个通道都使用字节宽度、内存地址递增、外设地址固定的同一缓冲区；
+/// 接收通道为外设到内存且优先级最高，发送通道为内存到外设。
+/// 
+/// # 返回值
+/// (接收通道CCR, 发送通道CCR)
+pub const fn in_place_dma_ccr() -> (u32, u32) {
+    let rx = Dma::ccr_config(
+        DmaDirection::PeripheralToMemory,
+        DmaPeripheralIncrementMode::Disabled,
+        DmaMemoryIncrementMode::Enabled,
+        DmaPeripheralDataSize::Byte,
+        DmaMemoryDataSize::Byte,
+        DmaChannelPriority::VeryHigh,
+        DmaCircularMode::Disabled,
+    );
+    let tx = Dma::ccr_config(
+        DmaDirection::MemoryToPeripheral,
+        DmaPeripheralIncrementMode::Disabled,
+        DmaMemoryIncrementMode::Enabled,
+        DmaPeripheralDataSize::Byte,
+        DmaMemoryDataSize::Byte,
+        DmaChannelPriority::High,
+        DmaCircularMode::Disabled,
+    );
+    (rx, tx)
+}
+
+/// 检查原地全双工传输是否完成
+/// 
+/// 发送通道计数归零时最后一个字节可能还在移位，接收通道计数归零才表示所有字节都已收回；
+/// 同时要求SPI不忙，之后才能关闭DMA请求或拉高片选。
+/// 
+/// # 参数
+/// * `rx_remaining` - 接收通道剩余计数
+/// * `tx_remaining` - 发送通道剩余计数
+/// * `busy` - SPI的BSY标志
+pub const fn in_place_transfer_complete(rx_remaining: u16, tx_remaining: u16, busy: bool) -> bool {
+    rx_remaining == 0 && tx_remaining == 0 && !busy
+}
+
+/// 进行中的SPI原地全双工DMA传输
+pub struct SpiInPlaceTransfer {
+    spi: Spi,
+    rx: Dma,
+    tx: Dma,
+    buf: &'static mut [u8],
+}
+
+impl SpiInPlaceTransfer {
+    /// 检查传输是否完成
+    /// # Safety
+    /// - 调用者必须确保DMA通道未被其他代码修改
+    pub unsafe fn is_complete(&self) -> bool {
+        in_place_transfer_complete(self.rx.get_remaining_count(), self.tx.get_remaining_count(), self.spi.is_busy())
+    }
+    
+    /// 等待传输完成，关闭DMA请求并取回缓冲区
+    /// # Safety
+    /// - 调用者必须确保DMA通道未被其他代码修改
+    pub unsafe fn wait(self) -> &'static mut [u8] {
+        while !self.is_complete() {
+            core::hint::spin_loop();
+        }
+        
+        let spi = self.spi.get_spi();
+        spi.cr2().modify(|r, w| unsafe { w.bits(r.bits() & !(CR2_RXDMAEN | CR2_TXDMAEN)) });
+        self.tx.disable();
+        self.rx.disable();
+        self.buf
+    }
+}
+
+/// SPI总线嗅探器
+/// 
+/// 配合`Spi::init_sniffer`使用，在接收中断中把收到的字节保存到缓冲区，缓冲区满后丢弃后续字节。
+pub struct SpiSniffer<const N: usize> {
+    spi: Spi,
+    buffer: Vec<u8, N>,
+    dropped: u32,
+}
+
+impl<const N: usize> SpiSniffer<N> {
+    /// 创建嗅探器
+    pub const fn new(number: SpiNumber) -> Self {
+        Self {
+            spi: Spi::new(number),
+            buffer: Vec::new(),
+            dropped: 0,
+        }
+    }
+    
+    /// 初始化SPI为嗅探模式并清空缓冲区
+    /// # Safety
+    /// - 调用者必须确保相关引脚已配置为输入，且SPI未被其他代码使用
+    pub unsafe fn init(&mut self, mode: SpiMode) {
+        self.clear();
+        self.spi.init_sniffer(mode);
+    }
+    
+    /// 接收中断处理，应在SPI中断服务函数中调用
+    /// # Safety
+    /// - 调用者必须确保已调用`init`
+    pub unsafe fn on_rx_interrupt(&mut self) {
+        if self.spi.is_rx_not_empty() {
+            let byte = self.spi.get_spi().dr().read().bits() as u8;
+            self.record(byte);
+        }
+    }
+    
+    /// 保存一个字节
+    pub fn record(&mut self, byte: u8) {
+        if self.buffer.push(byte).is_err() {
+            self.dropped += 1;
+        }
+    }
+    
+    /// 获取已捕获的字节
+    pub fn captured(&self) -> &[u8] {
+        &self.buffer
+    }
+    
+    /// 获取缓冲区满后丢弃的字节数
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+    
+    /// 清空缓冲区
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.dropped = 0;
     }
 }
 
@@ -257,3 +849,154 @@ impl Spi {
 pub const SPI1: Spi = Spi::new(SpiNumber::SPI1);
 pub const SPI2: Spi = Spi::new(SpiNumber::SPI2);
 pub const SPI3: Spi = Spi::new(SpiNumber::SPI3);
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试嗅探模式关闭输出并启用接收
+    #[test]
+    fn test_sniffer_config() {
+        let (cr1, cr2) = Spi::sniffer_config(SpiMode::Mode3);
+        
+        assert_eq!(cr1 & CR1_MSTR, 0, "嗅探模式必须为从模式");
+        assert_ne!(cr1 & CR1_RXONLY, 0, "应设置RXONLY，不驱动MISO");
+        assert_eq!(cr1 & (CR1_BIDIMODE | CR1_BIDIOE), 0, "不应使用双向模式输出");
+        assert_ne!(cr1 & CR1_SSM, 0, "应使用软件NSS");
+        assert_eq!(cr1 & CR1_SSI, 0, "SSI应为0使从机始终被选中");
+        assert_eq!(cr1 & 0x03, 0x03, "时钟极性和相位应与总线一致");
+        assert_ne!(cr1 & CR1_SPE, 0, "应启用SPI");
+        
+        assert_ne!(cr2 & CR2_RXNEIE, 0, "应启用接收中断");
+        assert_eq!(cr2 & (CR2_TXEIE | CR2_SSOE), 0, "不应启用发送中断和NSS输出");
+    }
+    
+    /// 测试捕获缓冲区满后丢弃字节
+    #[test]
+    fn test_sniffer_capture_buffer() {
+        let mut sniffer: SpiSniffer<4> = SpiSniffer::new(SpiNumber::SPI1);
+        for byte in [0x9F, 0xEF, 0x40, 0x18, 0xAA] {
+            sniffer.record(byte);
+        }
+        assert_eq!(sniffer.captured(), &[0x9F, 0xEF, 0x40, 0x18], "应按顺序保存捕获的字节");
+        assert_eq!(sniffer.dropped(), 1, "缓冲区满后应计数丢弃的字节");
+        
+        sniffer.clear();
+        assert!(sniffer.captured().is_empty(), "清空后缓冲区应为空");
+    }
+    
+    /// 测试原地全双工DMA的双通道配置和完成判断
+    #[test]
+    fn test_in_place_dma_channels() {
+        let (rx, tx) = SpiNumber::SPI1.dma_channels();
+        let (rx2, tx2) = SpiNumber::SPI2.dma_channels();
+        assert_eq!((rx.channel_regs() as u32, tx.channel_regs() as u32), (0x4002_001C, 0x4002_0030), "SPI1应使用DMA1通道2/3");
+        assert_eq!((rx2.channel_regs() as u32, tx2.channel_regs() as u32), (0x4002_0044, 0x4002_0058), "SPI2应使用DMA1通道4/5");
+        
+        let (rx_ccr, tx_ccr) = in_place_dma_ccr();
+        assert_eq!(rx_ccr & (1 << 4), 0, "接收通道应为外设到内存（DR到buf）");
+        assert_ne!(tx_ccr & (1 << 4), 0, "发送通道应为内存到外设（buf到DR）");
+        for ccr in [rx_ccr, tx_ccr] {
+            assert_ne!(ccr & (1 << 7), 0, "两个通道都应递增内存地址");
+            assert_eq!(ccr & (1 << 6), 0, "外设地址应固定为DR");
+            assert_eq!(ccr & (0b1111 << 8), 0, "数据宽度应为字节");
+            assert_eq!(ccr & (1 << 5), 0, "不应使用循环模式");
+        }
+        assert!((rx_ccr >> 12) & 0b11 > (tx_ccr >> 12) & 0b11, "接收通道优先级应高于发送通道");
+        
+        // 发送先结束，最后一个字节收回且SPI空闲后才算完成
+        assert!(!in_place_transfer_complete(1, 0, true), "接收未完成时不应结束");
+        assert!(!in_place_transfer_complete(0, 0, true), "SPI忙时不应结束");
+        assert!(in_place_transfer_complete(0, 0, false), "两个通道完成且SPI空闲时应结束");
+    }
+    
+    /// 测试主模式的CR1/CR2配置
+    #[test]
+    fn test_master_config() {
+        let (cr1, cr2) = Spi::master_config(
+            SpiMode::Mode1,
+            SpiDataSize::Bits8,
+            SpiBaudRatePrescaler::Div16,
+            SpiDirection::TwoLinesFullDuplex,
+            SpiNssMode::Software,
+            BitOrder::LsbFirst,
+        );
+        assert_ne!(cr1 & CR1_MSTR, 0, "应为主模式");
+        assert_eq!(cr1 & 0x03, 0b01, "模式1应为CPOL=0、CPHA=1");
+        assert_eq!((cr1 >> 3) & 0b111, 3, "16分频应为BR=011");
+        assert_ne!(cr1 & CR1_LSBFIRST, 0, "应设置低位先行");
+        assert_eq!(cr1 & CR1_DFF, 0, "应为8位数据帧");
+        assert_eq!(cr1 & (CR1_SSM | CR1_SSI), CR1_SSM | CR1_SSI, "软件NSS必须同时置位SSI，避免模式错误");
+        assert_eq!(cr1 & (CR1_RXONLY | CR1_BIDIMODE | CR1_BIDIOE), 0, "全双工不应设置单向位");
+        assert_ne!(cr1 & CR1_SPE, 0, "应启用SPI");
+        assert_eq!(cr2, 0, "阻塞驱动不应启用中断或DMA");
+        
+        let (cr1, _) = Spi::master_config(
+            SpiMode::Mode2,
+            SpiDataSize::Bits16,
+            SpiBaudRatePrescaler::Div2,
+            SpiDirection::TwoLinesRxOnly,
+            SpiNssMode::Software,
+            BitOrder::MsbFirst,
+        );
+        assert_eq!(cr1 & 0x03, 0b10, "模式2应为CPOL=1、CPHA=0");
+        assert_ne!(cr1 & CR1_DFF, 0, "应为16位数据帧");
+        assert_eq!(cr1 & CR1_LSBFIRST, 0, "应为高位先行");
+        assert_eq!(cr1 & (CR1_RXONLY | CR1_BIDIOE), CR1_RXONLY, "只接收模式应设置RXONLY而不是BIDIOE");
+        
+        let (cr1, cr2) = Spi::master_config(
+            SpiMode::Mode0,
+            SpiDataSize::Bits8,
+            SpiBaudRatePrescaler::Div256,
+            SpiDirection::OneLineTx,
+            SpiNssMode::Hardware,
+            BitOrder::MsbFirst,
+        );
+        assert_eq!(cr1 & (CR1_BIDIMODE | CR1_BIDIOE), CR1_BIDIMODE | CR1_BIDIOE, "单线发送应设置BIDIMODE和BIDIOE");
+        assert!(Spi::cr1_tx_only(cr1), "单线发送模式应被识别为只发送");
+        assert!(!Spi::cr1_tx_only(CR1_BIDIMODE), "单线接收不应被识别为只发送");
+        assert!(!Spi::cr1_tx_only(CR1_RXONLY), "只接收模式不应被识别为只发送");
+        assert_eq!(cr1 & CR1_SSM, 0, "硬件NSS不应使用软件管理");
+        assert_ne!(cr2 & CR2_SSOE, 0, "硬件NSS应启用NSS输出");
+    }
+    
+    /// 测试embedded-hal特性实现和接收状态解析
+    #[test]
+    fn test_embedded_hal_spi() {
+        fn assert_spi<T>()
+        where
+            T: spi::FullDuplex<u8, Error = SpiError>
+                + blocking::spi::Transfer<u8, Error = SpiError>
+                + blocking::spi::Write<u8, Error = SpiError>,
+        {
+        }
+        assert_spi::<Spi>();
+        
+        assert_eq!(Spi::decode_rx(SR_TXE | SR_RXNE, 0x1A5), Ok(0xA5), "RXNE置位时应返回数据低8位");
+        assert_eq!(Spi::decode_rx(SR_TXE, 0x12), Err(nb::Error::WouldBlock), "接收缓冲区为空时应返回WouldBlock");
+        assert_eq!(Spi::decode_rx(SR_RXNE | SR_OVR, 0x12), Err(nb::Error::Other(SpiError::Overrun)), "应报告溢出");
+        assert_eq!(Spi::decode_rx(SR_OVR | SR_MODF, 0), Err(nb::Error::Other(SpiError::ModeFault)), "模式错误应优先报告");
+    }
+    
+    /// 模块文档示例，只做类型检查，不在主机上运行
+    #[allow(dead_code)]
+    fn read_jedec_id<S: blocking::spi::Transfer<u8>, P: embedded_hal::digital::v2::OutputPin>(
+        spi: &mut S,
+        cs: &mut P,
+    ) -> Result<[u8; 3], S::Error> {
+        let mut buf = [0x9F, 0, 0, 0];
+        cs.set_low().ok();
+        let result = spi.transfer(&mut buf).map(|id| [id[1], id[2], id[3]]);
+        cs.set_high().ok();
+        result
+    }
+    
+    /// 模块文档示例中对`SPI1`的调用
+    #[allow(dead_code)]
+    fn example<P: embedded_hal::digital::v2::OutputPin>(cs: &mut P) -> Result<[u8; 3], SpiError> {
+        let mut spi = SPI1;
+        unsafe { spi.init(SpiMode::Mode0, SpiBaudRatePrescaler::Div4, BitOrder::MsbFirst) };
+        read_jedec_id(&mut spi, cs)
+    }
+}
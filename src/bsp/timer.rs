@@ -8,6 +8,13 @@
 use library::*;
 use core::ops::DerefMut;
 use crate::bsp::rcc::RccDriver;
+use crate::bsp::gpio::{GpioPortStruct, GpioPortBatch};
+use core::cell::UnsafeCell;
+use embedded_hal::{Pwm, PwmPin};
+use crate::bsp::dma::{
+    Dma, DmaChannel, DmaDirection, DmaPeripheralIncrementMode, DmaMemoryIncrementMode,
+    DmaPeripheralDataSize, DmaMemoryDataSize, DmaChannelPriority, DmaCircularMode, DmaInterrupt,
+};
 
 /// 定时器枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -43,6 +50,217 @@ pub enum PwmPolarity {
     Low,    // 有效电平为低电平
 }
 
+/// 主模式枚举（CR2.MMS，选择输出到TRGO的信号）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MasterMode {
+    Reset = 0,          // EGR.UG作为TRGO
+    Enable = 1,         // 计数器使能CNT_EN作为TRGO
+    Update = 2,         // 更新事件作为TRGO
+    ComparePulse = 3,   // CC1IF置位时输出脉冲
+    Oc1Ref = 4,         // OC1REF作为TRGO
+    Oc2Ref = 5,         // OC2REF作为TRGO
+    Oc3Ref = 6,         // OC3REF作为TRGO
+    Oc4Ref = 7,         // OC4REF作为TRGO
+}
+
+/// 从模式枚举（SMCR.SMS）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlaveMode {
+    Disabled = 0,       // 关闭从模式，由内部时钟计数
+    Encoder1 = 1,       // 编码器模式1
+    Encoder2 = 2,       // 编码器模式2
+    Encoder3 = 3,       // 编码器模式3
+    Reset = 4,          // 复位模式：触发上升沿清零计数器
+    Gated = 5,          // 门控模式：触发为高时计数
+    Trigger = 6,        // 触发模式：触发上升沿启动计数器
+    ExternalClock = 7,  // 外部时钟模式1：触发上升沿作为计数时钟
+}
+
+/// 触发源枚举（SMCR.TS）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerSource {
+    Itr0 = 0,   // 内部触发0
+    Itr1 = 1,   // 内部触发1
+    Itr2 = 2,   // 内部触发2
+    Itr3 = 3,   // 内部触发3
+    Ti1FEd = 4, // TI1边沿检测器
+    Ti1Fp1 = 5, // 滤波后的定时器输入1
+    Ti2Fp2 = 6, // 滤波后的定时器输入2
+    Etrf = 7,   // 外部触发输入
+}
+
+impl TriggerSource {
+    /// 获取从定时器连接到主定时器TRGO的内部触发源，主从相同时返回None
+    pub const fn from_master(master: TimerNumber, slave: TimerNumber) -> Option<TriggerSource> {
+        match internal_trigger_source(master, slave) {
+            Some(0) => Some(TriggerSource::Itr0),
+            Some(1) => Some(TriggerSource::Itr1),
+            Some(2) => Some(TriggerSource::Itr2),
+            Some(3) => Some(TriggerSource::Itr3),
+            _ => None,
+        }
+    }
+}
+
+/// 输入捕获边沿枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapturePolarity {
+    Rising,     // 上升沿捕获
+    Falling,    // 下降沿捕获
+}
+
+/// 输入捕获预分频枚举（ICxPSC）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapturePrescaler {
+    Div1 = 0,   // 每个边沿捕获一次
+    Div2 = 1,   // 每2个边沿捕获一次
+    Div4 = 2,   // 每4个边沿捕获一次
+    Div8 = 3,   // 每8个边沿捕获一次
+}
+
+/// 刹车输入极性枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakPolarity {
+    Low,    // BKIN低电平有效
+    High,   // BKIN高电平有效
+}
+
+/// BDTR.LOCK写保护级别枚举
+/// 
+/// 复位后只能写入一次，写入后直到下次复位都不能修改
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerLockLevel {
+    Off = 0b00,     // 不锁定
+    Level1 = 0b01,  // 锁定DTG、BKE、BKP、AOE、OISx
+    Level2 = 0b10,  // 在级别1基础上锁定CCxP/CCxNP、OSSR、OSSI
+    Level3 = 0b11,  // 在级别2基础上锁定OCxM、OCxPE
+}
+
+/// 定时器错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerError {
+    UnsupportedFeature, // 该定时器或通道不支持此功能
+    InvalidParameter,   // 参数超出硬件可实现的范围
+}
+
+/// 定时器寄存器
+/// 
+/// 保存寄存器地址，以原始值读写
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimReg(*mut u32);
+
+impl TimReg {
+    /// 读取寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保寄存器属于已启用时钟的定时器
+    pub unsafe fn read(self) -> u32 {
+        self.0.read_volatile()
+    }
+    
+    /// 写入寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保寄存器属于已启用时钟的定时器，且未被其他代码同时修改
+    pub unsafe fn write(self, value: u32) {
+        self.0.write_volatile(value)
+    }
+    
+    /// 修改寄存器中的指定位
+    /// 
+    /// # Safety
+    /// - 调用者必须确保寄存器属于已启用时钟的定时器，且未被其他代码同时修改
+    pub unsafe fn modify(self, mask: u32, value: u32) {
+        self.write((self.read() & !mask) | (value & mask));
+    }
+    
+    /// 寄存器地址，用作DMA的外设地址
+    pub fn address(self) -> u32 {
+        self.0 as u32
+    }
+}
+
+/// 定时器寄存器访问接口
+/// 
+/// TIM1（高级定时器）和TIM2~TIM4（通用定时器）的寄存器块是不同的类型，
+/// 但基本计数和PWM用到的寄存器偏移、位定义相同，通过此接口编写一份通用的驱动代码
+pub trait TimRegs {
+    /// 控制寄存器1
+    fn cr1(&self) -> TimReg;
+    /// 控制寄存器2
+    fn cr2(&self) -> TimReg;
+    /// 从模式控制寄存器
+    fn smcr(&self) -> TimReg;
+    /// DMA/中断使能寄存器
+    fn dier(&self) -> TimReg;
+    /// 状态寄存器
+    fn sr(&self) -> TimReg;
+    /// 事件产生寄存器
+    fn egr(&self) -> TimReg;
+    /// 捕获/比较模式寄存器1（通道1、2）
+    fn ccmr1(&self) -> TimReg;
+    /// 捕获/比较模式寄存器2（通道3、4）
+    fn ccmr2(&self) -> TimReg;
+    /// 捕获/比较使能寄存器
+    fn ccer(&self) -> TimReg;
+    /// 计数器
+    fn cnt(&self) -> TimReg;
+    /// 预分频器
+    fn psc(&self) -> TimReg;
+    /// 自动重装载寄存器
+    fn arr(&self) -> TimReg;
+    /// 通道的捕获/比较寄存器
+    fn ccr(&self, channel: PwmChannel) -> TimReg;
+    
+    /// 刹车和死区寄存器，只有高级定时器有此寄存器，通用定时器返回None
+    fn bdtr(&self) -> Option<TimReg> {
+        None
+    }
+    
+    /// 通道所在的捕获/比较模式寄存器
+    fn ccmr(&self, channel: PwmChannel) -> TimReg {
+        match channel {
+            PwmChannel::Channel1 | PwmChannel::Channel2 => self.ccmr1(),
+            PwmChannel::Channel3 | PwmChannel::Channel4 => self.ccmr2(),
+        }
+    }
+}
+
+/// 为定时器寄存器块实现`TimRegs`
+macro_rules! impl_tim_regs {
+    ($tim:ident $(, $($extra:tt)*)?) => {
+        impl TimRegs for $tim::RegisterBlock {
+            fn cr1(&self) -> TimReg { TimReg($tim::RegisterBlock::cr1(self).as_ptr()) }
+            fn cr2(&self) -> TimReg { TimReg($tim::RegisterBlock::cr2(self).as_ptr()) }
+            fn smcr(&self) -> TimReg { TimReg($tim::RegisterBlock::smcr(self).as_ptr()) }
+            fn dier(&self) -> TimReg { TimReg($tim::RegisterBlock::dier(self).as_ptr()) }
+            fn sr(&self) -> TimReg { TimReg($tim::RegisterBlock::sr(self).as_ptr()) }
+            fn egr(&self) -> TimReg { TimReg($tim::RegisterBlock::egr(self).as_ptr()) }
+            fn ccmr1(&self) -> TimReg { TimReg(self.ccmr1_output().as_ptr()) }
+            fn ccmr2(&self) -> TimReg { TimReg(self.ccmr2_output().as_ptr()) }
+            fn ccer(&self) -> TimReg { TimReg($tim::RegisterBlock::ccer(self).as_ptr()) }
+            fn cnt(&self) -> TimReg { TimReg($tim::RegisterBlock::cnt(self).as_ptr()) }
+            fn psc(&self) -> TimReg { TimReg($tim::RegisterBlock::psc(self).as_ptr()) }
+            fn arr(&self) -> TimReg { TimReg($tim::RegisterBlock::arr(self).as_ptr()) }
+            
+            fn ccr(&self, channel: PwmChannel) -> TimReg {
+                match channel {
+                    PwmChannel::Channel1 => TimReg(self.ccr1().as_ptr()),
+                    PwmChannel::Channel2 => TimReg(self.ccr2().as_ptr()),
+                    PwmChannel::Channel3 => TimReg(self.ccr3().as_ptr()),
+                    PwmChannel::Channel4 => TimReg(self.ccr4().as_ptr()),
+                }
+            }
+            
+            $($($extra)*)?
+        }
+    };
+}
+
+impl_tim_regs!(tim1, fn bdtr(&self) -> Option<TimReg> { Some(TimReg(tim1::RegisterBlock::bdtr(self).as_ptr())) });
+// TIM3、TIM4与TIM2是同一寄存器块类型
+impl_tim_regs!(tim2);
+
 /// 定时器结构体
 pub struct Timer {
     number: TimerNumber,
@@ -143,7 +361,7 @@ impl Timer {
     }
     
     /// 获取定时器时钟频率
-    unsafe fn get_timer_clock(&self) -> u32 {
+    pub(crate) unsafe fn get_timer_clock(&self) -> u32 {
         // 使用RCC驱动获取时钟频率
         let rcc_driver = RccDriver::new();
         let clocks = rcc_driver.get_clocks_freq();
@@ -170,6 +388,18 @@ impl Timer {
         }
     }
     
+    /// 获取定时器寄存器访问接口
+    /// 
+    /// 只在这里按定时器编号分派，其余方法通过`TimRegs`编写一份通用实现
+    unsafe fn regs(&self) -> &'static dyn TimRegs {
+        match self.number {
+            TimerNumber::TIM1 => self.get_tim1(),
+            TimerNumber::TIM2 => self.get_tim2(),
+            TimerNumber::TIM3 => self.get_tim3(),
+            TimerNumber::TIM4 => self.get_tim4(),
+        }
+    }
+    
     /// 初始化定时器
     /// 
     /// # 参数
@@ -184,120 +414,61 @@ impl Timer {
         self.enable_clock();
         
         // 2. 配置定时器
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(period));  // 自动重装载值
-                tim.cnt().write(|w| w.cnt().bits(0));  // 清零计数器
-                tim.egr().write(|w| w.ug().set_bit());  // 生成更新事件
-                tim.sr().write(|w| w.uif().clear_bit());  // 清除更新中断标志
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(period));  // 自动重装载值
-                tim.cnt().write(|w| w.cnt().bits(0));  // 清零计数器
-                tim.egr().write(|w| w.ug().set_bit());  // 生成更新事件
-                tim.sr().write(|w| w.uif().clear_bit());  // 清除更新中断标志
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(period));  // 自动重装载值
-                tim.cnt().write(|w| w.cnt().bits(0));  // 清零计数器
-                tim.egr().write(|w| w.ug().set_bit());  // 生成更新事件
-                tim.sr().write(|w| w.uif().clear_bit());  // 清除更新中断标志
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(period));  // 自动重装载值
-                tim.cnt().write(|w| w.cnt().bits(0));  // 清零计数器
-                tim.egr().write(|w| w.ug().set_bit());  // 生成更新事件
-                tim.sr().write(|w| w.uif().clear_bit());  // 清除更新中断标志
-            },
-        }
+        let tim = self.regs();
+        tim.cr1().write(0);  // 禁用定时器
+        tim.psc().write(prescaler as u32);  // 预分频器
+        tim.arr().write(period as u32);  // 自动重装载值
+        tim.cnt().write(0);  // 清零计数器
+        tim.egr().write(EGR_UG);  // 生成更新事件
+        tim.sr().write(0);  // 清除更新中断标志
     }
     
     /// 启动定时器
     pub unsafe fn start(&self) {
-        match self.number {
-            TimerNumber::TIM1 => { self.get_tim1().cr1().modify(|_, w| w.cen().set_bit()); },
-            TimerNumber::TIM2 => { self.get_tim2().cr1().modify(|_, w| w.cen().set_bit()); },
-            TimerNumber::TIM3 => { self.get_tim3().cr1().modify(|_, w| w.cen().set_bit()); },
-            TimerNumber::TIM4 => { self.get_tim4().cr1().modify(|_, w| w.cen().set_bit()); },
-        }
+        self.regs().cr1().modify(CR1_CEN, CR1_CEN);
     }
     
     /// 停止定时器
     pub unsafe fn stop(&self) {
-        match self.number {
-            TimerNumber::TIM1 => { self.get_tim1().cr1().modify(|_, w| w.cen().clear_bit()); },
-            TimerNumber::TIM2 => { self.get_tim2().cr1().modify(|_, w| w.cen().clear_bit()); },
-            TimerNumber::TIM3 => { self.get_tim3().cr1().modify(|_, w| w.cen().clear_bit()); },
-            TimerNumber::TIM4 => { self.get_tim4().cr1().modify(|_, w| w.cen().clear_bit()); },
-        }
+        self.regs().cr1().modify(CR1_CEN, 0);
     }
     
     /// 重置定时器
     pub unsafe fn reset(&self) {
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                tim.cnt().write(|w| w.cnt().bits(0));
-                tim.sr().write(|w| w.uif().clear_bit());
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                tim.cnt().write(|w| w.cnt().bits(0));
-                tim.sr().write(|w| w.uif().clear_bit());
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                tim.cnt().write(|w| w.cnt().bits(0));
-                tim.sr().write(|w| w.uif().clear_bit());
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                tim.cnt().write(|w| w.cnt().bits(0));
-                tim.sr().write(|w| w.uif().clear_bit());
-            },
-        }
+        let tim = self.regs();
+        tim.cnt().write(0);
+        tim.sr().write(0);
     }
     
     /// 检查更新中断标志
     pub unsafe fn has_update(&self) -> bool {
-        match self.number {
-            TimerNumber::TIM1 => self.get_tim1().sr().read().uif().bit_is_set(),
-            TimerNumber::TIM2 => self.get_tim2().sr().read().uif().bit_is_set(),
-            TimerNumber::TIM3 => self.get_tim3().sr().read().uif().bit_is_set(),
-            TimerNumber::TIM4 => self.get_tim4().sr().read().uif().bit_is_set(),
-        }
+        self.regs().sr().read() & SR_UIF != 0
     }
     
     /// 清除更新中断标志
     pub unsafe fn clear_update(&self) {
-        match self.number {
-            TimerNumber::TIM1 => { self.get_tim1().sr().write(|w| w.uif().clear_bit()); },
-            TimerNumber::TIM2 => { self.get_tim2().sr().write(|w| w.uif().clear_bit()); },
-            TimerNumber::TIM3 => { self.get_tim3().sr().write(|w| w.uif().clear_bit()); },
-            TimerNumber::TIM4 => { self.get_tim4().sr().write(|w| w.uif().clear_bit()); },
-        }
+        self.regs().sr().write(0);
     }
     
     /// 获取当前计数值
     pub unsafe fn get_count(&self) -> u16 {
-        match self.number {
-            TimerNumber::TIM1 => self.get_tim1().cnt().read().cnt().bits(),
-            TimerNumber::TIM2 => self.get_tim2().cnt().read().cnt().bits(),
-            TimerNumber::TIM3 => self.get_tim3().cnt().read().cnt().bits(),
-            TimerNumber::TIM4 => self.get_tim4().cnt().read().cnt().bits(),
-        }
+        self.regs().cnt().read() as u16
+    }
+    
+    /// 获取自动重装载值（ARR）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn get_period(&self) -> u16 {
+        self.regs().arr().read() as u16
+    }
+    
+    /// 设置自动重装载值（ARR），不修改预分频和比较值
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn set_period(&self, period: u16) {
+        self.regs().arr().write(period as u32);
     }
     
     /// 设置计数值
@@ -305,32 +476,17 @@ impl Timer {
         // 参数有效性验证
         assert!(count > 0 || count == 0, "Count value is valid");
         
-        match self.number {
-            TimerNumber::TIM1 => { self.get_tim1().cnt().write(|w| w.cnt().bits(count)); },
-            TimerNumber::TIM2 => { self.get_tim2().cnt().write(|w| w.cnt().bits(count)); },
-            TimerNumber::TIM3 => { self.get_tim3().cnt().write(|w| w.cnt().bits(count)); },
-            TimerNumber::TIM4 => { self.get_tim4().cnt().write(|w| w.cnt().bits(count)); },
-        }
+        self.regs().cnt().write(count as u32);
     }
     
     /// 使能更新中断
     pub unsafe fn enable_update_interrupt(&self) {
-        match self.number {
-            TimerNumber::TIM1 => { self.get_tim1().dier().modify(|_, w| w.uie().set_bit()); },
-            TimerNumber::TIM2 => { self.get_tim2().dier().modify(|_, w| w.uie().set_bit()); },
-            TimerNumber::TIM3 => { self.get_tim3().dier().modify(|_, w| w.uie().set_bit()); },
-            TimerNumber::TIM4 => { self.get_tim4().dier().modify(|_, w| w.uie().set_bit()); },
-        }
+        self.regs().dier().modify(DIER_UIE, DIER_UIE);
     }
     
     /// 禁用更新中断
     pub unsafe fn disable_update_interrupt(&self) {
-        match self.number {
-            TimerNumber::TIM1 => { self.get_tim1().dier().modify(|_, w| w.uie().clear_bit()); },
-            TimerNumber::TIM2 => { self.get_tim2().dier().modify(|_, w| w.uie().clear_bit()); },
-            TimerNumber::TIM3 => { self.get_tim3().dier().modify(|_, w| w.uie().clear_bit()); },
-            TimerNumber::TIM4 => { self.get_tim4().dier().modify(|_, w| w.uie().clear_bit()); },
-        }
+        self.regs().dier().modify(DIER_UIE, 0);
     }
     
     /// 初始化PWM通道
@@ -352,329 +508,27 @@ impl Timer {
         self.enable_clock();
         
         // 2. 配置PWM通用设置
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                self.config_pwm_channel_tim1(tim, channel, mode, polarity, period, prescaler, initial_duty);
-                
-                // 对于高级定时器TIM1，需要启用主输出
-                tim.bdtr().modify(|_, w| w.moe().set_bit());
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 清除更新中断标志
-                tim.sr().write(|w| w.uif().clear_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                self.config_pwm_channel_tim2(tim, channel, mode, polarity, period, prescaler, initial_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 清除更新中断标志
-                tim.sr().write(|w| w.uif().clear_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                self.config_pwm_channel_tim3(tim, channel, mode, polarity, period, prescaler, initial_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 清除更新中断标志
-                tim.sr().write(|w| w.uif().clear_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                self.config_pwm_channel_tim4(tim, channel, mode, polarity, period, prescaler, initial_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 清除更新中断标志
-                tim.sr().write(|w| w.uif().clear_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-        }
-    }
-    
-    /// 配置PWM通道（针对TIM1）
-    unsafe fn config_pwm_channel_tim1(
-        &self, 
-        tim: &mut tim1::RegisterBlock, 
-        channel: PwmChannel, 
-        mode: PwmMode, 
-        polarity: PwmPolarity,
-        period: u16,
-        prescaler: u16,
-        initial_duty: u16
-    ) {
-        // 禁用定时器
-        tim.cr1().write(|w| w.cen().clear_bit());
-        // 配置预分频器和自动重装载值
-        tim.psc().write(|w| w.psc().bits(prescaler));
-        tim.arr().write(|w| w.arr().bits(period));
+        let tim = self.regs();
+        self.config_pwm_channel(tim, channel, mode, polarity, period, prescaler, initial_duty);
         
-        // 配置PWM通道
-        self.config_pwm_channel_tim1_inner(tim, channel, mode, polarity, initial_duty);
-    }
-    
-    /// 配置PWM通道的内部方法（针对TIM1）
-    unsafe fn config_pwm_channel_tim1_inner(
-        &self, 
-        tim: &mut tim1::RegisterBlock, 
-        channel: PwmChannel, 
-        mode: PwmMode, 
-        polarity: PwmPolarity,
-        initial_duty: u16
-    ) {
-        match channel {
-            PwmChannel::Channel1 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc1m().bits(0b110),
-                        PwmMode::Mode2 => w.oc1m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc1pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc1p().clear_bit(),
-                        PwmPolarity::Low => w.cc1p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc1e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr1().write(|w| w.ccr1().bits(initial_duty));
-            },
-            PwmChannel::Channel2 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc2m().bits(0b110),
-                        PwmMode::Mode2 => w.oc2m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc2pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc2p().clear_bit(),
-                        PwmPolarity::Low => w.cc2p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc2e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr2().write(|w| w.ccr2().bits(initial_duty));
-            },
-            PwmChannel::Channel3 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc3m().bits(0b110),
-                        PwmMode::Mode2 => w.oc3m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc3pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc3p().clear_bit(),
-                        PwmPolarity::Low => w.cc3p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc3e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr3().write(|w| w.ccr3().bits(initial_duty));
-            },
-            PwmChannel::Channel4 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc4m().bits(0b110),
-                        PwmMode::Mode2 => w.oc4m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc4pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc4p().clear_bit(),
-                        PwmPolarity::Low => w.cc4p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc4e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr4().write(|w| w.ccr4().bits(initial_duty));
-            },
+        // 对于高级定时器TIM1，需要启用主输出
+        if let Some(bdtr) = tim.bdtr() {
+            bdtr.modify(BDTR_MOE, BDTR_MOE);
         }
-    }
-    
-    /// 配置PWM通道（针对TIM2）
-    unsafe fn config_pwm_channel_tim2(
-        &self, 
-        tim: &mut tim2::RegisterBlock, 
-        channel: PwmChannel, 
-        mode: PwmMode, 
-        polarity: PwmPolarity,
-        period: u16,
-        prescaler: u16,
-        initial_duty: u16
-    ) {
-        // 禁用定时器
-        tim.cr1().write(|w| w.cen().clear_bit());
-        // 配置预分频器和自动重装载值
-        tim.psc().write(|w| w.psc().bits(prescaler));
-        tim.arr().write(|w| w.arr().bits(period));
         
-        // 配置PWM通道
-        self.config_pwm_channel_tim2_inner(tim, channel, mode, polarity, initial_duty);
-    }
-    
-    /// 配置PWM通道的内部方法（针对TIM2）
-    unsafe fn config_pwm_channel_tim2_inner(
-        &self, 
-        tim: &mut tim2::RegisterBlock, 
-        channel: PwmChannel, 
-        mode: PwmMode, 
-        polarity: PwmPolarity,
-        initial_duty: u16
-    ) {
-        match channel {
-            PwmChannel::Channel1 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc1m().bits(0b110),
-                        PwmMode::Mode2 => w.oc1m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc1pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc1p().clear_bit(),
-                        PwmPolarity::Low => w.cc1p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc1e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr1().write(|w| w.ccr1().bits(initial_duty));
-            },
-            PwmChannel::Channel2 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc2m().bits(0b110),
-                        PwmMode::Mode2 => w.oc2m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc2pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc2p().clear_bit(),
-                        PwmPolarity::Low => w.cc2p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc2e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr2().write(|w| w.ccr2().bits(initial_duty));
-            },
-            PwmChannel::Channel3 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc3m().bits(0b110),
-                        PwmMode::Mode2 => w.oc3m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc3pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc3p().clear_bit(),
-                        PwmPolarity::Low => w.cc3p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc3e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr3().write(|w| w.ccr3().bits(initial_duty));
-            },
-            PwmChannel::Channel4 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc4m().bits(0b110),
-                        PwmMode::Mode2 => w.oc4m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc4pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc4p().clear_bit(),
-                        PwmPolarity::Low => w.cc4p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc4e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr4().write(|w| w.ccr4().bits(initial_duty));
-            },
-        }
+        // 生成更新事件，更新影子寄存器
+        tim.egr().write(EGR_UG);
+        // 清除更新中断标志
+        tim.sr().write(0);
+        // 启用定时器
+        tim.cr1().write(CR1_CEN);
     }
     
-    /// 配置PWM通道（针对TIM3）
-    unsafe fn config_pwm_channel_tim3(
+    /// 配置PWM通道
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn config_pwm_channel(
         &self, 
-        tim: &mut tim3::RegisterBlock, 
+        tim: &dyn TimRegs, 
         channel: PwmChannel, 
         mode: PwmMode, 
         polarity: PwmPolarity,
@@ -683,358 +537,81 @@ impl Timer {
         initial_duty: u16
     ) {
         // 禁用定时器
-        tim.cr1().write(|w| w.cen().clear_bit());
+        tim.cr1().write(0);
         // 配置预分频器和自动重装载值
-        tim.psc().write(|w| w.psc().bits(prescaler));
-        tim.arr().write(|w| w.arr().bits(period));
+        tim.psc().write(prescaler as u32);
+        tim.arr().write(period as u32);
         
         // 配置PWM通道
-        self.config_pwm_channel_tim3_inner(tim, channel, mode, polarity, initial_duty);
-    }
-    
-    /// 配置PWM通道的内部方法（针对TIM3）
-    unsafe fn config_pwm_channel_tim3_inner(
-        &self, 
-        tim: &mut tim3::RegisterBlock, 
-        channel: PwmChannel, 
-        mode: PwmMode, 
-        polarity: PwmPolarity,
-        initial_duty: u16
-    ) {
-        match channel {
-            PwmChannel::Channel1 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc1m().bits(0b110),
-                        PwmMode::Mode2 => w.oc1m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc1pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc1p().clear_bit(),
-                        PwmPolarity::Low => w.cc1p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc1e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr1().write(|w| w.ccr1().bits(initial_duty));
-            },
-            PwmChannel::Channel2 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc2m().bits(0b110),
-                        PwmMode::Mode2 => w.oc2m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc2pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc2p().clear_bit(),
-                        PwmPolarity::Low => w.cc2p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc2e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr2().write(|w| w.ccr2().bits(initial_duty));
-            },
-            PwmChannel::Channel3 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc3m().bits(0b110),
-                        PwmMode::Mode2 => w.oc3m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc3pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc3p().clear_bit(),
-                        PwmPolarity::Low => w.cc3p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc3e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr3().write(|w| w.ccr3().bits(initial_duty));
-            },
-            PwmChannel::Channel4 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc4m().bits(0b110),
-                        PwmMode::Mode2 => w.oc4m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc4pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc4p().clear_bit(),
-                        PwmPolarity::Low => w.cc4p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc4e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr4().write(|w| w.ccr4().bits(initial_duty));
-            },
-        }
+        self.config_pwm_channel_inner(tim, channel, mode, polarity, initial_duty);
     }
     
-    /// 配置PWM通道（针对TIM4）
-    unsafe fn config_pwm_channel_tim4(
+    /// 配置PWM通道的内部方法
+    unsafe fn config_pwm_channel_inner(
         &self, 
-        tim: &mut tim4::RegisterBlock, 
+        tim: &dyn TimRegs, 
         channel: PwmChannel, 
         mode: PwmMode, 
         polarity: PwmPolarity,
-        period: u16,
-        prescaler: u16,
         initial_duty: u16
     ) {
-        // 禁用定时器
-        tim.cr1().write(|w| w.cen().clear_bit());
-        // 配置预分频器和自动重装载值
-        tim.psc().write(|w| w.psc().bits(prescaler));
-        tim.arr().write(|w| w.arr().bits(period));
+        // 配置CCMR寄存器：PWM模式，使能预加载
+        let (mask, value) = Self::pwm_ccmr_bits(channel, mode);
+        tim.ccmr(channel).modify(mask, value);
         
-        // 配置PWM通道
-        self.config_pwm_channel_tim4_inner(tim, channel, mode, polarity, initial_duty);
+        // 配置CCER寄存器：配置极性，使能通道
+        let enable = Self::channel_enable_bit(channel);
+        let polarity_bit = enable << 1;
+        let polarity_value = match polarity {
+            PwmPolarity::High => 0,
+            PwmPolarity::Low => polarity_bit,
+        };
+        tim.ccer().modify(enable | polarity_bit, enable | polarity_value);
+        
+        // 设置初始占空比
+        tim.ccr(channel).write(initial_duty as u32);
     }
     
-    /// 配置PWM通道的内部方法（针对TIM4）
-    unsafe fn config_pwm_channel_tim4_inner(
-        &self, 
-        tim: &mut tim4::RegisterBlock, 
-        channel: PwmChannel, 
-        mode: PwmMode, 
-        polarity: PwmPolarity,
-        initial_duty: u16
-    ) {
-        match channel {
-            PwmChannel::Channel1 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc1m().bits(0b110),
-                        PwmMode::Mode2 => w.oc1m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc1pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc1p().clear_bit(),
-                        PwmPolarity::Low => w.cc1p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc1e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr1().write(|w| w.ccr1().bits(initial_duty));
-            },
-            PwmChannel::Channel2 => {
-                // 配置CCMR1寄存器：PWM模式，使能预加载
-                tim.ccmr1_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc2m().bits(0b110),
-                        PwmMode::Mode2 => w.oc2m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc2pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc2p().clear_bit(),
-                        PwmPolarity::Low => w.cc2p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc2e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr2().write(|w| w.ccr2().bits(initial_duty));
-            },
-            PwmChannel::Channel3 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc3m().bits(0b110),
-                        PwmMode::Mode2 => w.oc3m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc3pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc3p().clear_bit(),
-                        PwmPolarity::Low => w.cc3p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc3e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr3().write(|w| w.ccr3().bits(initial_duty));
-            },
-            PwmChannel::Channel4 => {
-                // 配置CCMR2寄存器：PWM模式，使能预加载
-                tim.ccmr2_output().write(|w| {
-                    // PWM模式1：0b110，PWM模式2：0b111
-                    let mode_bits = match mode {
-                        PwmMode::Mode1 => w.oc4m().bits(0b110),
-                        PwmMode::Mode2 => w.oc4m().bits(0b111),
-                    };
-                    mode_bits
-                        .oc4pe().set_bit()  // 使能预加载
-                });
-                
-                // 配置CCER寄存器：配置极性，使能通道
-                tim.ccer().modify(|_, w| {
-                    let polarity_bit = match polarity {
-                        PwmPolarity::High => w.cc4p().clear_bit(),
-                        PwmPolarity::Low => w.cc4p().set_bit(),
-                    };
-                    polarity_bit
-                        .cc4e().set_bit()  // 使能通道
-                });
-                
-                // 设置初始占空比
-                tim.ccr4().write(|w| w.ccr4().bits(initial_duty));
-            },
-        }
+    /// 计算PWM通道的CCMR配置
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：CCxS = 00（输出），OCxPE = 1（使能预加载），OCxM = 110（PWM模式1）或111（PWM模式2），
+    /// 掩码只覆盖该通道所在的字节，不影响同一CCMR中的另一个通道
+    pub const fn pwm_ccmr_bits(channel: PwmChannel, mode: PwmMode) -> (u32, u32) {
+        let ocm = match mode {
+            PwmMode::Mode1 => 0b110,
+            PwmMode::Mode2 => 0b111,
+        };
+        let (mask, value) = Self::output_compare_ccmr(channel, ocm);
+        let shift = 8 * (channel as u32 % 2);
+        (mask, value | (1 << (shift + 3)))
     }
     
     /// 设置PWM占空比
     pub unsafe fn set_pwm_duty(&self, channel: PwmChannel, duty: u16) {
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                self.set_pwm_duty_tim1(tim, channel, duty);
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                self.set_pwm_duty_tim2(tim, channel, duty);
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                self.set_pwm_duty_tim3(tim, channel, duty);
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                self.set_pwm_duty_tim4(tim, channel, duty);
-            },
-        }
-    }
-    
-    /// 设置PWM占空比（针对TIM1）
-    unsafe fn set_pwm_duty_tim1(
-        &self, 
-        tim: &mut tim1::RegisterBlock, 
-        channel: PwmChannel, 
-        duty: u16
-    ) {
-        // 参数有效性验证
-        let period = tim.arr().read().arr().bits();
-        assert!(duty <= period, "Duty value out of range (0-period)");
-        
-        match channel {
-            PwmChannel::Channel1 => { tim.ccr1().write(|w| w.ccr1().bits(duty)); },
-            PwmChannel::Channel2 => { tim.ccr2().write(|w| w.ccr2().bits(duty)); },
-            PwmChannel::Channel3 => { tim.ccr3().write(|w| w.ccr3().bits(duty)); },
-            PwmChannel::Channel4 => { tim.ccr4().write(|w| w.ccr4().bits(duty)); },
-        }
-    }
-    
-    /// 设置PWM占空比（针对TIM2）
-    unsafe fn set_pwm_duty_tim2(
-        &self, 
-        tim: &mut tim2::RegisterBlock, 
-        channel: PwmChannel, 
-        duty: u16
-    ) {
-        // 参数有效性验证
-        let period = tim.arr().read().arr().bits();
-        assert!(duty <= period, "Duty value out of range (0-period)");
-        
-        match channel {
-            PwmChannel::Channel1 => { tim.ccr1().write(|w| w.ccr1().bits(duty)); },
-            PwmChannel::Channel2 => { tim.ccr2().write(|w| w.ccr2().bits(duty)); },
-            PwmChannel::Channel3 => { tim.ccr3().write(|w| w.ccr3().bits(duty)); },
-            PwmChannel::Channel4 => { tim.ccr4().write(|w| w.ccr4().bits(duty)); },
-        }
+        self.set_pwm_duty_regs(self.regs(), channel, duty);
     }
     
-    /// 设置PWM占空比（针对TIM3）
-    unsafe fn set_pwm_duty_tim3(
+    /// 设置PWM占空比的内部方法
+    unsafe fn set_pwm_duty_regs(
         &self, 
-        tim: &mut tim3::RegisterBlock, 
+        tim: &dyn TimRegs, 
         channel: PwmChannel, 
         duty: u16
     ) {
         // 参数有效性验证
-        let period = tim.arr().read().arr().bits();
+        let period = tim.arr().read() as u16;
         assert!(duty <= period, "Duty value out of range (0-period)");
         
-        match channel {
-            PwmChannel::Channel1 => { tim.ccr1().write(|w| w.ccr1().bits(duty)); },
-            PwmChannel::Channel2 => { tim.ccr2().write(|w| w.ccr2().bits(duty)); },
-            PwmChannel::Channel3 => { tim.ccr3().write(|w| w.ccr3().bits(duty)); },
-            PwmChannel::Channel4 => { tim.ccr4().write(|w| w.ccr4().bits(duty)); },
-        }
+        tim.ccr(channel).write(duty as u32);
     }
     
-    /// 设置PWM占空比（针对TIM4）
-    unsafe fn set_pwm_duty_tim4(
-        &self, 
-        tim: &mut tim4::RegisterBlock, 
-        channel: PwmChannel, 
-        duty: u16
-    ) {
-        // 参数有效性验证
-        let period = tim.arr().read().arr().bits();
-        assert!(duty <= period, "Duty value out of range (0-period)");
-        
-        match channel {
-            PwmChannel::Channel1 => { tim.ccr1().write(|w| w.ccr1().bits(duty)); },
-            PwmChannel::Channel2 => { tim.ccr2().write(|w| w.ccr2().bits(duty)); },
-            PwmChannel::Channel3 => { tim.ccr3().write(|w| w.ccr3().bits(duty)); },
-            PwmChannel::Channel4 => { tim.ccr4().write(|w| w.ccr4().bits(duty)); },
-        }
+    /// 获取PWM通道的比较值（CCRx）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn get_pwm_duty(&self, channel: PwmChannel) -> u16 {
+        self.regs().ccr(channel).read() as u16
     }
     
     /// 设置PWM频率
@@ -1071,151 +648,1378 @@ impl Timer {
         let actual_duty = (duty_percent as u32 * arr as u32 / 100) as u16;
         
         // 配置定时器
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(arr));  // 自动重装载值
-                
-                // 设置占空比
-                self.set_pwm_duty_tim1(tim, channel, actual_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(arr));  // 自动重装载值
-                
-                // 设置占空比
-                self.set_pwm_duty_tim2(tim, channel, actual_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(arr));  // 自动重装载值
-                
-                // 设置占空比
-                self.set_pwm_duty_tim3(tim, channel, actual_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                tim.cr1().write(|w| w.cen().clear_bit());  // 禁用定时器
-                tim.psc().write(|w| w.psc().bits(prescaler));  // 预分频器
-                tim.arr().write(|w| w.arr().bits(arr));  // 自动重装载值
-                
-                // 设置占空比
-                self.set_pwm_duty_tim4(tim, channel, actual_duty);
-                
-                // 生成更新事件，更新影子寄存器
-                tim.egr().write(|w| w.ug().set_bit());
-                // 启用定时器
-                tim.cr1().write(|w| w.cen().set_bit());
-            },
+        let tim = self.regs();
+        tim.cr1().write(0);  // 禁用定时器
+        tim.psc().write(prescaler as u32);  // 预分频器
+        tim.arr().write(arr as u32);  // 自动重装载值
+        
+        // 设置占空比
+        self.set_pwm_duty_regs(tim, channel, actual_duty);
+        
+        // 生成更新事件，更新影子寄存器
+        tim.egr().write(EGR_UG);
+        // 启用定时器
+        tim.cr1().write(CR1_CEN);
+    }
+    
+    /// 启用PWM通道
+    pub unsafe fn enable_pwm_channel(&self, channel: PwmChannel) {
+        let bit = Self::channel_enable_bit(channel);
+        self.regs().ccer().modify(bit, bit);
+    }
+    
+    /// 禁用PWM通道
+    pub unsafe fn disable_pwm_channel(&self, channel: PwmChannel) {
+        self.regs().ccer().modify(Self::channel_enable_bit(channel), 0);
+    }
+    
+    /// 阻塞延时（毫秒），不占用SysTick
+    /// 
+    /// 计数频率约为1MHz，按`delay_us`的方式分段轮询更新标志，返回时定时器已停止。
+    /// 会覆盖定时器原有的PSC、ARR配置。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用，且未启用其更新中断
+    pub unsafe fn delay_ms(&self, ms: u32) {
+        self.delay_micros(ms as u64 * 1000);
+    }
+    
+    /// 阻塞延时（微秒），不占用SysTick
+    /// 
+    /// 预分频取最接近1MHz计数频率的值，计数值按实际计数频率换算，
+    /// 定时器时钟不是1MHz整数倍时延时仍然准确。
+    /// 每段最多65536个计数，轮询UIF直到累计达到延时时间。
+    /// 每段之间有若干条指令的软件开销，短延时的误差约为1~2微秒。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用，且未启用其更新中断
+    pub unsafe fn delay_us(&self, us: u32) {
+        self.delay_micros(us as u64);
+    }
+    
+    /// 延时指定的微秒数，按实际计数频率换算计数值
+    unsafe fn delay_micros(&self, us: u64) {
+        let timer_clk = self.get_timer_clock();
+        let prescaler = Self::delay_prescaler(timer_clk);
+        let ticks = Self::delay_ticks_for_us(us, timer_clk, prescaler);
+        if ticks == 0 {
+            return;
+        }
+        
+        self.init(prescaler, 0xFFFF);
+        let tim = self.regs();
+        let mut remaining = ticks;
+        while remaining > 0 {
+            let chunk = if remaining > DELAY_CHUNK_TICKS { DELAY_CHUNK_TICKS } else { remaining };
+            tim.arr().write((chunk - 1) as u32);
+            tim.cnt().write(0);
+            tim.sr().write(0);
+            self.start();
+            while !self.has_update() {
+                core::hint::spin_loop();
+            }
+            self.stop();
+            remaining -= chunk;
+        }
+        tim.sr().write(0);
+    }
+    
+    /// 计算最接近1MHz计数频率的预分频值
+    /// 
+    /// # 参数
+    /// * `timer_clk` - 定时器时钟频率（Hz）
+    /// 
+    /// # 返回值
+    /// 分频系数四舍五入后减1，例如4.5MHz下为4（计数频率0.9MHz）；
+    /// 定时器时钟低于1.5MHz时为0（不分频）
+    pub const fn delay_prescaler(timer_clk: u32) -> u16 {
+        let div = (timer_clk as u64 + 500_000) / 1_000_000;
+        if div <= 1 {
+            0
+        } else if div > 0x1_0000 {
+            0xFFFF
+        } else {
+            (div - 1) as u16
+        }
+    }
+    
+    /// 将微秒数换算为给定预分频下的计数值，向上取整，保证延时不短于请求值
+    /// 
+    /// # 参数
+    /// * `us` - 延时时间（微秒）
+    /// * `timer_clk` - 定时器时钟频率（Hz）
+    /// * `prescaler` - PSC寄存器值
+    pub const fn delay_ticks_for_us(us: u64, timer_clk: u32, prescaler: u16) -> u64 {
+        let denom = (prescaler as u64 + 1) * 1_000_000;
+        (us * timer_clk as u64).div_ceil(denom)
+    }
+    
+    /// 初始化单脉冲模式
+    /// 
+    /// ARR设为`pulse_width`并置位CR1.OPM，调用`trigger_one_pulse`后计数器从0计数到ARR，
+    /// 更新事件时硬件自动清除CEN停止计数。需要在引脚上输出脉冲时再调用`enable_one_pulse_output`。
+    /// 
+    /// # 参数
+    /// * `prescaler` - 预分频器值
+    /// * `pulse_width` - 脉冲宽度（计数周期数，1~65535）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn init_one_pulse(&self, prescaler: u16, pulse_width: u16) {
+        assert!(pulse_width > 0, "Pulse width must be at least one tick");
+        
+        // init产生UG装载PSC并清除UIF，之后再启用单脉冲模式
+        self.init(prescaler, pulse_width);
+        self.regs().cr1().modify(CR1_OPM, CR1_OPM);
+    }
+    
+    /// 在比较通道上输出单脉冲
+    /// 
+    /// 通道配置为PWM模式2、CCR = 1：触发后延迟1个计数周期输出有效电平，
+    /// 持续`pulse_width`个计数周期后计数器停止并回到0，输出恢复无效电平。
+    /// 需在`init_one_pulse`之后调用，通道引脚需配置为复用推挽输出。
+    /// 
+    /// # 参数
+    /// * `channel` - 比较通道
+    /// * `polarity` - 脉冲的有效电平
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn enable_one_pulse_output(&self, channel: PwmChannel, polarity: PwmPolarity) {
+        let tim = self.regs();
+        let (mask, value) = Self::output_compare_ccmr(channel, OCM_PWM2);
+        let enable = Self::channel_enable_bit(channel);
+        let polarity_bit = enable << 1;
+        
+        // 通道先关闭才能修改CCxS
+        tim.ccer().modify(enable | polarity_bit, 0);
+        tim.ccmr(channel).modify(mask, value);
+        tim.ccr(channel).write(1);
+        let polarity_value = match polarity {
+            PwmPolarity::High => 0,
+            PwmPolarity::Low => polarity_bit,
+        };
+        tim.ccer().modify(enable | polarity_bit, enable | polarity_value);
+        
+        if let Some(bdtr) = tim.bdtr() {
+            bdtr.modify(BDTR_MOE, BDTR_MOE);
+        }
+    }
+    
+    /// 触发一次单脉冲（置位CEN）
+    /// 
+    /// 脉冲结束前再次调用不会重新开始计数
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn trigger_one_pulse(&self) {
+        self.start();
+    }
+    
+    /// 计算比较输出通道的CCMR配置
+    /// 
+    /// # 参数
+    /// * `channel` - 比较通道
+    /// * `ocm` - OCxM输出比较模式（3位）
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：CCxS = 00（输出），OCxPE = 0，OCxM = `ocm`，
+    /// 掩码只覆盖该通道在`TimRegs::ccmr`中所在的字节
+    pub const fn output_compare_ccmr(channel: PwmChannel, ocm: u32) -> (u32, u32) {
+        let shift = 8 * (channel as u32 % 2);
+        (0xFF << shift, (ocm & 0b111) << (shift + 4))
+    }
+    
+    /// 初始化带互补输出（CHxN）和死区的PWM，用于半桥驱动
+    /// 
+    /// 在`init_pwm`的基础上使能CCxNE，互补输出的有效电平与主输出相同（CCxNP = CCxP），
+    /// 并按定时器时钟把死区时间换算写入BDTR.DTG（CKD = 00，tDTS = 定时器时钟周期）。
+    /// 主输出和互补输出引脚都需配置为复用推挽输出。
+    /// 
+    /// # 参数
+    /// * `channel` - PWM通道（1~3，通道4没有互补输出）
+    /// * `mode` - PWM模式
+    /// * `polarity` - 主输出和互补输出的有效电平
+    /// * `dead_time_ns` - 死区时间（纳秒），72MHz下最大约14000ns
+    /// * `period` - 自动重装载值
+    /// * `prescaler` - 预分频器值
+    /// * `initial_duty` - 初始比较值
+    /// 
+    /// # 返回值
+    /// 非高级定时器或通道4返回`UnsupportedFeature`，死区时间超出范围返回`InvalidParameter`，
+    /// 出错时不修改任何寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn init_pwm_complementary(
+        &self,
+        channel: PwmChannel,
+        mode: PwmMode,
+        polarity: PwmPolarity,
+        dead_time_ns: u32,
+        period: u16,
+        prescaler: u16,
+        initial_duty: u16,
+    ) -> Result<(), TimerError> {
+        if channel == PwmChannel::Channel4 {
+            return Err(TimerError::UnsupportedFeature);
+        }
+        let bdtr = self.bdtr()?;
+        let dtg = Self::dead_time_dtg(Self::dead_time_ticks(dead_time_ns, self.get_timer_clock()))?;
+        
+        self.init_pwm(channel, mode, polarity, period, prescaler, initial_duty);
+        
+        let (mask, value) = Self::complementary_ccer_bits(channel, polarity);
+        self.regs().ccer().modify(mask, value);
+        bdtr.modify(BDTR_DTG_MASK | BDTR_MOE, dtg as u32 | BDTR_MOE);
+        Ok(())
+    }
+    
+    /// 设置死区时间（BDTR.DTG）
+    /// 
+    /// 按定时器时钟把纳秒换算为周期数并编码，BDTR.LOCK级别不低于1时DTG被锁定，
+    /// 需在`set_lock_level`之前配置。
+    /// 
+    /// # 返回值
+    /// 非高级定时器返回`UnsupportedFeature`；死区时间超出范围时写入最大值（1008个周期）
+    /// 并返回`InvalidParameter`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn set_dead_time(&self, ns: u32) -> Result<(), TimerError> {
+        let bdtr = self.bdtr()?;
+        let (dtg, result) = match Self::dead_time_dtg(Self::dead_time_ticks(ns, self.get_timer_clock())) {
+            Ok(dtg) => (dtg, Ok(())),
+            Err(error) => (0xFF, Err(error)),
+        };
+        bdtr.modify(BDTR_DTG_MASK, dtg as u32);
+        result
+    }
+    
+    /// 设置BDTR.LOCK写保护级别
+    /// 
+    /// LOCK复位后只能写入一次，之后直到系统复位都不能修改，应在死区、刹车、空闲电平等
+    /// 配置完成后最后调用。
+    /// 
+    /// # 参数
+    /// * `level` - 写保护级别
+    /// 
+    /// # 返回值
+    /// 非高级定时器返回`UnsupportedFeature`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保死区、刹车和空闲电平已配置完成，写入后直到复位都不能修改
+    pub unsafe fn set_lock_level(&self, level: TimerLockLevel) -> Result<(), TimerError> {
+        let bdtr = self.bdtr()?;
+        let (mask, value) = Self::lock_bdtr_bits(level);
+        bdtr.modify(mask, value);
+        Ok(())
+    }
+    
+    /// 计算写保护级别的BDTR位
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：LOCK（位9:8）
+    pub const fn lock_bdtr_bits(level: TimerLockLevel) -> (u32, u32) {
+        (BDTR_LOCK_MASK, (level as u32) << BDTR_LOCK_SHIFT)
+    }
+    
+    /// 配置刹车输入（BKIN）
+    /// 
+    /// 刹车输入有效时硬件清除MOE，所有输出切换到空闲电平（见`set_idle_state`）。
+    /// 刹车引脚需配置为浮空或上拉/下拉输入，BDTR.LOCK级别不低于1时BKE/BKP被锁定。
+    /// 
+    /// # 参数
+    /// * `polarity` - 刹车输入的有效电平
+    /// * `enable` - 是否启用刹车输入
+    /// 
+    /// # 返回值
+    /// 非高级定时器返回`UnsupportedFeature`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn config_break(&self, polarity: BreakPolarity, enable: bool) -> Result<(), TimerError> {
+        let bdtr = self.bdtr()?;
+        let (mask, value) = Self::break_bdtr_bits(polarity, enable);
+        bdtr.modify(mask, value);
+        Ok(())
+    }
+    
+    /// 计算刹车配置的BDTR位
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：BKE（位12）和BKP（位13）
+    pub const fn break_bdtr_bits(polarity: BreakPolarity, enable: bool) -> (u32, u32) {
+        let mut value = 0;
+        if enable {
+            value |= BDTR_BKE;
+        }
+        if let BreakPolarity::High = polarity {
+            value |= BDTR_BKP;
+        }
+        (BDTR_BKE | BDTR_BKP, value)
+    }
+    
+    /// 计算互补输出的CCER位
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：CCxNE置位，CCxNP按极性设置
+    pub const fn complementary_ccer_bits(channel: PwmChannel, polarity: PwmPolarity) -> (u32, u32) {
+        let enable = Self::channel_enable_bit(channel) << 2;
+        let polarity_bit = enable << 1;
+        let value = match polarity {
+            PwmPolarity::High => enable,
+            PwmPolarity::Low => enable | polarity_bit,
+        };
+        (enable | polarity_bit, value)
+    }
+    
+    /// 将死区时间换算为定时器时钟周期数（四舍五入）
+    pub const fn dead_time_ticks(ns: u32, timer_clk: u32) -> u32 {
+        ((ns as u64 * timer_clk as u64 + 500_000_000) / 1_000_000_000) as u32
+    }
+    
+    /// 将死区时钟周期数编码为BDTR.DTG
+    /// 
+    /// 参考手册的四段编码（tDTS为定时器时钟周期）：
+    /// - DTG[7] = 0：DT = DTG[6:0] × tDTS，0~127
+    /// - DTG[7:5] = 10：DT = (64 + DTG[5:0]) × 2 × tDTS，128~254
+    /// - DTG[7:5] = 110：DT = (32 + DTG[4:0]) × 8 × tDTS，256~504
+    /// - DTG[7:5] = 111：DT = (32 + DTG[4:0]) × 16 × tDTS，512~1008
+    /// 
+    /// 各段内向下取到可表示的值
+    /// 
+    /// # 返回值
+    /// 超过1008个周期时返回`InvalidParameter`
+    pub const fn dead_time_dtg(ticks: u32) -> Result<u8, TimerError> {
+        if ticks < 128 {
+            Ok(ticks as u8)
+        } else if ticks < 256 {
+            Ok(0x80 | (ticks / 2 - 64) as u8)
+        } else if ticks < 512 {
+            Ok(0xC0 | (ticks / 8 - 32) as u8)
+        } else if ticks <= 1008 {
+            Ok(0xE0 | (ticks / 16 - 32) as u8)
+        } else {
+            Err(TimerError::InvalidParameter)
+        }
+    }
+    
+    /// 将更新事件作为TRGO输出（CR2.MMS = 010）
+    /// 
+    /// 常用于每个PWM周期触发一次ADC注入转换
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn enable_update_trgo(&self) {
+        self.modify_cr2(CR2_MMS_MASK, CR2_MMS_UPDATE);
+    }
+    
+    /// 设置主模式（CR2.MMS），选择输出到TRGO供其他定时器或ADC使用的信号
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn set_master_mode(&self, mms: MasterMode) {
+        self.modify_cr2(CR2_MMS_MASK, Self::master_mode_cr2(mms));
+    }
+    
+    /// 设置从模式和触发源（SMCR.SMS/TS）
+    /// 
+    /// 例如将TIM3设为`SlaveMode::ExternalClock`、触发源为TIM2的ITRx，TIM2设为`MasterMode::Update`，
+    /// TIM3即对TIM2的溢出计数，两级级联得到32位时基。内部触发源可由`TriggerSource::from_master`获得。
+    /// 修改TS前先关闭从模式，避免切换过程中产生误触发。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn set_slave_mode(&self, sms: SlaveMode, trigger: TriggerSource) {
+        self.modify_smcr(SMCR_SMS_TS_MASK, 0);
+        self.modify_smcr(SMCR_SMS_TS_MASK, Self::slave_mode_smcr(sms, trigger));
+    }
+    
+    /// 主模式对应的CR2.MMS字段值
+    pub const fn master_mode_cr2(mms: MasterMode) -> u32 {
+        (mms as u32) << 4
+    }
+    
+    /// 从模式和触发源对应的SMCR.SMS/TS字段值
+    pub const fn slave_mode_smcr(sms: SlaveMode, trigger: TriggerSource) -> u32 {
+        ((trigger as u32) << 4) | sms as u32
+    }
+    
+    /// 软件产生刹车事件
+    /// 
+    /// 置位EGR.BG，硬件立即清除BDTR.MOE并置位SR.BIF，所有PWM输出（含互补输出）被关闭。
+    /// 仅高级定时器TIM1支持刹车功能。
+    /// 
+    /// # 返回值
+    /// 非高级定时器返回`UnsupportedFeature`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn generate_break(&self) -> Result<(), TimerError> {
+        self.bdtr()?;
+        self.regs().egr().write(EGR_BG);
+        Ok(())
+    }
+    
+    /// 检查刹车标志（SR.BIF）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn has_break(&self) -> bool {
+        let tim = self.regs();
+        tim.bdtr().is_some() && tim.sr().read() & SR_BIF != 0
+    }
+    
+    /// 设置PWM通道的空闲输出电平（CR2.OISx）
+    /// 
+    /// 当MOE被清零（禁用输出或刹车）时，通道输出切换到此电平，用于继电器、阀门等需要失效安全状态的负载。
+    /// 仅高级定时器TIM1支持；BDTR.LOCK级别不低于1时OISx被锁定，需在锁定前配置。
+    /// 
+    /// 通用定时器（TIM2-TIM4）没有空闲状态位，停止时输出保持当前比较电平，
+    /// 需要在停止定时器后将对应引脚切换为普通推挽输出并显式驱动到安全电平。
+    /// 
+    /// # 参数
+    /// * `channel` - PWM通道
+    /// * `level` - 空闲电平，true为高电平
+    /// 
+    /// # 返回值
+    /// 非高级定时器返回`UnsupportedFeature`
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn set_idle_state(&self, channel: PwmChannel, level: bool) -> Result<(), TimerError> {
+        if self.number != TimerNumber::TIM1 {
+            return Err(TimerError::UnsupportedFeature);
+        }
+        
+        let mask = Self::idle_state_bit(channel);
+        self.modify_cr2(mask, if level { mask } else { 0 });
+        Ok(())
+    }
+    
+    /// 获取通道对应的CR2.OISx位
+    pub const fn idle_state_bit(channel: PwmChannel) -> u32 {
+        match channel {
+            PwmChannel::Channel1 => 1 << 8,   // OIS1
+            PwmChannel::Channel2 => 1 << 10,  // OIS2
+            PwmChannel::Channel3 => 1 << 12,  // OIS3
+            PwmChannel::Channel4 => 1 << 14,  // OIS4
+        }
+    }
+    
+    /// 配置通道为输入捕获模式
+    /// 
+    /// ICx直接映射到对应的TIx（CCxS = 01），不使用输入滤波，配置完成后使能通道
+    /// 
+    /// # 参数
+    /// * `channel` - 捕获通道，引脚需配置为浮空输入
+    /// * `polarity` - 捕获边沿
+    /// * `prescaler` - 捕获预分频
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn config_input_capture(&self, channel: PwmChannel, polarity: CapturePolarity, prescaler: CapturePrescaler) {
+        Self::config_input_capture_regs(self.regs(), channel, polarity, prescaler);
+    }
+    
+    /// 配置输入捕获的内部方法
+    unsafe fn config_input_capture_regs(
+        tim: &dyn TimRegs,
+        channel: PwmChannel,
+        polarity: CapturePolarity,
+        prescaler: CapturePrescaler,
+    ) {
+        let enable = Self::channel_enable_bit(channel);
+        let polarity_bit = enable << 1;
+        
+        // 通道先关闭才能修改CCxS
+        tim.ccer().modify(enable | polarity_bit, 0);
+        let (mask, value) = Self::input_capture_ccmr(channel, prescaler);
+        tim.ccmr(channel).modify(mask, value);
+        let polarity_value = match polarity {
+            CapturePolarity::Rising => 0,
+            CapturePolarity::Falling => polarity_bit,
+        };
+        tim.ccer().modify(enable | polarity_bit, enable | polarity_value);
+    }
+    
+    /// 计算输入捕获通道的CCMR配置
+    /// 
+    /// # 返回值
+    /// (掩码, 值)：CCxS = 01（ICx映射到TIx），ICxPSC = `prescaler`，ICxF = 0000，
+    /// 掩码只覆盖该通道所在的字节
+    pub const fn input_capture_ccmr(channel: PwmChannel, prescaler: CapturePrescaler) -> (u32, u32) {
+        let shift = 8 * (channel as u32 % 2);
+        (0xFF << shift, (CCMR1_CC1S_TI1 | (prescaler as u32) << 2) << shift)
+    }
+    
+    /// 注册输入捕获回调并启用对应通道的捕获/比较中断（DIER.CCxIE）
+    /// 
+    /// 在定时器中断服务函数中调用`capture_interrupt_handler`，捕获发生时会以CCRx的值调用回调。
+    /// 通道需已配置为输入捕获模式（`config_input_capture`）。
+    /// 
+    /// # 参数
+    /// * `channel` - 捕获通道
+    /// * `handler` - 回调函数，参数为捕获值，在中断上下文中执行
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道已配置为输入捕获，且NVIC中已启用对应中断
+    pub unsafe fn on_capture(&self, channel: PwmChannel, handler: fn(u16)) {
+        CAPTURE_HANDLERS.register(self.number, channel, Some(handler));
+        let bit = Self::capture_interrupt_bit(channel);
+        self.regs().dier().modify(bit, bit);
+    }
+    
+    /// 关闭通道的捕获/比较中断并注销回调
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn remove_capture_handler(&self, channel: PwmChannel) {
+        self.regs().dier().modify(Self::capture_interrupt_bit(channel), 0);
+        CAPTURE_HANDLERS.register(self.number, channel, None);
+    }
+    
+    /// 捕获/比较中断处理，应在TIMx（TIM1为TIM1_CC）中断服务函数中调用
+    /// 
+    /// 对每个标志置位且已启用中断的通道读取CCRx并调用回调，然后清除对应的CCxIF标志。
+    /// 
+    /// # Safety
+    /// - 只能在该定时器的捕获/比较中断服务函数中调用
+    pub unsafe fn capture_interrupt_handler(&self) {
+        let tim = self.regs();
+        let sr = tim.sr().read();
+        let dier = tim.dier().read();
+        let handlers = CAPTURE_HANDLERS.handlers(self.number);
+        
+        let handled = dispatch_captures(sr, dier, &handlers, |channel| {
+            tim.ccr(channel).read() as u16
+        });
+        // SR标志为rc_w0，写1的位保持不变
+        if handled != 0 {
+            tim.sr().write(!handled & 0xFFFF);
+        }
+    }
+    
+    /// 获取通道对应的DIER.CCxIE位（与SR.CCxIF位号相同）
+    pub const fn capture_interrupt_bit(channel: PwmChannel) -> u32 {
+        1 << (channel as u32 + 1)
+    }
+    
+    /// 启动输入捕获DMA传输
+    /// 
+    /// 启用通道的CCxDE请求，每次捕获由DMA把CCRx搬运到`buf`，不需要CPU参与，
+    /// 适合捕获一整帧边沿（如红外遥控）后再统一解码。缓冲区写满后传输停止，
+    /// 用`CaptureDmaTransfer::wait`取回缓冲区。
+    /// 
+    /// 通道需已配置为输入捕获模式，定时器需已启动。
+    /// 
+    /// # 参数
+    /// * `channel` - 捕获通道，TIM3通道2和TIM4通道4没有DMA请求
+    /// * `buf` - 捕获值缓冲区，长度不超过65535
+    /// 
+    /// # 返回值
+    /// 通道没有DMA请求返回`UnsupportedFeature`，缓冲区过长返回`InvalidParameter`，
+    /// 出错时不修改任何寄存器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保通道对应的DMA1通道未被其他代码占用
+    pub unsafe fn start_capture_dma(&self, channel: PwmChannel, buf: &'static mut [u16]) -> Result<CaptureDmaTransfer, TimerError> {
+        let dma = Self::capture_dma_channel(self.number, channel).ok_or(TimerError::UnsupportedFeature)?;
+        if buf.len() > u16::MAX as usize {
+            return Err(TimerError::InvalidParameter);
+        }
+        
+        dma.init(
+            DmaDirection::PeripheralToMemory,
+            DmaPeripheralIncrementMode::Disabled,
+            DmaMemoryIncrementMode::Enabled,
+            DmaPeripheralDataSize::HalfWord,
+            DmaMemoryDataSize::HalfWord,
+            DmaChannelPriority::High,
+            DmaCircularMode::Disabled,
+        );
+        let tim = self.regs();
+        dma.clear_interrupt(DmaInterrupt::TransferComplete);
+        dma.configure_transfer(tim.ccr(channel).address(), buf.as_mut_ptr() as u32, buf.len() as u16);
+        dma.enable();
+        
+        // 清除已挂起的捕获标志，避免启动时立即搬运一个旧值
+        tim.sr().write(!Self::capture_interrupt_bit(channel) & 0xFFFF);
+        let bit = Self::capture_dma_bit(channel);
+        tim.dier().modify(bit, bit);
+        
+        Ok(CaptureDmaTransfer {
+            timer: Timer::new(self.number),
+            channel,
+            dma,
+            buf,
+        })
+    }
+    
+    /// 获取通道对应的DIER.CCxDE位
+    pub const fn capture_dma_bit(channel: PwmChannel) -> u32 {
+        1 << (channel as u32 + 9)
+    }
+    
+    /// 获取定时器通道固定映射的DMA1通道
+    /// 
+    /// # 返回值
+    /// 没有DMA请求的通道（TIM3_CH2、TIM4_CH4）返回None
+    pub const fn capture_dma_channel(timer: TimerNumber, channel: PwmChannel) -> Option<Dma> {
+        let dma_channel = match (timer, channel) {
+            (TimerNumber::TIM1, PwmChannel::Channel1) => DmaChannel::Channel2,
+            (TimerNumber::TIM1, PwmChannel::Channel2) => DmaChannel::Channel3,
+            (TimerNumber::TIM1, PwmChannel::Channel3) => DmaChannel::Channel6,
+            (TimerNumber::TIM1, PwmChannel::Channel4) => DmaChannel::Channel4,
+            (TimerNumber::TIM2, PwmChannel::Channel1) => DmaChannel::Channel5,
+            (TimerNumber::TIM2, PwmChannel::Channel2) => DmaChannel::Channel7,
+            (TimerNumber::TIM2, PwmChannel::Channel3) => DmaChannel::Channel1,
+            (TimerNumber::TIM2, PwmChannel::Channel4) => DmaChannel::Channel7,
+            (TimerNumber::TIM3, PwmChannel::Channel1) => DmaChannel::Channel6,
+            (TimerNumber::TIM3, PwmChannel::Channel3) => DmaChannel::Channel2,
+            (TimerNumber::TIM3, PwmChannel::Channel4) => DmaChannel::Channel3,
+            (TimerNumber::TIM4, PwmChannel::Channel1) => DmaChannel::Channel1,
+            (TimerNumber::TIM4, PwmChannel::Channel2) => DmaChannel::Channel4,
+            (TimerNumber::TIM4, PwmChannel::Channel3) => DmaChannel::Channel5,
+            (TimerNumber::TIM3, PwmChannel::Channel2) | (TimerNumber::TIM4, PwmChannel::Channel4) => return None,
+        };
+        Some(Dma::new(1, dma_channel))
+    }
+    
+    /// 配置从模式门控模式，门控信号为TI1（通道1引脚）
+    /// 
+    /// SMCR.SMS = 101、TS = 101（TI1FP1），CC1配置为输入并映射到TI1，CC1P = 0：
+    /// 计数器只在TI1为高电平时计数，低电平时暂停但不复位。
+    /// 计数频率由`init`设置的预分频决定。
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn configure_gated_mode(&self) {
+        let tim = self.regs();
+        // 通道1先关闭才能修改CC1S
+        tim.ccer().modify(CCER_CC1E | CCER_CC1P, 0);
+        tim.ccmr1().modify(CCMR1_CC1S_MASK, CCMR1_CC1S_TI1);
+        tim.smcr().modify(SMCR_SMS_TS_MASK, Self::gated_smcr());
+    }
+    
+    /// 测量门控信号一次高电平的持续时间
+    /// 
+    /// 等待门控信号为低后清零计数器并启动，门控信号经历一次完整的高电平后停止，
+    /// 返回的计数值即高电平持续的计数周期数，用`gated_duration_us`换算为时间。
+    /// 高电平超过`(ARR + 1)`个计数周期时计数器会回绕，需选择足够大的预分频。
+    /// 
+    /// # 参数
+    /// * `gate_pin` - 门控信号引脚，必须为该定时器的通道1引脚（如TIM2为PA0、TIM3为PA6）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器已用`init`初始化，且未被其他代码同时使用
+    /// - 门控信号不翻转时该函数不会返回
+    pub unsafe fn measure_gated_duration(&self, gate_pin: GpioPortStruct) -> u16 {
+        gate_pin.into_floating_input();
+        self.configure_gated_mode();
+        
+        let gate_high = || GpioPortBatch::new(gate_pin.port).read_input_data() & (1 << gate_pin.pin) != 0;
+        
+        self.stop();
+        while gate_high() {
+            core::hint::spin_loop();
+        }
+        self.set_count(0);
+        self.start();
+        
+        while !gate_high() {
+            core::hint::spin_loop();
+        }
+        while gate_high() {
+            core::hint::spin_loop();
+        }
+        
+        self.stop();
+        self.get_count()
+    }
+    
+    /// 初始化PWM输入模式，测量通道1引脚上PWM信号的周期和高电平时间
+    /// 
+    /// CC1和CC2都映射到TI1：IC1上升沿捕获周期，IC2下降沿捕获高电平时间；
+    /// 从模式为复位模式（SMS = 100，TS = TI1FP1），每个上升沿清零计数器。
+    /// 信号周期超过65536个计数周期时计数器回绕，需选择足够大的预分频。
+    /// 
+    /// # 参数
+    /// * `prescaler` - 预分频器值，计数频率为定时器时钟 / (prescaler + 1)
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用，通道1引脚已配置为浮空输入
+    pub unsafe fn init_pwm_input(&self, prescaler: u16) {
+        self.init(prescaler, 0xFFFF);
+        
+        let tim = self.regs();
+        let (ccmr1, ccer, smcr) = Self::pwm_input_config();
+        // 通道先关闭才能修改CCxS
+        tim.ccer().modify(0xFF, 0);
+        tim.ccmr1().modify(0xFFFF, ccmr1);
+        tim.ccer().modify(0xFF, ccer);
+        tim.smcr().modify(SMCR_SMS_TS_MASK, smcr);
+        self.start();
+    }
+    
+    /// 读取PWM输入测量结果
+    /// 
+    /// # 返回值
+    /// (周期计数, 高电平计数)，用`pwm_input_frequency_hz`和`pwm_input_duty_permille`换算
+    /// 
+    /// # Safety
+    /// - 调用者必须确保已调用`init_pwm_input`
+    pub unsafe fn read_pwm_input(&self) -> (u16, u16) {
+        let tim = self.regs();
+        let period = tim.ccr(PwmChannel::Channel1).read() as u16;
+        let high = tim.ccr(PwmChannel::Channel2).read() as u16;
+        (period, high)
+    }
+    
+    /// PWM输入模式的寄存器配置
+    /// 
+    /// # 返回值
+    /// (CCMR1, CCER低8位, SMCR的SMS/TS)：CC1S = 01、CC2S = 10，CC1上升沿、CC2下降沿，
+    /// TS = TI1FP1、SMS = 复位模式
+    pub const fn pwm_input_config() -> (u32, u32, u32) {
+        let ccmr1 = CCMR1_CC1S_TI1 | (0b10 << 8);
+        let ccer = CCER_CC1E | (CCER_CC1E | CCER_CC1P) << 4;
+        (ccmr1, ccer, SMCR_SMS_RESET | SMCR_TS_TI1FP1)
+    }
+    
+    /// 由周期计数计算信号频率（Hz），周期为0时返回0
+    pub const fn pwm_input_frequency_hz(period_ticks: u16, timer_clk: u32, prescaler: u16) -> u32 {
+        if period_ticks == 0 {
+            return 0;
+        }
+        timer_clk / ((prescaler as u32 + 1) * period_ticks as u32)
+    }
+    
+    /// 由周期和高电平计数计算占空比（千分比），周期为0时返回0
+    pub const fn pwm_input_duty_permille(period_ticks: u16, high_ticks: u16) -> u16 {
+        if period_ticks == 0 {
+            return 0;
+        }
+        let permille = high_ticks as u32 * 1000 / period_ticks as u32;
+        if permille > 1000 { 1000 } else { permille as u16 }
+    }
+    
+    /// 门控模式的SMCR配置值（SMS = 101，TS = 101）
+    pub const fn gated_smcr() -> u32 {
+        SMCR_SMS_GATED | SMCR_TS_TI1FP1
+    }
+    
+    /// 将门控计数值换算为微秒
+    /// 
+    /// # 参数
+    /// * `count` - 门控期间的计数值
+    /// * `timer_clk` - 定时器时钟频率（Hz）
+    /// * `prescaler` - 预分频值（PSC寄存器值）
+    pub const fn gated_duration_us(count: u16, timer_clk: u32, prescaler: u16) -> u32 {
+        (count as u64 * (prescaler as u64 + 1) * 1_000_000 / timer_clk as u64) as u32
+    }
+    
+    /// 检查PWM通道输出是否已使能（CCER.CCxE）
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
+    pub unsafe fn is_channel_enabled(&self, channel: PwmChannel) -> bool {
+        Self::ccer_channel_enabled(self.regs().ccer().read(), channel)
+    }
+    
+    /// 获取通道对应的CCER.CCxE位
+    pub const fn channel_enable_bit(channel: PwmChannel) -> u32 {
+        1 << (4 * channel as u32)
+    }
+    
+    /// 由CCER值判断通道是否使能
+    pub const fn ccer_channel_enabled(ccer: u32, channel: PwmChannel) -> bool {
+        ccer & Self::channel_enable_bit(channel) != 0
+    }
+    
+    /// 获取高级定时器的BDTR寄存器，通用定时器返回`UnsupportedFeature`
+    unsafe fn bdtr(&self) -> Result<TimReg, TimerError> {
+        self.regs().bdtr().ok_or(TimerError::UnsupportedFeature)
+    }
+    
+    /// 修改CR2寄存器中的指定位
+    unsafe fn modify_cr2(&self, mask: u32, value: u32) {
+        self.regs().cr2().modify(mask, value);
+    }
+    
+    /// 修改SMCR寄存器中的指定位
+    unsafe fn modify_smcr(&self, mask: u32, value: u32) {
+        self.regs().smcr().modify(mask, value);
+    }
+}
+
+/// 进行中的输入捕获DMA传输
+pub struct CaptureDmaTransfer {
+    timer: Timer,
+    channel: PwmChannel,
+    dma: Dma,
+    buf: &'static mut [u16],
+}
+
+impl CaptureDmaTransfer {
+    /// 获取已捕获的数量
+    /// # Safety
+    /// - 调用者必须确保DMA通道未被其他代码修改
+    pub unsafe fn captured(&self) -> usize {
+        self.buf.len() - self.dma.get_remaining_count() as usize
+    }
+    
+    /// 检查缓冲区是否已写满
+    /// # Safety
+    /// - 调用者必须确保DMA通道未被其他代码修改
+    pub unsafe fn is_complete(&self) -> bool {
+        self.dma.get_remaining_count() == 0
+    }
+    
+    /// 等待缓冲区写满，关闭DMA请求并取回缓冲区
+    /// # Safety
+    /// - 调用者必须确保DMA通道未被其他代码修改
+    pub unsafe fn wait(self) -> &'static mut [u16] {
+        while !self.is_complete() {
+            core::hint::spin_loop();
+        }
+        self.stop().0
+    }
+    
+    /// 立即停止传输（如一帧提前结束）
+    /// 
+    /// # 返回值
+    /// (缓冲区, 已捕获的数量)
+    /// 
+    /// # Safety
+    /// - 调用者必须确保DMA通道未被其他代码修改
+    pub unsafe fn stop(self) -> (&'static mut [u16], usize) {
+        self.timer.regs().dier().modify(Timer::capture_dma_bit(self.channel), 0);
+        self.dma.disable();
+        let captured = self.buf.len() - self.dma.get_remaining_count() as usize;
+        (self.buf, captured)
+    }
+}
+
+/// OCxM = 111：PWM模式2
+const OCM_PWM2: u32 = 0b111;
+/// BDTR.MOE：主输出使能
+const BDTR_MOE: u32 = 1 << 15;
+/// BDTR.DTG死区时间字段掩码
+const BDTR_DTG_MASK: u32 = 0xFF;
+/// BDTR.LOCK字段位置
+const BDTR_LOCK_SHIFT: u32 = 8;
+/// BDTR.LOCK字段掩码
+const BDTR_LOCK_MASK: u32 = 0b11 << BDTR_LOCK_SHIFT;
+/// BDTR.BKE：刹车输入使能
+const BDTR_BKE: u32 = 1 << 12;
+/// BDTR.BKP：刹车输入极性（1为高电平有效）
+const BDTR_BKP: u32 = 1 << 13;
+/// CCMR1.CC1S字段掩码
+const CCMR1_CC1S_MASK: u32 = 0b11;
+/// CCMR1.CC1S = 01：CC1为输入，IC1映射到TI1
+const CCMR1_CC1S_TI1: u32 = 0b01;
+/// CCER.CC1E：通道1使能
+const CCER_CC1E: u32 = 1 << 0;
+/// CCER.CC1P：通道1极性
+const CCER_CC1P: u32 = 1 << 1;
+/// SMCR.SMS = 101：门控模式
+const SMCR_SMS_GATED: u32 = 0b101;
+/// SMCR.SMS = 100：复位模式，触发输入上升沿清零计数器
+const SMCR_SMS_RESET: u32 = 0b100;
+/// SMCR.TS = 101：滤波后的定时器输入1（TI1FP1）
+const SMCR_TS_TI1FP1: u32 = 0b101 << 4;
+
+/// 按[定时器][通道]索引的输入捕获回调数组
+type CaptureHandlerTable = [[Option<fn(u16)>; 4]; 4];
+
+/// 输入捕获回调表
+/// 
+/// 每个定时器的每个通道一个回调，由`Timer::on_capture`注册，`Timer::capture_interrupt_handler`分发。
+pub struct CaptureHandlers {
+    handlers: UnsafeCell<CaptureHandlerTable>,
+}
+
+/// 实现 Sync trait，回调的修改都在临界区内进行
+unsafe impl Sync for CaptureHandlers {}
+
+impl CaptureHandlers {
+    /// 创建空的回调表
+    pub const fn new() -> Self {
+        Self {
+            handlers: UnsafeCell::new([[None; 4]; 4]),
         }
     }
     
-    /// 启用PWM通道
-    pub unsafe fn enable_pwm_channel(&self, channel: PwmChannel) {
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().set_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().set_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().set_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().set_bit()); },
-                }
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().set_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().set_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().set_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().set_bit()); },
-                }
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().set_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().set_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().set_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().set_bit()); },
-                }
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().set_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().set_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().set_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().set_bit()); },
-                }
-            },
+    /// 注册或注销回调
+    fn register(&self, number: TimerNumber, channel: PwmChannel, handler: Option<fn(u16)>) {
+        cortex_m::interrupt::free(|_| unsafe {
+            (*self.handlers.get())[number as usize][channel as usize] = handler;
+        });
+    }
+    
+    /// 获取定时器各通道的回调
+    fn handlers(&self, number: TimerNumber) -> [Option<fn(u16)>; 4] {
+        unsafe { (*self.handlers.get())[number as usize] }
+    }
+}
+
+impl Default for CaptureHandlers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 全局输入捕获回调表
+pub static CAPTURE_HANDLERS: CaptureHandlers = CaptureHandlers::new();
+
+/// 按SR和DIER分发捕获值
+/// 
+/// # 参数
+/// * `sr` - SR寄存器值
+/// * `dier` - DIER寄存器值，未启用中断的通道不处理
+/// * `handlers` - 各通道的回调
+/// * `read_ccr` - 读取通道CCRx的函数
+/// 
+/// # 返回值
+/// 已处理的CCxIF标志位，调用者需将其清除
+pub fn dispatch_captures<F: FnMut(PwmChannel) -> u16>(
+    sr: u32,
+    dier: u32,
+    handlers: &[Option<fn(u16)>; 4],
+    mut read_ccr: F,
+) -> u32 {
+    const CHANNELS: [PwmChannel; 4] = [PwmChannel::Channel1, PwmChannel::Channel2, PwmChannel::Channel3, PwmChannel::Channel4];
+    
+    let mut handled = 0;
+    for channel in CHANNELS {
+        let bit = Timer::capture_interrupt_bit(channel);
+        if sr & dier & bit == 0 {
+            continue;
+        }
+        // 读取CCRx同时会清除CCxIF，没有回调的通道也要读取以免中断反复进入
+        let value = read_ccr(channel);
+        if let Some(handler) = handlers[channel as usize] {
+            handler(value);
         }
+        handled |= bit;
+    }
+    handled
+}
+
+/// CR1.URS：只有计数器溢出产生更新中断，软件UG不触发中断
+const CR1_URS: u32 = 1 << 2;
+/// CR1.OPM：单脉冲模式，更新事件时自动清除CEN
+pub const CR1_OPM: u32 = 1 << 3;
+/// 延时时每段的最大计数值（ARR = 0xFFFF）
+const DELAY_CHUNK_TICKS: u64 = 0x1_0000;
+/// CR1.CEN：计数器使能
+const CR1_CEN: u32 = 1 << 0;
+/// SR.UIF：更新中断标志
+const SR_UIF: u32 = 1 << 0;
+/// DIER.UIE：更新中断使能
+const DIER_UIE: u32 = 1 << 0;
+/// SR.BIF：刹车中断标志（仅高级定时器）
+const SR_BIF: u32 = 1 << 7;
+/// EGR.UG：产生更新事件
+const EGR_UG: u32 = 1 << 0;
+
+/// 按定时器索引的超时回调数组
+type TimeoutCallbackTable = [Option<fn()>; 4];
+
+/// 单次超时回调表
+struct TimeoutCallbacks {
+    callbacks: UnsafeCell<TimeoutCallbackTable>,
+}
+
+/// 实现 Sync trait，回调的修改都在临界区内进行
+unsafe impl Sync for TimeoutCallbacks {}
+
+impl TimeoutCallbacks {
+    /// 设置定时器的回调
+    fn set(&self, number: TimerNumber, callback: Option<fn()>) {
+        cortex_m::interrupt::free(|_| unsafe {
+            (*self.callbacks.get())[number as usize] = callback;
+        });
     }
     
-    /// 禁用PWM通道
-    pub unsafe fn disable_pwm_channel(&self, channel: PwmChannel) {
-        match self.number {
-            TimerNumber::TIM1 => {
-                let tim = self.get_tim1();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().clear_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().clear_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().clear_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().clear_bit()); },
-                }
-            },
-            TimerNumber::TIM2 => {
-                let tim = self.get_tim2();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().clear_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().clear_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().clear_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().clear_bit()); },
-                }
-            },
-            TimerNumber::TIM3 => {
-                let tim = self.get_tim3();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().clear_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().clear_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().clear_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().clear_bit()); },
-                }
-            },
-            TimerNumber::TIM4 => {
-                let tim = self.get_tim4();
-                match channel {
-                    PwmChannel::Channel1 => { tim.ccer().modify(|_, w| w.cc1e().clear_bit()); },
-                    PwmChannel::Channel2 => { tim.ccer().modify(|_, w| w.cc2e().clear_bit()); },
-                    PwmChannel::Channel3 => { tim.ccer().modify(|_, w| w.cc3e().clear_bit()); },
-                    PwmChannel::Channel4 => { tim.ccer().modify(|_, w| w.cc4e().clear_bit()); },
-                }
-            },
+    /// 取出定时器的回调，取出后清空
+    fn take(&self, number: TimerNumber) -> Option<fn()> {
+        cortex_m::interrupt::free(|_| unsafe {
+            (*self.callbacks.get())[number as usize].take()
+        })
+    }
+}
+
+/// 全局单次超时回调表
+static TIMEOUT_CALLBACKS: TimeoutCallbacks = TimeoutCallbacks {
+    callbacks: UnsafeCell::new([None; 4]),
+};
+
+/// 单次超时定时器
+/// 
+/// 使用定时器的单脉冲模式在指定毫秒后调用一次回调，与周期性定时不同，触发后定时器自动停止。
+/// 需要在对应的定时器中断服务函数（TIM1为TIM1_UP）中调用`Timeout::on_interrupt`。
+pub struct Timeout;
+
+impl Timeout {
+    /// 在指定毫秒后调用一次回调
+    /// 
+    /// 重新调用会取消该定时器上尚未触发的超时。
+    /// 
+    /// # 参数
+    /// * `timer` - 使用的定时器
+    /// * `ms` - 延时毫秒数（1~59000，72MHz定时器时钟下最长约59.6秒）
+    /// * `callback` - 回调函数，在中断上下文中执行
+    /// 
+    /// # 返回值
+    /// 延时为0或超出16位PSC×ARR范围时返回`InvalidParameter`，此时不修改定时器
+    /// 
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用，且已在NVIC中启用对应中断
+    pub unsafe fn after_ms(timer: TimerNumber, ms: u32, callback: fn()) -> Result<(), TimerError> {
+        let tim = Timer::new(timer);
+        let (psc, arr) = Self::psc_arr_for_ms(tim.get_timer_clock(), ms)?;
+        
+        tim.stop();
+        TIMEOUT_CALLBACKS.set(timer, Some(callback));
+        
+        // init产生UG装载PSC并清除UIF，之后再启用单脉冲模式
+        tim.init(psc, arr);
+        tim.regs().cr1().modify(Self::cr1_bits(), Self::cr1_bits());
+        tim.enable_update_interrupt();
+        tim.start();
+        Ok(())
+    }
+    
+    /// 取消尚未触发的超时
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn cancel(timer: TimerNumber) {
+        let tim = Timer::new(timer);
+        tim.stop();
+        tim.disable_update_interrupt();
+        tim.clear_update();
+        TIMEOUT_CALLBACKS.set(timer, None);
+    }
+    
+    /// 更新中断处理，应在定时器中断服务函数中调用
+    /// # Safety
+    /// - 调用者必须确保定时器未被其他代码同时使用
+    pub unsafe fn on_interrupt(timer: TimerNumber) {
+        let tim = Timer::new(timer);
+        if !tim.has_update() {
+            return;
+        }
+        tim.clear_update();
+        tim.disable_update_interrupt();
+        
+        if let Some(callback) = TIMEOUT_CALLBACKS.take(timer) {
+            callback();
+        }
+    }
+    
+    /// 计算延时对应的预分频和自动重装载值
+    /// 
+    /// 预分频取能使ARR不超过65535的最小值，以获得最高的分辨率。
+    /// 
+    /// # 参数
+    /// * `timer_clk` - 定时器时钟频率（Hz）
+    /// * `ms` - 延时毫秒数
+    /// 
+    /// # 返回值
+    /// (PSC, ARR)，延时为`(PSC + 1) × (ARR + 1) / timer_clk`；
+    /// 延时不足一个计数周期或超过65536×65536个计数时返回`InvalidParameter`
+    pub const fn psc_arr_for_ms(timer_clk: u32, ms: u32) -> Result<(u16, u16), TimerError> {
+        let ticks = timer_clk as u64 * ms as u64 / 1000;
+        let divider = ticks.div_ceil(65536);
+        if ticks == 0 || divider > 65536 {
+            return Err(TimerError::InvalidParameter);
         }
+        
+        let arr = (ticks + divider / 2) / divider;
+        Ok(((divider - 1) as u16, (arr - 1) as u16))
+    }
+    
+    /// 单次超时需要置位的CR1位（OPM和URS）
+    pub const fn cr1_bits() -> u32 {
+        CR1_OPM | CR1_URS
+    }
+}
+
+/// CR2.MMS字段掩码
+const CR2_MMS_MASK: u32 = 0b111 << 4;
+/// CR2.MMS = 001：计数器使能信号CNT_EN作为TRGO
+const CR2_MMS_ENABLE: u32 = 0b001 << 4;
+/// CR2.MMS = 010：更新事件作为TRGO
+const CR2_MMS_UPDATE: u32 = 0b010 << 4;
+/// EGR.BG：软件产生刹车事件
+pub const EGR_BG: u32 = 1 << 7;
+/// SMCR.SMS、TS字段掩码
+const SMCR_SMS_TS_MASK: u32 = 0b111 | (0b111 << 4);
+/// SMCR.SMS = 110：触发模式，计数器在TRGI上升沿启动
+const SMCR_SMS_TRIGGER: u32 = 0b110;
+/// SMCR.MSM：主/从模式，延迟主定时器的触发输入以实现完美同步
+const SMCR_MSM: u32 = 1 << 7;
+
+/// 获取从定时器连接到主定时器TRGO的内部触发输入ITRx编号
+/// 
+/// 参考手册“TIMx内部触发连接”表：
+/// - TIM1：ITR0=TIM5，ITR1=TIM2，ITR2=TIM3，ITR3=TIM4
+/// - TIM2：ITR0=TIM1，ITR1=TIM8，ITR2=TIM3，ITR3=TIM4
+/// - TIM3：ITR0=TIM1，ITR1=TIM2，ITR2=TIM5，ITR3=TIM4
+/// - TIM4：ITR0=TIM1，ITR1=TIM2，ITR2=TIM3，ITR3=TIM8
+/// 
+/// # 返回值
+/// ITRx编号（即SMCR.TS的值），主从为同一定时器时返回None
+pub const fn internal_trigger_source(master: TimerNumber, slave: TimerNumber) -> Option<u8> {
+    match (slave, master) {
+        (TimerNumber::TIM1, TimerNumber::TIM2) => Some(1),
+        (TimerNumber::TIM1, TimerNumber::TIM3) => Some(2),
+        (TimerNumber::TIM1, TimerNumber::TIM4) => Some(3),
+        (TimerNumber::TIM2, TimerNumber::TIM1) => Some(0),
+        (TimerNumber::TIM2, TimerNumber::TIM3) => Some(2),
+        (TimerNumber::TIM2, TimerNumber::TIM4) => Some(3),
+        (TimerNumber::TIM3, TimerNumber::TIM1) => Some(0),
+        (TimerNumber::TIM3, TimerNumber::TIM2) => Some(1),
+        (TimerNumber::TIM3, TimerNumber::TIM4) => Some(3),
+        (TimerNumber::TIM4, TimerNumber::TIM1) => Some(0),
+        (TimerNumber::TIM4, TimerNumber::TIM2) => Some(1),
+        (TimerNumber::TIM4, TimerNumber::TIM3) => Some(2),
+        _ => None,
+    }
+}
+
+/// 计算同步启动时从定时器SMCR中SMS/TS字段的值
+/// 
+/// # 返回值
+/// SMS=触发模式、TS=ITRx的SMCR值，主从无内部连接时返回None
+pub const fn sync_slave_smcr(master: TimerNumber, slave: TimerNumber) -> Option<u32> {
+    match internal_trigger_source(master, slave) {
+        Some(itr) => Some(((itr as u32) << 4) | SMCR_SMS_TRIGGER),
+        None => None,
+    }
+}
+
+/// 计算同步启动时主定时器CR2中MMS字段的值
+pub const fn sync_master_cr2() -> u32 {
+    CR2_MMS_ENABLE
+}
+
+/// 同步启动多个定时器，实现零相位差的PWM输出
+/// 
+/// 主定时器配置为MMS=使能（CEN作为TRGO），从定时器配置为触发模式并选择主定时器的ITRx，
+/// 所有计数器清零后只由软件置位主定时器的CEN，从定时器的CEN由硬件在同一时钟周期置位。
+/// 
+/// 调用前应先用`init`/`init_pwm`配置好各定时器的预分频和周期，且保持停止状态。
+/// 
+/// # 参数
+/// * `master` - 主定时器
+/// * `slaves` - 从定时器列表
+/// 
+/// # 返回值
+/// 从定时器列表中包含主定时器时返回`InvalidParameter`，此时不修改任何定时器
+/// 
+/// # Safety
+/// 直接修改多个定时器的控制寄存器，调用者必须确保这些定时器不被其他代码同时使用
+pub unsafe fn synchronized_start(master: TimerNumber, slaves: &[TimerNumber]) -> Result<(), TimerError> {
+    if slaves.iter().any(|&slave| sync_slave_smcr(master, slave).is_none()) {
+        return Err(TimerError::InvalidParameter);
+    }
+    
+    let master_timer = Timer::new(master);
+    master_timer.stop();
+    
+    // 配置从定时器：触发模式，触发源为主定时器TRGO
+    for &slave in slaves {
+        configure_sync_slave(master, slave, 0)?;
+    }
+    
+    start_sync_master(&master_timer);
+    Ok(())
+}
+
+/// 将从定时器配置为由主定时器TRGO触发启动，并预置计数值
+/// 
+/// 主从为同一定时器时返回`InvalidParameter`
+unsafe fn configure_sync_slave(master: TimerNumber, slave: TimerNumber, count: u16) -> Result<(), TimerError> {
+    let smcr = sync_slave_smcr(master, slave).ok_or(TimerError::InvalidParameter)?;
+    
+    let slave_timer = Timer::new(slave);
+    slave_timer.stop();
+    slave_timer.set_count(count);
+    slave_timer.modify_smcr(SMCR_SMS_TS_MASK, smcr);
+    Ok(())
+}
+
+/// 配置主定时器CEN作为TRGO并启动，从定时器在同一时钟周期被触发
+unsafe fn start_sync_master(master_timer: &Timer) {
+    // 配置主定时器：CEN作为TRGO，并启用主/从模式同步
+    master_timer.set_count(0);
+    master_timer.modify_cr2(CR2_MMS_MASK, sync_master_cr2());
+    master_timer.modify_smcr(SMCR_MSM, SMCR_MSM);
+    
+    // 只启动主定时器，从定时器由TRGO同时启动
+    master_timer.start();
+}
+
+/// 两路相移PWM输出
+/// 
+/// 同一定时器的各通道共用一个计数器，无法产生相位差，因此使用两个同步启动的定时器：
+/// 启动前将从定时器的计数器预置一个偏移量，两路PWM的频率相同而相位固定错开，
+/// 适用于交错并联的变换器。
+/// 
+/// 两个定时器应先用`init_pwm`配置为相同的预分频和周期。
+pub struct PhaseShiftedPwm {
+    master: TimerNumber,
+    slave: TimerNumber,
+}
+
+impl PhaseShiftedPwm {
+    /// 创建相移PWM
+    /// 
+    /// # 参数
+    /// * `master` - 主定时器（相位基准）
+    /// * `slave` - 从定时器（输出滞后于主定时器）
+    pub const fn new(master: TimerNumber, slave: TimerNumber) -> Self {
+        assert!(internal_trigger_source(master, slave).is_some(), "Slave timer must differ from master timer");
+        Self { master, slave }
+    }
+    
+    /// 设置从定时器输出相对主定时器的滞后相位，并重新同步启动两个定时器
+    /// 
+    /// # 参数
+    /// * `degrees` - 相位差（度），超过360度时取模
+    /// 
+    /// # 返回值
+    /// 主从定时器无内部触发连接时返回`InvalidParameter`
+    /// 
+    /// # Safety
+    /// 会短暂停止两个定时器，调用者必须确保这些定时器不被其他代码同时使用
+    pub unsafe fn set_phase_shift(&self, degrees: u16) -> Result<(), TimerError> {
+        let master_timer = Timer::new(self.master);
+        master_timer.stop();
+        
+        let offset = Self::phase_offset_count(degrees, master_timer.get_period());
+        configure_sync_slave(self.master, self.slave, offset)?;
+        start_sync_master(&master_timer);
+        Ok(())
+    }
+    
+    /// 根据相位角和自动重装载值计算从定时器的初始计数值
+    /// 
+    /// 从定时器计数器超前`offset`个计数即输出超前，因此滞后θ时预置为`周期 - 周期×θ/360`。
+    /// 
+    /// # 参数
+    /// * `degrees` - 滞后相位（度）
+    /// * `arr` - 自动重装载值，计数周期为`arr + 1`
+    pub const fn phase_offset_count(degrees: u16, arr: u16) -> u16 {
+        let period = arr as u32 + 1;
+        let lag = (period * (degrees % 360) as u32 + 180) / 360;
+        ((period - lag) % period) as u16
+    }
+}
+
+/// 单个PWM通道的句柄
+/// 
+/// 持有定时器和通道，实现embedded-hal的`PwmPin`，可直接交给按该特性编写的舵机、LED等驱动。
+/// 定时器需先用`init_pwm`配置好该通道，最大占空比为当前的ARR。
+pub struct PwmChannelHandle {
+    timer: Timer,
+    channel: PwmChannel,
+}
+
+impl PwmChannelHandle {
+    /// 创建PWM通道句柄
+    /// 
+    /// # 参数
+    /// * `timer` - 输出PWM的定时器
+    /// * `channel` - PWM通道
+    pub const fn new(timer: Timer, channel: PwmChannel) -> Self {
+        Self { timer, channel }
+    }
+    
+    /// 获取句柄对应的通道
+    pub const fn channel(&self) -> PwmChannel {
+        self.channel
+    }
+    
+    /// 释放句柄，返回定时器
+    pub fn release(self) -> Timer {
+        self.timer
+    }
+}
+
+/// 实现embedded-hal单通道PWM特性
+/// 
+/// 占空比超过ARR时按ARR处理
+impl PwmPin for PwmChannelHandle {
+    type Duty = u16;
+    
+    fn disable(&mut self) {
+        unsafe { self.timer.disable_pwm_channel(self.channel) }
+    }
+    
+    fn enable(&mut self) {
+        unsafe { self.timer.enable_pwm_channel(self.channel) }
+    }
+    
+    fn get_duty(&self) -> u16 {
+        unsafe { self.timer.get_pwm_duty(self.channel) }
+    }
+    
+    fn get_max_duty(&self) -> u16 {
+        unsafe { self.timer.get_period() }
     }
     
+    fn set_duty(&mut self, duty: u16) {
+        unsafe {
+            let duty = duty.min(self.timer.get_period());
+            self.timer.set_pwm_duty(self.channel, duty);
+        }
+    }
+}
 
+/// 实现embedded-hal多通道PWM特性
+/// 
+/// 周期以自动重装载值（计数个数减1）表示，修改周期不会调整各通道的比较值
+impl Pwm for Timer {
+    type Channel = PwmChannel;
+    type Time = u16;
+    type Duty = u16;
+    
+    fn disable(&mut self, channel: PwmChannel) {
+        unsafe { self.disable_pwm_channel(channel) }
+    }
+    
+    fn enable(&mut self, channel: PwmChannel) {
+        unsafe { self.enable_pwm_channel(channel) }
+    }
+    
+    fn get_period(&self) -> u16 {
+        unsafe { Timer::get_period(self) }
+    }
+    
+    fn get_duty(&self, channel: PwmChannel) -> u16 {
+        unsafe { self.get_pwm_duty(channel) }
+    }
+    
+    fn get_max_duty(&self) -> u16 {
+        unsafe { Timer::get_period(self) }
+    }
+    
+    fn set_duty(&mut self, channel: PwmChannel, duty: u16) {
+        unsafe {
+            let duty = duty.min(Timer::get_period(self));
+            self.set_pwm_duty(channel, duty);
+        }
+    }
+    
+    fn set_period<P: Into<u16>>(&mut self, period: P) {
+        unsafe { Timer::set_period(self, period.into()) }
+    }
 }
 
 /// 预定义的定时器常量
@@ -1223,3 +2027,366 @@ pub const TIM1: Timer = Timer::new(TimerNumber::TIM1);
 pub const TIM2: Timer = Timer::new(TimerNumber::TIM2);
 pub const TIM3: Timer = Timer::new(TimerNumber::TIM3);
 pub const TIM4: Timer = Timer::new(TimerNumber::TIM4);
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试同步启动时主定时器的MMS配置
+    #[test]
+    fn test_sync_master_mms() {
+        assert_eq!(sync_master_cr2(), 0x0000_0010, "主定时器MMS应为001（使能）");
+        assert_eq!(sync_master_cr2() & !CR2_MMS_MASK, 0, "不应修改MMS以外的CR2位");
+    }
+    
+    /// 测试同步启动时从定时器的SMS/TS配置
+    #[test]
+    fn test_sync_slave_smcr() {
+        assert_eq!(sync_slave_smcr(TimerNumber::TIM1, TimerNumber::TIM2), Some(0x06), "TIM1->TIM2应为ITR0");
+        assert_eq!(sync_slave_smcr(TimerNumber::TIM1, TimerNumber::TIM3), Some(0x06), "TIM1->TIM3应为ITR0");
+        assert_eq!(sync_slave_smcr(TimerNumber::TIM2, TimerNumber::TIM3), Some(0x16), "TIM2->TIM3应为ITR1");
+        assert_eq!(sync_slave_smcr(TimerNumber::TIM3, TimerNumber::TIM4), Some(0x26), "TIM3->TIM4应为ITR2");
+        assert_eq!(sync_slave_smcr(TimerNumber::TIM4, TimerNumber::TIM1), Some(0x36), "TIM4->TIM1应为ITR3");
+        assert_eq!(sync_slave_smcr(TimerNumber::TIM2, TimerNumber::TIM2), None, "主从相同时应无触发连接");
+        
+        // 从定时器列表包含主定时器时在访问寄存器前返回错误
+        unsafe {
+            assert_eq!(
+                synchronized_start(TimerNumber::TIM2, &[TimerNumber::TIM3, TimerNumber::TIM2]),
+                Err(TimerError::InvalidParameter),
+                "主从相同时应返回参数错误"
+            );
+        }
+        
+        // 从定时器的SMCR值只包含SMS/TS字段，且SMS为触发模式
+        for &master in &[TimerNumber::TIM1, TimerNumber::TIM2, TimerNumber::TIM3, TimerNumber::TIM4] {
+            for &slave in &[TimerNumber::TIM1, TimerNumber::TIM2, TimerNumber::TIM3, TimerNumber::TIM4] {
+                if let Some(smcr) = sync_slave_smcr(master, slave) {
+                    assert_eq!(smcr & 0x07, 0b110, "SMS应为触发模式");
+                    assert_eq!(smcr & !SMCR_SMS_TS_MASK, 0, "不应修改SMS/TS以外的SMCR位");
+                    assert_eq!(smcr & SMCR_MSM, 0, "从定时器不应设置MSM");
+                }
+            }
+        }
+    }
+    
+    /// 测试空闲输出电平的CR2.OISx位
+    #[test]
+    fn test_idle_state_bit() {
+        assert_eq!(Timer::idle_state_bit(PwmChannel::Channel1), 0x0100, "通道1应为OIS1");
+        assert_eq!(Timer::idle_state_bit(PwmChannel::Channel2), 0x0400, "通道2应为OIS2");
+        assert_eq!(Timer::idle_state_bit(PwmChannel::Channel3), 0x1000, "通道3应为OIS3");
+        assert_eq!(Timer::idle_state_bit(PwmChannel::Channel4), 0x4000, "通道4应为OIS4");
+        
+        unsafe {
+            assert_eq!(
+                TIM3.set_idle_state(PwmChannel::Channel1, true),
+                Err(TimerError::UnsupportedFeature),
+                "通用定时器没有OISx位"
+            );
+        }
+    }
+    
+    /// 测试由相位角计算从定时器初始计数值
+    #[test]
+    fn test_phase_offset_count() {
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(0, 999), 0, "0度时不应有偏移");
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(90, 999), 750, "滞后90度应预置3/4周期");
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(180, 999), 500, "滞后180度应预置半个周期");
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(270, 999), 250, "滞后270度应预置1/4周期");
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(360, 999), 0, "360度应等同于0度");
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(450, 999), 750, "超过360度应取模");
+        assert_eq!(PhaseShiftedPwm::phase_offset_count(120, 0xFFFF), 43691, "最大周期时计算不应溢出");
+    }
+    
+    /// 测试捕获中断使能位和中断分发逻辑
+    #[test]
+    fn test_capture_dispatch() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+        static CH1_VALUE: AtomicU32 = AtomicU32::new(0);
+        static CH3_VALUE: AtomicU32 = AtomicU32::new(0);
+        
+        assert_eq!(Timer::capture_interrupt_bit(PwmChannel::Channel1), 1 << 1, "CC1IE应为DIER位1");
+        assert_eq!(Timer::capture_interrupt_bit(PwmChannel::Channel4), 1 << 4, "CC4IE应为DIER位4");
+        
+        let mut handlers: [Option<fn(u16)>; 4] = [None; 4];
+        handlers[0] = Some(|value| CH1_VALUE.store(value as u32, Ordering::Relaxed));
+        handlers[2] = Some(|value| CH3_VALUE.store(value as u32, Ordering::Relaxed));
+        let ccr = [1000u16, 2000, 3000, 4000];
+        
+        // CC1、CC3、CC4标志置位，但CC4未启用中断；UIF不属于捕获标志
+        let sr = (1 << 0) | (1 << 1) | (1 << 3) | (1 << 4);
+        let dier = (1 << 1) | (1 << 3);
+        let mut reads = 0;
+        let handled = dispatch_captures(sr, dier, &handlers, |channel| {
+            reads += 1;
+            ccr[channel as usize]
+        });
+        
+        assert_eq!(handled, (1 << 1) | (1 << 3), "只应处理已启用中断的通道");
+        assert_eq!(reads, 2, "每个已处理通道应读取一次CCR");
+        assert_eq!(CH1_VALUE.load(Ordering::Relaxed), 1000, "通道1回调应收到CCR1的值");
+        assert_eq!(CH3_VALUE.load(Ordering::Relaxed), 3000, "通道3回调应收到CCR3的值");
+        
+        // 没有注册回调的通道仍应读取并报告已处理
+        let handled = dispatch_captures(1 << 2, 1 << 2, &handlers, |channel| ccr[channel as usize]);
+        assert_eq!(handled, 1 << 2, "无回调的通道也应清除标志");
+    }
+    
+    /// 测试单次超时的PSC/ARR计算和单脉冲模式配置
+    #[test]
+    fn test_timeout_psc_arr() {
+        // 72MHz下1ms：72000个计数，PSC=1（2分频），ARR=35999
+        assert_eq!(Timeout::psc_arr_for_ms(72_000_000, 1), Ok((1, 35999)), "1ms分频计算错误");
+        
+        // 72MHz下500ms：36000000个计数，分频550后ARR约65455
+        let (psc, arr) = Timeout::psc_arr_for_ms(72_000_000, 500).unwrap();
+        assert_eq!(psc, 549, "500ms预分频计算错误");
+        let actual_us = (psc as u64 + 1) * (arr as u64 + 1) * 1_000_000 / 72_000_000;
+        assert!(actual_us.abs_diff(500_000) < 10, "500ms实际延时误差过大：{}us", actual_us);
+        
+        // 8MHz下1ms不需要分频
+        assert_eq!(Timeout::psc_arr_for_ms(8_000_000, 1), Ok((0, 7999)), "8MHz下1ms分频计算错误");
+        
+        // 0ms和超出PSC×ARR范围（72MHz下约59.6秒）的延时返回错误
+        assert_eq!(Timeout::psc_arr_for_ms(72_000_000, 0), Err(TimerError::InvalidParameter), "0ms应返回参数错误");
+        assert!(Timeout::psc_arr_for_ms(72_000_000, 59_000).is_ok(), "59秒应在范围内");
+        assert_eq!(Timeout::psc_arr_for_ms(72_000_000, 60_000), Err(TimerError::InvalidParameter), "60秒应超出范围");
+        
+        assert_eq!(Timeout::cr1_bits() & CR1_OPM, 1 << 3, "应设置单脉冲模式OPM");
+        assert_ne!(Timeout::cr1_bits() & (1 << 2), 0, "应设置URS以免UG触发回调");
+    }
+    
+    /// 测试从CCER读取各通道的CCxE位
+    #[test]
+    fn test_channel_enabled_from_ccer() {
+        // CC1E、CC3E使能，CC2P（极性位）置位但CC2E未使能，CC4NP不影响CC4E
+        let ccer: u32 = (1 << 0) | (1 << 5) | (1 << 8) | (1 << 15);
+        
+        assert!(Timer::ccer_channel_enabled(ccer, PwmChannel::Channel1), "通道1应为使能");
+        assert!(!Timer::ccer_channel_enabled(ccer, PwmChannel::Channel2), "极性位不应视为使能");
+        assert!(Timer::ccer_channel_enabled(ccer, PwmChannel::Channel3), "通道3应为使能");
+        assert!(!Timer::ccer_channel_enabled(ccer, PwmChannel::Channel4), "通道4应为禁用");
+        assert_eq!(Timer::channel_enable_bit(PwmChannel::Channel4), 1 << 12, "CC4E应为CCER位12");
+    }
+    
+    /// 测试门控模式的SMCR配置和计数值换算
+    #[test]
+    fn test_gated_mode_config() {
+        let smcr = Timer::gated_smcr();
+        assert_eq!(smcr & 0b111, 0b101, "SMS应为门控模式101");
+        assert_eq!((smcr >> 4) & 0b111, 0b101, "TS应为TI1FP1");
+        assert_eq!(smcr & !SMCR_SMS_TS_MASK, 0, "不应设置SMS和TS以外的位");
+        
+        // 72MHz、PSC=71时每个计数为1us
+        assert_eq!(Timer::gated_duration_us(1500, 72_000_000, 71), 1500, "1MHz计数时计数值即为微秒数");
+        // PSC=0时每个计数约13.9ns
+        assert_eq!(Timer::gated_duration_us(7200, 72_000_000, 0), 100, "72MHz计数换算错误");
+    }
+    
+    /// 测试捕获DMA请求位和各定时器通道的DMA通道映射
+    #[test]
+    fn test_capture_dma_mapping() {
+        assert_eq!(Timer::capture_dma_bit(PwmChannel::Channel1), 1 << 9, "CC1DE应为DIER位9");
+        assert_eq!(Timer::capture_dma_bit(PwmChannel::Channel4), 1 << 12, "CC4DE应为DIER位12");
+        
+        // DMA1通道N的寄存器地址为0x4002_0008 + 20 × (N - 1)
+        let channel_of = |timer, channel| {
+            Timer::capture_dma_channel(timer, channel).map(|dma| (dma.channel_regs() as u32 - 0x4002_0008) / 20 + 1)
+        };
+        assert_eq!(channel_of(TimerNumber::TIM1, PwmChannel::Channel1), Some(2), "TIM1_CH1应为DMA1通道2");
+        assert_eq!(channel_of(TimerNumber::TIM1, PwmChannel::Channel3), Some(6), "TIM1_CH3应为DMA1通道6");
+        assert_eq!(channel_of(TimerNumber::TIM2, PwmChannel::Channel1), Some(5), "TIM2_CH1应为DMA1通道5");
+        assert_eq!(channel_of(TimerNumber::TIM2, PwmChannel::Channel3), Some(1), "TIM2_CH3应为DMA1通道1");
+        assert_eq!(channel_of(TimerNumber::TIM3, PwmChannel::Channel1), Some(6), "TIM3_CH1应为DMA1通道6");
+        assert_eq!(channel_of(TimerNumber::TIM4, PwmChannel::Channel2), Some(4), "TIM4_CH2应为DMA1通道4");
+        assert_eq!(channel_of(TimerNumber::TIM3, PwmChannel::Channel2), None, "TIM3_CH2没有DMA请求");
+        assert_eq!(channel_of(TimerNumber::TIM4, PwmChannel::Channel4), None, "TIM4_CH4没有DMA请求");
+    }
+    
+    /// 测试单脉冲输出通道的CCMR配置
+    #[test]
+    fn test_one_pulse_output_ccmr() {
+        assert_eq!(Timer::output_compare_ccmr(PwmChannel::Channel1, OCM_PWM2), (0x00FF, 0x0070), "通道1应配置CCMR1低字节");
+        assert_eq!(Timer::output_compare_ccmr(PwmChannel::Channel2, OCM_PWM2), (0xFF00, 0x7000), "通道2应配置CCMR1高字节");
+        assert_eq!(Timer::output_compare_ccmr(PwmChannel::Channel3, OCM_PWM2), (0x00FF, 0x0070), "通道3应配置CCMR2低字节");
+        assert_eq!(Timer::output_compare_ccmr(PwmChannel::Channel4, 0b110), (0xFF00, 0x6000), "通道4应配置CCMR2高字节");
+    }
+    
+    /// 测试互补输出的CCER位和死区编码
+    #[test]
+    fn test_complementary_dead_time() {
+        assert_eq!(Timer::complementary_ccer_bits(PwmChannel::Channel1, PwmPolarity::High), (0x000C, 0x0004), "通道1应只置位CC1NE");
+        assert_eq!(Timer::complementary_ccer_bits(PwmChannel::Channel2, PwmPolarity::Low), (0x00C0, 0x00C0), "低电平有效应同时置位CC2NP");
+        
+        // 72MHz下1us为72个周期
+        assert_eq!(Timer::dead_time_ticks(1000, 72_000_000), 72, "1us应为72个周期");
+        assert_eq!(Timer::dead_time_dtg(72), Ok(72), "小于128个周期时直接编码");
+        assert_eq!(Timer::dead_time_dtg(200), Ok(0x80 | 36), "128~254应按2倍编码");
+        assert_eq!(Timer::dead_time_dtg(300), Ok(0xC0 | 5), "256~504应按8倍编码");
+        assert_eq!(Timer::dead_time_dtg(1008), Ok(0xFF), "最大值应为0xFF");
+        assert_eq!(Timer::dead_time_dtg(1009), Err(TimerError::InvalidParameter), "超过1008个周期应报错");
+    }
+    
+    /// 测试刹车输入的BDTR配置和非高级定时器的错误
+    #[test]
+    fn test_break_config() {
+        assert_eq!(Timer::break_bdtr_bits(BreakPolarity::Low, true), (0x3000, 0x1000), "低电平有效应只置位BKE");
+        assert_eq!(Timer::break_bdtr_bits(BreakPolarity::High, true), (0x3000, 0x3000), "高电平有效应同时置位BKP");
+        assert_eq!(Timer::break_bdtr_bits(BreakPolarity::High, false), (0x3000, 0x2000), "禁用时应清除BKE");
+        assert_eq!(Timer::lock_bdtr_bits(TimerLockLevel::Level1), (0x0300, 0x0100), "级别1应写入LOCK=01");
+        assert_eq!(Timer::lock_bdtr_bits(TimerLockLevel::Level3), (0x0300, 0x0300), "级别3应写入LOCK=11");
+        
+        // 非高级定时器在访问寄存器前返回错误
+        unsafe {
+            assert_eq!(TIM2.config_break(BreakPolarity::Low, true), Err(TimerError::UnsupportedFeature), "TIM2不支持刹车");
+            assert_eq!(TIM3.set_dead_time(100), Err(TimerError::UnsupportedFeature), "TIM3不支持死区");
+            assert_eq!(TIM4.set_lock_level(TimerLockLevel::Level1), Err(TimerError::UnsupportedFeature), "TIM4不支持写保护");
+            assert_eq!(TIM2.generate_break(), Err(TimerError::UnsupportedFeature), "TIM2不能产生刹车");
+        }
+    }
+    
+    /// 测试PWM输入模式的配置和换算
+    #[test]
+    fn test_pwm_input() {
+        let (ccmr1, ccer, smcr) = Timer::pwm_input_config();
+        assert_eq!(ccmr1, 0x0201, "CC1S应为TI1，CC2S应为TI1（交叉映射）");
+        assert_eq!(ccer, 0x31, "CC1上升沿、CC2下降沿，两通道均使能");
+        assert_eq!(smcr, 0x54, "应为TI1FP1触发的复位模式");
+        
+        // 72MHz、预分频71时计数频率为1MHz，舵机信号周期20000、高电平1500
+        assert_eq!(Timer::pwm_input_frequency_hz(20000, 72_000_000, 71), 50, "舵机信号应为50Hz");
+        assert_eq!(Timer::pwm_input_duty_permille(20000, 1500), 75, "1.5ms/20ms应为7.5%");
+        assert_eq!(Timer::pwm_input_frequency_hz(0, 72_000_000, 71), 0, "无信号时频率应为0");
+        assert_eq!(Timer::pwm_input_duty_permille(0, 10), 0, "无信号时占空比应为0");
+    }
+    
+    /// 测试主从模式的寄存器字段
+    #[test]
+    fn test_master_slave_mode() {
+        assert_eq!(Timer::master_mode_cr2(MasterMode::Update), CR2_MMS_UPDATE, "更新事件应为MMS=010");
+        assert_eq!(Timer::master_mode_cr2(MasterMode::Enable), sync_master_cr2(), "使能信号应为MMS=001");
+        assert_eq!(Timer::master_mode_cr2(MasterMode::Oc4Ref), 0x70, "OC4REF应为MMS=111");
+        
+        // TIM3对TIM2的溢出计数：外部时钟模式1，ITR1
+        let trigger = TriggerSource::from_master(TimerNumber::TIM2, TimerNumber::TIM3).unwrap();
+        assert_eq!(trigger, TriggerSource::Itr1, "TIM3上TIM2应为ITR1");
+        assert_eq!(Timer::slave_mode_smcr(SlaveMode::ExternalClock, trigger), 0x17, "SMCR应为TS=001、SMS=111");
+        assert_eq!(
+            Some(Timer::slave_mode_smcr(SlaveMode::Trigger, trigger)),
+            sync_slave_smcr(TimerNumber::TIM2, TimerNumber::TIM3),
+            "触发模式应与同步启动的配置一致"
+        );
+        assert_eq!(TriggerSource::from_master(TimerNumber::TIM4, TimerNumber::TIM4), None, "主从相同时应返回None");
+    }
+    
+    /// 测试PWM通道的CCMR配置只修改该通道所在的字节
+    #[test]
+    fn test_pwm_ccmr_bits() {
+        assert_eq!(Timer::pwm_ccmr_bits(PwmChannel::Channel1, PwmMode::Mode1), (0x00FF, 0x0068), "通道1 PWM模式1配置错误");
+        assert_eq!(Timer::pwm_ccmr_bits(PwmChannel::Channel2, PwmMode::Mode1), (0xFF00, 0x6800), "通道2 PWM模式1配置错误");
+        assert_eq!(Timer::pwm_ccmr_bits(PwmChannel::Channel3, PwmMode::Mode2), (0x00FF, 0x0078), "通道3 PWM模式2配置错误");
+        assert_eq!(Timer::pwm_ccmr_bits(PwmChannel::Channel4, PwmMode::Mode2), (0xFF00, 0x7800), "通道4 PWM模式2配置错误");
+        
+        // 先配置通道1再配置通道2，通道1的配置应保留
+        let (mask, value) = Timer::pwm_ccmr_bits(PwmChannel::Channel2, PwmMode::Mode1);
+        let ccmr1 = (0x0068 & !mask) | value;
+        assert_eq!(ccmr1, 0x6868, "配置通道2不应清除通道1的配置");
+    }
+    
+    const TIM_CR1: usize = 0x00;
+    const TIM_DIER: usize = 0x0C;
+    const TIM_SR: usize = 0x10;
+    const TIM_CCMR1: usize = 0x18;
+    const TIM_CCMR2: usize = 0x1C;
+    const TIM_CCER: usize = 0x20;
+    const TIM_CCR1: usize = 0x34;
+    
+    /// 以内存模拟的定时器寄存器块，寄存器按偏移/4存放
+    struct FakeTimRegs(UnsafeCell<[u32; 17]>);
+    
+    impl FakeTimRegs {
+        fn at(&self, offset: usize) -> TimReg {
+            TimReg(unsafe { (self.0.get() as *mut u32).add(offset / 4) })
+        }
+        
+        fn get(&self, offset: usize) -> u32 {
+            unsafe { (*self.0.get())[offset / 4] }
+        }
+    }
+    
+    impl TimRegs for FakeTimRegs {
+        fn cr1(&self) -> TimReg { self.at(TIM_CR1) }
+        fn cr2(&self) -> TimReg { self.at(0x04) }
+        fn smcr(&self) -> TimReg { self.at(0x08) }
+        fn dier(&self) -> TimReg { self.at(TIM_DIER) }
+        fn sr(&self) -> TimReg { self.at(TIM_SR) }
+        fn egr(&self) -> TimReg { self.at(0x14) }
+        fn ccmr1(&self) -> TimReg { self.at(TIM_CCMR1) }
+        fn ccmr2(&self) -> TimReg { self.at(TIM_CCMR2) }
+        fn ccer(&self) -> TimReg { self.at(TIM_CCER) }
+        fn cnt(&self) -> TimReg { self.at(0x24) }
+        fn psc(&self) -> TimReg { self.at(0x28) }
+        fn arr(&self) -> TimReg { self.at(0x2C) }
+        fn ccr(&self, channel: PwmChannel) -> TimReg { self.at(TIM_CCR1 + 4 * channel as usize) }
+    }
+    
+    /// 测试输入捕获预分频写入各自通道的ICxPSC字段
+    #[test]
+    fn test_input_capture_prescaler() {
+        let tim = FakeTimRegs(UnsafeCell::new([0; 17]));
+        unsafe {
+            Timer::config_input_capture_regs(&tim, PwmChannel::Channel1, CapturePolarity::Rising, CapturePrescaler::Div4);
+        }
+        let ccmr1 = tim.get(TIM_CCMR1);
+        assert_eq!(ccmr1 & 0b11, 0b01, "CC1S应为01（IC1映射到TI1）");
+        assert_eq!((ccmr1 >> 2) & 0b11, 0b10, "通道1的4分频应写入IC1PSC（位3:2）");
+        assert_eq!((ccmr1 >> 10) & 0b11, 0, "配置通道1不应修改IC2PSC");
+        assert_eq!(tim.get(TIM_CCER), CCER_CC1E, "通道1应上升沿捕获并使能");
+        
+        unsafe {
+            Timer::config_input_capture_regs(&tim, PwmChannel::Channel2, CapturePolarity::Falling, CapturePrescaler::Div2);
+        }
+        let ccmr1 = tim.get(TIM_CCMR1);
+        assert_eq!(ccmr1, 0x0509, "通道2的配置应写入高字节，并保留通道1的配置");
+        assert_eq!(tim.get(TIM_CCER), 0x31, "通道2应下降沿捕获并使能");
+        
+        assert_eq!(Timer::input_capture_ccmr(PwmChannel::Channel4, CapturePrescaler::Div8), (0xFF00, 0x0D00), "通道4的配置应位于CCMR2高字节");
+    }
+    
+    /// 测试定时器延时的预分频计算
+    #[test]
+    fn test_delay_prescaler() {
+        assert_eq!(Timer::delay_prescaler(72_000_000), 71, "72MHz下应72分频得到1MHz");
+        assert_eq!(Timer::delay_prescaler(36_000_000), 35, "36MHz下应36分频得到1MHz");
+        assert_eq!(Timer::delay_prescaler(8_000_000), 7, "8MHz下应8分频得到1MHz");
+        assert_eq!(Timer::delay_prescaler(4_500_000), 4, "4.5MHz下应取最接近的5分频");
+        assert_eq!(Timer::delay_prescaler(500_000), 0, "低于1MHz时不分频");
+        
+        // 整数倍时钟下计数值与微秒数相同，其他时钟按实际计数频率补偿
+        assert_eq!(Timer::delay_ticks_for_us(1000, 72_000_000, 71), 1000, "72MHz下1ms应为1000个计数");
+        assert_eq!(Timer::delay_ticks_for_us(1000, 4_500_000, 4), 900, "0.9MHz计数下1ms应为900个计数");
+        assert_eq!(Timer::delay_ticks_for_us(1000, 500_000, 0), 500, "0.5MHz计数下1ms应为500个计数");
+        assert_eq!(Timer::delay_ticks_for_us(1, 4_500_000, 4), 1, "不足一个计数时应向上取整");
+        assert_eq!(Timer::delay_ticks_for_us(0, 72_000_000, 71), 0, "0微秒应为0个计数");
+        
+        // 1秒延时分为15段65536个计数和1段16960个计数
+        let ticks = 1000u64 * 1000;
+        assert_eq!(ticks / DELAY_CHUNK_TICKS, 15, "分段数计算错误");
+        assert_eq!(ticks % DELAY_CHUNK_TICKS, 16960, "最后一段计数错误");
+    }
+    
+    /// 测试PWM通道句柄实现embedded-hal的PWM特性
+    #[test]
+    fn test_embedded_hal_pwm() {
+        fn assert_pwm_pin<T: PwmPin<Duty = u16>>() {}
+        fn assert_pwm<T: Pwm<Channel = PwmChannel, Time = u16, Duty = u16>>() {}
+        assert_pwm_pin::<PwmChannelHandle>();
+        assert_pwm::<Timer>();
+        
+        let handle = PwmChannelHandle::new(TIM3, PwmChannel::Channel2);
+        assert_eq!(handle.channel(), PwmChannel::Channel2, "句柄通道错误");
+        assert_eq!(handle.release().number, TimerNumber::TIM3, "释放后应返回原定时器");
+    }
+}
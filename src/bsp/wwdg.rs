@@ -5,6 +5,16 @@
 
 // 导入内部生成的设备驱动库
 use library::*;
+use crate::bsp::rcc::{Apb1Peripheral, RccDriver};
+
+/// 计数器最小有效值，T6位清零（计数到0x3F）时产生复位
+pub const WWDG_COUNTER_MIN: u8 = 0x40;
+
+/// 计数器最大值（T[6:0]）
+pub const WWDG_COUNTER_MAX: u8 = 0x7F;
+
+/// 计数器时钟为PCLK1/4096再经WDGTB分频
+const WWDG_CLOCK_DIVIDER: u32 = 4096;
 
 /// WWDG预分频系数枚举
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -15,6 +25,13 @@ pub enum WwdgPrescaler {
     Div8 = 0x03,    // 8分频
 }
 
+impl WwdgPrescaler {
+    /// 获取分频系数
+    pub const fn divider(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+
 /// WWDG结构体
 pub struct Wwdg;
 
@@ -29,17 +46,40 @@ impl Wwdg {
         &mut *(0x40002C00 as *mut library::wwdg::RegisterBlock)
     }
     
+    /// 以最大预分频（8分频）启动WWDG
+    /// 
+    /// 计数器从`counter`递减，递减到`window`以下后才允许喂狗（过早喂狗会复位），
+    /// 递减到0x3F时复位。WWDG启动后只有系统复位才能关闭。
+    /// 
+    /// # 参数
+    /// * `window` - 窗口值 (0x40-0x7F)，不能大于`counter`
+    /// * `counter` - 计数器初值 (0x40-0x7F)
+    /// 
+    /// # 返回值
+    /// 按当前PCLK1计算的超时时间（毫秒）
+    /// 
+    /// # Safety
+    /// - 调用者必须确保启动后在窗口内按时喂狗，否则会触发系统复位
+    pub unsafe fn start(&self, window: u8, counter: u8) -> u32 {
+        self.init(WwdgPrescaler::Div8, window, counter);
+        let pclk1 = RccDriver::new().get_clocks_freq().pclk1_frequency;
+        Self::calculate_timeout(WwdgPrescaler::Div8, counter, pclk1)
+    }
+    
     /// 初始化WWDG
     /// 
     /// # 参数
     /// * `prescaler` - 预分频系数
-    /// * `window` - 窗口值 (0x40-0x7F)
+    /// * `window` - 窗口值 (0x40-0x7F)，不能大于`counter`
     /// * `counter` - 计数器值 (0x40-0x7F)
+    /// 
+    /// # Safety
+    /// - 调用者必须确保启动后在窗口内按时喂狗，否则会触发系统复位
     pub unsafe fn init(&self, prescaler: WwdgPrescaler, window: u8, counter: u8) {
         // 检查参数范围
-        assert!((window & 0xC0) == 0, "Window value must be between 0x40 and 0x7F");
-        assert!((counter & 0xC0) == 0, "Counter value must be between 0x40 and 0x7F");
+        Self::check_config(window, counter);
         
+        RccDriver::new().enable_apb1_peripheral(Apb1Peripheral::WWDG);
         let wwdg = Wwdg::wwdg();
         
         // 配置预分频系数和窗口值
@@ -61,9 +101,12 @@ impl Wwdg {
     /// 
     /// # 参数
     /// * `window` - 窗口值 (0x40-0x7F)
+    /// 
+    /// # Safety
+    /// - 调用者必须确保WWDG寄存器未被其他代码同时修改
     pub unsafe fn set_window(&self, window: u8) {
         // 检查参数范围
-        assert!((window & 0xC0) == 0, "Window value must be between 0x40 and 0x7F");
+        assert!((WWDG_COUNTER_MIN..=WWDG_COUNTER_MAX).contains(&window), "Window value must be between 0x40 and 0x7F");
         
         let wwdg = Wwdg::wwdg();
         
@@ -77,6 +120,9 @@ impl Wwdg {
     /// 
     /// # 参数
     /// * `prescaler` - 预分频系数
+    /// 
+    /// # Safety
+    /// - 调用者必须确保WWDG寄存器未被其他代码同时修改
     pub unsafe fn set_prescaler(&self, prescaler: WwdgPrescaler) {
         let wwdg = Wwdg::wwdg();
         
@@ -90,9 +136,12 @@ impl Wwdg {
     /// 
     /// # 参数
     /// * `counter` - 计数器值 (0x40-0x7F)
+    /// 
+    /// # Safety
+    /// - 调用者必须确保启动后在窗口内按时喂狗，否则会触发系统复位
     pub unsafe fn set_counter(&self, counter: u8) {
-        // 检查参数范围
-        assert!((counter & 0xC0) == 0, "Counter value must be between 0x40 and 0x7F");
+        // 检查参数范围，T6为0时写入会立即复位
+        assert!((WWDG_COUNTER_MIN..=WWDG_COUNTER_MAX).contains(&counter), "Counter value must be between 0x40 and 0x7F");
         
         let wwdg = Wwdg::wwdg();
         
@@ -103,6 +152,9 @@ impl Wwdg {
     }
     
     /// 获取计数器值
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_counter(&self) -> u8 {
         let wwdg = Wwdg::wwdg();
         wwdg.cr().read().t().bits()
@@ -110,13 +162,21 @@ impl Wwdg {
     
     /// 喂狗
     /// 
+    /// 必须在计数器递减到窗口值以下后调用，过早喂狗会触发复位
+    /// 
     /// # 参数
     /// * `counter` - 计数器值 (0x40-0x7F)
+    /// 
+    /// # Safety
+    /// - 调用者必须确保在窗口内喂狗，过早喂狗会触发系统复位
     pub unsafe fn feed(&self, counter: u8) {
         self.set_counter(counter);
     }
     
     /// 启用早期唤醒中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保WWDG寄存器未被其他代码同时修改
     pub unsafe fn enable_ewi(&self) {
         let wwdg = Wwdg::wwdg();
         wwdg.cfr().modify(|_, w: &mut library::wwdg::cfr::W| {
@@ -125,6 +185,9 @@ impl Wwdg {
     }
     
     /// 禁用早期唤醒中断
+    /// 
+    /// # Safety
+    /// - 调用者必须确保WWDG寄存器未被其他代码同时修改
     pub unsafe fn disable_ewi(&self) {
         let wwdg = Wwdg::wwdg();
         wwdg.cfr().modify(|_, w: &mut library::wwdg::cfr::W| {
@@ -133,6 +196,9 @@ impl Wwdg {
     }
     
     /// 清除早期唤醒中断标志
+    /// 
+    /// # Safety
+    /// - 调用者必须确保WWDG寄存器未被其他代码同时修改
     pub unsafe fn clear_ewi_flag(&self) {
         let wwdg = Wwdg::wwdg();
         wwdg.sr().write(|w: &mut library::wwdg::sr::W| {
@@ -141,39 +207,85 @@ impl Wwdg {
     }
     
     /// 检查早期唤醒中断标志
+    /// 
+    /// # Safety
+    /// - 仅能在目标硬件上调用
     pub unsafe fn get_ewi_flag(&self) -> bool {
         let wwdg = Wwdg::wwdg();
         wwdg.sr().read().ewi().bit()
     }
     
+    /// 检查窗口值和计数器值
+    /// 
+    /// 计数器必须大于0x3F（否则写入后立即复位），窗口值不能大于计数器值
+    pub const fn check_config(window: u8, counter: u8) {
+        assert!(counter >= WWDG_COUNTER_MIN && counter <= WWDG_COUNTER_MAX, "Counter value must be between 0x40 and 0x7F");
+        assert!(window >= WWDG_COUNTER_MIN && window <= WWDG_COUNTER_MAX, "Window value must be between 0x40 and 0x7F");
+        assert!(window <= counter, "Window value must not exceed the counter value");
+    }
+    
     /// 计算超时时间
     /// 
+    /// 计数器从`counter`递减到0x3F时复位，共`counter - 0x3F`个计数周期，
+    /// 每个周期为4096 × 2^WDGTB个PCLK1周期
+    /// 
     /// # 参数
     /// * `prescaler` - 预分频系数
     /// * `counter` - 计数器值 (0x40-0x7F)
     /// * `apb1_freq` - APB1时钟频率 (Hz)
     /// 
     /// # 返回值
-    /// 超时时间 (ms)
-    pub fn calculate_timeout(prescaler: WwdgPrescaler, counter: u8, apb1_freq: u32) -> u32 {
-        // WWDG时钟频率 = APB1时钟频率 / 4096
-        let wwdg_freq = apb1_freq / 4096;
-        
-        // 预分频系数
-        let prescaler_value = match prescaler {
-            WwdgPrescaler::Div1 => 1,
-            WwdgPrescaler::Div2 => 2,
-            WwdgPrescaler::Div4 => 4,
-            WwdgPrescaler::Div8 => 8,
-        };
-        
-        // 计算周期
-        let period = (counter & 0x7F) as u32;
-        
-        // 超时时间 (ms) = (period * prescaler_value * 1000) / wwdg_freq
-        (period * prescaler_value * 1000) / wwdg_freq
+    /// 超时时间 (ms)，向下取整
+    pub const fn calculate_timeout(prescaler: WwdgPrescaler, counter: u8, apb1_freq: u32) -> u32 {
+        let ticks = (counter & WWDG_COUNTER_MAX).saturating_sub(WWDG_COUNTER_MIN - 1) as u64;
+        Self::ticks_to_ms(prescaler, ticks, apb1_freq)
+    }
+    
+    /// 计算喂狗后窗口打开的时间
+    /// 
+    /// 计数器从`counter`递减到`window`以下之前喂狗会复位
+    /// 
+    /// # 返回值
+    /// 最早允许喂狗的时间 (ms)，向上取整
+    pub const fn calculate_window_open(prescaler: WwdgPrescaler, window: u8, counter: u8, apb1_freq: u32) -> u32 {
+        let ticks = counter.saturating_sub(window) as u64;
+        let cycles = ticks * WWDG_CLOCK_DIVIDER as u64 * prescaler.divider() as u64 * 1000;
+        cycles.div_ceil(apb1_freq as u64) as u32
+    }
+    
+    /// 计数周期数换算为毫秒，向下取整
+    const fn ticks_to_ms(prescaler: WwdgPrescaler, ticks: u64, apb1_freq: u32) -> u32 {
+        (ticks * WWDG_CLOCK_DIVIDER as u64 * prescaler.divider() as u64 * 1000 / apb1_freq as u64) as u32
+    }
+}
+
+impl Default for Wwdg {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// 预定义的WWDG实例
 pub const WWDG: Wwdg = Wwdg::new();
+
+/// 测试模块
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 测试超时时间和窗口打开时间的计算
+    #[test]
+    fn test_wwdg_timing() {
+        // PCLK1为36MHz、8分频时每个计数周期约910µs
+        assert_eq!(Wwdg::calculate_timeout(WwdgPrescaler::Div8, 0x7F, 36_000_000), 58, "0x7F应约为58ms");
+        assert_eq!(Wwdg::calculate_timeout(WwdgPrescaler::Div8, 0x40, 36_000_000), 0, "0x40只有一个计数周期");
+        assert_eq!(Wwdg::calculate_timeout(WwdgPrescaler::Div1, 0x7F, 8_000_000), 32, "8MHz不分频时0x7F应约为32ms");
+        assert_eq!(WwdgPrescaler::Div4.divider(), 4, "分频系数错误");
+        
+        assert_eq!(Wwdg::calculate_window_open(WwdgPrescaler::Div8, 0x50, 0x7F, 36_000_000), 43, "窗口应在约43ms后打开");
+        assert_eq!(Wwdg::calculate_window_open(WwdgPrescaler::Div8, 0x7F, 0x7F, 36_000_000), 0, "窗口等于计数器时应立即打开");
+        
+        Wwdg::check_config(0x50, 0x7F);
+        Wwdg::check_config(0x40, 0x40);
+    }
+}
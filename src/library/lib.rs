@@ -372,6 +372,16 @@
 #[doc = "58 - DMA2 Channel3 global interrupt"] DMA2_Channel3 = 58 ,
 #[doc = "59 - DMA2 Channel4 and DMA2 Channel5 global interrupt"] DMA2_Channel4_5 = 59 , } unsafe impl cortex_m :: interrupt :: InterruptNumber for Interrupt {
 #[inline (always)] fn number (self) -> u16 { self as u16 } }
+#[cfg (feature = "rt")] pub use self :: Interrupt as interrupt ;
+#[doc = r"Attribute to declare an interrupt handler. The function name must be a variant of [`Interrupt`], so a typo is rejected at compile time instead of silently falling back to `DefaultHandler`:"]
+#[doc = r""]
+#[doc = r" ```compile_fail"]
+#[doc = r" use library::interrupt;"]
+#[doc = r""]
+#[doc = r" #[interrupt]"]
+#[doc = r" fn USART9() {}"]
+#[doc = r" ```"]
+#[cfg (feature = "rt")] pub use cortex_m_rt :: interrupt ;
 #[doc = "Flexible static memory controller"] pub type Fsmc = crate :: Periph < fsmc :: RegisterBlock , 0xa000_0000 > ; impl core :: fmt :: Debug for Fsmc { fn fmt (& self , f : & mut core :: fmt :: Formatter) -> core :: fmt :: Result { f . debug_struct ("Fsmc") . finish () } }
 #[doc = "Flexible static memory controller"] pub mod fsmc {
 #[repr (C)]
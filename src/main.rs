@@ -12,18 +12,16 @@ use crate::bsp::gpio;
 
 #[entry]
 fn main() -> ! {
+    // 取得GPIO引脚的所有权
+    let gpio = gpio::Gpio::take().unwrap().split();
+    
     unsafe {
-        // 获取LED引脚（GPIOA.0）
-        let led = gpio::PA0;
+        // 配置GPIOA.0为推挽输出（LED）
+        let mut led = gpio.pa0.into_push_pull_output(gpio::GpioSpeed::Speed50MHz);
         
-        // 配置GPIOA.0为推挽输出
-        led.into_push_pull_output();
-        
-        // 配置USART3的GPIO引脚（PB10=TX, PB11=RX）
-        let pb10 = gpio::PB10;
-        let pb11 = gpio::PB11;
-        pb10.into_alternate_push_pull();
-        pb11.into_floating_input();
+        // 配置USART3的GPIO引脚（PB10=TX, PB11=RX，split后已为浮空输入）
+        let _tx = gpio.pb10.into_alternate_push_pull(gpio::GpioSpeed::Speed50MHz);
+        let _rx = gpio.pb11;
         
         // 主循环，闪烁LED
         loop {